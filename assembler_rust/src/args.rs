@@ -3,21 +3,26 @@ use std::path::PathBuf;
 
 pub struct AssemblerArgs {
     pub src: PathBuf,
+    pub listing: bool,
 }
 
 impl AssemblerArgs {
     pub fn parse() -> Result<Self, &'static str> {
-        let mut args = env::args();
+        let mut src = None;
+        let mut listing = false;
         // skip executable path
-        args.next();
+        for arg in env::args().skip(1) {
+            match arg.as_str() {
+                "--listing" => listing = true,
+                _ => src = Some(PathBuf::from(arg)),
+            }
+        }
 
         // see if there was an argument passed in.
-        if let Some(path) = args.next() {
-            return Ok(Self {
-                src: PathBuf::from(path),
-            });
+        if let Some(src) = src {
+            return Ok(Self { src, listing });
         }
 
-        Err("usage: cargo run [path]")
+        Err("usage: cargo run [path] [--listing]")
     }
 }
@@ -1,6 +1,24 @@
 use crate::parser::{Address, Instruction, Parser};
 use std::collections::HashMap;
 
+/// assembles a program into the words of its ROM image.
+pub fn assemble(src: &str) -> Result<Vec<u16>, String> {
+    let mut code = CodeGenerator::new(src);
+    code.generate()?;
+    code.take_code()
+        .lines()
+        .map(|line| u16::from_str_radix(line, 2).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// the .hack text of a ROM image, one word per line in binary.
+pub fn hack_text(words: &[u16]) -> String {
+    words
+        .iter()
+        .map(|word| format!("{:016b}\n", word))
+        .collect()
+}
+
 pub struct CodeGenerator<'a> {
     out: String,
     parser: Parser<'a>,
@@ -13,7 +31,7 @@ impl<'a> CodeGenerator<'a> {
     pub fn new(src: &'a str) -> Self {
         Self {
             out: String::new(),
-            parser: Parser::new(&src),
+            parser: Parser::new(src),
             instruction_count: 0,
             symbol_table: SymbolTable::new(),
             translation_table: TranslationTable::new(),
@@ -25,8 +43,8 @@ impl<'a> CodeGenerator<'a> {
     }
 
     pub fn generate(&mut self) -> Result<(), String> {
-        let _ = self.build_symbol_table()?;
-        let _ = self.parser.reset();
+        self.build_symbol_table()?;
+        self.parser.reset();
         while let Some(instruction) = self.parser.next_instruction()? {
             match instruction {
                 Instruction::AInstruction(addr) => self.translate_a_instruction(addr)?,
@@ -109,6 +127,12 @@ pub struct SymbolTable {
     variable_counter: u16,
 }
 
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SymbolTable {
     pub fn new() -> Self {
         Self {
@@ -118,7 +142,7 @@ impl SymbolTable {
     }
 
     fn get_init_table() -> HashMap<String, u16> {
-        let mut map = HashMap::with_capacity((2 as usize).pow(16));
+        let mut map = HashMap::with_capacity(2_usize.pow(16));
         // virtual registers
         map.insert("R0".to_string(), 0);
         map.insert("R1".to_string(), 1);
@@ -167,7 +191,7 @@ impl SymbolTable {
     }
 
     pub fn get_symbol(&self, symbol: &str) -> Option<u16> {
-        self.map.get(symbol).map(|i| *i)
+        self.map.get(symbol).copied()
     }
 
     pub fn has(&self, symbol: &str) -> bool {
@@ -181,6 +205,12 @@ pub struct TranslationTable {
     jump_map: HashMap<String, u16>,
 }
 
+impl Default for TranslationTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TranslationTable {
     pub fn new() -> Self {
         Self {
@@ -265,3 +295,18 @@ impl TranslationTable {
             .ok_or(format!("invalid jump translation \"{}\"", jump))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{assemble, hack_text};
+
+    #[test]
+    fn test_assemble() {
+        let words = assemble("(LOOP)\n@2\nD=A\n@LOOP\n0;JMP\n").unwrap();
+        assert_eq!(words, vec![2, 0b1110110000010000, 0, 0b1110101010000111]);
+        assert_eq!(
+            hack_text(&words[..2]),
+            "0000000000000010\n1110110000010000\n"
+        );
+    }
+}
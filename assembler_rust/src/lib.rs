@@ -1,4 +1,6 @@
 pub mod args;
 pub mod code;
+pub mod listing;
 pub mod parser;
+pub mod source_map;
 pub mod token;
@@ -0,0 +1,83 @@
+use crate::source_map::SourceMap;
+
+/// a listing of the assembled program: the ROM address and binary of every instruction next to
+/// the assembly it came from. With a source map the first instruction of every vm command is
+/// annotated with its location in the vm source.
+pub fn listing(source: &str, code: &str, map: Option<&SourceMap>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut binary = code.lines();
+    let mut address: u16 = 0;
+    let mut previous = None;
+    for line in source.lines() {
+        let instruction = line.split("//").next().unwrap_or("").trim();
+        if instruction.is_empty() {
+            continue;
+        }
+        if instruction.starts_with('(') {
+            out.push_str(&format!("{:23}{}\n", "", instruction));
+            continue;
+        }
+
+        let word = binary
+            .next()
+            .ok_or("the listing has more instructions than the assembled code")?;
+        out.push_str(&format!("{:>5}  {}  {}", address, word, instruction));
+        if let Some(entry) = map.and_then(|map| map.lookup(address)) {
+            if previous != Some(&entry.location) {
+                out.push_str(&format!(
+                    "{:width$}// {}",
+                    "",
+                    entry.location,
+                    width = 20_usize.saturating_sub(instruction.len())
+                ));
+                previous = Some(&entry.location);
+            }
+        }
+        out.push('\n');
+        address += 1;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::listing;
+    use crate::code::CodeGenerator;
+    use crate::source_map::{SourceEntry, SourceLocation, SourceMap};
+
+    #[test]
+    fn test_listing() {
+        let source = "// push constant 7\n@7\nD=A\n(LOOP)\n@LOOP\n0;JMP\n";
+        let mut code = CodeGenerator::new(source);
+        code.generate().unwrap();
+        let code = code.take_code();
+
+        let mut map = SourceMap::new();
+        for (address, asm_line, line, command) in [
+            (0, 2, 1, "push constant 7"),
+            (1, 3, 1, "push constant 7"),
+            (2, 5, 2, "goto LOOP"),
+            (3, 6, 2, "goto LOOP"),
+        ] {
+            map.push(SourceEntry {
+                address,
+                asm_line,
+                location: SourceLocation {
+                    file: "Main.vm".to_string(),
+                    line,
+                    function: None,
+                    command: command.to_string(),
+                },
+            });
+        }
+
+        let expected = "    0  0000000000000111  @7                  // Main.vm:1 push constant 7
+    1  1110110000010000  D=A
+                       (LOOP)
+    2  0000000000000010  @LOOP               // Main.vm:2 goto LOOP
+    3  1110101010000111  0;JMP
+";
+        assert_eq!(listing(source, &code, Some(&map)), Ok(expected.to_string()));
+        assert!(listing(source, "", None).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use assembler_rust::args::AssemblerArgs;
 use assembler_rust::code::CodeGenerator;
+use assembler_rust::listing::listing;
+use assembler_rust::source_map::SourceMap;
 use std::fs;
 
 fn main() {
@@ -19,11 +21,24 @@ fn main() {
 
 fn translate(args: AssemblerArgs) -> Result<(), String> {
     println!("[info] reading source {:?}...", args.src);
-    let raw_file = fs::read_to_string(args.src).map_err(|e| format!("{e}"))?;
+    let raw_file = fs::read_to_string(&args.src).map_err(|e| format!("{e}"))?;
     let mut code = CodeGenerator::new(&raw_file);
-    code.build_symbol_table()?;
     code.generate()?;
-    fs::write("Prog.hack", code.take_code()).map_err(|e| format!("{e}"))?;
+    let code = code.take_code();
+    if args.listing {
+        // the translator writes the source map of X.asm to X.map
+        let map_path = args.src.with_extension("map");
+        let map = match fs::read_to_string(&map_path) {
+            Ok(map) => Some(SourceMap::parse(&map)?),
+            Err(_) => None,
+        };
+        if map.is_some() {
+            println!("[info] using source map {:?}", map_path);
+        }
+        let listing = listing(&raw_file, &code, map.as_ref())?;
+        fs::write("Prog.lst", listing).map_err(|e| format!("{e}"))?;
+    }
+    fs::write("Prog.hack", code).map_err(|e| format!("{e}"))?;
     println!("[info] done");
     Ok(())
 }
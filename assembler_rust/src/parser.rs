@@ -86,8 +86,8 @@ impl<'a> Parser<'a> {
             ))));
         }
 
-        let _ = self.validate_symbol(&address)?;
-        let _ = self.peek_token().map_or(Ok(()), |t| {
+        self.validate_symbol(&address)?;
+        self.peek_token().map_or(Ok(()), |t| {
             t.expect_one_of(&[
                 TokenType::WhiteSpace,
                 TokenType::Newline,
@@ -102,7 +102,7 @@ impl<'a> Parser<'a> {
         // expect a string token.
         let label = self.expect_read(TokenType::Text)?;
         // validate the label's syntax
-        let _ = self.validate_symbol(&label)?;
+        self.validate_symbol(&label)?;
         // expect a closing parenthesis
         let _ = self
             .take_token()
@@ -148,7 +148,7 @@ impl<'a> Parser<'a> {
         }
 
         // must not begin with a digit
-        if label.chars().next().unwrap().is_digit(10) {
+        if label.chars().next().unwrap().is_ascii_digit() {
             return Err("label cannot begin with a digit".to_string());
         }
 
@@ -156,9 +156,11 @@ impl<'a> Parser<'a> {
     }
 
     fn skip_to_next_instruction(&self) {
-        self.skip_while(|toke| match toke.get_type() {
-            TokenType::WhiteSpace | TokenType::Newline | TokenType::Comment => true,
-            _ => false,
+        self.skip_while(|toke| {
+            matches!(
+                toke.get_type(),
+                TokenType::WhiteSpace | TokenType::Newline | TokenType::Comment
+            )
         })
     }
 
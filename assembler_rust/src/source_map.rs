@@ -0,0 +1,152 @@
+use std::fmt;
+
+/// where an instruction comes from in the vm source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: usize,
+    pub function: Option<String>,
+    pub command: String,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} {}", self.file, self.line, self.command)
+    }
+}
+
+/// the location of the instruction at a ROM address, and the line it is on in the .asm file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceEntry {
+    pub address: u16,
+    pub asm_line: usize,
+    pub location: SourceLocation,
+}
+
+/// maps ROM addresses of a translated program back to the vm source. The text form has one tab
+/// separated entry per instruction: address, asm line, file, line, function (`-` outside of
+/// functions) and the command as written in the source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    entries: Vec<SourceEntry>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// entries have to be pushed in address order.
+    pub fn push(&mut self, entry: SourceEntry) {
+        debug_assert!(self
+            .entries
+            .last()
+            .is_none_or(|last| last.address < entry.address));
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[SourceEntry] {
+        &self.entries
+    }
+
+    pub fn lookup(&self, address: u16) -> Option<&SourceEntry> {
+        self.entries
+            .binary_search_by_key(&address, |entry| entry.address)
+            .ok()
+            .map(|index| &self.entries[index])
+    }
+
+    pub fn parse(source: &str) -> Result<SourceMap, String> {
+        let mut map = SourceMap::new();
+        for (number, line) in source.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || format!("invalid source map entry on line {}", number + 1);
+            let fields = line.splitn(6, '\t').collect::<Vec<&str>>();
+            let [address, asm_line, file, vm_line, function, command] = fields[..] else {
+                return Err(invalid());
+            };
+            let address = address.parse::<u16>().map_err(|_| invalid())?;
+            if map
+                .entries
+                .last()
+                .is_some_and(|last| last.address >= address)
+            {
+                return Err(invalid());
+            }
+            map.entries.push(SourceEntry {
+                address,
+                asm_line: asm_line.parse().map_err(|_| invalid())?,
+                location: SourceLocation {
+                    file: file.to_string(),
+                    line: vm_line.parse().map_err(|_| invalid())?,
+                    function: (function != "-").then(|| function.to_string()),
+                    command: command.to_string(),
+                },
+            });
+        }
+        Ok(map)
+    }
+}
+
+impl fmt::Display for SourceMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let location = &entry.location;
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                entry.address,
+                entry.asm_line,
+                location.file,
+                location.line,
+                location.function.as_deref().unwrap_or("-"),
+                location.command
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(address: u16, asm_line: usize, function: Option<&str>) -> SourceEntry {
+        SourceEntry {
+            address,
+            asm_line,
+            location: SourceLocation {
+                file: "Main.vm".to_string(),
+                line: 42,
+                function: function.map(|f| f.to_string()),
+                command: "call Math.multiply 2".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_source_map_round_trip() {
+        let mut map = SourceMap::new();
+        map.push(entry(3, 10, Some("Main.main")));
+        map.push(entry(4, 11, None));
+        let text = map.to_string();
+        assert_eq!(
+            text,
+            "3\t10\tMain.vm\t42\tMain.main\tcall Math.multiply 2\n4\t11\tMain.vm\t42\t-\tcall Math.multiply 2\n"
+        );
+        assert_eq!(SourceMap::parse(&text), Ok(map.clone()));
+        assert_eq!(
+            map.lookup(3).map(|e| e.location.to_string()),
+            Some("Main.vm:42 call Math.multiply 2".to_string())
+        );
+        assert_eq!(map.lookup(5), None);
+    }
+
+    #[test]
+    fn test_source_map_invalid() {
+        assert!(SourceMap::parse("3\t10\tMain.vm\n").is_err());
+        assert!(SourceMap::parse("4\t1\tA.vm\t1\t-\tadd\n3\t2\tA.vm\t2\t-\tadd\n").is_err());
+    }
+}
@@ -71,8 +71,7 @@ impl<'a> Tokenizer<'a> {
             src: self.src,
             pos: self.pos,
         };
-        let token = tokenizer.next_token();
-        token
+        tokenizer.next_token()
     }
 
     pub fn get_slice(&self, start: usize, end: usize) -> &'a str {
@@ -129,7 +128,7 @@ impl<'a> Tokenizer<'a> {
             return self.comment_token();
         }
 
-        return self.text_token();
+        self.text_token()
     }
 
     fn text_token(&mut self) -> Option<Token> {
@@ -170,10 +169,7 @@ impl<'a> Tokenizer<'a> {
 
     fn is_comment(&self) -> bool {
         // check if the next two chars are "//" for a comment
-        match self.slice_to(2) {
-            Some("//") => true,
-            _ => false,
-        }
+        matches!(self.slice_to(2), Some("//"))
     }
 
     fn is_newline(&self) -> bool {
@@ -238,8 +234,8 @@ impl<'a> Tokenizer<'a> {
     }
 }
 
-/// Tests for the Tokenizer
-/// These tests are not exhaustive, but they should cover the basic functionality
+// Tests for the Tokenizer
+// These tests are not exhaustive, but they should cover the basic functionality
 
 #[cfg(test)]
 mod test {
@@ -1,5 +1,6 @@
 use crate::alu::Alu;
 use crate::instruction::Instruction;
+use crate::native::NativeCalls;
 use crate::ram::Ram;
 
 const MAX_MEMORY: usize = 32_768; // 32KB the size of the hack computer memory.
@@ -15,6 +16,7 @@ pub struct Chipset {
     pc: usize,
     d_reg: i16,
     a_reg: u16,
+    native: Option<NativeCalls>,
 }
 
 impl Chipset {
@@ -26,10 +28,36 @@ impl Chipset {
             pc: 0,
             d_reg: 0,
             a_reg: 0,
+            native: None,
         }
     }
 
+    /// serves the calls to OS functions `native` knows the entry points of natively, a jump
+    /// to one of them completes the whole call in a single cycle.
+    pub fn with_native_calls(mut self, native: NativeCalls) -> Self {
+        self.native = Some(native);
+        self
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn a_register(&self) -> u16 {
+        self.a_reg
+    }
+
+    pub fn d_register(&self) -> i16 {
+        self.d_reg
+    }
+
     pub fn run_next_instruction(&mut self) {
+        if let Some(native) = &mut self.native {
+            if let Some(return_address) = native.call(self.pc, &self.ram) {
+                self.pc = return_address;
+                return;
+            }
+        }
         let instruction = self.fetch_instruction();
 
         if instruction.is_address() {
@@ -2,8 +2,10 @@ use crate::chipset::Chipset;
 use crate::cpu_thread::CpuThread;
 use crate::events::{CpuThreadMessage, MainThreadMessage};
 use crate::keyboard::Keyboard;
+use crate::native::NativeCalls;
 use crate::ram::Ram;
 use crate::screen::{Dimension, HackScreenBuffer, Scaler};
+use assembler_rust::source_map::SourceMap;
 use pixels::{Pixels, SurfaceTexture};
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread::JoinHandle;
@@ -14,11 +16,14 @@ use winit::event::WindowEvent;
 use winit::event_loop::ActiveEventLoop;
 use winit::window::{Window, WindowAttributes, WindowId};
 
+const TITLE: &str = "Hack Emulator";
+
 pub struct ComputerConstants {
     screen_location: (usize, usize),
     keyboard_location: usize,
     ram_size: usize,
     rom_size: usize,
+    #[allow(dead_code)]
     screen_update_interval: Duration,
 }
 
@@ -38,6 +43,9 @@ pub struct ComputerOptions {
     pub max_cycles: Option<u32>,
     pub config: ComputerConstants,
     pub screen_dimensions: Dimension,
+    /// run the OS functions natively when the source map shows where they start, see
+    /// `native::NativeCalls`. Off, the OS runs as the hack code it was translated to.
+    pub native_os: bool,
 }
 
 impl Default for ComputerOptions {
@@ -46,6 +54,7 @@ impl Default for ComputerOptions {
             max_cycles: None,
             config: ComputerConstants::default(),
             screen_dimensions: Dimension::default(),
+            native_os: true,
         }
     }
 }
@@ -53,6 +62,7 @@ impl Default for ComputerOptions {
 pub struct Computer {
     ram: Ram,
     rom: Vec<u16>,
+    #[allow(dead_code)]
     max_cycles: Option<u32>,
     constants: ComputerConstants,
     screen_dimensions: Dimension,
@@ -62,6 +72,9 @@ pub struct Computer {
     cpu_thread: Option<JoinHandle<()>>,
     pixels: Option<Pixels>,
     keyboard: Keyboard,
+    source_map: Option<SourceMap>,
+    native_os: bool,
+    title: String,
 }
 
 impl Computer {
@@ -80,6 +93,9 @@ impl Computer {
             tx: None,
             cpu_thread: None,
             keyboard,
+            source_map: None,
+            native_os: options.native_os,
+            title: TITLE.to_string(),
         }
     }
 
@@ -87,9 +103,15 @@ impl Computer {
         self.rom[..rom.len()].copy_from_slice(&rom);
     }
 
+    /// with a source map of the program the window title shows the vm command being executed,
+    /// and the OS functions it locates run natively unless turned off in the options.
+    pub fn load_source_map(&mut self, source_map: SourceMap) {
+        self.source_map = Some(source_map);
+    }
+
     fn init_window(&mut self, event_loop: &ActiveEventLoop) -> Result<(), String> {
         let attributes = WindowAttributes::default()
-            .with_title("Hack Emulator")
+            .with_title(TITLE)
             .with_inner_size(LogicalSize::new(
                 self.screen_dimensions.logical_width() as f32,
                 self.screen_dimensions.logical_height() as f32,
@@ -116,7 +138,14 @@ impl Computer {
         let rom = self.rom.clone();
         let (tx_cpu, rx_cpu) = std::sync::mpsc::channel();
         let (tx_main, rx_main) = std::sync::mpsc::channel();
-        let chipset = Chipset::new(rom, ram);
+        let mut chipset = Chipset::new(rom, ram);
+        if let Some(map) = self.source_map.as_ref().filter(|_| self.native_os) {
+            let native = NativeCalls::new(map);
+            if !native.is_empty() {
+                println!("Running {} OS functions natively", native.len());
+            }
+            chipset = chipset.with_native_calls(native);
+        }
         let cpu = CpuThread::new(chipset, rx_main, tx_cpu, Duration::from_millis(10));
         self.cpu_thread = Some(cpu.spawn());
         self.rx = Some(rx_cpu);
@@ -131,6 +160,33 @@ impl Computer {
         Err("No message channel".to_string())
     }
 
+    /// drains the messages of the CPU thread, the last heartbeat tells where the program is.
+    fn handle_cpu_messages(&mut self) {
+        let Some(rx) = &self.rx else {
+            return;
+        };
+        let mut pc = None;
+        while let Ok(message) = rx.try_recv() {
+            if let CpuThreadMessage::Heartbeat(address) = message {
+                pc = Some(address);
+            }
+        }
+        let location = pc.and_then(|pc| {
+            let map = self.source_map.as_ref()?;
+            map.lookup(u16::try_from(pc).ok()?)
+        });
+        let title = match location {
+            Some(entry) => format!("{} - {}", TITLE, entry.location),
+            None => return,
+        };
+        if title != self.title {
+            if let Some(window) = &self.window {
+                window.set_title(&title);
+            }
+            self.title = title;
+        }
+    }
+
     fn render_hack_screen(&mut self) -> Result<(), String> {
         let (start, end) = self.constants.screen_location;
         let screen_words = end - start;
@@ -202,6 +258,7 @@ impl ApplicationHandler for Computer {
 
                 // Draw.
                 // println!("Redraw requested");
+                self.handle_cpu_messages();
                 if let Err(e) = self.render_hack_screen() {
                     println!("Error rendering pixels: {}", e);
                     let _ = self.send_message(MainThreadMessage::Error);
@@ -127,7 +127,7 @@ impl CpuThread {
         if self.last_hearbeat.elapsed() > self.heartbeat_interval {
             // to-do: lets make this something the main thread asks for. we can keep track of the last time it
             // asked for a heartbeat and if it's been too long, we can send an error message
-            self.try_send_message(CpuThreadMessage::Heartbeat);
+            self.try_send_message(CpuThreadMessage::Heartbeat(self.chipset.pc()));
             self.last_hearbeat = Instant::now();
             match self.rx.try_recv() {
                 Ok(MainThreadMessage::Error) => {
@@ -181,6 +181,7 @@ impl CpuThrottle {
     /// # Example
     ///
     /// ```
+    /// use emulator::cpu_thread::CpuThrottle;
     /// use std::time::Duration;
     /// let throttle = CpuThrottle::new(1_000_000.0, Duration::from_millis(100));
     /// ```
@@ -198,14 +199,13 @@ impl CpuThrottle {
     /// This method should be called periodically (e.g., after executing a batch of instructions)
     /// with the current total instruction count from the CPU emulator.
     ///
-    /// # Arguments
-    ///
-    /// * `current_instruction_count` - The total number of instructions executed by the CPU.
-    ///
     /// # Example
     ///
     /// ```
-    /// throttle.throttle(current_instr_count);
+    /// use emulator::cpu_thread::CpuThrottle;
+    /// use std::time::Duration;
+    /// let mut throttle = CpuThrottle::new(1_000_000.0, Duration::from_millis(100));
+    /// throttle.throttle();
     /// ```
     pub fn throttle(&mut self) {
         let elapsed = Instant::now().duration_since(self.last_poll_time);
@@ -4,8 +4,8 @@ pub enum CpuThreadMessage {
     Finished,
     /// something went wrong with the CPU thread
     Error,
-    /// a heartbeat message to keep the CPU thread alive
-    Heartbeat,
+    /// a heartbeat message to keep the CPU thread alive, with the program counter at the time
+    Heartbeat(usize),
 }
 
 /// The types of events that can be sent by the main thread
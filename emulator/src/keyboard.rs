@@ -1,5 +1,4 @@
 use crate::ram::Ram;
-use std::collections::HashMap;
 use winit::event::KeyEvent;
 use winit::keyboard::{Key, NamedKey};
 
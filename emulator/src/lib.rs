@@ -5,5 +5,7 @@ pub mod cpu_thread;
 pub mod events;
 pub mod instruction;
 pub mod keyboard;
+pub mod native;
+pub mod os;
 pub mod ram;
 pub mod screen;
@@ -1,11 +1,28 @@
+use assembler_rust::source_map::SourceMap;
 use emulator::computer::{Computer, ComputerOptions};
+use std::path::PathBuf;
 use winit::{event_loop::ControlFlow, event_loop::EventLoop};
 
 fn main() {
-    let prog = read_prog("Prog.hack");
-    let options = ComputerOptions::default();
+    let mut path = PathBuf::from("Prog.hack");
+    let mut options = ComputerOptions::default();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            // runs the OS as hack code even where the source map locates its functions
+            "--no-native-os" => options.native_os = false,
+            _ => path = PathBuf::from(arg),
+        }
+    }
+    let prog = read_prog(&path);
     let mut computer = Computer::new(options);
     computer.load_rom(prog);
+    // the vm translator writes the source map of X.asm to X.map
+    if let Ok(map) = std::fs::read_to_string(path.with_extension("map")) {
+        match SourceMap::parse(&map) {
+            Ok(map) => computer.load_source_map(map),
+            Err(e) => eprintln!("Error reading source map: {}", e),
+        }
+    }
     let event_loop = EventLoop::new().unwrap();
     // ControlFlow::Poll continuously runs the event loop, even if the OS hasn't
     // dispatched any events. This is ideal for games and similar applications.
@@ -16,7 +33,7 @@ fn main() {
     }
 }
 
-fn read_prog(path: &str) -> Vec<u16> {
+fn read_prog(path: &PathBuf) -> Vec<u16> {
     let prog = std::fs::read_to_string(path).unwrap();
     read_prog_as_u16(&prog)
 }
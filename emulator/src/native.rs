@@ -0,0 +1,240 @@
+use crate::os::{Os, FUNCTIONS};
+use crate::ram::Ram;
+use assembler_rust::source_map::SourceMap;
+
+const SP: usize = 0;
+const LCL: usize = 1;
+const ARG: usize = 2;
+const THIS: usize = 3;
+const THAT: usize = 4;
+
+/// the OS functions left to their hack code: `Sys.halt` has to keep the program spinning and
+/// `Sys.wait` has to take its time.
+const KEPT: [&str; 2] = ["Sys.halt", "Sys.wait"];
+
+/// the functions that allocate from the heap or give memory back to it. The native free list
+/// knows nothing of the one `Memory.jack` keeps in its statics, so they are only served natively
+/// when the whole allocator is, else every allocation goes through the hack code.
+const ALLOCATION: [&str; 7] = [
+    "Memory.init",
+    "Memory.alloc",
+    "Memory.deAlloc",
+    "Array.new",
+    "Array.dispose",
+    "String.new",
+    "String.dispose",
+];
+
+/// the functions that make up the allocator itself.
+const ALLOCATOR: [&str; 3] = ["Memory.init", "Memory.alloc", "Memory.deAlloc"];
+
+/// serves calls to the OS functions of a translated program with `os::Os` instead of running
+/// their hack code. The entry point of a function is the first ROM address the source map
+/// attributes to it, which is where its label is.
+#[derive(Debug)]
+pub struct NativeCalls {
+    os: Os,
+    /// the index into `os::FUNCTIONS` of the function starting at each ROM address
+    entries: Vec<Option<usize>>,
+}
+
+impl NativeCalls {
+    pub fn new(source_map: &SourceMap) -> Self {
+        let mut entries = Vec::new();
+        let mut seen = Vec::new();
+        for entry in source_map.entries() {
+            let Some(function) = entry.location.function.as_deref() else {
+                continue;
+            };
+            let Some(index) = FUNCTIONS.iter().position(|(name, _)| *name == function) else {
+                continue;
+            };
+            if seen.contains(&index) || KEPT.contains(&function) {
+                continue;
+            }
+            seen.push(index);
+            let address = entry.address as usize;
+            if entries.len() <= address {
+                entries.resize(address + 1, None);
+            }
+            entries[address] = Some(index);
+        }
+        let served = |function: &str| seen.iter().any(|index| FUNCTIONS[*index].0 == function);
+        if !ALLOCATOR.into_iter().all(served) {
+            for entry in entries.iter_mut() {
+                if entry.is_some_and(|index| ALLOCATION.contains(&FUNCTIONS[index].0)) {
+                    *entry = None;
+                }
+            }
+        }
+        Self {
+            os: Os::new(),
+            entries,
+        }
+    }
+
+    /// the number of functions served natively.
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// runs the function starting at `pc` natively, right after the call jumped there, and
+    /// returns to the caller the way the `return` command does. Returns the address the program
+    /// continues at, or `None` if nothing starts at `pc` or the native function fails, in which
+    /// case the hack code runs and reports the error the way the program does. So do a frame or
+    /// arguments that aren't in memory.
+    pub fn call(&mut self, pc: usize, ram: &Ram) -> Option<usize> {
+        let index = (*self.entries.get(pc)?)?;
+        let (name, arity) = FUNCTIONS[index];
+        // the return address and the caller's LCL, ARG, THIS and THAT
+        let saved = (ram.read(LCL) as u16 as usize).checked_sub(5)?;
+        let arg = ram.read(ARG) as u16 as usize;
+        // the return value goes where the first argument is, even for functions without any
+        if saved + 5 > ram.size() || arg + arity.max(1) > ram.size() {
+            return None;
+        }
+        let args = (0..arity).map(|i| ram.read(arg + i)).collect::<Vec<i16>>();
+        let value = self.os.call(ram, name, &args).ok()?;
+
+        let return_address = ram.read(saved) as usize;
+        ram.write(arg, value);
+        ram.write(SP, arg as i16 + 1);
+        for (offset, pointer) in [THAT, THIS, ARG, LCL].into_iter().enumerate() {
+            ram.write(pointer, ram.read(saved + 4 - offset));
+        }
+        Some(return_address)
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+    use assembler_rust::source_map::{SourceEntry, SourceLocation};
+
+    fn entry(address: u16, function: &str) -> SourceEntry {
+        SourceEntry {
+            address,
+            asm_line: address as usize,
+            location: SourceLocation {
+                file: "Math.vm".to_string(),
+                line: 1,
+                function: Some(function.to_string()),
+                command: "push argument 0".to_string(),
+            },
+        }
+    }
+
+    fn source_map() -> SourceMap {
+        let mut map = SourceMap::new();
+        map.push(entry(10, "Math.multiply"));
+        map.push(entry(11, "Math.multiply"));
+        map.push(entry(12, "Main.main"));
+        map.push(entry(13, "Sys.halt"));
+        map
+    }
+
+    #[test]
+    fn test_entry_points() {
+        let mut native = NativeCalls::new(&source_map());
+        let ram = Ram::new(32768);
+        assert_eq!(native.len(), 1);
+        for pc in [11, 12, 13, 100] {
+            assert_eq!(native.call(pc, &ram), None);
+        }
+    }
+
+    /// the frame `call Math.multiply 2` leaves, with the caller's segments at 300, 400, 3000 and
+    /// 4000 and the return address 42.
+    #[test]
+    fn test_call_returns_like_the_vm() {
+        let mut native = NativeCalls::new(&source_map());
+        let ram = Ram::new(32768);
+        for (address, value) in (256..).zip([6, -7, 42, 300, 400, 3000, 4000]) {
+            ram.write(address, value);
+        }
+        ram.write(SP, 263);
+        ram.write(LCL, 263);
+        ram.write(ARG, 256);
+        assert_eq!(native.call(10, &ram), Some(42));
+        let registers = [SP, LCL, ARG, THIS, THAT].map(|address| ram.read(address));
+        assert_eq!(registers, [257, 300, 400, 3000, 4000]);
+        assert_eq!(ram.read(256), -42);
+    }
+
+    #[test]
+    fn test_errors_are_left_to_the_program() {
+        let mut map = SourceMap::new();
+        map.push(entry(5, "Math.divide"));
+        let mut native = NativeCalls::new(&map);
+        let ram = Ram::new(32768);
+        // Math.divide(1, 0)
+        ram.write(256, 1);
+        ram.write(SP, 263);
+        ram.write(LCL, 263);
+        ram.write(ARG, 256);
+        assert_eq!(native.call(5, &ram), None);
+        assert_eq!(ram.read(SP), 263);
+    }
+
+    /// a frame that would start below address 0 is no call the program made, its hack code runs.
+    #[test]
+    fn test_frame_below_memory() {
+        let mut native = NativeCalls::new(&source_map());
+        let ram = Ram::new(32768);
+        ram.write(SP, 4);
+        ram.write(LCL, 4);
+        ram.write(ARG, 0);
+        assert_eq!(native.call(10, &ram), None);
+        assert_eq!(ram.read(SP), 4);
+    }
+
+    /// segment pointers outside of memory, negative ones included, leave the call to the hack
+    /// code too.
+    #[test]
+    fn test_frame_outside_memory() {
+        let mut native = NativeCalls::new(&source_map());
+        let ram = Ram::new(32768);
+        for (lcl, arg) in [(263, -2), (263, 32767), (-3, 256)] {
+            ram.write(SP, 263);
+            ram.write(LCL, lcl);
+            ram.write(ARG, arg);
+            assert_eq!(native.call(10, &ram), None);
+            assert_eq!(ram.read(SP), 263);
+        }
+    }
+
+    /// allocation is only native when the allocator of `Memory` is, so blocks never come from
+    /// two free lists.
+    #[test]
+    fn test_allocation_needs_the_native_allocator() {
+        let mut map = SourceMap::new();
+        map.push(entry(10, "Memory.alloc"));
+        map.push(entry(20, "Array.new"));
+        map.push(entry(30, "String.new"));
+        map.push(entry(40, "String.length"));
+        assert_eq!(NativeCalls::new(&map).len(), 1);
+
+        map.push(entry(50, "Memory.init"));
+        map.push(entry(60, "Memory.deAlloc"));
+        let mut native = NativeCalls::new(&map);
+        assert_eq!(native.len(), 6);
+        // Array.new(2) twice, from the frame of `call Array.new 1`
+        let ram = Ram::new(32768);
+        let mut blocks = Vec::new();
+        for _ in 0..2 {
+            for (address, value) in (256..).zip([2, 42, 300, 400, 3000, 4000]) {
+                ram.write(address, value);
+            }
+            ram.write(SP, 262);
+            ram.write(LCL, 262);
+            ram.write(ARG, 256);
+            assert_eq!(native.call(20, &ram), Some(42));
+            blocks.push(ram.read(256));
+        }
+        assert_eq!(blocks[1] - blocks[0], 3);
+    }
+}
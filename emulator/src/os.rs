@@ -0,0 +1,735 @@
+use crate::ram::Ram;
+
+pub const HEAP_BASE: usize = 2048;
+pub const HEAP_END: usize = 16384;
+pub const SCREEN: usize = 16384;
+pub const KEYBOARD: usize = 24576;
+
+const SCREEN_WIDTH: i16 = 512;
+const SCREEN_HEIGHT: i16 = 256;
+const SCREEN_WORDS: usize = 8192;
+const WORDS_PER_ROW: usize = 32;
+const TEXT_ROWS: usize = 23;
+const TEXT_COLUMNS: usize = 64;
+const CHAR_HEIGHT: usize = 11;
+const NEW_LINE: i16 = 128;
+const BACKSPACE: i16 = 129;
+const DOUBLE_QUOTE: i16 = 34;
+
+/// the Jack OS functions with a native implementation and the number of arguments they take,
+/// methods count `this` as their first argument.
+pub const FUNCTIONS: [(&str, usize); 44] = [
+    ("Math.init", 0),
+    ("Math.multiply", 2),
+    ("Math.divide", 2),
+    ("Math.min", 2),
+    ("Math.max", 2),
+    ("Math.abs", 1),
+    ("Math.sqrt", 1),
+    ("Memory.init", 0),
+    ("Memory.peek", 1),
+    ("Memory.poke", 2),
+    ("Memory.alloc", 1),
+    ("Memory.deAlloc", 1),
+    ("Array.new", 1),
+    ("Array.dispose", 1),
+    ("String.new", 1),
+    ("String.dispose", 1),
+    ("String.length", 1),
+    ("String.charAt", 2),
+    ("String.setCharAt", 3),
+    ("String.appendChar", 2),
+    ("String.eraseLastChar", 1),
+    ("String.intValue", 1),
+    ("String.setInt", 2),
+    ("String.newLine", 0),
+    ("String.backSpace", 0),
+    ("String.doubleQuote", 0),
+    ("Output.init", 0),
+    ("Output.moveCursor", 2),
+    ("Output.printChar", 1),
+    ("Output.printString", 1),
+    ("Output.printInt", 1),
+    ("Output.println", 0),
+    ("Output.backSpace", 0),
+    ("Screen.init", 0),
+    ("Screen.clearScreen", 0),
+    ("Screen.setColor", 1),
+    ("Screen.drawPixel", 2),
+    ("Screen.drawLine", 4),
+    ("Screen.drawRectangle", 4),
+    ("Screen.drawCircle", 3),
+    ("Keyboard.init", 0),
+    ("Keyboard.keyPressed", 0),
+    ("Sys.halt", 0),
+    ("Sys.wait", 1),
+];
+
+/// the bitmaps of the printable characters 32..=126, one byte per row of the 8x11 frame with the
+/// leftmost pixel in the lowest bit.
+const FONT: [[u8; CHAR_HEIGHT]; 95] = [
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],           // space
+    [12, 30, 30, 30, 12, 12, 0, 12, 12, 0, 0],   // !
+    [54, 54, 20, 0, 0, 0, 0, 0, 0, 0, 0],        // "
+    [0, 18, 18, 63, 18, 18, 63, 18, 18, 0, 0],   // #
+    [12, 30, 51, 3, 30, 48, 51, 30, 12, 12, 0],  // $
+    [0, 0, 35, 51, 24, 12, 6, 51, 49, 0, 0],     // %
+    [12, 30, 30, 12, 54, 27, 27, 27, 54, 0, 0],  // &
+    [12, 12, 6, 0, 0, 0, 0, 0, 0, 0, 0],         // '
+    [24, 12, 6, 6, 6, 6, 6, 12, 24, 0, 0],       // (
+    [6, 12, 24, 24, 24, 24, 24, 12, 6, 0, 0],    // )
+    [0, 0, 0, 51, 30, 63, 30, 51, 0, 0, 0],      // *
+    [0, 0, 0, 12, 12, 63, 12, 12, 0, 0, 0],      // +
+    [0, 0, 0, 0, 0, 0, 0, 12, 12, 6, 0],         // ,
+    [0, 0, 0, 0, 0, 63, 0, 0, 0, 0, 0],          // -
+    [0, 0, 0, 0, 0, 0, 0, 12, 12, 0, 0],         // .
+    [0, 0, 32, 48, 24, 12, 6, 3, 1, 0, 0],       // /
+    [12, 30, 51, 51, 51, 51, 51, 30, 12, 0, 0],  // 0
+    [12, 14, 15, 12, 12, 12, 12, 12, 63, 0, 0],  // 1
+    [30, 51, 48, 24, 12, 6, 3, 51, 63, 0, 0],    // 2
+    [30, 51, 48, 48, 28, 48, 48, 51, 30, 0, 0],  // 3
+    [16, 24, 28, 26, 25, 63, 24, 24, 60, 0, 0],  // 4
+    [63, 3, 3, 31, 48, 48, 48, 51, 30, 0, 0],    // 5
+    [28, 6, 3, 3, 31, 51, 51, 51, 30, 0, 0],     // 6
+    [63, 49, 48, 48, 24, 12, 12, 12, 12, 0, 0],  // 7
+    [30, 51, 51, 51, 30, 51, 51, 51, 30, 0, 0],  // 8
+    [30, 51, 51, 51, 62, 48, 48, 24, 14, 0, 0],  // 9
+    [0, 0, 12, 12, 0, 0, 12, 12, 0, 0, 0],       // :
+    [0, 0, 12, 12, 0, 0, 12, 12, 6, 0, 0],       // ;
+    [0, 0, 24, 12, 6, 3, 6, 12, 24, 0, 0],       // <
+    [0, 0, 0, 63, 0, 0, 63, 0, 0, 0, 0],         // =
+    [0, 0, 3, 6, 12, 24, 12, 6, 3, 0, 0],        // >
+    [30, 51, 51, 24, 12, 12, 0, 12, 12, 0, 0],   // ?
+    [30, 51, 51, 59, 59, 59, 27, 3, 30, 0, 0],   // @
+    [12, 30, 51, 51, 63, 51, 51, 51, 51, 0, 0],  // A
+    [31, 51, 51, 51, 31, 51, 51, 51, 31, 0, 0],  // B
+    [28, 54, 35, 3, 3, 3, 35, 54, 28, 0, 0],     // C
+    [15, 27, 51, 51, 51, 51, 51, 27, 15, 0, 0],  // D
+    [63, 51, 35, 11, 15, 11, 35, 51, 63, 0, 0],  // E
+    [63, 51, 35, 11, 15, 11, 3, 3, 3, 0, 0],     // F
+    [28, 54, 35, 3, 59, 51, 51, 54, 44, 0, 0],   // G
+    [51, 51, 51, 51, 63, 51, 51, 51, 51, 0, 0],  // H
+    [30, 12, 12, 12, 12, 12, 12, 12, 30, 0, 0],  // I
+    [60, 24, 24, 24, 24, 24, 27, 27, 14, 0, 0],  // J
+    [51, 51, 51, 27, 15, 27, 51, 51, 51, 0, 0],  // K
+    [3, 3, 3, 3, 3, 3, 35, 51, 63, 0, 0],        // L
+    [33, 51, 63, 63, 51, 51, 51, 51, 51, 0, 0],  // M
+    [51, 51, 55, 55, 63, 59, 59, 51, 51, 0, 0],  // N
+    [30, 51, 51, 51, 51, 51, 51, 51, 30, 0, 0],  // O
+    [31, 51, 51, 51, 31, 3, 3, 3, 3, 0, 0],      // P
+    [30, 51, 51, 51, 51, 51, 63, 59, 30, 48, 0], // Q
+    [31, 51, 51, 51, 31, 27, 51, 51, 51, 0, 0],  // R
+    [30, 51, 51, 6, 28, 48, 51, 51, 30, 0, 0],   // S
+    [63, 63, 45, 12, 12, 12, 12, 12, 30, 0, 0],  // T
+    [51, 51, 51, 51, 51, 51, 51, 51, 30, 0, 0],  // U
+    [51, 51, 51, 51, 51, 30, 30, 12, 12, 0, 0],  // V
+    [51, 51, 51, 51, 51, 63, 63, 63, 18, 0, 0],  // W
+    [51, 51, 30, 30, 12, 30, 30, 51, 51, 0, 0],  // X
+    [51, 51, 51, 51, 30, 12, 12, 12, 30, 0, 0],  // Y
+    [63, 51, 49, 24, 12, 6, 35, 51, 63, 0, 0],   // Z
+    [30, 6, 6, 6, 6, 6, 6, 6, 30, 0, 0],         // [
+    [0, 0, 1, 3, 6, 12, 24, 48, 32, 0, 0],       // \
+    [30, 24, 24, 24, 24, 24, 24, 24, 30, 0, 0],  // ]
+    [8, 28, 54, 0, 0, 0, 0, 0, 0, 0, 0],         // ^
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 63, 0],          // _
+    [6, 12, 24, 0, 0, 0, 0, 0, 0, 0, 0],         // `
+    [0, 0, 0, 14, 24, 30, 27, 27, 54, 0, 0],     // a
+    [3, 3, 3, 15, 27, 51, 51, 51, 30, 0, 0],     // b
+    [0, 0, 0, 30, 51, 3, 3, 51, 30, 0, 0],       // c
+    [48, 48, 48, 60, 54, 51, 51, 51, 30, 0, 0],  // d
+    [0, 0, 0, 30, 51, 63, 3, 51, 30, 0, 0],      // e
+    [28, 54, 38, 6, 15, 6, 6, 6, 15, 0, 0],      // f
+    [0, 0, 30, 51, 51, 51, 62, 48, 51, 30, 0],   // g
+    [3, 3, 3, 27, 55, 51, 51, 51, 51, 0, 0],     // h
+    [12, 12, 0, 14, 12, 12, 12, 12, 30, 0, 0],   // i
+    [48, 48, 0, 56, 48, 48, 48, 48, 51, 30, 0],  // j
+    [3, 3, 3, 51, 27, 15, 15, 27, 51, 0, 0],     // k
+    [14, 12, 12, 12, 12, 12, 12, 12, 30, 0, 0],  // l
+    [0, 0, 0, 29, 63, 43, 43, 43, 43, 0, 0],     // m
+    [0, 0, 0, 29, 51, 51, 51, 51, 51, 0, 0],     // n
+    [0, 0, 0, 30, 51, 51, 51, 51, 30, 0, 0],     // o
+    [0, 0, 0, 30, 51, 51, 51, 31, 3, 3, 0],      // p
+    [0, 0, 0, 30, 51, 51, 51, 62, 48, 48, 0],    // q
+    [0, 0, 0, 29, 55, 51, 3, 3, 7, 0, 0],        // r
+    [0, 0, 0, 30, 51, 6, 24, 51, 30, 0, 0],      // s
+    [4, 6, 6, 15, 6, 6, 6, 54, 28, 0, 0],        // t
+    [0, 0, 0, 27, 27, 27, 27, 27, 54, 0, 0],     // u
+    [0, 0, 0, 51, 51, 51, 51, 30, 12, 0, 0],     // v
+    [0, 0, 0, 51, 51, 51, 63, 63, 18, 0, 0],     // w
+    [0, 0, 0, 51, 30, 12, 12, 30, 51, 0, 0],     // x
+    [0, 0, 0, 51, 51, 51, 62, 48, 24, 15, 0],    // y
+    [0, 0, 0, 63, 27, 12, 6, 51, 63, 0, 0],      // z
+    [56, 12, 12, 12, 7, 12, 12, 12, 56, 0, 0],   // {
+    [12, 12, 12, 12, 12, 12, 12, 12, 12, 0, 0],  // |
+    [7, 12, 12, 12, 56, 12, 12, 12, 7, 0, 0],    // }
+    [38, 45, 25, 0, 0, 0, 0, 0, 0, 0, 0],        // ~
+];
+
+/// the bitmap drawn for characters outside of the printable range.
+const BLACK_SQUARE: [u8; CHAR_HEIGHT] = [63, 63, 63, 63, 63, 63, 63, 63, 63, 0, 0];
+
+/// native implementation of the Jack OS, working directly on the memory of the computer so it
+/// can stand in for the compiled OS classes. Objects use the same layout as the Jack
+/// implementation: a `String` is `[chars, length, maxLength]` and every heap block keeps its size
+/// in the word before it.
+#[derive(Debug)]
+pub struct Os {
+    free_list: Vec<(usize, usize)>,
+    color: bool,
+    cursor: (usize, usize),
+    halted: bool,
+}
+
+impl Default for Os {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Os {
+    pub fn new() -> Self {
+        Self {
+            free_list: vec![(HEAP_BASE, HEAP_END - HEAP_BASE)],
+            color: true,
+            cursor: (0, 0),
+            halted: false,
+        }
+    }
+
+    /// true once `Sys.halt` has been called.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// whether the function has a native implementation.
+    pub fn implements(name: &str) -> bool {
+        FUNCTIONS.iter().any(|(function, _)| *function == name)
+    }
+
+    /// calls the native implementation of an OS function, void functions return 0 like their
+    /// compiled counterparts.
+    pub fn call(&mut self, ram: &Ram, name: &str, args: &[i16]) -> Result<i16, String> {
+        let arity = FUNCTIONS
+            .iter()
+            .find(|(function, _)| *function == name)
+            .map(|(_, arity)| *arity)
+            .ok_or_else(|| format!("no native implementation of {name}"))?;
+        if args.len() != arity {
+            return Err(format!(
+                "{name} expects {arity} arguments, got {}",
+                args.len()
+            ));
+        }
+
+        match name {
+            "Math.init" | "Keyboard.init" | "Screen.init" => Ok(0),
+            "Math.multiply" => Ok(args[0].wrapping_mul(args[1])),
+            "Math.divide" => {
+                if args[1] == 0 {
+                    return Err("Math.divide: division by zero".to_string());
+                }
+                Ok(args[0].wrapping_div(args[1]))
+            }
+            "Math.min" => Ok(args[0].min(args[1])),
+            "Math.max" => Ok(args[0].max(args[1])),
+            "Math.abs" => Ok(args[0].wrapping_abs()),
+            "Math.sqrt" => {
+                if args[0] < 0 {
+                    return Err(
+                        "Math.sqrt: cannot compute square root of a negative number".to_string()
+                    );
+                }
+                Ok(sqrt(args[0]))
+            }
+
+            "Memory.init" => {
+                self.free_list = vec![(HEAP_BASE, HEAP_END - HEAP_BASE)];
+                Ok(0)
+            }
+            "Memory.peek" => read(ram, args[0]),
+            "Memory.poke" => write(ram, args[0], args[1]).map(|_| 0),
+            "Memory.alloc" | "Array.new" => self.alloc(ram, args[0]),
+            "Memory.deAlloc" | "Array.dispose" => self.de_alloc(ram, args[0]).map(|_| 0),
+
+            "String.new" => self.new_string(ram, args[0]),
+            "String.dispose" => {
+                self.de_alloc(ram, read(ram, args[0])?)?;
+                self.de_alloc(ram, args[0])?;
+                Ok(0)
+            }
+            "String.length" => read(ram, args[0].wrapping_add(1)),
+            "String.charAt" => {
+                let address = string_index(ram, args[0], args[1])?;
+                read(ram, address)
+            }
+            "String.setCharAt" => {
+                let address = string_index(ram, args[0], args[1])?;
+                write(ram, address, args[2]).map(|_| 0)
+            }
+            "String.appendChar" => append_char(ram, args[0], args[1]).map(|_| args[0]),
+            "String.eraseLastChar" => {
+                let length = read(ram, args[0].wrapping_add(1))?;
+                if length == 0 {
+                    return Err("String.eraseLastChar: string is empty".to_string());
+                }
+                write(ram, args[0].wrapping_add(1), length - 1).map(|_| 0)
+            }
+            "String.intValue" => Ok(int_value(&read_string(ram, args[0])?)),
+            "String.setInt" => {
+                write(ram, args[0].wrapping_add(1), 0)?;
+                for c in args[1].to_string().chars() {
+                    append_char(ram, args[0], c as i16)?;
+                }
+                Ok(0)
+            }
+            "String.newLine" => Ok(NEW_LINE),
+            "String.backSpace" => Ok(BACKSPACE),
+            "String.doubleQuote" => Ok(DOUBLE_QUOTE),
+
+            "Output.init" => {
+                self.cursor = (0, 0);
+                Ok(0)
+            }
+            "Output.moveCursor" => {
+                let (row, column) = (args[0], args[1]);
+                if !(0..TEXT_ROWS as i16).contains(&row)
+                    || !(0..TEXT_COLUMNS as i16).contains(&column)
+                {
+                    return Err("Output.moveCursor: illegal cursor location".to_string());
+                }
+                self.cursor = (row as usize, column as usize);
+                Ok(0)
+            }
+            "Output.printChar" => self.print_char(ram, args[0]).map(|_| 0),
+            "Output.printString" => {
+                for c in read_string(ram, args[0])? {
+                    self.print_char(ram, c)?;
+                }
+                Ok(0)
+            }
+            "Output.printInt" => {
+                for c in args[0].to_string().chars() {
+                    self.print_char(ram, c as i16)?;
+                }
+                Ok(0)
+            }
+            "Output.println" => {
+                self.println();
+                Ok(0)
+            }
+            "Output.backSpace" => self.back_space(ram).map(|_| 0),
+
+            "Screen.clearScreen" => {
+                for address in SCREEN..SCREEN + SCREEN_WORDS {
+                    ram.write(address, 0);
+                }
+                Ok(0)
+            }
+            "Screen.setColor" => {
+                self.color = args[0] != 0;
+                Ok(0)
+            }
+            "Screen.drawPixel" => {
+                check_pixel("Screen.drawPixel", args[0], args[1])?;
+                self.draw_pixel(ram, args[0], args[1]);
+                Ok(0)
+            }
+            "Screen.drawLine" => {
+                check_pixel("Screen.drawLine", args[0], args[1])?;
+                check_pixel("Screen.drawLine", args[2], args[3])?;
+                self.draw_line(ram, (args[0], args[1]), (args[2], args[3]));
+                Ok(0)
+            }
+            "Screen.drawRectangle" => {
+                let (x1, y1, x2, y2) = (args[0], args[1], args[2], args[3]);
+                check_pixel("Screen.drawRectangle", x1, y1)?;
+                check_pixel("Screen.drawRectangle", x2, y2)?;
+                if x1 > x2 || y1 > y2 {
+                    return Err("Screen.drawRectangle: illegal rectangle coordinates".to_string());
+                }
+                for y in y1..=y2 {
+                    self.draw_line(ram, (x1, y), (x2, y));
+                }
+                Ok(0)
+            }
+            "Screen.drawCircle" => {
+                let (x, y, r) = (args[0], args[1], args[2]);
+                check_pixel("Screen.drawCircle", x, y)?;
+                if !(0..=181).contains(&r)
+                    || x - r < 0
+                    || y - r < 0
+                    || x + r >= SCREEN_WIDTH
+                    || y + r >= SCREEN_HEIGHT
+                {
+                    return Err("Screen.drawCircle: illegal radius".to_string());
+                }
+                for dy in -r..=r {
+                    let half_width = sqrt(r * r - dy * dy);
+                    self.draw_line(ram, (x - half_width, y + dy), (x + half_width, y + dy));
+                }
+                Ok(0)
+            }
+
+            "Keyboard.keyPressed" => Ok(ram.read(KEYBOARD)),
+
+            "Sys.halt" => {
+                self.halted = true;
+                Ok(0)
+            }
+            "Sys.wait" => {
+                if args[0] < 0 {
+                    return Err("Sys.wait: duration must be positive".to_string());
+                }
+                Ok(0)
+            }
+            _ => Err(format!("no native implementation of {name}")),
+        }
+    }
+
+    /// first fit allocation from the free list, the size of the block is stored right before the
+    /// returned address so `deAlloc` knows how much to give back.
+    fn alloc(&mut self, ram: &Ram, size: i16) -> Result<i16, String> {
+        if size <= 0 {
+            return Err("Memory.alloc: allocated memory size must be positive".to_string());
+        }
+        let needed = size as usize + 1;
+        let position = self
+            .free_list
+            .iter()
+            .position(|(_, length)| *length >= needed)
+            .ok_or("Memory.alloc: heap overflow")?;
+        let (base, length) = self.free_list[position];
+        if length == needed {
+            self.free_list.remove(position);
+        } else {
+            self.free_list[position] = (base + needed, length - needed);
+        }
+        ram.write(base, needed as i16);
+        Ok(base as i16 + 1)
+    }
+
+    /// freed blocks are kept sorted by address so allocations prefer the bottom of the heap, and
+    /// merged with the free blocks right before and after them like `Memory.deAlloc` does.
+    fn de_alloc(&mut self, ram: &Ram, address: i16) -> Result<(), String> {
+        if address <= HEAP_BASE as i16 || address as usize >= HEAP_END {
+            return Err(format!("Memory.deAlloc: {address} is not a heap address"));
+        }
+        let base = address as usize - 1;
+        let length = ram.read(base);
+        if length < 2 || base + length as usize > HEAP_END {
+            return Err(format!(
+                "Memory.deAlloc: {address} has an invalid block size"
+            ));
+        }
+        let mut length = length as usize;
+        let position = self.free_list.partition_point(|(start, _)| *start < base);
+        let previous = position.checked_sub(1).map(|i| self.free_list[i]);
+        let next = self.free_list.get(position).copied();
+        if previous.is_some_and(|(start, size)| start + size > base)
+            || next.is_some_and(|(start, _)| base + length > start)
+        {
+            return Err(format!("Memory.deAlloc: {address} is already free"));
+        }
+        if let Some((_, size)) = next.filter(|(start, _)| base + length == *start) {
+            self.free_list.remove(position);
+            length += size;
+        }
+        match previous {
+            Some((start, size)) if start + size == base => {
+                self.free_list[position - 1].1 += length;
+            }
+            _ => self.free_list.insert(position, (base, length)),
+        }
+        Ok(())
+    }
+
+    fn new_string(&mut self, ram: &Ram, max_length: i16) -> Result<i16, String> {
+        if max_length < 0 {
+            return Err("String.new: maximum length must be non-negative".to_string());
+        }
+        let string = self.alloc(ram, 3)?;
+        let chars = self.alloc(ram, max_length.max(1))?;
+        write(ram, string, chars)?;
+        write(ram, string + 1, 0)?;
+        write(ram, string + 2, max_length)?;
+        Ok(string)
+    }
+
+    fn print_char(&mut self, ram: &Ram, c: i16) -> Result<(), String> {
+        match c {
+            NEW_LINE => self.println(),
+            BACKSPACE => self.back_space(ram)?,
+            _ => {
+                self.draw_char(ram, c);
+                self.cursor.1 += 1;
+                if self.cursor.1 == TEXT_COLUMNS {
+                    self.println();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn println(&mut self) {
+        self.cursor = ((self.cursor.0 + 1) % TEXT_ROWS, 0);
+    }
+
+    fn back_space(&mut self, ram: &Ram) -> Result<(), String> {
+        let (row, column) = self.cursor;
+        self.cursor = match (row, column) {
+            (0, 0) => (0, 0),
+            (_, 0) => (row - 1, TEXT_COLUMNS - 1),
+            _ => (row, column - 1),
+        };
+        self.draw_char(ram, ' ' as i16);
+        Ok(())
+    }
+
+    /// two characters share every screen word, even columns use the low byte.
+    fn draw_char(&self, ram: &Ram, c: i16) {
+        let bitmap = if (32..127).contains(&c) {
+            &FONT[c as usize - 32]
+        } else {
+            &BLACK_SQUARE
+        };
+        let (row, column) = self.cursor;
+        for (line, bits) in bitmap.iter().enumerate() {
+            let address = SCREEN + (row * CHAR_HEIGHT + line) * WORDS_PER_ROW + column / 2;
+            let word = ram.read(address) as u16;
+            let word = if column % 2 == 0 {
+                (word & 0xFF00) | *bits as u16
+            } else {
+                (word & 0x00FF) | ((*bits as u16) << 8)
+            };
+            ram.write(address, word as i16);
+        }
+    }
+
+    fn draw_pixel(&self, ram: &Ram, x: i16, y: i16) {
+        let address = SCREEN + y as usize * WORDS_PER_ROW + x as usize / 16;
+        let mask = (1u16 << (x % 16)) as i16;
+        let word = ram.read(address);
+        let word = if self.color {
+            word | mask
+        } else {
+            word & !mask
+        };
+        ram.write(address, word);
+    }
+
+    /// Bresenham's line algorithm, both ends are drawn.
+    fn draw_line(&self, ram: &Ram, from: (i16, i16), to: (i16, i16)) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let step_x = if x < to.0 { 1 } else { -1 };
+        let step_y = if y < to.1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            self.draw_pixel(ram, x, y);
+            if (x, y) == to {
+                return;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
+
+fn check_address(address: i16) -> Result<usize, String> {
+    if address < 0 {
+        return Err(format!("illegal memory address {address}"));
+    }
+    Ok(address as usize)
+}
+
+fn read(ram: &Ram, address: i16) -> Result<i16, String> {
+    Ok(ram.read(check_address(address)?))
+}
+
+fn write(ram: &Ram, address: i16, value: i16) -> Result<(), String> {
+    ram.write(check_address(address)?, value);
+    Ok(())
+}
+
+fn check_pixel(function: &str, x: i16, y: i16) -> Result<(), String> {
+    if !(0..SCREEN_WIDTH).contains(&x) || !(0..SCREEN_HEIGHT).contains(&y) {
+        return Err(format!("{function}: illegal pixel coordinates ({x}, {y})"));
+    }
+    Ok(())
+}
+
+/// the address of the j-th character of a string, checked against its length.
+fn string_index(ram: &Ram, string: i16, j: i16) -> Result<i16, String> {
+    let length = read(ram, string.wrapping_add(1))?;
+    if !(0..length).contains(&j) {
+        return Err(format!(
+            "string index {j} out of bounds for length {length}"
+        ));
+    }
+    Ok(read(ram, string)?.wrapping_add(j))
+}
+
+fn append_char(ram: &Ram, string: i16, c: i16) -> Result<(), String> {
+    let length = read(ram, string.wrapping_add(1))?;
+    if length >= read(ram, string.wrapping_add(2))? {
+        return Err("String.appendChar: string is full".to_string());
+    }
+    write(ram, read(ram, string)?.wrapping_add(length), c)?;
+    write(ram, string.wrapping_add(1), length + 1)
+}
+
+/// the characters of a string object.
+pub fn read_string(ram: &Ram, string: i16) -> Result<Vec<i16>, String> {
+    let chars = read(ram, string)?;
+    let length = read(ram, string.wrapping_add(1))?;
+    (0..length)
+        .map(|j| read(ram, chars.wrapping_add(j)))
+        .collect()
+}
+
+/// the integer value of the leading digits of a string, with an optional minus sign.
+fn int_value(chars: &[i16]) -> i16 {
+    let (negative, digits) = match chars.first() {
+        Some(&c) if c == '-' as i16 => (true, &chars[1..]),
+        _ => (false, chars),
+    };
+    let value = digits
+        .iter()
+        .take_while(|c| (('0' as i16)..=('9' as i16)).contains(c))
+        .fold(0i16, |value, c| {
+            value.wrapping_mul(10).wrapping_add(c - '0' as i16)
+        });
+    if negative {
+        value.wrapping_neg()
+    } else {
+        value
+    }
+}
+
+fn sqrt(x: i16) -> i16 {
+    let mut root = 0i32;
+    while (root + 1) * (root + 1) <= x as i32 {
+        root += 1;
+    }
+    root as i16
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn setup() -> (Os, Ram) {
+        (Os::new(), Ram::new(32768))
+    }
+
+    #[test]
+    fn test_math() {
+        let (mut os, ram) = setup();
+        assert_eq!(os.call(&ram, "Math.multiply", &[-7, 6]), Ok(-42));
+        assert_eq!(os.call(&ram, "Math.divide", &[-42, 5]), Ok(-8));
+        assert_eq!(os.call(&ram, "Math.sqrt", &[32767]), Ok(181));
+        assert_eq!(os.call(&ram, "Math.abs", &[-3]), Ok(3));
+        assert!(os.call(&ram, "Math.divide", &[1, 0]).is_err());
+        assert!(os.call(&ram, "Math.max", &[1]).is_err());
+    }
+
+    #[test]
+    fn test_alloc_reuses_freed_blocks() {
+        let (mut os, ram) = setup();
+        let first = os.call(&ram, "Memory.alloc", &[20]).unwrap();
+        let second = os.call(&ram, "Memory.alloc", &[3]).unwrap();
+        assert_eq!(first, HEAP_BASE as i16 + 1);
+        assert_eq!(second, first + 21);
+        os.call(&ram, "Memory.deAlloc", &[first]).unwrap();
+        assert_eq!(os.call(&ram, "Memory.alloc", &[20]), Ok(first));
+        assert!(os.call(&ram, "Memory.alloc", &[0]).is_err());
+    }
+
+    /// a heap filled with blocks and freed in any order is one free block again.
+    #[test]
+    fn test_freed_blocks_merge() {
+        let (mut os, ram) = setup();
+        let blocks = (0..100)
+            .map(|_| os.call(&ram, "Memory.alloc", &[142]).unwrap())
+            .collect::<Vec<i16>>();
+        assert!(os.call(&ram, "Memory.alloc", &[142]).is_err());
+        for block in blocks
+            .iter()
+            .step_by(2)
+            .chain(blocks.iter().skip(1).step_by(2))
+        {
+            os.call(&ram, "Memory.deAlloc", &[*block]).unwrap();
+        }
+        let whole = (HEAP_END - HEAP_BASE - 1) as i16;
+        assert_eq!(os.call(&ram, "Memory.alloc", &[whole]), Ok(blocks[0]));
+    }
+
+    #[test]
+    fn test_bad_frees() {
+        let (mut os, ram) = setup();
+        let first = os.call(&ram, "Memory.alloc", &[10]).unwrap();
+        let second = os.call(&ram, "Memory.alloc", &[10]).unwrap();
+        os.call(&ram, "Memory.deAlloc", &[first]).unwrap();
+        assert!(os.call(&ram, "Memory.deAlloc", &[first]).is_err());
+        // a size word overwritten by the program
+        ram.write(second as usize - 1, -5);
+        assert!(os.call(&ram, "Memory.deAlloc", &[second]).is_err());
+        ram.write(second as usize - 1, 11);
+        assert_eq!(os.call(&ram, "Memory.deAlloc", &[second]), Ok(0));
+        assert_eq!(os.call(&ram, "Memory.alloc", &[21]), Ok(first));
+    }
+
+    #[test]
+    fn test_strings() {
+        let (mut os, ram) = setup();
+        let string = os.call(&ram, "String.new", &[6]).unwrap();
+        for c in "-123".chars() {
+            os.call(&ram, "String.appendChar", &[string, c as i16])
+                .unwrap();
+        }
+        assert_eq!(os.call(&ram, "String.length", &[string]), Ok(4));
+        assert_eq!(os.call(&ram, "String.intValue", &[string]), Ok(-123));
+        os.call(&ram, "String.setInt", &[string, 4567]).unwrap();
+        assert_eq!(os.call(&ram, "String.charAt", &[string, 3]), Ok('7' as i16));
+        assert!(os.call(&ram, "String.charAt", &[string, 4]).is_err());
+        os.call(&ram, "String.setInt", &[string, -32767]).unwrap();
+        assert!(os
+            .call(&ram, "String.appendChar", &[string, 'x' as i16])
+            .is_err());
+    }
+
+    #[test]
+    fn test_print_char() {
+        let (mut os, ram) = setup();
+        os.call(&ram, "Output.printChar", &['A' as i16]).unwrap();
+        os.call(&ram, "Output.printChar", &['A' as i16]).unwrap();
+        assert_eq!(ram.read(SCREEN) as u16, (12 << 8) | 12);
+        assert_eq!(ram.read(SCREEN + 4 * WORDS_PER_ROW) as u16, (63 << 8) | 63);
+        os.call(&ram, "Output.backSpace", &[]).unwrap();
+        assert_eq!(ram.read(SCREEN), 12);
+    }
+
+    #[test]
+    fn test_draw() {
+        let (mut os, ram) = setup();
+        os.call(&ram, "Screen.drawLine", &[0, 0, 31, 0]).unwrap();
+        assert_eq!((ram.read(SCREEN), ram.read(SCREEN + 1)), (-1, -1));
+        os.call(&ram, "Screen.setColor", &[0]).unwrap();
+        os.call(&ram, "Screen.drawPixel", &[15, 0]).unwrap();
+        assert_eq!(ram.read(SCREEN), i16::MAX);
+        os.call(&ram, "Screen.setColor", &[-1]).unwrap();
+        os.call(&ram, "Screen.drawRectangle", &[16, 1, 31, 2])
+            .unwrap();
+        assert_eq!(ram.read(SCREEN + 2 * WORDS_PER_ROW + 1), -1);
+        assert!(os.call(&ram, "Screen.drawPixel", &[512, 0]).is_err());
+        assert!(os.call(&ram, "Screen.drawCircle", &[10, 10, 11]).is_err());
+    }
+}
@@ -17,6 +17,11 @@ impl Ram {
         }
     }
 
+    /// the number of words of the memory
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
     /// reads a value from the memory at the given address
     pub fn read(&self, address: usize) -> i16 {
         let memory = self.memory.lock().unwrap();
@@ -0,0 +1,64 @@
+use crate::code::Emit;
+use std::{env::args, path::PathBuf};
+
+pub struct CompilerArgs {
+    pub src: PathBuf,
+    pub emit: Emit,
+    /// `--jack-ext`, compile the extended dialect, see `parser::ext`
+    pub extensions: bool,
+    pub out_dir: Option<PathBuf>,
+}
+
+impl CompilerArgs {
+    /// a single positional argument, the .jack file or a directory of .jack files, followed by
+    /// any options.
+    pub fn parse() -> Result<CompilerArgs, String> {
+        let mut src = None;
+        let mut emit = Emit::default();
+        let mut extensions = false;
+        let mut out_dir = None;
+        for arg in args().skip(1) {
+            match arg.as_str() {
+                flag if flag.starts_with("--emit=") => {
+                    emit = Emit::parse(&flag["--emit=".len()..])?
+                }
+                "--jack-ext" => extensions = true,
+                flag if flag.starts_with("--out=") => {
+                    out_dir = Some(PathBuf::from(&flag["--out=".len()..]))
+                }
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+                _ => src = Some(PathBuf::from(arg)),
+            }
+        }
+        let src = src.ok_or("missing source file")?;
+        Ok(CompilerArgs {
+            src,
+            emit,
+            extensions,
+            out_dir,
+        })
+    }
+}
+
+pub struct FormatterArgs {
+    pub src: PathBuf,
+    /// `--check`, only report the files that aren't formatted
+    pub check: bool,
+}
+
+impl FormatterArgs {
+    /// the .jack file or directory of .jack files, and `--check`.
+    pub fn parse() -> Result<FormatterArgs, String> {
+        let mut src = None;
+        let mut check = false;
+        for arg in args().skip(1) {
+            match arg.as_str() {
+                "--check" => check = true,
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+                _ => src = Some(PathBuf::from(arg)),
+            }
+        }
+        let src = src.ok_or("missing source file")?;
+        Ok(FormatterArgs { src, check })
+    }
+}
@@ -0,0 +1,242 @@
+/// a byte range of the source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// the span from the start of this one to the end of `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identifier {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Char,
+    Boolean,
+    Class(Identifier),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Class {
+    pub name: Identifier,
+    pub vars: Vec<ClassVarDec>,
+    pub subroutines: Vec<Subroutine>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassVarKind {
+    Static,
+    Field,
+}
+
+/// `static int x, y;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassVarDec {
+    pub kind: ClassVarKind,
+    pub ty: Type,
+    pub names: Vec<Identifier>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubroutineKind {
+    Constructor,
+    Function,
+    Method,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subroutine {
+    pub kind: SubroutineKind,
+    /// `None` for `void`
+    pub return_type: Option<Type>,
+    pub name: Identifier,
+    pub parameters: Vec<Parameter>,
+    pub locals: Vec<VarDec>,
+    pub statements: Vec<Statement>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub ty: Type,
+    pub name: Identifier,
+}
+
+/// `var int x, y;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarDec {
+    pub ty: Type,
+    pub names: Vec<Identifier>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Let {
+        target: Identifier,
+        /// `let a[i] = ...`
+        index: Option<Expression>,
+        value: Expression,
+        span: Span,
+    },
+    If {
+        condition: Expression,
+        then: Vec<Statement>,
+        otherwise: Option<Vec<Statement>>,
+        span: Span,
+    },
+    While {
+        condition: Expression,
+        body: Vec<Statement>,
+        span: Span,
+    },
+    Do {
+        call: SubroutineCall,
+        span: Span,
+    },
+    Return {
+        value: Option<Expression>,
+        span: Span,
+    },
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Let { span, .. }
+            | Statement::If { span, .. }
+            | Statement::While { span, .. }
+            | Statement::Do { span, .. }
+            | Statement::Return { span, .. } => *span,
+        }
+    }
+}
+
+/// jack has no operator precedence, the operators apply from left to right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    pub first: Term,
+    pub rest: Vec<(BinaryOp, Term)>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    Lt,
+    Gt,
+    Eq,
+}
+
+impl BinaryOp {
+    pub fn from_symbol(symbol: char) -> Option<BinaryOp> {
+        let op = match symbol {
+            '+' => BinaryOp::Add,
+            '-' => BinaryOp::Sub,
+            '*' => BinaryOp::Mul,
+            '/' => BinaryOp::Div,
+            '&' => BinaryOp::And,
+            '|' => BinaryOp::Or,
+            '<' => BinaryOp::Lt,
+            '>' => BinaryOp::Gt,
+            '=' => BinaryOp::Eq,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            BinaryOp::Add => '+',
+            BinaryOp::Sub => '-',
+            BinaryOp::Mul => '*',
+            BinaryOp::Div => '/',
+            BinaryOp::And => '&',
+            BinaryOp::Or => '|',
+            BinaryOp::Lt => '<',
+            BinaryOp::Gt => '>',
+            BinaryOp::Eq => '=',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+impl UnaryOp {
+    pub fn symbol(&self) -> char {
+        match self {
+            UnaryOp::Neg => '-',
+            UnaryOp::Not => '~',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordConstant {
+    True,
+    False,
+    Null,
+    This,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Integer(u16, Span),
+    String(String, Span),
+    Keyword(KeywordConstant, Span),
+    Variable(Identifier),
+    /// `a[i]`
+    Index(Identifier, Box<Expression>, Span),
+    Call(SubroutineCall),
+    Parenthesized(Box<Expression>, Span),
+    Unary(UnaryOp, Box<Term>, Span),
+}
+
+impl Term {
+    pub fn span(&self) -> Span {
+        match self {
+            Term::Integer(_, span)
+            | Term::String(_, span)
+            | Term::Keyword(_, span)
+            | Term::Index(_, _, span)
+            | Term::Parenthesized(_, span)
+            | Term::Unary(_, _, span) => *span,
+            Term::Variable(identifier) => identifier.span,
+            Term::Call(call) => call.span,
+        }
+    }
+}
+
+/// `name(...)`, or `receiver.name(...)` where the receiver is a variable or a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubroutineCall {
+    pub receiver: Option<Identifier>,
+    pub name: Identifier,
+    pub arguments: Vec<Expression>,
+    pub span: Span,
+}
@@ -0,0 +1,11 @@
+use jack_compiler_rust::lsp::serve;
+use std::io::{stdin, stdout};
+use std::process::exit;
+
+/// a language server for jack, talking the protocol over stdin and stdout.
+fn main() {
+    if let Err(e) = serve(&mut stdin().lock(), &mut stdout().lock()) {
+        eprintln!("[err] {e}");
+        exit(1);
+    }
+}
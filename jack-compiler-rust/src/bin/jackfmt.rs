@@ -0,0 +1,34 @@
+use jack_compiler_rust::args::FormatterArgs;
+use jack_compiler_rust::format::format_files;
+use std::process::exit;
+
+fn main() {
+    let args = match FormatterArgs::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            println!("[err] {e}");
+            println!("usage: jackfmt <source file | directory> [--check]");
+            exit(2);
+        }
+    };
+    match format_files(&args.src, args.check) {
+        // a check fails when anything would change, so scripts can enforce the style
+        Ok(changed) if args.check => {
+            for file_name in &changed {
+                println!("{file_name} is not formatted");
+            }
+            if !changed.is_empty() {
+                exit(1);
+            }
+        }
+        Ok(changed) => {
+            for file_name in changed {
+                println!("formatted {file_name}");
+            }
+        }
+        Err(e) => {
+            println!("[err] {e}");
+            exit(2);
+        }
+    }
+}
@@ -0,0 +1,679 @@
+use crate::ast::{
+    BinaryOp, Class, ClassVarKind, Expression, Identifier, KeywordConstant, Span, Statement,
+    Subroutine, SubroutineCall, SubroutineKind, Term, Type, UnaryOp,
+};
+use crate::diagnostic::Diagnostic;
+use crate::os;
+use crate::parser::Parser;
+use crate::symbols::{Kind, SymbolTable};
+use std::collections::HashMap;
+use std::fmt;
+
+/// checks every class of a program against the others and the OS, returns the diagnostics of
+/// each class in the order of `classes`. Classes of the program replace OS classes of the same
+/// name, so the OS itself can be checked.
+pub fn check_program(classes: &[Class]) -> Vec<Vec<Diagnostic>> {
+    let mut known = HashMap::new();
+    for (_, src) in os::OS {
+        let class = Parser::parse(src).expect("the OS parses");
+        known.insert(class.name.name.clone(), signatures(&class));
+    }
+    for class in classes {
+        known.insert(class.name.name.clone(), signatures(class));
+    }
+    classes
+        .iter()
+        .map(|class| {
+            let mut checker = Checker {
+                classes: &known,
+                class,
+                symbols: SymbolTable::new(),
+                subroutine: None,
+                diagnostics: Vec::new(),
+            };
+            checker.class();
+            checker.diagnostics
+        })
+        .collect()
+}
+
+/// how a subroutine can be called.
+#[derive(Debug, Clone)]
+struct Signature {
+    kind: SubroutineKind,
+    return_type: Option<Type>,
+    parameters: Vec<Type>,
+}
+
+/// the subroutines of a class by name.
+type Signatures = HashMap<String, Signature>;
+
+fn signatures(class: &Class) -> Signatures {
+    class
+        .subroutines
+        .iter()
+        .map(|subroutine| {
+            let signature = Signature {
+                kind: subroutine.kind,
+                return_type: subroutine.return_type.clone(),
+                parameters: subroutine
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.ty.clone())
+                    .collect(),
+            };
+            (subroutine.name.name.clone(), signature)
+        })
+        .collect()
+}
+
+/// the type of an expression. `Unknown` comes from array elements and from anything already
+/// reported, it's compatible with every type so one mistake isn't reported twice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Ty {
+    Int,
+    Char,
+    Boolean,
+    Object(String),
+    Null,
+    Void,
+    Unknown,
+}
+
+impl Ty {
+    fn of(ty: &Type) -> Ty {
+        match ty {
+            Type::Int => Ty::Int,
+            Type::Char => Ty::Char,
+            Type::Boolean => Ty::Boolean,
+            Type::Class(name) => Ty::Object(name.name.clone()),
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Char | Ty::Unknown) || self.is_array()
+    }
+
+    fn is_array(&self) -> bool {
+        matches!(self, Ty::Object(name) if name == "Array")
+    }
+
+    /// whether a value of type `value` can be stored in a variable of this type. Arrays are
+    /// plain addresses in jack, they mix with any object and with integers.
+    fn accepts(&self, value: &Ty) -> bool {
+        match (self, value) {
+            (Ty::Unknown, _) | (_, Ty::Unknown) => true,
+            (a, b) if a == b => true,
+            (Ty::Int | Ty::Char, Ty::Int | Ty::Char) => true,
+            (Ty::Object(_), Ty::Null) => true,
+            (Ty::Object(_), Ty::Object(_))
+            | (Ty::Object(_), Ty::Int)
+            | (Ty::Int, Ty::Object(_)) => self.is_array() || value.is_array(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Int => f.write_str("int"),
+            Ty::Char => f.write_str("char"),
+            Ty::Boolean => f.write_str("boolean"),
+            Ty::Object(name) => f.write_str(name),
+            Ty::Null => f.write_str("null"),
+            Ty::Void => f.write_str("void"),
+            Ty::Unknown => f.write_str("unknown"),
+        }
+    }
+}
+
+struct Checker<'a> {
+    classes: &'a HashMap<String, Signatures>,
+    class: &'a Class,
+    symbols: SymbolTable,
+    /// the subroutine being checked
+    subroutine: Option<&'a Subroutine>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn class(&mut self) {
+        for var in &self.class.vars {
+            self.ty(&var.ty);
+            let kind = match var.kind {
+                ClassVarKind::Static => Kind::Static,
+                ClassVarKind::Field => Kind::Field,
+            };
+            for name in &var.names {
+                self.define(name, &var.ty, kind);
+            }
+        }
+        for subroutine in &self.class.subroutines {
+            self.subroutine(subroutine);
+        }
+    }
+
+    fn subroutine(&mut self, subroutine: &'a Subroutine) {
+        self.subroutine = Some(subroutine);
+        self.symbols.start_subroutine();
+        if let Some(ty) = &subroutine.return_type {
+            self.ty(ty);
+        }
+        if subroutine.kind == SubroutineKind::Constructor
+            && !matches!(&subroutine.return_type, Some(Type::Class(name)) if name.name == self.class.name.name)
+        {
+            self.error(
+                subroutine.name.span,
+                format!("a constructor must return {}", self.class.name.name),
+            );
+        }
+        for parameter in &subroutine.parameters {
+            self.ty(&parameter.ty);
+            self.define(&parameter.name, &parameter.ty, Kind::Argument);
+        }
+        for local in &subroutine.locals {
+            self.ty(&local.ty);
+            for name in &local.names {
+                self.define(name, &local.ty, Kind::Local);
+            }
+        }
+        for statement in &subroutine.statements {
+            self.statement(statement);
+        }
+        if !returns(&subroutine.statements) {
+            self.error(
+                subroutine.name.span,
+                format!(
+                    "{} doesn't end with a return statement",
+                    subroutine.name.name
+                ),
+            );
+        }
+    }
+
+    fn define(&mut self, name: &Identifier, ty: &Type, kind: Kind) {
+        if !self.symbols.define(&name.name, ty.clone(), kind) {
+            self.error(name.span, format!("{} is already declared", name.name));
+        }
+    }
+
+    /// reports class types that name no class.
+    fn ty(&mut self, ty: &Type) {
+        if let Type::Class(name) = ty {
+            if !self.classes.contains_key(&name.name) {
+                self.error(name.span, format!("unknown class {}", name.name));
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let {
+                target,
+                index,
+                value,
+                ..
+            } => {
+                let target_ty = self.variable(target);
+                let value_ty = self.expression(value);
+                match index {
+                    Some(index) => {
+                        if !target_ty.is_number() {
+                            self.error(
+                                target.span,
+                                format!("{} has type {}, not Array", target.name, target_ty),
+                            );
+                        }
+                        self.number(index);
+                    }
+                    None if !target_ty.accepts(&value_ty) => self.error(
+                        value.span,
+                        format!(
+                            "cannot assign {} to {} of type {}",
+                            value_ty, target.name, target_ty
+                        ),
+                    ),
+                    None => {}
+                }
+            }
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                self.condition(condition);
+                for statement in then.iter().chain(otherwise.iter().flatten()) {
+                    self.statement(statement);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.condition(condition);
+                for statement in body {
+                    self.statement(statement);
+                }
+            }
+            Statement::Do { call, .. } => {
+                self.call(call);
+            }
+            Statement::Return { value, span } => {
+                let Some(subroutine) = self.subroutine else {
+                    return;
+                };
+                let name = &subroutine.name.name;
+                match (&subroutine.return_type, value) {
+                    (Some(ty), Some(value)) => {
+                        let expected = Ty::of(ty);
+                        let actual = self.expression(value);
+                        if !expected.accepts(&actual) {
+                            self.error(
+                                value.span,
+                                format!("{} must return {}, found {}", name, expected, actual),
+                            );
+                        }
+                    }
+                    (Some(ty), None) => self.error(
+                        *span,
+                        format!("{} must return a value of type {}", name, Ty::of(ty)),
+                    ),
+                    (None, Some(value)) => {
+                        self.expression(value);
+                        self.error(
+                            value.span,
+                            format!("{} is void, it can't return a value", name),
+                        );
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+    }
+
+    /// conditions are booleans, or numbers where any bit set counts as true.
+    fn condition(&mut self, condition: &Expression) {
+        let ty = self.expression(condition);
+        if ty != Ty::Boolean && !ty.is_number() {
+            self.error(
+                condition.span,
+                format!("condition must be boolean, found {}", ty),
+            );
+        }
+    }
+
+    /// checks an expression that must be a number, like an array index.
+    fn number(&mut self, expression: &Expression) {
+        let ty = self.expression(expression);
+        if !ty.is_number() {
+            self.error(expression.span, format!("expected int, found {}", ty));
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Ty {
+        let mut left = self.term(&expression.first);
+        let mut span = expression.first.span();
+        for (op, term) in &expression.rest {
+            let right = self.term(term);
+            span = span.to(term.span());
+            left = self.binary(*op, left, right, span);
+        }
+        left
+    }
+
+    fn binary(&mut self, op: BinaryOp, left: Ty, right: Ty, span: Span) -> Ty {
+        let symbol = op.symbol();
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                if !left.is_number() || !right.is_number() {
+                    self.mismatch(symbol, &left, &right, span);
+                }
+                Ty::Int
+            }
+            BinaryOp::And | BinaryOp::Or => match (&left, &right) {
+                (Ty::Boolean, Ty::Boolean)
+                | (Ty::Boolean, Ty::Unknown)
+                | (Ty::Unknown, Ty::Boolean) => Ty::Boolean,
+                _ if left.is_number() && right.is_number() => Ty::Int,
+                (Ty::Unknown, _) | (_, Ty::Unknown) => Ty::Unknown,
+                _ => {
+                    self.mismatch(symbol, &left, &right, span);
+                    Ty::Unknown
+                }
+            },
+            BinaryOp::Lt | BinaryOp::Gt => {
+                if !left.is_number() || !right.is_number() {
+                    self.mismatch(symbol, &left, &right, span);
+                }
+                Ty::Boolean
+            }
+            BinaryOp::Eq => {
+                if !left.accepts(&right) && !right.accepts(&left) {
+                    self.mismatch(symbol, &left, &right, span);
+                }
+                Ty::Boolean
+            }
+        }
+    }
+
+    fn mismatch(&mut self, symbol: char, left: &Ty, right: &Ty, span: Span) {
+        self.error(
+            span,
+            format!("can't apply {} to {} and {}", symbol, left, right),
+        );
+    }
+
+    fn term(&mut self, term: &Term) -> Ty {
+        match term {
+            Term::Integer(..) => Ty::Int,
+            Term::String(..) => Ty::Object("String".to_string()),
+            Term::Keyword(constant, span) => match constant {
+                KeywordConstant::True | KeywordConstant::False => Ty::Boolean,
+                KeywordConstant::Null => Ty::Null,
+                KeywordConstant::This => {
+                    if self.in_function() {
+                        self.error(*span, "this can't be used in a function");
+                        return Ty::Unknown;
+                    }
+                    Ty::Object(self.class.name.name.clone())
+                }
+            },
+            Term::Variable(name) => self.variable(name),
+            Term::Index(name, index, _) => {
+                let ty = self.variable(name);
+                if !ty.is_number() {
+                    self.error(
+                        name.span,
+                        format!("{} has type {}, not Array", name.name, ty),
+                    );
+                }
+                self.number(index);
+                Ty::Unknown
+            }
+            Term::Call(call) => match self.call(call) {
+                Ty::Void => {
+                    self.error(
+                        call.span,
+                        format!("{} is void, it has no value", call_name(call)),
+                    );
+                    Ty::Unknown
+                }
+                ty => ty,
+            },
+            Term::Parenthesized(expression, _) => self.expression(expression),
+            Term::Unary(op, term, span) => {
+                let ty = self.term(term);
+                match op {
+                    UnaryOp::Not if ty == Ty::Boolean => Ty::Boolean,
+                    _ if ty.is_number() => Ty::Int,
+                    _ => {
+                        self.error(*span, format!("can't apply {} to {}", op.symbol(), ty));
+                        Ty::Unknown
+                    }
+                }
+            }
+        }
+    }
+
+    /// the type of a variable, fields only exist in constructors and methods.
+    fn variable(&mut self, name: &Identifier) -> Ty {
+        let Some(symbol) = self.symbols.get(&name.name) else {
+            self.error(name.span, format!("undeclared variable {}", name.name));
+            return Ty::Unknown;
+        };
+        let ty = Ty::of(&symbol.ty);
+        if symbol.kind == Kind::Field && self.in_function() {
+            self.error(
+                name.span,
+                format!("field {} can't be used in a function", name.name),
+            );
+        }
+        ty
+    }
+
+    /// checks a call and its arguments, returns the type of its value.
+    fn call(&mut self, call: &SubroutineCall) -> Ty {
+        let callee = self.callee(call);
+        let mut arguments = Vec::with_capacity(call.arguments.len());
+        for argument in &call.arguments {
+            arguments.push((self.expression(argument), argument.span));
+        }
+        let Some(signature) = callee else {
+            return Ty::Unknown;
+        };
+
+        if arguments.len() != signature.parameters.len() {
+            self.error(
+                call.span,
+                format!(
+                    "{} expects {} argument{}, found {}",
+                    call_name(call),
+                    signature.parameters.len(),
+                    if signature.parameters.len() == 1 {
+                        ""
+                    } else {
+                        "s"
+                    },
+                    arguments.len()
+                ),
+            );
+        } else {
+            for ((ty, span), parameter) in arguments.iter().zip(&signature.parameters) {
+                let expected = Ty::of(parameter);
+                if !expected.accepts(ty) {
+                    self.error(*span, format!("expected {}, found {}", expected, ty));
+                }
+            }
+        }
+        match &signature.return_type {
+            Some(ty) => Ty::of(ty),
+            None => Ty::Void,
+        }
+    }
+
+    /// the subroutine a call refers to, if it exists and is called the way its kind allows.
+    fn callee(&mut self, call: &SubroutineCall) -> Option<Signature> {
+        let (class, on_object) = match &call.receiver {
+            None => {
+                let class = self.class.name.name.clone();
+                (class, None)
+            }
+            Some(receiver) => match self.symbols.get(&receiver.name) {
+                Some(symbol) => {
+                    let ty = Ty::of(&symbol.ty);
+                    self.variable(receiver);
+                    let Ty::Object(class) = ty else {
+                        self.error(
+                            receiver.span,
+                            format!("{} has type {}, not a class", receiver.name, ty),
+                        );
+                        return None;
+                    };
+                    (class, Some(true))
+                }
+                None if self.classes.contains_key(&receiver.name) => {
+                    (receiver.name.clone(), Some(false))
+                }
+                None => {
+                    self.error(
+                        receiver.span,
+                        format!("unknown class or variable {}", receiver.name),
+                    );
+                    return None;
+                }
+            },
+        };
+        // an object of an unknown class was already reported where the variable is declared
+        let signatures = self.classes.get(&class)?;
+        let Some(signature) = signatures.get(&call.name.name).cloned() else {
+            self.error(
+                call.name.span,
+                format!("{} has no subroutine {}", class, call.name.name),
+            );
+            return None;
+        };
+
+        let is_method = signature.kind == SubroutineKind::Method;
+        match on_object {
+            None if is_method && self.in_function() => self.error(
+                call.name.span,
+                format!("method {} can't be called from a function", call.name.name),
+            ),
+            Some(false) if is_method => self.error(
+                call.name.span,
+                format!(
+                    "{} is a method, it must be called on an object",
+                    call_name(call)
+                ),
+            ),
+            Some(true) if !is_method => self.error(
+                call.name.span,
+                format!(
+                    "{}.{} is not a method, call it on the class",
+                    class, call.name.name
+                ),
+            ),
+            _ => {}
+        }
+        Some(signature)
+    }
+
+    fn in_function(&self) -> bool {
+        self.subroutine
+            .is_some_and(|subroutine| subroutine.kind == SubroutineKind::Function)
+    }
+
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::new(span, message));
+    }
+}
+
+/// whether the statements end in a return on every path.
+fn returns(statements: &[Statement]) -> bool {
+    match statements.last() {
+        Some(Statement::Return { .. }) => true,
+        Some(Statement::If {
+            then,
+            otherwise: Some(otherwise),
+            ..
+        }) => returns(then) && returns(otherwise),
+        _ => false,
+    }
+}
+
+/// `f` or `x.f` as written in the source.
+fn call_name(call: &SubroutineCall) -> String {
+    match &call.receiver {
+        Some(receiver) => format!("{}.{}", receiver.name, call.name.name),
+        None => call.name.name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    /// the rendered diagnostics of the first class, checked together with the others.
+    fn check(sources: &[&str]) -> Vec<String> {
+        let classes = sources
+            .iter()
+            .map(|src| Parser::parse(src).unwrap())
+            .collect::<Vec<Class>>();
+        check_program(&classes)[0]
+            .iter()
+            .map(|diagnostic| diagnostic.render(sources[0]))
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_program() {
+        let main = "class Main { function void main() { var Point p; var int x;
+            let p = Point.new(1, 2); let x = p.getX() + 3; do Output.printInt(x);
+            if (x > 2) { let p = null; } return; } }";
+        let point = "class Point { field int x, y;
+            constructor Point new(int ax, int ay) { let x = ax; let y = ay; return this; }
+            method int getX() { return x; } }";
+        assert_eq!(check(&[main, point]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_undefined_names() {
+        let src = "class Main { function void main() { var Foo f;
+            let y = 1; do Bar.baz(); do Main.nope(); do q.f(); return; } }";
+        assert_eq!(
+            check(&[src]),
+            vec![
+                "line 1, column 41: unknown class Foo",
+                "line 2, column 17: undeclared variable y",
+                "line 2, column 27: unknown class or variable Bar",
+                "line 2, column 46: Main has no subroutine nope",
+                "line 2, column 57: unknown class or variable q",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        let src = "class Main { field int n;
+            function void main() { var Main m;
+              do Math.max(1); do Main.run(); do m.main(); do run(); let n = this;
+              return; }
+            method void run() { return; } }";
+        assert_eq!(
+            check(&[src]),
+            vec![
+                "line 3, column 18: Math.max expects 2 arguments, found 1",
+                "line 3, column 39: Main.run is a method, it must be called on an object",
+                "line 3, column 51: Main.main is not a method, call it on the class",
+                "line 3, column 62: method run can't be called from a function",
+                "line 3, column 73: field n can't be used in a function",
+                "line 3, column 77: this can't be used in a function",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_returns() {
+        let src = "class Main {
+            function int f(int x) { if (x) { return 1; } }
+            function int g(int x) { if (x) { return 1; } else { return; } }
+            function void h() { let x = Main.h(); return 2; }
+            constructor int new() { return 0; } }";
+        assert_eq!(
+            check(&[src]),
+            vec![
+                "line 2, column 26: f doesn't end with a return statement",
+                "line 3, column 65: g must return a value of type int",
+                "line 4, column 37: undeclared variable x",
+                "line 4, column 41: Main.h is void, it has no value",
+                "line 4, column 58: h is void, it can't return a value",
+                "line 5, column 29: a constructor must return Main",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_mismatches() {
+        let src = "class Main { function void main() { var boolean b; var int i; var String s;
+              let i = true; let b = 1 < s; let s = i; let b = ~s; let b[0] = 1;
+              if (s) { do Output.printString(i); }
+              let s = s.charAt(0) + 1; let b = (b & i) | s; let b = i = s; let b = b = false;
+              return; } }";
+        assert_eq!(
+            check(&[src]),
+            vec![
+                "line 2, column 23: cannot assign boolean to i of type int",
+                "line 2, column 37: can't apply < to int and String",
+                "line 2, column 52: cannot assign int to s of type String",
+                "line 2, column 63: can't apply ~ to String",
+                "line 2, column 71: b has type boolean, not Array",
+                "line 3, column 19: condition must be boolean, found String",
+                "line 3, column 46: expected String, found int",
+                "line 4, column 23: cannot assign int to s of type String",
+                "line 4, column 49: can't apply & to boolean and int",
+                "line 4, column 69: can't apply = to int and String",
+            ]
+        );
+    }
+}
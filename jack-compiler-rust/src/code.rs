@@ -0,0 +1,182 @@
+use crate::check::check_program;
+use crate::codegen::compile_class;
+use crate::diagnostic::Diagnostic;
+use crate::parser::Parser;
+use crate::token::Tokenizer;
+use crate::xml::{class_xml, tokens_xml};
+use std::path::{Path, PathBuf};
+
+/// what the compiler writes for every class.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Emit {
+    /// the compiled class, a .vm file
+    #[default]
+    Vm,
+    /// the tokens as xml, a `T.xml` file
+    Tokens,
+    /// the parse tree as xml, a .xml file
+    Xml,
+}
+
+impl Emit {
+    pub fn parse(emit: &str) -> Result<Emit, String> {
+        match emit {
+            "vm" => Ok(Emit::Vm),
+            "tokens" => Ok(Emit::Tokens),
+            "xml" => Ok(Emit::Xml),
+            other => Err(format!(
+                "unknown output kind {}, expected vm, tokens or xml",
+                other
+            )),
+        }
+    }
+
+    /// the output file of the class, `Main.jack` gives `Main.vm`, `MainT.xml` or `Main.xml`.
+    pub fn output_name(&self, class_file: &str) -> String {
+        let stem = class_file.strip_suffix(".jack").unwrap_or(class_file);
+        match self {
+            Emit::Vm => format!("{}.vm", stem),
+            Emit::Tokens => format!("{}T.xml", stem),
+            Emit::Xml => format!("{}.xml", stem),
+        }
+    }
+}
+
+/// the output `emit` asks for from the source of a class. A class compiled on its own isn't
+/// checked, that needs the other classes of the program, see `emit_sources`. `extensions`
+/// compiles the `--jack-ext` dialect.
+pub fn emit_source(src: &str, emit: Emit, extensions: bool) -> Result<String, String> {
+    let parse = || Parser::with_extensions(src, extensions).into_class();
+    match emit {
+        Emit::Vm => Ok(compile_class(src, &parse()?)?.to_string()),
+        Emit::Tokens => {
+            let tokens = Tokenizer::new(src).with_extensions(extensions).tokens()?;
+            Ok(tokens_xml(src, &tokens))
+        }
+        Emit::Xml => Ok(class_xml(&parse()?)),
+    }
+}
+
+/// the outputs for the (file name, source) pairs of a program, in order. Parse trees and vm
+/// code are only produced once no class has a syntax error, and vm code once the classes check
+/// against each other. The error shows every diagnostic with the source it's about.
+pub fn emit_sources(
+    files: &[(String, String)],
+    emit: Emit,
+    extensions: bool,
+) -> Result<Vec<String>, String> {
+    if emit == Emit::Tokens {
+        return files
+            .iter()
+            .map(|(file_name, src)| {
+                emit_source(src, emit, extensions).map_err(|e| format!("{}: {}", file_name, e))
+            })
+            .collect();
+    }
+
+    let mut classes = Vec::with_capacity(files.len());
+    let mut syntax_errors = Vec::with_capacity(files.len());
+    for (_, src) in files {
+        let (class, errors) = Parser::with_extensions(src, extensions).into_class_with_errors();
+        classes.extend(class);
+        syntax_errors.push(errors);
+    }
+    if syntax_errors.iter().any(|errors| !errors.is_empty()) {
+        return Err(render_diagnostics(files, &syntax_errors));
+    }
+    if emit == Emit::Xml {
+        return Ok(classes.iter().map(class_xml).collect());
+    }
+
+    let diagnostics = check_program(&classes);
+    if diagnostics
+        .iter()
+        .any(|diagnostics| !diagnostics.is_empty())
+    {
+        return Err(render_diagnostics(files, &diagnostics));
+    }
+    classes
+        .iter()
+        .zip(files)
+        .map(|(class, (file_name, src))| {
+            compile_class(src, class)
+                .map(|file| file.to_string())
+                .map_err(|e| format!("{}: {}", file_name, e))
+        })
+        .collect()
+}
+
+/// the diagnostics of every file with their source snippets, separated by blank lines.
+fn render_diagnostics(files: &[(String, String)], diagnostics: &[Vec<Diagnostic>]) -> String {
+    files
+        .iter()
+        .zip(diagnostics)
+        .flat_map(|((file_name, src), diagnostics)| {
+            diagnostics
+                .iter()
+                .map(move |diagnostic| diagnostic.render_snippet(file_name, src))
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+/// compiles a .jack file, or every .jack file of a directory. Each output is written to
+/// `out_dir`, or next to its source without one. A single class is still checked against the
+/// other classes of its directory.
+pub fn compile(
+    path: &Path,
+    emit: Emit,
+    extensions: bool,
+    out_dir: Option<&Path>,
+) -> Result<(), String> {
+    let dir = match path.parent() {
+        _ if path.is_dir() => path,
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let targets = read_sources(path)?;
+    let program = match emit == Emit::Vm && !path.is_dir() {
+        true => read_sources(dir)?,
+        false => targets.clone(),
+    };
+    let outputs = emit_sources(&program, emit, extensions)?;
+    for ((file_name, _), output) in program.iter().zip(outputs) {
+        if !targets.iter().any(|(target, _)| target == file_name) {
+            continue;
+        }
+        let output_path = out_dir.unwrap_or(dir).join(emit.output_name(file_name));
+        std::fs::write(&output_path, output).map_err(|e| format!("{e}"))?;
+    }
+    Ok(())
+}
+
+/// reads the file, or all the .jack files of the directory sorted by name, as (file name,
+/// source) pairs.
+pub fn read_sources(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let paths = if path.is_dir() {
+        let mut paths = std::fs::read_dir(path)
+            .map_err(|e| format!("{e}"))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "jack"))
+            .collect::<Vec<PathBuf>>();
+        if paths.is_empty() {
+            return Err(format!("no .jack files found in {:?}", path));
+        }
+        paths.sort();
+        paths
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut sources = Vec::with_capacity(paths.len());
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .ok_or("invalid file name structure")?
+            .to_string();
+        let src = std::fs::read_to_string(&path).map_err(|e| format!("{:?}: {e}", path))?;
+        sources.push((file_name, src));
+    }
+    Ok(sources)
+}
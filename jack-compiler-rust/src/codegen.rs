@@ -0,0 +1,488 @@
+use crate::ast::{
+    BinaryOp, Class, ClassVarKind, Expression, Identifier, KeywordConstant, Span, Statement,
+    Subroutine, SubroutineCall, SubroutineKind, Term, Type, UnaryOp,
+};
+use crate::diagnostic::Diagnostic;
+use crate::symbols::{Kind, SymbolTable};
+use vm_translator_rust::parser::Segment;
+use vm_translator_rust::program::{Operation, Statement as Vm, VmFile, VmFunction};
+
+/// lowers a class to the vm file `Class.vm`, one vm function per subroutine. `src` is the source
+/// the class was parsed from, for the line and column of errors.
+pub fn compile_class(src: &str, class: &Class) -> Result<VmFile, String> {
+    let mut symbols = SymbolTable::new();
+    for var in &class.vars {
+        let kind = match var.kind {
+            ClassVarKind::Static => Kind::Static,
+            ClassVarKind::Field => Kind::Field,
+        };
+        for name in &var.names {
+            if !symbols.define(&name.name, var.ty.clone(), kind) {
+                return Err(error(
+                    src,
+                    name.span,
+                    &format!("{} is already declared", name.name),
+                ));
+            }
+        }
+    }
+
+    let mut file = VmFile::new(&format!("{}.vm", class.name.name));
+    for subroutine in &class.subroutines {
+        let mut generator = CodeGen {
+            src,
+            class,
+            symbols: &mut symbols,
+            function: VmFunction::new(&format!("{}.{}", class.name.name, subroutine.name.name), 0),
+            if_count: 0,
+            while_count: 0,
+        };
+        generator.subroutine(subroutine)?;
+        file.functions.push(generator.function);
+    }
+    Ok(file)
+}
+
+fn error(src: &str, span: Span, message: &str) -> String {
+    Diagnostic::new(span, message).render(src)
+}
+
+/// generates the body of one subroutine. Labels are numbered per subroutine, `IF_FALSEn` and
+/// `IF_ENDn` for the nth if statement and `WHILE_EXPn` and `WHILE_ENDn` for the nth loop.
+struct CodeGen<'a> {
+    src: &'a str,
+    class: &'a Class,
+    symbols: &'a mut SymbolTable,
+    function: VmFunction,
+    if_count: usize,
+    while_count: usize,
+}
+
+impl CodeGen<'_> {
+    fn subroutine(&mut self, subroutine: &Subroutine) -> Result<(), String> {
+        self.symbols.start_subroutine();
+        if subroutine.kind == SubroutineKind::Method {
+            // `this` is a keyword, it can't clash with a parameter
+            let ty = Type::Class(self.class.name.clone());
+            self.symbols.define("this", ty, Kind::Argument);
+        }
+        for parameter in &subroutine.parameters {
+            self.define(&parameter.name, &parameter.ty, Kind::Argument)?;
+        }
+        for local in &subroutine.locals {
+            for name in &local.names {
+                self.define(name, &local.ty, Kind::Local)?;
+            }
+        }
+        self.function.locals = self.symbols.count(Kind::Local);
+
+        match subroutine.kind {
+            SubroutineKind::Constructor => {
+                let fields = self.symbols.count(Kind::Field);
+                self.emit(Vm::Push(Segment::Constant, fields));
+                self.call("Memory.alloc", 1);
+                self.emit(Vm::Pop(Segment::Pointer, 0));
+            }
+            SubroutineKind::Method => {
+                self.emit(Vm::Push(Segment::Argument, 0));
+                self.emit(Vm::Pop(Segment::Pointer, 0));
+            }
+            SubroutineKind::Function => {}
+        }
+        self.statements(&subroutine.statements)
+    }
+
+    fn define(&mut self, name: &Identifier, ty: &Type, kind: Kind) -> Result<(), String> {
+        match self.symbols.define(&name.name, ty.clone(), kind) {
+            true => Ok(()),
+            false => Err(self.error(name.span, &format!("{} is already declared", name.name))),
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Result<(), String> {
+        statements
+            .iter()
+            .try_for_each(|statement| self.statement(statement))
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), String> {
+        match statement {
+            Statement::Let {
+                target,
+                index: None,
+                value,
+                ..
+            } => {
+                self.expression(value)?;
+                let (segment, index) = self.variable(target)?;
+                self.emit(Vm::Pop(segment, index));
+            }
+            Statement::Let {
+                target,
+                index: Some(index),
+                value,
+                ..
+            } => {
+                // the value is computed before `that` is aimed at the element, it may use
+                // arrays too
+                self.element_address(target, index)?;
+                self.expression(value)?;
+                self.emit(Vm::Pop(Segment::Temp, 0));
+                self.emit(Vm::Pop(Segment::Pointer, 1));
+                self.emit(Vm::Push(Segment::Temp, 0));
+                self.emit(Vm::Pop(Segment::That, 0));
+            }
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                let n = self.if_count;
+                self.if_count += 1;
+                self.expression(condition)?;
+                self.operation(Operation::Not);
+                self.emit(Vm::IfGoto(format!("IF_FALSE{}", n)));
+                self.statements(then)?;
+                match otherwise {
+                    Some(otherwise) => {
+                        self.emit(Vm::Goto(format!("IF_END{}", n)));
+                        self.emit(Vm::Label(format!("IF_FALSE{}", n)));
+                        self.statements(otherwise)?;
+                        self.emit(Vm::Label(format!("IF_END{}", n)));
+                    }
+                    None => self.emit(Vm::Label(format!("IF_FALSE{}", n))),
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                let n = self.while_count;
+                self.while_count += 1;
+                self.emit(Vm::Label(format!("WHILE_EXP{}", n)));
+                self.expression(condition)?;
+                self.operation(Operation::Not);
+                self.emit(Vm::IfGoto(format!("WHILE_END{}", n)));
+                self.statements(body)?;
+                self.emit(Vm::Goto(format!("WHILE_EXP{}", n)));
+                self.emit(Vm::Label(format!("WHILE_END{}", n)));
+            }
+            Statement::Do { call, .. } => {
+                self.subroutine_call(call)?;
+                // the value every call leaves on the stack
+                self.emit(Vm::Pop(Segment::Temp, 0));
+            }
+            Statement::Return { value, .. } => {
+                match value {
+                    Some(value) => self.expression(value)?,
+                    // void subroutines still return a value to pop
+                    None => self.emit(Vm::Push(Segment::Constant, 0)),
+                }
+                self.emit(Vm::Return);
+            }
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<(), String> {
+        self.term(&expression.first)?;
+        for (op, term) in &expression.rest {
+            self.term(term)?;
+            match op {
+                BinaryOp::Add => self.operation(Operation::Add),
+                BinaryOp::Sub => self.operation(Operation::Sub),
+                BinaryOp::Mul => self.call("Math.multiply", 2),
+                BinaryOp::Div => self.call("Math.divide", 2),
+                BinaryOp::And => self.operation(Operation::And),
+                BinaryOp::Or => self.operation(Operation::Or),
+                BinaryOp::Lt => self.operation(Operation::Lt),
+                BinaryOp::Gt => self.operation(Operation::Gt),
+                BinaryOp::Eq => self.operation(Operation::Eq),
+            }
+        }
+        Ok(())
+    }
+
+    fn term(&mut self, term: &Term) -> Result<(), String> {
+        match term {
+            Term::Integer(value, _) => self.emit(Vm::Push(Segment::Constant, *value)),
+            Term::String(value, _) => {
+                let length = value.chars().count() as u16;
+                self.emit(Vm::Push(Segment::Constant, length));
+                self.call("String.new", 1);
+                for c in value.chars() {
+                    self.emit(Vm::Push(Segment::Constant, c as u16));
+                    self.call("String.appendChar", 2);
+                }
+            }
+            Term::Keyword(constant, _) => match constant {
+                KeywordConstant::True => {
+                    self.emit(Vm::Push(Segment::Constant, 0));
+                    self.operation(Operation::Not);
+                }
+                KeywordConstant::False | KeywordConstant::Null => {
+                    self.emit(Vm::Push(Segment::Constant, 0))
+                }
+                KeywordConstant::This => self.emit(Vm::Push(Segment::Pointer, 0)),
+            },
+            Term::Variable(name) => {
+                let (segment, index) = self.variable(name)?;
+                self.emit(Vm::Push(segment, index));
+            }
+            Term::Index(name, index, _) => {
+                self.element_address(name, index)?;
+                self.emit(Vm::Pop(Segment::Pointer, 1));
+                self.emit(Vm::Push(Segment::That, 0));
+            }
+            Term::Call(call) => self.subroutine_call(call)?,
+            Term::Parenthesized(expression, _) => self.expression(expression)?,
+            Term::Unary(op, term, _) => {
+                self.term(term)?;
+                self.operation(match op {
+                    UnaryOp::Neg => Operation::Neg,
+                    UnaryOp::Not => Operation::Not,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// pushes the address of `array[index]`.
+    fn element_address(&mut self, array: &Identifier, index: &Expression) -> Result<(), String> {
+        let (segment, offset) = self.variable(array)?;
+        self.emit(Vm::Push(segment, offset));
+        self.expression(index)?;
+        self.operation(Operation::Add);
+        Ok(())
+    }
+
+    /// `f(...)` calls a subroutine of this class, on `this` if it's a method. `x.f(...)` calls a
+    /// method on the object in variable `x`, and `Class.f(...)` a function or constructor.
+    fn subroutine_call(&mut self, call: &SubroutineCall) -> Result<(), String> {
+        let args = call.arguments.len() as u16;
+        let (name, args) = match &call.receiver {
+            None => {
+                let class = self.class;
+                let subroutine = class
+                    .subroutines
+                    .iter()
+                    .find(|subroutine| subroutine.name.name == call.name.name)
+                    .ok_or_else(|| {
+                        self.error(
+                            call.name.span,
+                            &format!("{} has no subroutine {}", class.name.name, call.name.name),
+                        )
+                    })?;
+                let name = format!("{}.{}", class.name.name, call.name.name);
+                match subroutine.kind {
+                    SubroutineKind::Method => {
+                        self.emit(Vm::Push(Segment::Pointer, 0));
+                        (name, args + 1)
+                    }
+                    _ => (name, args),
+                }
+            }
+            Some(receiver) => match self.symbols.get(&receiver.name).cloned() {
+                Some(symbol) => {
+                    let Type::Class(class) = &symbol.ty else {
+                        return Err(self.error(
+                            receiver.span,
+                            &format!("{} is not an object", receiver.name),
+                        ));
+                    };
+                    self.emit(Vm::Push(symbol.kind.segment(), symbol.index));
+                    (format!("{}.{}", class.name, call.name.name), args + 1)
+                }
+                None => (format!("{}.{}", receiver.name, call.name.name), args),
+            },
+        };
+        for argument in &call.arguments {
+            self.expression(argument)?;
+        }
+        self.call(&name, args);
+        Ok(())
+    }
+
+    fn variable(&self, name: &Identifier) -> Result<(Segment, u16), String> {
+        match self.symbols.get(&name.name) {
+            Some(symbol) => Ok((symbol.kind.segment(), symbol.index)),
+            None => Err(self.error(name.span, &format!("undefined variable {}", name.name))),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: u16) {
+        self.emit(Vm::Call {
+            name: name.to_string(),
+            args,
+        });
+    }
+
+    fn operation(&mut self, operation: Operation) {
+        self.emit(Vm::Operation(operation));
+    }
+
+    fn emit(&mut self, statement: Vm) {
+        self.function.push(statement);
+    }
+
+    fn error(&self, span: Span, message: &str) -> String {
+        error(self.src, span, message)
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn compile(src: &str) -> Result<String, String> {
+        let class = Parser::parse(src)?;
+        Ok(compile_class(src, &class)?.to_string())
+    }
+
+    fn lines(vm: &str) -> Vec<&str> {
+        vm.lines().collect()
+    }
+
+    #[test]
+    fn test_constructor_and_method() {
+        let vm = compile(
+            "class P { field int x, y; static int n;
+               constructor P new(int ax) { let x = ax; return this; }
+               method int sum() { return x + y + n; } }",
+        )
+        .unwrap();
+        assert_eq!(
+            lines(&vm),
+            vec![
+                "function P.new 0",
+                "push constant 2",
+                "call Memory.alloc 1",
+                "pop pointer 0",
+                "push argument 0",
+                "pop this 0",
+                "push pointer 0",
+                "return",
+                "function P.sum 0",
+                "push argument 0",
+                "pop pointer 0",
+                "push this 0",
+                "push this 1",
+                "add",
+                "push static 0",
+                "add",
+                "return",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        let vm = compile(
+            "class M { field Array a;
+               method void f(M m) { do g(); do h(1); do m.f(m); do a.dispose(); return; }
+               method void g() { return; }
+               function void h(int x) { return; } }",
+        )
+        .unwrap();
+        let f = lines(&vm)[..18].join("\n");
+        assert_eq!(
+            f,
+            "function M.f 0\npush argument 0\npop pointer 0\n\
+             push pointer 0\ncall M.g 1\npop temp 0\n\
+             push constant 1\ncall M.h 1\npop temp 0\n\
+             push argument 1\npush argument 1\ncall M.f 2\npop temp 0\n\
+             push this 0\ncall Array.dispose 1\npop temp 0\n\
+             push constant 0\nreturn"
+        );
+    }
+
+    #[test]
+    fn test_arrays_and_strings() {
+        let vm = compile(
+            "class M { function void f(Array a) { var int i; let a[i] = a[1] + \"ok\"; return; } }",
+        )
+        .unwrap();
+        assert_eq!(
+            lines(&vm)[1..22].join("\n"),
+            "push argument 0\npush local 0\nadd\n\
+             push argument 0\npush constant 1\nadd\npop pointer 1\npush that 0\n\
+             push constant 2\ncall String.new 1\n\
+             push constant 111\ncall String.appendChar 2\n\
+             push constant 107\ncall String.appendChar 2\nadd\n\
+             pop temp 0\npop pointer 1\npush temp 0\npop that 0\n\
+             push constant 0\nreturn"
+        );
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let vm = compile(
+            "class M { function int f(int x) {
+               while (x > 0) { if (x = 3) { return x; } else { let x = x - 1; } }
+               if (true) { let x = -x * 2; }
+               return x; } }",
+        )
+        .unwrap();
+        assert_eq!(
+            lines(&vm),
+            vec![
+                "function M.f 0",
+                "label WHILE_EXP0",
+                "push argument 0",
+                "push constant 0",
+                "gt",
+                "not",
+                "if-goto WHILE_END0",
+                "push argument 0",
+                "push constant 3",
+                "eq",
+                "not",
+                "if-goto IF_FALSE0",
+                "push argument 0",
+                "return",
+                "goto IF_END0",
+                "label IF_FALSE0",
+                "push argument 0",
+                "push constant 1",
+                "sub",
+                "pop argument 0",
+                "label IF_END0",
+                "goto WHILE_EXP0",
+                "label WHILE_END0",
+                "push constant 0",
+                "not",
+                "not",
+                "if-goto IF_FALSE1",
+                "push argument 0",
+                "neg",
+                "push constant 2",
+                "call Math.multiply 2",
+                "pop argument 0",
+                "label IF_FALSE1",
+                "push argument 0",
+                "return",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            compile("class M { function void f() { let y = 1; return; } }").unwrap_err(),
+            "line 1, column 35: undefined variable y"
+        );
+        assert_eq!(
+            compile("class M { function void f() { do g(); return; } }").unwrap_err(),
+            "line 1, column 34: M has no subroutine g"
+        );
+        assert_eq!(
+            compile("class M { function void f(int x) { do x.g(); return; } }").unwrap_err(),
+            "line 1, column 39: x is not an object"
+        );
+        assert_eq!(
+            compile("class M { function void f(int x) { var int x; return; } }").unwrap_err(),
+            "line 1, column 44: x is already declared"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::ast::Span;
+use crate::token::line_column;
+
+/// a problem found in a class, and the part of its source it's about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// "line L, column C: message", the position of the start of the span in `src`.
+    pub fn render(&self, src: &str) -> String {
+        let (line, column) = line_column(src, self.span.start);
+        format!("line {}, column {}: {}", line, column, self.message)
+    }
+
+    /// the message followed by the line of `src` it's about, with the span underlined:
+    ///
+    /// ```text
+    /// error: expected ';', found '}'
+    ///  --> Main.jack:2:33
+    ///   |
+    /// 2 |   function void f() { let x = 1 }
+    ///   |                                 ^
+    /// ```
+    pub fn render_snippet(&self, file_name: &str, src: &str) -> String {
+        let start = self.span.start.min(src.len());
+        let (line, column) = line_column(src, start);
+        let line_start = src[..start].rfind('\n').map_or(0, |newline| newline + 1);
+        let text = src[line_start..].lines().next().unwrap_or("");
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        // tabs stay tabs so the carets line up however they are displayed
+        let indent = src[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let end = self.span.end.clamp(start, src.len());
+        let width = src[start..end]
+            .chars()
+            .take_while(|&c| c != '\n')
+            .count()
+            .max(1);
+        let gutter = " ".repeat(line.to_string().len());
+        format!(
+            "error: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self.message,
+            gutter,
+            file_name,
+            line,
+            column,
+            gutter,
+            line,
+            text,
+            gutter,
+            indent,
+            "^".repeat(width)
+        )
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn test_render_snippet() {
+        let src = "class Main {\n\tfunction void f() { let x = 1 }\n}";
+        let diagnostic = Diagnostic::new(Span::new(43, 44), "expected ';', found '}'");
+        assert_eq!(
+            diagnostic.render_snippet("Main.jack", src),
+            "error: expected ';', found '}'\n --> Main.jack:2:31\n  |\n\
+             2 | \tfunction void f() { let x = 1 }\n  | \t                             ^"
+        );
+        let diagnostic = Diagnostic::new(Span::new(23, 27), "unknown class void");
+        assert!(diagnostic
+            .render_snippet("Main.jack", src)
+            .ends_with("\n  | \t         ^^^^"));
+    }
+
+    #[test]
+    fn test_render_at_the_end() {
+        let src = "class Main {\n";
+        let diagnostic = Diagnostic::new(Span::new(13, 13), "expected '}'");
+        assert_eq!(
+            diagnostic.render_snippet("Main.jack", src),
+            "error: expected '}'\n --> Main.jack:2:1\n  |\n2 | \n  | ^"
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use crate::ast::*;
+use crate::code::read_sources;
+use crate::parser::Parser;
+use crate::token::{Token, TokenType, Tokenizer};
+use std::path::Path;
+
+/// one level of indentation.
+const INDENT: &str = "    ";
+
+/// the source of a class printed the canonical way: indented by `INDENT` per block, one
+/// statement or declaration per line, spaces around binary operators and after commas, and a
+/// blank line between subroutines. Blank lines of the source are kept as one, and comments
+/// stay before the code they were before or at the end of the line they ended.
+pub fn format_source(src: &str) -> Result<String, String> {
+    let class = Parser::parse(src)?;
+    let mut formatter = Formatter {
+        src,
+        tokens: Tokenizer::tokenize(src)?,
+        next: 0,
+        out: String::with_capacity(src.len()),
+        blocks: Vec::new(),
+        last: 0,
+        blank: false,
+    };
+    formatter.class(&class);
+    formatter.comments_before(src.len());
+    Ok(formatter.out)
+}
+
+/// formats a .jack file, or every .jack file of a directory, in place. With `check` nothing is
+/// written. Returns the names of the files that weren't formatted.
+pub fn format_files(path: &Path, check: bool) -> Result<Vec<String>, String> {
+    let dir = match path.parent() {
+        _ if path.is_dir() => path,
+        Some(parent) => parent,
+        None => Path::new("."),
+    };
+    let mut changed = Vec::new();
+    for (file_name, src) in read_sources(path)? {
+        let formatted = format_source(&src).map_err(|e| format!("{}: {}", file_name, e))?;
+        if formatted == src {
+            continue;
+        }
+        if !check {
+            std::fs::write(dir.join(&file_name), formatted).map_err(|e| format!("{e}"))?;
+        }
+        changed.push(file_name);
+    }
+    Ok(changed)
+}
+
+struct Formatter<'a> {
+    src: &'a str,
+    /// the tokens of the source, comments included
+    tokens: Vec<Token>,
+    /// the index into `tokens` of the first token that may be a comment not printed yet
+    next: usize,
+    out: String,
+    /// the length of the output where each block around the line being printed opened
+    blocks: Vec<usize>,
+    /// the end of the source printed last, the source after it tells blank lines and comments
+    /// at the end of a line
+    last: usize,
+    /// whether the next line printed gets a blank line before it whatever the source has
+    blank: bool,
+}
+
+impl Formatter<'_> {
+    fn class(&mut self, class: &Class) {
+        self.start(class.span.start);
+        self.line(&format!("class {} {{", class.name.name));
+        self.open(class.name.span.end);
+        for var in &class.vars {
+            let kind = match var.kind {
+                ClassVarKind::Static => "static",
+                ClassVarKind::Field => "field",
+            };
+            self.start(var.span.start);
+            self.line(&format!("{} {} {};", kind, ty(&var.ty), names(&var.names)));
+            self.last = var.span.end;
+        }
+        for (i, subroutine) in class.subroutines.iter().enumerate() {
+            self.blank = i > 0 || !class.vars.is_empty();
+            self.subroutine(subroutine);
+        }
+        self.close(self.last);
+    }
+
+    fn subroutine(&mut self, subroutine: &Subroutine) {
+        let kind = match subroutine.kind {
+            SubroutineKind::Constructor => "constructor",
+            SubroutineKind::Function => "function",
+            SubroutineKind::Method => "method",
+        };
+        let return_type = subroutine
+            .return_type
+            .as_ref()
+            .map_or("void".to_string(), ty);
+        let parameters = subroutine
+            .parameters
+            .iter()
+            .map(|parameter| format!("{} {}", ty(&parameter.ty), parameter.name.name))
+            .collect::<Vec<String>>()
+            .join(", ");
+        self.start(subroutine.span.start);
+        self.line(&format!(
+            "{} {} {}({}) {{",
+            kind, return_type, subroutine.name.name, parameters
+        ));
+        self.open(subroutine.name.span.end);
+        for local in &subroutine.locals {
+            self.start(local.span.start);
+            self.line(&format!("var {} {};", ty(&local.ty), names(&local.names)));
+            self.last = local.span.end;
+        }
+        self.statements(&subroutine.statements);
+        self.close(self.last);
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let span = statement.span();
+        self.start(span.start);
+        match statement {
+            Statement::Let {
+                target,
+                index,
+                value,
+                ..
+            } => {
+                let index = index
+                    .as_ref()
+                    .map_or(String::new(), |index| format!("[{}]", expression(index)));
+                let value = expression(value);
+                self.line(&format!("let {}{} = {};", target.name, index, value));
+            }
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                self.line(&format!("if ({}) {{", expression(condition)));
+                self.open(condition.span.end);
+                self.statements(then);
+                let end = self.close(self.last);
+                if let Some(otherwise) = otherwise {
+                    self.out.pop();
+                    self.out.push_str(" else {\n");
+                    self.open(end);
+                    self.statements(otherwise);
+                    self.close(self.last);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.line(&format!("while ({}) {{", expression(condition)));
+                self.open(condition.span.end);
+                self.statements(body);
+                self.close(self.last);
+            }
+            Statement::Do { call, .. } => self.line(&format!("do {};", subroutine_call(call))),
+            Statement::Return { value, .. } => match value {
+                Some(value) => self.line(&format!("return {};", expression(value))),
+                None => self.line("return;"),
+            },
+        }
+        self.last = span.end;
+    }
+
+    /// goes into the block of the opening brace at or after `from`.
+    fn open(&mut self, from: usize) {
+        self.last = self.symbol('{', from).end;
+        self.blocks.push(self.out.len());
+    }
+
+    /// prints the comments before the closing brace at or after `from`, and the brace. A block
+    /// with nothing in it closes on the line it opened. Returns the end of the brace.
+    fn close(&mut self, from: usize) -> usize {
+        let brace = self.symbol('}', from);
+        self.blank = false;
+        self.comments_before(brace.start);
+        match self.blocks.pop() == Some(self.out.len()) {
+            true => {
+                self.out.pop();
+                self.out.push_str("}\n");
+            }
+            false => self.line("}"),
+        }
+        self.last = brace.end;
+        brace.end
+    }
+
+    /// the first token of the symbol at or after `from`.
+    fn symbol(&self, symbol: char, from: usize) -> Token {
+        *self
+            .tokens
+            .iter()
+            .find(|token| token.start >= from && token.t == TokenType::Symbol(symbol))
+            .expect("the parser checked the braces")
+    }
+
+    /// prints the comments before something starting at `start`, which goes on a line of its
+    /// own after them.
+    fn start(&mut self, start: usize) {
+        self.comments_before(start);
+        self.blank_line(start);
+    }
+
+    /// prints the comments of the source before `pos`. A comment that was at the end of a line
+    /// of code stays there, after a single space.
+    fn comments_before(&mut self, pos: usize) {
+        while let Some(&token) = self.tokens.get(self.next).filter(|token| token.start < pos) {
+            self.next += 1;
+            if !token.is_comment() {
+                continue;
+            }
+            let text = &self.src[token.start..token.end];
+            // comments inside the code printed last end up after it
+            let trailing = !self.out.is_empty()
+                && !self.between(token.start).contains('\n')
+                && !text.contains('\n');
+            if trailing {
+                self.out.pop();
+                self.out.push(' ');
+                self.out.push_str(text.trim_end());
+                self.out.push('\n');
+            } else {
+                // a comment right under code and apart from what follows belongs to the code,
+                // the blank line due goes after it
+                let next = self
+                    .tokens
+                    .get(self.next)
+                    .map_or(self.src.len(), |t| t.start);
+                let below = self.blank
+                    && !has_blank_line(self.between(token.start))
+                    && has_blank_line(&self.src[token.end..next]);
+                if !below {
+                    self.blank_line(token.start);
+                }
+                self.comment_lines(text);
+            }
+            self.last = self.last.max(token.end);
+        }
+    }
+
+    /// a comment on lines of its own. The lines of a block comment starting with `*` are
+    /// indented to line up with the first one, the others are left as they are.
+    fn comment_lines(&mut self, text: &str) {
+        let mut lines = text.lines();
+        self.line(lines.next().unwrap_or("").trim_end());
+        for line in lines {
+            let line = line.trim_end();
+            if line.trim_start().starts_with('*') {
+                self.line(&format!(" {}", line.trim_start()));
+            } else {
+                self.out.push_str(line);
+                self.out.push('\n');
+            }
+        }
+    }
+
+    /// a blank line when the source has one before `pos`, or when one is due anyway.
+    fn blank_line(&mut self, pos: usize) {
+        if !self.out.is_empty() && (self.blank || has_blank_line(self.between(pos))) {
+            self.out.push('\n');
+        }
+        self.blank = false;
+    }
+
+    /// the source from the end of what was printed last to `pos`.
+    fn between(&self, pos: usize) -> &str {
+        self.src.get(self.last..pos).unwrap_or("")
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.blocks.len() {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+}
+
+fn has_blank_line(whitespace: &str) -> bool {
+    whitespace.matches('\n').count() > 1
+}
+
+fn ty(ty: &Type) -> String {
+    match ty {
+        Type::Int => "int".to_string(),
+        Type::Char => "char".to_string(),
+        Type::Boolean => "boolean".to_string(),
+        Type::Class(name) => name.name.clone(),
+    }
+}
+
+fn names(names: &[Identifier]) -> String {
+    names
+        .iter()
+        .map(|name| name.name.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+fn expression(expression: &Expression) -> String {
+    let mut out = term(&expression.first);
+    for (op, operand) in &expression.rest {
+        out.push_str(&format!(" {} {}", op.symbol(), term(operand)));
+    }
+    out
+}
+
+fn term(term: &Term) -> String {
+    match term {
+        Term::Integer(value, _) => value.to_string(),
+        Term::String(text, _) => format!("\"{}\"", text),
+        Term::Keyword(constant, _) => match constant {
+            KeywordConstant::True => "true",
+            KeywordConstant::False => "false",
+            KeywordConstant::Null => "null",
+            KeywordConstant::This => "this",
+        }
+        .to_string(),
+        Term::Variable(name) => name.name.clone(),
+        Term::Index(name, index, _) => format!("{}[{}]", name.name, expression(index)),
+        Term::Call(call) => subroutine_call(call),
+        Term::Parenthesized(inner, _) => format!("({})", expression(inner)),
+        Term::Unary(op, operand, _) => format!("{}{}", op.symbol(), self::term(operand)),
+    }
+}
+
+fn subroutine_call(call: &SubroutineCall) -> String {
+    let arguments = call
+        .arguments
+        .iter()
+        .map(expression)
+        .collect::<Vec<String>>()
+        .join(", ");
+    match &call.receiver {
+        Some(receiver) => format!("{}.{}({})", receiver.name, call.name.name, arguments),
+        None => format!("{}({})", call.name.name, arguments),
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn test_canonical_layout() {
+        let src = "class Main{field int x,y;
+function int f(int a,boolean b){var int c;let c=a+(-x*2);if(b){let x=c;}else{do Main.g(1,\"s\",c[2]);}
+while(~b){}return c;}method void g(){return;}}";
+        assert_eq!(
+            format_source(src).unwrap(),
+            "class Main {
+    field int x, y;
+
+    function int f(int a, boolean b) {
+        var int c;
+        let c = a + (-x * 2);
+        if (b) {
+            let x = c;
+        } else {
+            do Main.g(1, \"s\", c[2]);
+        }
+        while (~b) {}
+        return c;
+    }
+
+    method void g() {
+        return;
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_keeps_comments() {
+        let src = "// header
+/**
+   * the class
+   */
+class Main {
+
+
+    /** runs */
+    function void main() {  // starts
+        var int x;   /* one */ var int y;
+        let x = 1 + // a comment inside
+            2;
+
+        // the end
+        return;
+        // after the last statement
+    }
+} // the class ends
+";
+        assert_eq!(
+            format_source(src).unwrap(),
+            "// header
+/**
+ * the class
+ */
+class Main {
+
+    /** runs */
+    function void main() { // starts
+        var int x; /* one */
+        var int y;
+        let x = 1 + 2; // a comment inside
+
+        // the end
+        return;
+        // after the last statement
+    }
+} // the class ends
+"
+        );
+    }
+
+    #[test]
+    fn test_formatted_source_is_kept() {
+        let src = "class A {\n    function void f() {\n        while (true) {}\n        return;\n    }\n}\n";
+        assert_eq!(format_source(src).unwrap(), src);
+        assert!(format_source("class A { function void f() { return } }").is_err());
+    }
+}
@@ -0,0 +1,13 @@
+pub mod args;
+pub mod ast;
+pub mod check;
+pub mod code;
+pub mod codegen;
+pub mod diagnostic;
+pub mod format;
+pub mod lsp;
+pub mod os;
+pub mod parser;
+pub mod symbols;
+pub mod token;
+pub mod xml;
@@ -0,0 +1,435 @@
+pub mod analysis;
+pub mod json;
+
+use crate::ast::Span;
+use crate::code::read_sources;
+use analysis::{CompletionKind, Program};
+use json::Json;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// the error of a request the server doesn't know.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// the error of a request that can't be done, like renaming a field.
+const REQUEST_FAILED: i64 = -32803;
+
+/// serves the language server protocol over `input` and `output` until the client says
+/// `exit`. The program of a file is every .jack file of its directory, the open documents as
+/// they are in the editor.
+pub fn serve(input: &mut impl BufRead, output: &mut impl Write) -> Result<(), String> {
+    let mut server = Server {
+        documents: HashMap::new(),
+        output,
+    };
+    while let Some(message) = read_message(input)? {
+        let message = Json::parse(&message)?;
+        if !server.handle(&message)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// the content of the next message, `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<String>, String> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).map_err(|e| format!("{e}"))? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or("a message without a Content-Length")?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content).map_err(|e| format!("{e}"))?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|_| "a message that isn't utf-8".to_string())
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> Result<(), String> {
+    let content = message.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )
+    .and_then(|_| output.flush())
+    .map_err(|e| format!("{e}"))
+}
+
+struct Server<'a, W: Write> {
+    /// the text of the open documents by uri
+    documents: HashMap<String, String>,
+    output: &'a mut W,
+}
+
+impl<W: Write> Server<'_, W> {
+    /// answers a request or acts on a notification. Returns false on `exit`.
+    fn handle(&mut self, message: &Json) -> Result<bool, String> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let result = match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => Ok(Json::Null),
+            "exit" => return Ok(false),
+            "textDocument/didOpen" => {
+                let uri = text(params, &["textDocument", "uri"]);
+                let src = text(params, &["textDocument", "text"]);
+                self.documents.insert(uri.clone(), src);
+                self.publish_diagnostics(&uri)?;
+                return Ok(true);
+            }
+            "textDocument/didChange" => {
+                let uri = text(params, &["textDocument", "uri"]);
+                // the server asked for the whole text on every change
+                let changes = match params.get("contentChanges") {
+                    Some(Json::Array(changes)) => changes.as_slice(),
+                    _ => &[],
+                };
+                if let Some(src) = changes.last().and_then(|c| c.get("text")?.as_str()) {
+                    self.documents.insert(uri, src.to_string());
+                }
+                return Ok(true);
+            }
+            "textDocument/didSave" => {
+                self.publish_diagnostics(&text(params, &["textDocument", "uri"]))?;
+                return Ok(true);
+            }
+            "textDocument/didClose" => {
+                self.documents
+                    .remove(&text(params, &["textDocument", "uri"]));
+                return Ok(true);
+            }
+            "textDocument/definition" => Ok(self.definition(params)),
+            "textDocument/hover" => Ok(self.hover(params)),
+            "textDocument/completion" => Ok(self.completion(params)),
+            "textDocument/rename" => self.rename(params),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        };
+        // notifications have no id and get no answer
+        let Some(id) = message.get("id") else {
+            return Ok(true);
+        };
+        let response = match result {
+            Ok(result) => Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("result", result),
+            ]),
+            Err((code, message)) => Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                (
+                    "error",
+                    Json::object([
+                        ("code", Json::Number(code as f64)),
+                        ("message", message.into()),
+                    ]),
+                ),
+            ]),
+        };
+        write_message(self.output, &response)?;
+        Ok(true)
+    }
+
+    /// the diagnostics of every file of the program of the document, so fixing a class clears
+    /// what the others got from it.
+    fn publish_diagnostics(&mut self, uri: &str) -> Result<(), String> {
+        let (program, uris, _) = self.program(uri);
+        for (source, (diagnostics, uri)) in program
+            .sources
+            .iter()
+            .zip(program.diagnostics().into_iter().zip(uris))
+        {
+            let diagnostics = diagnostics
+                .iter()
+                .map(|diagnostic| {
+                    Json::object([
+                        ("range", range(&source.src, diagnostic.span)),
+                        ("severity", 1.into()),
+                        ("source", "jack".into()),
+                        ("message", diagnostic.message.as_str().into()),
+                    ])
+                })
+                .collect::<Vec<Json>>();
+            let notification = Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("method", "textDocument/publishDiagnostics".into()),
+                (
+                    "params",
+                    Json::object([("uri", uri.into()), ("diagnostics", diagnostics.into())]),
+                ),
+            ]);
+            write_message(self.output, &notification)?;
+        }
+        Ok(())
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let (program, uris, file, offset) = self.position(params);
+        match program.definition(file, offset) {
+            Some(location) => Json::object([
+                ("uri", uris[location.file].as_str().into()),
+                (
+                    "range",
+                    range(&program.sources[location.file].src, location.span),
+                ),
+            ]),
+            None => Json::Null,
+        }
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let (program, _, file, offset) = self.position(params);
+        match program.hover(file, offset) {
+            Some(markdown) => Json::object([(
+                "contents",
+                Json::object([("kind", "markdown".into()), ("value", markdown.into())]),
+            )]),
+            None => Json::Null,
+        }
+    }
+
+    fn completion(&self, params: &Json) -> Json {
+        let (program, _, file, offset) = self.position(params);
+        let items = program
+            .completions(file, offset)
+            .into_iter()
+            .map(|completion| {
+                // the kinds of the protocol's CompletionItemKind
+                let kind = match completion.kind {
+                    CompletionKind::Method => 2,
+                    CompletionKind::Function => 3,
+                    CompletionKind::Constructor => 4,
+                    CompletionKind::Variable => 6,
+                    CompletionKind::Class => 7,
+                };
+                Json::object([
+                    ("label", completion.label.into()),
+                    ("kind", kind.into()),
+                    ("detail", completion.detail.into()),
+                ])
+            })
+            .collect::<Vec<Json>>();
+        items.into()
+    }
+
+    fn rename(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (program, uris, file, offset) = self.position(params);
+        let new_name = text(params, &["newName"]);
+        let spans = program
+            .rename(file, offset, &new_name)
+            .map_err(|e| (REQUEST_FAILED, e))?;
+        let src = &program.sources[file].src;
+        let edits = spans
+            .into_iter()
+            .map(|span| {
+                Json::object([
+                    ("range", range(src, span)),
+                    ("newText", new_name.as_str().into()),
+                ])
+            })
+            .collect::<Vec<Json>>();
+        let changes = Json::Object(vec![(uris[file].clone(), edits.into())]);
+        Ok(Json::object([("changes", changes)]))
+    }
+
+    /// the program of the document of a `TextDocumentPositionParams`, the uris of its files,
+    /// the document's file and the byte offset of the position.
+    fn position(&self, params: &Json) -> (Program, Vec<String>, usize, usize) {
+        let uri = text(params, &["textDocument", "uri"]);
+        let (program, uris, file) = self.program(&uri);
+        let line = params.at(&["position", "line"]).and_then(Json::as_usize);
+        let character = params
+            .at(&["position", "character"])
+            .and_then(Json::as_usize);
+        let offset = offset(
+            &program.sources[file].src,
+            line.unwrap_or(0),
+            character.unwrap_or(0),
+        );
+        (program, uris, file, offset)
+    }
+
+    /// the classes of the document's directory with the open documents as they are edited,
+    /// their uris, and which one is the document. A document that isn't a file of a directory
+    /// is a program of its own.
+    fn program(&self, uri: &str) -> (Program, Vec<String>, usize) {
+        let mut files = uri_to_path(uri)
+            .and_then(|path| {
+                let dir = path.parent()?.to_path_buf();
+                let sources = read_sources(&dir).ok()?;
+                Some(
+                    sources
+                        .into_iter()
+                        .map(|(file_name, src)| (path_to_uri(&dir.join(&file_name)), src))
+                        .collect::<Vec<(String, String)>>(),
+                )
+            })
+            .unwrap_or_default();
+        for (file_uri, src) in &mut files {
+            if let Some(document) = self.documents.get(file_uri) {
+                *src = document.clone();
+            }
+        }
+        if !files.iter().any(|(file_uri, _)| file_uri == uri) {
+            let src = self.documents.get(uri).cloned().unwrap_or_default();
+            files.push((uri.to_string(), src));
+        }
+        let file = files.iter().position(|(file_uri, _)| file_uri == uri);
+        let uris = files.iter().map(|(uri, _)| uri.clone()).collect();
+        let files = files
+            .into_iter()
+            .map(|(uri, src)| {
+                let file_name = uri.rsplit('/').next().unwrap_or("").to_string();
+                (file_name, src)
+            })
+            .collect();
+        (Program::new(files), uris, file.unwrap_or(0))
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object([(
+        "capabilities",
+        Json::object([
+            (
+                "textDocumentSync",
+                Json::object([
+                    ("openClose", true.into()),
+                    // the whole text on every change
+                    ("change", 1.into()),
+                    ("save", Json::object([("includeText", false.into())])),
+                ]),
+            ),
+            ("definitionProvider", true.into()),
+            ("hoverProvider", true.into()),
+            (
+                "completionProvider",
+                Json::object([("triggerCharacters", vec![".".into()].into())]),
+            ),
+            ("renameProvider", true.into()),
+        ]),
+    )])
+}
+
+/// the string at the path of keys, empty when it's missing.
+fn text(params: &Json, path: &[&str]) -> String {
+    params
+        .at(path)
+        .and_then(Json::as_str)
+        .unwrap_or("")
+        .to_string()
+}
+
+/// the byte offset of a zero based line and a column counted in utf-16 code units, as the
+/// protocol has them. Positions past the end of a line are at its end.
+fn offset(src: &str, line: usize, character: usize) -> usize {
+    let start = match line {
+        0 => 0,
+        _ => match src.match_indices('\n').nth(line - 1) {
+            Some((newline, _)) => newline + 1,
+            None => return src.len(),
+        },
+    };
+    let mut units = 0;
+    for (i, c) in src[start..].char_indices() {
+        if c == '\n' || units >= character {
+            return start + i;
+        }
+        units += c.len_utf16();
+    }
+    src.len()
+}
+
+/// the zero based line and utf-16 column of a byte offset.
+fn position(src: &str, offset: usize) -> Json {
+    let before = &src[..offset.min(src.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let character = before[line_start..].encode_utf16().count();
+    Json::object([
+        ("line", before.matches('\n').count().into()),
+        ("character", character.into()),
+    ])
+}
+
+fn range(src: &str, span: Span) -> Json {
+    Json::object([
+        ("start", position(src, span.start)),
+        ("end", position(src, span.end)),
+    ])
+}
+
+/// the path of a `file://` uri, with its escapes decoded.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let escaped = after
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &after[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = after;
+            }
+        }
+    }
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// the `file://` uri of a path, escaping what isn't allowed in one.
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = "file://".to_string();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let src = "ab\n// é😀x\nc";
+        let x = src.find('x').unwrap();
+        assert_eq!(offset(src, 1, 6), x);
+        assert_eq!(position(src, x).to_string(), r#"{"line":1,"character":6}"#);
+        assert_eq!(offset(src, 0, 10), 2);
+        assert_eq!(offset(src, 9, 0), src.len());
+    }
+
+    #[test]
+    fn test_uris() {
+        let path = Path::new("/tmp/my project/Main.jack");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///tmp/my%20project/Main.jack");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+        assert_eq!(uri_to_path("untitled:1"), None);
+    }
+}
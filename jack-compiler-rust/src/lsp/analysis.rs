@@ -0,0 +1,804 @@
+use crate::ast::*;
+use crate::check::check_program;
+use crate::diagnostic::Diagnostic;
+use crate::os;
+use crate::parser::Parser;
+use crate::symbols::{Kind, Symbol, SymbolTable};
+use crate::token::Keyword;
+use std::collections::HashMap;
+
+/// a .jack file of the program being edited.
+#[derive(Debug)]
+pub struct Source {
+    pub file_name: String,
+    pub src: String,
+    /// what could be parsed of the class, see `Parser::parse_with_errors`
+    pub class: Option<Class>,
+    pub syntax_errors: Vec<Diagnostic>,
+}
+
+/// the classes of a directory, which is a program, and the OS classes it can call.
+#[derive(Debug)]
+pub struct Program {
+    pub sources: Vec<Source>,
+    os: Vec<(Class, &'static str)>,
+}
+
+/// what a name in the source refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// a variable, with the span of its name where it's declared
+    Variable {
+        name: String,
+        symbol: Symbol,
+        declaration: Span,
+    },
+    Class(String),
+    Subroutine {
+        class: String,
+        name: String,
+    },
+}
+
+/// a name in the source and what it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub span: Span,
+    pub target: Target,
+}
+
+/// where something is declared, a file of `Program::sources` and the span of the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub file: usize,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Variable,
+    Function,
+    Method,
+    Constructor,
+    Class,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// the type of a variable, the signature of a subroutine
+    pub detail: String,
+}
+
+impl Program {
+    /// parses the (file name, source) pairs, broken classes included.
+    pub fn new(files: Vec<(String, String)>) -> Program {
+        let sources = files
+            .into_iter()
+            .map(|(file_name, src)| {
+                let (class, syntax_errors) = Parser::parse_with_errors(&src);
+                Source {
+                    file_name,
+                    src,
+                    class,
+                    syntax_errors,
+                }
+            })
+            .collect();
+        let os = os::OS
+            .iter()
+            .map(|(_, src)| (Parser::parse(src).expect("the OS parses"), *src))
+            .collect();
+        Program { sources, os }
+    }
+
+    /// the diagnostics of every file: its syntax errors, or what checking it against the rest
+    /// of the program finds once it has none.
+    pub fn diagnostics(&self) -> Vec<Vec<Diagnostic>> {
+        let classes = self
+            .sources
+            .iter()
+            .filter_map(|source| source.class.clone())
+            .collect::<Vec<Class>>();
+        let mut checked = check_program(&classes).into_iter();
+        self.sources
+            .iter()
+            .map(|source| {
+                let checked = match source.class {
+                    Some(_) => checked.next().unwrap_or_default(),
+                    None => Vec::new(),
+                };
+                match source.syntax_errors.is_empty() {
+                    true => checked,
+                    false => source.syntax_errors.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// every name in the file that refers to a variable, class or subroutine, in order.
+    pub fn occurrences(&self, file: usize) -> Vec<Occurrence> {
+        let Some(class) = &self.sources[file].class else {
+            return Vec::new();
+        };
+        let mut walker = Walker {
+            class,
+            symbols: SymbolTable::new(),
+            class_declarations: HashMap::new(),
+            declarations: HashMap::new(),
+            occurrences: Vec::new(),
+        };
+        walker.class();
+        walker.occurrences
+    }
+
+    /// the name at a byte offset of the file, the cursor can be right after it.
+    pub fn occurrence_at(&self, file: usize, offset: usize) -> Option<Occurrence> {
+        self.occurrences(file)
+            .into_iter()
+            .find(|occurrence| occurrence.span.start <= offset && offset <= occurrence.span.end)
+    }
+
+    /// where the name at the offset is declared. OS classes have no file to go to.
+    pub fn definition(&self, file: usize, offset: usize) -> Option<Location> {
+        match self.occurrence_at(file, offset)?.target {
+            Target::Variable { declaration, .. } => Some(Location {
+                file,
+                span: declaration,
+            }),
+            Target::Class(name) => {
+                let file = self.class_file(&name)?;
+                let class = self.sources[file].class.as_ref()?;
+                Some(Location {
+                    file,
+                    span: class.name.span,
+                })
+            }
+            Target::Subroutine { class, name } => {
+                let file = self.class_file(&class)?;
+                let class = self.sources[file].class.as_ref()?;
+                let subroutine = class.subroutines.iter().find(|s| s.name.name == name)?;
+                Some(Location {
+                    file,
+                    span: subroutine.name.span,
+                })
+            }
+        }
+    }
+
+    /// markdown about the name at the offset: the type, kind and index of a variable, the
+    /// signature of a subroutine, and the doc comment of a class or subroutine.
+    pub fn hover(&self, file: usize, offset: usize) -> Option<String> {
+        let (code, doc) = match self.occurrence_at(file, offset)?.target {
+            Target::Variable { name, symbol, .. } => {
+                let kind = match symbol.kind {
+                    Kind::Static => "static",
+                    Kind::Field => "field",
+                    Kind::Argument => "argument",
+                    Kind::Local => "local",
+                };
+                let code = format!("{} {}", type_name(&symbol.ty), name);
+                (code, Some(format!("{} {}", kind, symbol.index)))
+            }
+            Target::Class(name) => {
+                let (class, src) = self.class(&name)?;
+                (
+                    format!("class {}", name),
+                    doc_comment(src, class.span.start),
+                )
+            }
+            Target::Subroutine { class, name } => {
+                let (class, src) = self.class(&class)?;
+                let subroutine = class.subroutines.iter().find(|s| s.name.name == name)?;
+                let doc = doc_comment(src, subroutine.span.start);
+                (signature(class, subroutine), doc)
+            }
+        };
+        let mut markdown = format!("```jack\n{}\n```", code);
+        if let Some(doc) = doc {
+            markdown.push_str("\n\n");
+            markdown.push_str(&doc);
+        }
+        Some(markdown)
+    }
+
+    /// what can be typed at the offset. After `name.` that's the methods of the variable's
+    /// class, or the functions and constructors of the class of that name, OS classes
+    /// included. Otherwise it's the variables in scope, the subroutines of the class and the
+    /// classes. The source doesn't have to parse around the offset.
+    pub fn completions(&self, file: usize, offset: usize) -> Vec<Completion> {
+        let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let before = &self.sources[file].src[..offset];
+        let partial_start = before.trim_end_matches(is_name).len();
+        let partial = &before[partial_start..];
+        let receiver = before[..partial_start].strip_suffix('.').map(|before| {
+            let start = before.trim_end_matches(is_name).len();
+            &before[start..]
+        });
+        let (symbols, subroutine) = self.scope(file, offset);
+
+        let mut completions = match receiver {
+            Some(receiver) => match symbols.get(receiver) {
+                Some(Symbol {
+                    ty: Type::Class(class),
+                    ..
+                }) => self.subroutines(&class.name, |kind| kind == SubroutineKind::Method),
+                Some(_) => Vec::new(),
+                None => self.subroutines(receiver, |kind| kind != SubroutineKind::Method),
+            },
+            None => {
+                let function = subroutine.is_some_and(|s| s.kind == SubroutineKind::Function);
+                let mut completions = symbols
+                    .visible()
+                    .filter(|(_, symbol)| !(function && symbol.kind == Kind::Field))
+                    .filter(|(name, _)| *name != "this")
+                    .map(|(name, symbol)| Completion {
+                        label: name.to_string(),
+                        kind: CompletionKind::Variable,
+                        detail: type_name(&symbol.ty),
+                    })
+                    .collect::<Vec<Completion>>();
+                if let Some(class) = &self.sources[file].class {
+                    completions.extend(self.subroutines(&class.name.name, |_| true));
+                }
+                completions.extend(self.class_names().into_iter().map(|name| Completion {
+                    detail: format!("class {}", name),
+                    label: name,
+                    kind: CompletionKind::Class,
+                }));
+                completions
+            }
+        };
+        completions.retain(|completion| completion.label.starts_with(partial));
+        completions.sort_by(|a, b| a.label.cmp(&b.label));
+        completions.dedup_by(|a, b| a.label == b.label);
+        completions
+    }
+
+    /// the spans to replace to rename the local variable or parameter at the offset, the
+    /// declaration and every use. The new name can't be taken by a variable or class the
+    /// subroutine could mean.
+    pub fn rename(&self, file: usize, offset: usize, new_name: &str) -> Result<Vec<Span>, String> {
+        let occurrences = self.occurrences(file);
+        let target = occurrences
+            .iter()
+            .find(|occurrence| occurrence.span.start <= offset && offset <= occurrence.span.end)
+            .map(|occurrence| &occurrence.target);
+        let Some(Target::Variable {
+            symbol,
+            declaration,
+            ..
+        }) = target
+        else {
+            return Err("only local variables and parameters can be renamed".to_string());
+        };
+        if !matches!(symbol.kind, Kind::Local | Kind::Argument) {
+            return Err("only local variables and parameters can be renamed".to_string());
+        }
+        let valid = new_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && new_name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+        if !valid || Keyword::parse(new_name).is_some() {
+            return Err(format!("{} is not a valid name", new_name));
+        }
+        let (symbols, _) = self.scope(file, declaration.start);
+        if symbols.get(new_name).is_some() || self.class(new_name).is_some() {
+            return Err(format!("{} is already taken", new_name));
+        }
+        Ok(occurrences
+            .iter()
+            .filter(|occurrence| {
+                matches!(&occurrence.target, Target::Variable { declaration: d, .. } if d == declaration)
+            })
+            .map(|occurrence| occurrence.span)
+            .collect())
+    }
+
+    /// the variables in scope at the offset, and the subroutine around it.
+    fn scope(&self, file: usize, offset: usize) -> (SymbolTable, Option<&Subroutine>) {
+        let mut symbols = SymbolTable::new();
+        let Some(class) = &self.sources[file].class else {
+            return (symbols, None);
+        };
+        define_class_vars(&mut symbols, class);
+        let subroutine = class
+            .subroutines
+            .iter()
+            .find(|s| s.span.start <= offset && offset <= s.span.end);
+        if let Some(subroutine) = subroutine {
+            define_subroutine_vars(&mut symbols, class, subroutine);
+        }
+        (symbols, subroutine)
+    }
+
+    /// the subroutines of a class of the program or the OS whose kind is wanted.
+    fn subroutines(&self, class: &str, wanted: impl Fn(SubroutineKind) -> bool) -> Vec<Completion> {
+        let Some((class, _)) = self.class(class) else {
+            return Vec::new();
+        };
+        class
+            .subroutines
+            .iter()
+            .filter(|subroutine| wanted(subroutine.kind))
+            .map(|subroutine| Completion {
+                label: subroutine.name.name.clone(),
+                kind: match subroutine.kind {
+                    SubroutineKind::Constructor => CompletionKind::Constructor,
+                    SubroutineKind::Function => CompletionKind::Function,
+                    SubroutineKind::Method => CompletionKind::Method,
+                },
+                detail: signature(class, subroutine),
+            })
+            .collect()
+    }
+
+    /// the class and its source, classes of the program replace OS classes of the same name.
+    fn class(&self, name: &str) -> Option<(&Class, &str)> {
+        let program = self.sources.iter().find_map(|source| {
+            let class = source.class.as_ref()?;
+            (class.name.name == name).then_some((class, source.src.as_str()))
+        });
+        program.or_else(|| {
+            self.os
+                .iter()
+                .find(|(class, _)| class.name.name == name)
+                .map(|(class, src)| (class, *src))
+        })
+    }
+
+    fn class_file(&self, name: &str) -> Option<usize> {
+        self.sources.iter().position(|source| {
+            source
+                .class
+                .as_ref()
+                .is_some_and(|class| class.name.name == name)
+        })
+    }
+
+    fn class_names(&self) -> Vec<String> {
+        let program = self
+            .sources
+            .iter()
+            .filter_map(|source| source.class.as_ref());
+        program
+            .chain(self.os.iter().map(|(class, _)| class))
+            .map(|class| class.name.name.clone())
+            .collect()
+    }
+}
+
+fn define_class_vars(symbols: &mut SymbolTable, class: &Class) {
+    for var in &class.vars {
+        let kind = match var.kind {
+            ClassVarKind::Static => Kind::Static,
+            ClassVarKind::Field => Kind::Field,
+        };
+        for name in &var.names {
+            symbols.define(&name.name, var.ty.clone(), kind);
+        }
+    }
+}
+
+/// the arguments and locals numbered the way the compiler does, `this` is the first argument
+/// of a method.
+fn define_subroutine_vars(symbols: &mut SymbolTable, class: &Class, subroutine: &Subroutine) {
+    symbols.start_subroutine();
+    if subroutine.kind == SubroutineKind::Method {
+        let ty = Type::Class(class.name.clone());
+        symbols.define("this", ty, Kind::Argument);
+    }
+    for parameter in &subroutine.parameters {
+        symbols.define(&parameter.name.name, parameter.ty.clone(), Kind::Argument);
+    }
+    for local in &subroutine.locals {
+        for name in &local.names {
+            symbols.define(&name.name, local.ty.clone(), Kind::Local);
+        }
+    }
+}
+
+/// collects the occurrences of a class, keeping the variables in scope like the compiler.
+struct Walker<'a> {
+    class: &'a Class,
+    symbols: SymbolTable,
+    /// where the class variables are declared, the first declaration of a name counts
+    class_declarations: HashMap<String, Span>,
+    /// where the arguments and locals of the subroutine are declared
+    declarations: HashMap<String, Span>,
+    occurrences: Vec<Occurrence>,
+}
+
+impl Walker<'_> {
+    fn class(&mut self) {
+        let class = self.class;
+        self.push(class.name.span, Target::Class(class.name.name.clone()));
+        define_class_vars(&mut self.symbols, class);
+        for var in &class.vars {
+            self.ty(&var.ty);
+            for name in &var.names {
+                self.class_declarations
+                    .entry(name.name.clone())
+                    .or_insert(name.span);
+                self.variable(name);
+            }
+        }
+        for subroutine in &class.subroutines {
+            self.subroutine(subroutine);
+        }
+    }
+
+    fn subroutine(&mut self, subroutine: &Subroutine) {
+        define_subroutine_vars(&mut self.symbols, self.class, subroutine);
+        self.declarations.clear();
+        let class = self.class;
+        if let Some(ty) = &subroutine.return_type {
+            self.ty(ty);
+        }
+        let target = Target::Subroutine {
+            class: class.name.name.clone(),
+            name: subroutine.name.name.clone(),
+        };
+        self.push(subroutine.name.span, target);
+        for parameter in &subroutine.parameters {
+            self.ty(&parameter.ty);
+            self.declare(&parameter.name);
+        }
+        for local in &subroutine.locals {
+            self.ty(&local.ty);
+            for name in &local.names {
+                self.declare(name);
+            }
+        }
+        self.statements(&subroutine.statements);
+    }
+
+    /// an argument or local where it's declared.
+    fn declare(&mut self, name: &Identifier) {
+        self.declarations
+            .entry(name.name.clone())
+            .or_insert(name.span);
+        self.variable(name);
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        if let Type::Class(name) = ty {
+            self.push(name.span, Target::Class(name.name.clone()));
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Let {
+                    target,
+                    index,
+                    value,
+                    ..
+                } => {
+                    self.variable(target);
+                    if let Some(index) = index {
+                        self.expression(index);
+                    }
+                    self.expression(value);
+                }
+                Statement::If {
+                    condition,
+                    then,
+                    otherwise,
+                    ..
+                } => {
+                    self.expression(condition);
+                    self.statements(then);
+                    if let Some(otherwise) = otherwise {
+                        self.statements(otherwise);
+                    }
+                }
+                Statement::While {
+                    condition, body, ..
+                } => {
+                    self.expression(condition);
+                    self.statements(body);
+                }
+                Statement::Do { call, .. } => self.call(call),
+                Statement::Return { value, .. } => {
+                    if let Some(value) = value {
+                        self.expression(value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        self.term(&expression.first);
+        for (_, term) in &expression.rest {
+            self.term(term);
+        }
+    }
+
+    fn term(&mut self, term: &Term) {
+        match term {
+            Term::Variable(name) => self.variable(name),
+            Term::Index(name, index, _) => {
+                self.variable(name);
+                self.expression(index);
+            }
+            Term::Call(call) => self.call(call),
+            Term::Parenthesized(expression, _) => self.expression(expression),
+            Term::Unary(_, term, _) => self.term(term),
+            Term::Integer(..) | Term::String(..) | Term::Keyword(..) => {}
+        }
+    }
+
+    /// the receiver, which is a variable or a class, and the subroutine of its class.
+    fn call(&mut self, call: &SubroutineCall) {
+        let class = match &call.receiver {
+            None => Some(self.class.name.name.clone()),
+            Some(receiver) => match self.symbols.get(&receiver.name).cloned() {
+                Some(symbol) => {
+                    self.variable(receiver);
+                    match symbol.ty {
+                        Type::Class(class) => Some(class.name),
+                        _ => None,
+                    }
+                }
+                None => {
+                    self.push(receiver.span, Target::Class(receiver.name.clone()));
+                    Some(receiver.name.clone())
+                }
+            },
+        };
+        if let Some(class) = class {
+            let name = call.name.name.clone();
+            self.push(call.name.span, Target::Subroutine { class, name });
+        }
+        for argument in &call.arguments {
+            self.expression(argument);
+        }
+    }
+
+    fn variable(&mut self, name: &Identifier) {
+        let Some(symbol) = self.symbols.get(&name.name).cloned() else {
+            return;
+        };
+        let declarations = match symbol.kind {
+            Kind::Static | Kind::Field => &self.class_declarations,
+            Kind::Argument | Kind::Local => &self.declarations,
+        };
+        let Some(declaration) = declarations.get(&name.name).copied() else {
+            return;
+        };
+        let target = Target::Variable {
+            name: name.name.clone(),
+            symbol,
+            declaration,
+        };
+        self.push(name.span, target);
+    }
+
+    fn push(&mut self, span: Span, target: Target) {
+        self.occurrences.push(Occurrence { span, target });
+    }
+}
+
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Int => "int".to_string(),
+        Type::Char => "char".to_string(),
+        Type::Boolean => "boolean".to_string(),
+        Type::Class(name) => name.name.clone(),
+    }
+}
+
+/// `function int Math.max(int a, int b)`.
+fn signature(class: &Class, subroutine: &Subroutine) -> String {
+    let kind = match subroutine.kind {
+        SubroutineKind::Constructor => "constructor",
+        SubroutineKind::Function => "function",
+        SubroutineKind::Method => "method",
+    };
+    let return_type = subroutine
+        .return_type
+        .as_ref()
+        .map_or("void".to_string(), type_name);
+    let parameters = subroutine
+        .parameters
+        .iter()
+        .map(|parameter| format!("{} {}", type_name(&parameter.ty), parameter.name.name))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!(
+        "{} {} {}.{}({})",
+        kind, return_type, class.name.name, subroutine.name.name, parameters
+    )
+}
+
+/// the text of the `/** */` comment right before `start`, without the stars.
+fn doc_comment(src: &str, start: usize) -> Option<String> {
+    let before = src[..start].trim_end().strip_suffix("*/")?;
+    let open = before.rfind("/**")?;
+    let lines = before[open + 3..]
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            line.strip_prefix('*').unwrap_or(line).trim()
+        })
+        .collect::<Vec<&str>>();
+    let text = lines.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    const MAIN: &str = "class Main {
+    static int count;
+
+    /** runs the game */
+    function void main() {
+        var Ball ball;
+        var int i, steps;
+        let ball = Ball.new(3);
+        let steps = ball.move(i) + count;
+        do Output.printInt(steps);
+        return;
+    }
+}
+";
+
+    const BALL: &str = "class Ball {
+    field int x;
+
+    constructor Ball new(int start) {
+        let x = start;
+        return this;
+    }
+
+    /** moves by the steps */
+    method int move(int steps) {
+        let x = x + steps;
+        return x;
+    }
+}
+";
+
+    fn program() -> Program {
+        Program::new(vec![
+            ("Ball.jack".to_string(), BALL.to_string()),
+            ("Main.jack".to_string(), MAIN.to_string()),
+        ])
+    }
+
+    /// the offset of the nth match of `text` in the source.
+    fn at(src: &str, text: &str, nth: usize) -> usize {
+        src.match_indices(text).nth(nth).unwrap().0
+    }
+
+    #[test]
+    fn test_definition() {
+        let program = program();
+        let definition = |offset| program.definition(1, offset).unwrap();
+        // a local, a method of another class through the variable, and a class
+        let steps = definition(at(MAIN, "steps", 1));
+        assert_eq!((steps.file, steps.span.start), (1, at(MAIN, "steps", 0)));
+        let step = definition(at(MAIN, "move", 0));
+        assert_eq!((step.file, step.span.start), (0, at(BALL, "move(", 0)));
+        let ball = definition(at(MAIN, "Ball.new", 0));
+        assert_eq!((ball.file, ball.span.start), (0, at(BALL, "Ball", 0)));
+        // the OS has no file
+        assert_eq!(program.definition(1, at(MAIN, "printInt", 0)), None);
+    }
+
+    #[test]
+    fn test_hover() {
+        let program = program();
+        let hover = |file, offset| program.hover(file, offset).unwrap();
+        assert_eq!(
+            hover(1, at(MAIN, "steps", 2)),
+            "```jack\nint steps\n```\n\nlocal 2"
+        );
+        assert_eq!(
+            hover(0, at(BALL, "x;", 1)),
+            "```jack\nint x\n```\n\nfield 0"
+        );
+        // `this` is the first argument of a method
+        assert_eq!(
+            hover(0, at(BALL, "steps", 2)),
+            "```jack\nint steps\n```\n\nargument 1"
+        );
+        assert_eq!(
+            hover(1, at(MAIN, "move", 0)),
+            "```jack\nmethod int Ball.move(int steps)\n```\n\nmoves by the steps"
+        );
+        assert_eq!(
+            hover(1, at(MAIN, "printInt", 0)).lines().next(),
+            Some("```jack")
+        );
+    }
+
+    #[test]
+    fn test_completions() {
+        let edited = MAIN.replace("do Output.printInt(steps);", "do Math.m\n        let i = s");
+        let program = Program::new(vec![
+            ("Ball.jack".to_string(), BALL.to_string()),
+            ("Main.jack".to_string(), edited.clone()),
+        ]);
+        let labels = |offset| {
+            program
+                .completions(1, offset)
+                .into_iter()
+                .map(|completion| completion.label)
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            labels(at(&edited, "Math.m", 0) + 6),
+            vec!["max", "min", "multiply"]
+        );
+        assert_eq!(labels(at(&edited, "ball.move", 0) + 5), vec!["move"]);
+        assert_eq!(labels(at(&edited, "Ball.new", 0) + 5), vec!["new"]);
+        // names are case sensitive, the classes String and Sys don't start with s
+        assert_eq!(labels(at(&edited, "= s", 0) + 3), vec!["steps"]);
+        let all = labels(at(&edited, "let ball", 0) + 4);
+        for label in ["ball", "count", "i", "main", "Ball", "Keyboard"] {
+            assert!(all.contains(&label.to_string()), "{label}");
+        }
+    }
+
+    #[test]
+    fn test_rename() {
+        let program = program();
+        let spans = program.rename(1, at(MAIN, "steps", 0), "total").unwrap();
+        let starts = spans.iter().map(|span| span.start).collect::<Vec<usize>>();
+        assert_eq!(
+            starts,
+            (0..3).map(|n| at(MAIN, "steps", n)).collect::<Vec<usize>>()
+        );
+        assert_eq!(
+            program.rename(1, at(MAIN, "steps", 0), "i").unwrap_err(),
+            "i is already taken"
+        );
+        assert_eq!(
+            program.rename(1, at(MAIN, "steps", 0), "Ball").unwrap_err(),
+            "Ball is already taken"
+        );
+        assert_eq!(
+            program
+                .rename(1, at(MAIN, "steps", 0), "while")
+                .unwrap_err(),
+            "while is not a valid name"
+        );
+        assert!(program.rename(1, at(MAIN, "count", 1), "total").is_err());
+        assert!(program.rename(0, at(BALL, "x", 0), "y").is_err());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let program = Program::new(vec![
+            (
+                "Main.jack".to_string(),
+                MAIN.replace("ball.move(i)", "ball.jump(i)"),
+            ),
+            (
+                "Ball.jack".to_string(),
+                "class Ball { field int x }".to_string(),
+            ),
+        ]);
+        let diagnostics = program.diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        let messages = |file: usize| {
+            diagnostics[file]
+                .iter()
+                .map(|diagnostic| diagnostic.message.as_str())
+                .collect::<Vec<&str>>()
+        };
+        // the classes are checked against what could be parsed of the broken one
+        assert_eq!(
+            messages(0),
+            vec!["Ball has no subroutine new", "Ball has no subroutine jump"]
+        );
+        assert_eq!(messages(1), vec!["expected ';', found '}'"]);
+    }
+}
@@ -0,0 +1,25 @@
+use jack_compiler_rust::args::CompilerArgs;
+use jack_compiler_rust::code::compile;
+
+fn main() {
+    let args = match CompilerArgs::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("[err] {e}");
+            eprintln!(
+                "usage: jack-compiler <source file | directory> [--emit=vm|tokens|xml] [--jack-ext] [--out=DIR]"
+            );
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = compile(
+        &args.src,
+        args.emit,
+        args.extensions,
+        args.out_dir.as_deref(),
+    ) {
+        // a failed compile has to fail the script or build that ran it
+        eprintln!("[err] {e}");
+        std::process::exit(1);
+    }
+}
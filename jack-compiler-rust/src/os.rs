@@ -0,0 +1,12 @@
+/// the Jack OS as (file name, source) pairs. Programs are checked against its subroutines, and
+/// the vm translator bundles its compiled code, see `vm_translator_rust::os`.
+pub const OS: [(&str, &str); 8] = [
+    ("Array.jack", include_str!("../os/Array.jack")),
+    ("Keyboard.jack", include_str!("../os/Keyboard.jack")),
+    ("Math.jack", include_str!("../os/Math.jack")),
+    ("Memory.jack", include_str!("../os/Memory.jack")),
+    ("Output.jack", include_str!("../os/Output.jack")),
+    ("Screen.jack", include_str!("../os/Screen.jack")),
+    ("String.jack", include_str!("../os/String.jack")),
+    ("Sys.jack", include_str!("../os/Sys.jack")),
+];
@@ -0,0 +1,794 @@
+use crate::ast::*;
+use crate::diagnostic::Diagnostic;
+use crate::token::{Keyword, Token, TokenType, Tokenizer};
+
+mod ext;
+
+type Parse<T> = Result<T, Diagnostic>;
+
+/// a recursive descent parser for a jack class, one method per rule of the grammar. A syntax
+/// error doesn't stop it: the error is collected and parsing goes on from the next statement or
+/// declaration, so one run reports every mistake of a class.
+pub struct Parser<'a> {
+    src: &'a str,
+    /// the tokens without comments
+    tokens: Vec<Token>,
+    pos: usize,
+    errors: Vec<Diagnostic>,
+    /// the `--jack-ext` dialect, desugared to standard jack as it's parsed, see `ext`
+    extensions: bool,
+    desugaring: ext::Desugaring,
+}
+
+impl<'a> Parser<'a> {
+    /// tokenizes the source, errors of the tokenizer are the first errors of the parser.
+    pub fn new(src: &'a str) -> Self {
+        Parser::with_extensions(src, false)
+    }
+
+    pub fn with_extensions(src: &'a str, extensions: bool) -> Self {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for token in Tokenizer::new(src).with_extensions(extensions) {
+            match token {
+                Ok(token) if token.is_comment() => {}
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            }
+        }
+        Self {
+            src,
+            tokens,
+            pos: 0,
+            errors,
+            extensions,
+            desugaring: ext::Desugaring::default(),
+        }
+    }
+
+    /// parses the class in the source, which is all a .jack file holds. The error lists every
+    /// syntax error, one per line.
+    pub fn parse(src: &str) -> Result<Class, String> {
+        Parser::new(src).into_class()
+    }
+
+    /// the class and every syntax error in it. The class is what could be parsed around the
+    /// errors, there's none when even its header is broken.
+    pub fn parse_with_errors(src: &str) -> (Option<Class>, Vec<Diagnostic>) {
+        Parser::new(src).into_class_with_errors()
+    }
+
+    /// the class of `Parser::parse`, for a parser of either dialect.
+    pub fn into_class(self) -> Result<Class, String> {
+        let src = self.src;
+        match self.into_class_with_errors() {
+            (Some(class), errors) if errors.is_empty() => Ok(class),
+            (_, errors) => Err(errors
+                .iter()
+                .map(|error| error.render(src))
+                .collect::<Vec<String>>()
+                .join("\n")),
+        }
+    }
+
+    /// the class and errors of `Parser::parse_with_errors`, for a parser of either dialect.
+    pub fn into_class_with_errors(mut self) -> (Option<Class>, Vec<Diagnostic>) {
+        let class = match self.class() {
+            Ok(class) => Some(class),
+            Err(error) => {
+                self.errors.push(error);
+                None
+            }
+        };
+        if class.is_some() && self.peek().is_some() {
+            let error = self.error("end of file");
+            self.errors.push(error);
+        }
+        self.errors.sort_by_key(|error| error.span.start);
+        (class, self.errors)
+    }
+
+    fn class(&mut self) -> Parse<Class> {
+        let start = self.expect_keyword(Keyword::Class)?;
+        let name = self.identifier()?;
+        self.expect_symbol('{')?;
+        let mut vars = Vec::new();
+        let mut subroutines = Vec::new();
+        loop {
+            let result = match self.peek().map(|token| token.t) {
+                Some(TokenType::Keyword(Keyword::Static | Keyword::Field))
+                    if subroutines.is_empty() =>
+                {
+                    self.class_var_dec().map(|var| vars.push(var))
+                }
+                Some(TokenType::Keyword(
+                    Keyword::Constructor | Keyword::Function | Keyword::Method,
+                )) => self
+                    .subroutine()
+                    .map(|subroutine| subroutines.push(subroutine)),
+                Some(TokenType::Symbol('}')) | None => break,
+                Some(TokenType::Keyword(Keyword::Static | Keyword::Field)) => {
+                    // class variables come first, this one is skipped with the rest of it
+                    let error = self.error("a subroutine declaration");
+                    self.next();
+                    Err(error)
+                }
+                _ if subroutines.is_empty() => Err(self.error("a declaration")),
+                _ => Err(self.error("a subroutine declaration")),
+            };
+            if let Err(error) = result {
+                self.errors.push(error);
+                self.synchronize_declaration();
+            }
+        }
+        let end = self.expect_symbol('}')?;
+        Ok(Class {
+            name,
+            vars,
+            subroutines,
+            span: start.to(end),
+        })
+    }
+
+    fn class_var_dec(&mut self) -> Parse<ClassVarDec> {
+        let (keyword, start) = self.keyword()?;
+        let kind = match keyword {
+            Keyword::Static => ClassVarKind::Static,
+            _ => ClassVarKind::Field,
+        };
+        let ty = self.ty()?;
+        let (names, end) = self.names()?;
+        Ok(ClassVarDec {
+            kind,
+            ty,
+            names,
+            span: start.to(end),
+        })
+    }
+
+    /// errors in the header skip the whole subroutine, the body recovers statement by statement.
+    fn subroutine(&mut self) -> Parse<Subroutine> {
+        self.desugaring = ext::Desugaring::default();
+        let (keyword, start) = self.keyword()?;
+        let kind = match keyword {
+            Keyword::Constructor => SubroutineKind::Constructor,
+            Keyword::Function => SubroutineKind::Function,
+            _ => SubroutineKind::Method,
+        };
+        let return_type = match self.is_keyword(Keyword::Void) {
+            true => {
+                self.next();
+                None
+            }
+            false => Some(self.ty()?),
+        };
+        let name = self.identifier()?;
+        self.expect_symbol('(')?;
+        let mut parameters = Vec::new();
+        if !self.is_symbol(')') {
+            loop {
+                let ty = self.ty()?;
+                let name = self.identifier()?;
+                parameters.push(Parameter { ty, name });
+                if !self.eat_symbol(',') {
+                    break;
+                }
+            }
+        }
+        self.expect_symbol(')')?;
+        self.expect_symbol('{')?;
+        let mut locals = Vec::new();
+        while self.is_keyword(Keyword::Var) {
+            match self.var_dec() {
+                Ok(local) => locals.push(local),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize_statement();
+                }
+            }
+        }
+        let statements = self.statements();
+        locals.append(&mut self.desugaring.locals);
+        // without its closing brace the subroutine ends where the next declaration starts
+        let end = match self.expect_symbol('}') {
+            Ok(end) => end,
+            Err(error) => {
+                self.errors.push(error);
+                self.previous_span()
+            }
+        };
+        Ok(Subroutine {
+            kind,
+            return_type,
+            name,
+            parameters,
+            locals,
+            statements,
+            span: start.to(end),
+        })
+    }
+
+    fn var_dec(&mut self) -> Parse<VarDec> {
+        let start = self.expect_keyword(Keyword::Var)?;
+        let ty = self.ty()?;
+        let (names, end) = self.names()?;
+        Ok(VarDec {
+            ty,
+            names,
+            span: start.to(end),
+        })
+    }
+
+    /// `name (, name)* ;` of a variable declaration, and the span of the semicolon.
+    fn names(&mut self) -> Parse<(Vec<Identifier>, Span)> {
+        let mut names = vec![self.identifier()?];
+        while self.eat_symbol(',') {
+            names.push(self.identifier()?);
+        }
+        let end = self.expect_symbol(';')?;
+        Ok((names, end))
+    }
+
+    fn ty(&mut self) -> Parse<Type> {
+        let ty = match self.peek().map(|token| token.t) {
+            Some(TokenType::Keyword(Keyword::Int)) => Type::Int,
+            Some(TokenType::Keyword(Keyword::Char)) => Type::Char,
+            Some(TokenType::Keyword(Keyword::Boolean)) => Type::Boolean,
+            Some(TokenType::Identifier) => return Ok(Type::Class(self.identifier()?)),
+            _ => return Err(self.error("a type")),
+        };
+        self.next();
+        Ok(ty)
+    }
+
+    /// the statements up to the closing brace of the block, or up to a declaration when the
+    /// brace is missing. A broken statement is skipped.
+    fn statements(&mut self) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        while !self.is_symbol('}') && !self.at_declaration() {
+            let statement = match self.extensions {
+                true => self.extended_statement(),
+                false => self.statement().map(|statement| vec![statement]),
+            };
+            match statement {
+                Ok(statement) => statements.extend(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize_statement();
+                }
+            }
+        }
+        statements
+    }
+
+    fn statement(&mut self) -> Parse<Statement> {
+        let (keyword, start) = match self.peek().map(|token| token.t) {
+            Some(TokenType::Keyword(
+                keyword @ (Keyword::Let
+                | Keyword::If
+                | Keyword::While
+                | Keyword::Do
+                | Keyword::Return),
+            )) => self.keyword().map(|(_, span)| (keyword, span))?,
+            _ => return Err(self.error("a statement")),
+        };
+        match keyword {
+            Keyword::Let => {
+                let target = self.identifier()?;
+                let index = match self.eat_symbol('[') {
+                    true => {
+                        let index = self.expression()?;
+                        self.expect_symbol(']')?;
+                        Some(index)
+                    }
+                    false => None,
+                };
+                self.expect_symbol('=')?;
+                let value = self.expression()?;
+                let end = self.expect_symbol(';')?;
+                Ok(Statement::Let {
+                    target,
+                    index,
+                    value,
+                    span: start.to(end),
+                })
+            }
+            Keyword::If => {
+                let condition = self.condition()?;
+                let (then, mut end) = self.block()?;
+                let otherwise = match self.is_keyword(Keyword::Else) {
+                    true => {
+                        self.next();
+                        let (otherwise, block_end) =
+                            match self.extensions && self.is_keyword(Keyword::If) {
+                                // `else if` is an if in the else block
+                                true => {
+                                    let statement = self.statement()?;
+                                    let end = statement.span();
+                                    (vec![statement], end)
+                                }
+                                false => self.block()?,
+                            };
+                        end = block_end;
+                        Some(otherwise)
+                    }
+                    false => None,
+                };
+                Ok(Statement::If {
+                    condition,
+                    then,
+                    otherwise,
+                    span: start.to(end),
+                })
+            }
+            Keyword::While => {
+                let condition = self.condition()?;
+                let (body, end) = self.block()?;
+                Ok(Statement::While {
+                    condition,
+                    body,
+                    span: start.to(end),
+                })
+            }
+            Keyword::Do => {
+                let name = self.identifier()?;
+                let call = self.call(name)?;
+                let end = self.expect_symbol(';')?;
+                Ok(Statement::Do {
+                    call,
+                    span: start.to(end),
+                })
+            }
+            _ => {
+                let value = match self.is_symbol(';') {
+                    true => None,
+                    false => Some(self.expression()?),
+                };
+                let end = self.expect_symbol(';')?;
+                Ok(Statement::Return {
+                    value,
+                    span: start.to(end),
+                })
+            }
+        }
+    }
+
+    /// `( expression )` of an if or while statement.
+    fn condition(&mut self) -> Parse<Expression> {
+        self.expect_symbol('(')?;
+        let condition = self.expression()?;
+        self.expect_symbol(')')?;
+        Ok(condition)
+    }
+
+    /// `{ statements }`, and the span of the closing brace.
+    fn block(&mut self) -> Parse<(Vec<Statement>, Span)> {
+        self.expect_symbol('{')?;
+        let statements = self.statements();
+        let end = self.expect_symbol('}')?;
+        Ok((statements, end))
+    }
+
+    fn expression(&mut self) -> Parse<Expression> {
+        if self.extensions {
+            return self.ranked_expression(0);
+        }
+        let first = self.term()?;
+        let mut rest = Vec::new();
+        while let Some(TokenType::Symbol(symbol)) = self.peek().map(|token| token.t) {
+            let Some(op) = BinaryOp::from_symbol(symbol) else {
+                break;
+            };
+            self.next();
+            rest.push((op, self.term()?));
+        }
+        let end = rest.last().map_or(first.span(), |(_, term)| term.span());
+        Ok(Expression {
+            span: first.span().to(end),
+            first,
+            rest,
+        })
+    }
+
+    fn term(&mut self) -> Parse<Term> {
+        let Some(token) = self.peek().copied() else {
+            return Err(self.error("a term"));
+        };
+        let span = Span::new(token.start, token.end);
+        let term = match token.t {
+            TokenType::IntegerConstant(value) | TokenType::CharConstant(value) => {
+                self.next();
+                Term::Integer(value, span)
+            }
+            TokenType::StringConstant => {
+                self.next();
+                Term::String(token.text(self.src).to_string(), span)
+            }
+            TokenType::Keyword(
+                keyword @ (Keyword::True | Keyword::False | Keyword::Null | Keyword::This),
+            ) => {
+                self.next();
+                let constant = match keyword {
+                    Keyword::True => KeywordConstant::True,
+                    Keyword::False => KeywordConstant::False,
+                    Keyword::Null => KeywordConstant::Null,
+                    _ => KeywordConstant::This,
+                };
+                Term::Keyword(constant, span)
+            }
+            TokenType::Symbol('(') => {
+                self.next();
+                let expression = self.expression()?;
+                let end = self.expect_symbol(')')?;
+                Term::Parenthesized(Box::new(expression), span.to(end))
+            }
+            TokenType::Symbol(symbol @ ('-' | '~')) => {
+                self.next();
+                let op = match symbol {
+                    '-' => UnaryOp::Neg,
+                    _ => UnaryOp::Not,
+                };
+                let term = self.term()?;
+                let end = term.span();
+                Term::Unary(op, Box::new(term), span.to(end))
+            }
+            TokenType::Identifier => {
+                let name = self.identifier()?;
+                if self.eat_symbol('[') {
+                    let index = self.expression()?;
+                    let end = self.expect_symbol(']')?;
+                    Term::Index(name, Box::new(index), span.to(end))
+                } else if self.is_symbol('(') || self.is_symbol('.') {
+                    Term::Call(self.call(name)?)
+                } else {
+                    Term::Variable(name)
+                }
+            }
+            _ => return Err(self.error("a term")),
+        };
+        Ok(term)
+    }
+
+    /// the rest of a subroutine call after its first name.
+    fn call(&mut self, first: Identifier) -> Parse<SubroutineCall> {
+        let start = first.span;
+        let (receiver, name) = match self.eat_symbol('.') {
+            true => (Some(first), self.identifier()?),
+            false => (None, first),
+        };
+        self.expect_symbol('(')?;
+        let mut arguments = Vec::new();
+        if !self.is_symbol(')') {
+            arguments.push(self.expression()?);
+            while self.eat_symbol(',') {
+                arguments.push(self.expression()?);
+            }
+        }
+        let end = self.expect_symbol(')')?;
+        Ok(SubroutineCall {
+            receiver,
+            name,
+            arguments,
+            span: start.to(end),
+        })
+    }
+
+    fn identifier(&mut self) -> Parse<Identifier> {
+        match self.peek().copied() {
+            Some(token) if token.t == TokenType::Identifier => {
+                self.next();
+                Ok(Identifier {
+                    name: token.text(self.src).to_string(),
+                    span: Span::new(token.start, token.end),
+                })
+            }
+            _ => Err(self.error("an identifier")),
+        }
+    }
+
+    fn keyword(&mut self) -> Parse<(Keyword, Span)> {
+        match self.peek().copied() {
+            Some(Token {
+                t: TokenType::Keyword(keyword),
+                start,
+                end,
+            }) => {
+                self.next();
+                Ok((keyword, Span::new(start, end)))
+            }
+            _ => Err(self.error("a keyword")),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: Keyword) -> Parse<Span> {
+        match self.is_keyword(keyword) {
+            true => self.keyword().map(|(_, span)| span),
+            false => Err(self.error(&format!("'{}'", keyword))),
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Parse<Span> {
+        match self.peek().copied() {
+            Some(token) if token.t == TokenType::Symbol(symbol) => {
+                self.next();
+                Ok(Span::new(token.start, token.end))
+            }
+            _ => Err(self.error(&format!("'{}'", symbol))),
+        }
+    }
+
+    /// skips the symbol when it is next.
+    fn eat_symbol(&mut self, symbol: char) -> bool {
+        let is_symbol = self.is_symbol(symbol);
+        if is_symbol {
+            self.next();
+        }
+        is_symbol
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        self.peek()
+            .is_some_and(|token| token.t == TokenType::Symbol(symbol))
+    }
+
+    fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.peek()
+            .is_some_and(|token| token.t == TokenType::Keyword(keyword))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) {
+        self.pos += 1;
+    }
+
+    /// skips the rest of a broken statement: up to and including its semicolon, or up to the
+    /// next statement, closing brace or declaration. Blocks of the broken statement are skipped
+    /// whole, and at least one token is skipped when it broke on its first token.
+    fn synchronize_statement(&mut self) {
+        let start = self.pos;
+        while let Some(token) = self.peek() {
+            match token.t {
+                TokenType::Symbol(';') => {
+                    self.next();
+                    return;
+                }
+                TokenType::Symbol('{') => self.skip_block(),
+                TokenType::Symbol('}') => return,
+                TokenType::Keyword(
+                    Keyword::Let
+                    | Keyword::If
+                    | Keyword::While
+                    | Keyword::Do
+                    | Keyword::Return
+                    | Keyword::For
+                    | Keyword::Break
+                    | Keyword::Continue,
+                ) if self.pos > start => return,
+                _ if self.at_declaration() => return,
+                _ => self.next(),
+            }
+        }
+    }
+
+    /// skips from an opening brace past its closing brace, or to the next declaration when it
+    /// has none.
+    fn skip_block(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            match token.t {
+                TokenType::Symbol('{') => depth += 1,
+                TokenType::Symbol('}') => depth -= 1,
+                _ if self.at_declaration() => return,
+                _ => {}
+            }
+            self.next();
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
+    /// skips to the next declaration of the class, or to the brace closing it.
+    fn synchronize_declaration(&mut self) {
+        while self.peek().is_some() {
+            let last = self.pos + 1 == self.tokens.len();
+            if self.at_declaration() || (last && self.is_symbol('}')) {
+                return;
+            }
+            self.next();
+        }
+    }
+
+    /// whether a class variable or subroutine declaration starts at the next token.
+    fn at_declaration(&self) -> bool {
+        self.peek().is_some_and(|token| {
+            matches!(
+                token.t,
+                TokenType::Keyword(
+                    Keyword::Static
+                        | Keyword::Field
+                        | Keyword::Constructor
+                        | Keyword::Function
+                        | Keyword::Method
+                )
+            )
+        })
+    }
+
+    /// the span of the last token parsed.
+    fn previous_span(&self) -> Span {
+        self.pos
+            .checked_sub(1)
+            .and_then(|pos| self.tokens.get(pos))
+            .map_or(Span::default(), |token| Span::new(token.start, token.end))
+    }
+
+    /// "expected X, found Y" about the next token.
+    fn error(&self, expected: &str) -> Diagnostic {
+        match self.peek() {
+            Some(token) => {
+                let found = &self.src[token.start..token.end];
+                Diagnostic::new(
+                    Span::new(token.start, token.end),
+                    format!("expected {}, found '{}'", expected, found),
+                )
+            }
+            None => Diagnostic::new(
+                Span::new(self.src.len(), self.src.len()),
+                format!("expected {}, found the end of the file", expected),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn parse_expression(src: &str) -> Expression {
+        let mut parser = Parser::new(src);
+        parser.expression().unwrap()
+    }
+
+    #[test]
+    fn test_parse_class() {
+        let src = "class Main {\n  field int x, y;\n  method void run(int a, Foo b) {\n    var Array c;\n    let c[a] = b.size() + 1;\n    if (~(a = 0)) { do run(a - 1, b); } else { return; }\n    return;\n  }\n}\n";
+        let class = Parser::parse(src).unwrap();
+        assert_eq!(class.name.name, "Main");
+        assert_eq!(class.vars[0].kind, ClassVarKind::Field);
+        assert_eq!(class.vars[0].names.len(), 2);
+        let run = &class.subroutines[0];
+        assert_eq!(run.kind, SubroutineKind::Method);
+        assert_eq!(run.return_type, None);
+        assert!(matches!(&run.parameters[1].ty, Type::Class(name) if name.name == "Foo"));
+        assert_eq!(run.statements.len(), 3);
+        let Statement::Let { index, value, .. } = &run.statements[0] else {
+            panic!("expected a let statement");
+        };
+        assert!(index.is_some());
+        assert!(matches!(value.first, Term::Call(_)));
+        let span = run.statements[1].span();
+        assert!(src[span.start..span.end].starts_with("if"));
+        assert!(src[span.start..span.end].ends_with("return; }"));
+    }
+
+    #[test]
+    fn test_expressions_are_flat() {
+        let expression = parse_expression("1 + 2 * -x");
+        assert_eq!(expression.first, Term::Integer(1, Span::new(0, 1)));
+        assert_eq!(expression.rest.len(), 2);
+        assert_eq!(expression.rest[1].0, BinaryOp::Mul);
+        assert!(matches!(
+            expression.rest[1].1,
+            Term::Unary(UnaryOp::Neg, _, _)
+        ));
+        assert_eq!(expression.span, Span::new(0, 10));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |src| Parser::parse(src).unwrap_err();
+        assert_eq!(
+            error("class Main {\n  function void f() { let x = 1 }\n}"),
+            "line 2, column 33: expected ';', found '}'"
+        );
+        assert_eq!(
+            error("class Main {"),
+            "line 1, column 13: expected '}', found the end of the file"
+        );
+        assert_eq!(
+            error("class Main { } }"),
+            "line 1, column 16: expected end of file, found '}'"
+        );
+        assert_eq!(
+            error("class Main { function void f() { foo(); } }"),
+            "line 1, column 34: expected a statement, found 'foo'"
+        );
+    }
+
+    fn errors(src: &str) -> Vec<String> {
+        let (_, errors) = Parser::parse_with_errors(src);
+        errors.iter().map(|error| error.render(src)).collect()
+    }
+
+    #[test]
+    fn test_recovers_at_statements() {
+        let src = "class Main {
+  field int x
+  function void f() {
+    var int a, ;
+    let a = ;
+    do g(;
+    if (x +) { let a = 1; } else { let a = 2 }
+    while (a) { let = 3; }
+    return;
+  }
+  method void g() { return 1 }
+}";
+        assert_eq!(
+            errors(src),
+            vec![
+                "line 3, column 3: expected ';', found 'function'",
+                "line 4, column 16: expected an identifier, found ';'",
+                "line 5, column 13: expected a term, found ';'",
+                "line 6, column 10: expected a term, found ';'",
+                "line 7, column 12: expected a term, found ')'",
+                "line 8, column 21: expected an identifier, found '='",
+                "line 11, column 30: expected ';', found '}'",
+            ]
+        );
+        let (class, _) = Parser::parse_with_errors(src);
+        let class = class.unwrap();
+        assert_eq!(class.subroutines.len(), 2);
+        // `while` and `return` survive the broken statements before them
+        assert_eq!(class.subroutines[0].statements.len(), 2);
+    }
+
+    #[test]
+    fn test_recovers_at_declarations() {
+        let src = "class Main {
+  field 1 x;
+  static int y;
+  function void (int a) { let b = 1; }
+  method void g() {
+    let a = 1;
+  function int h() { return 1; }
+  field int z;
+  let q = 1;
+}";
+        assert_eq!(
+            errors(src),
+            vec![
+                "line 2, column 9: expected a type, found '1'",
+                "line 4, column 17: expected an identifier, found '('",
+                "line 7, column 3: expected '}', found 'function'",
+                "line 8, column 3: expected a subroutine declaration, found 'field'",
+            ]
+        );
+        let class = Parser::parse_with_errors(src).0.unwrap();
+        assert_eq!(class.vars.len(), 1);
+        let names = class
+            .subroutines
+            .iter()
+            .map(|subroutine| subroutine.name.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["g", "h"]);
+    }
+
+    #[test]
+    fn test_tokenizer_errors_are_collected() {
+        let src = "class Main { function void f() { let a = 1 # 2; let s = \"open\n; } }";
+        assert_eq!(
+            errors(src),
+            vec![
+                "line 1, column 44: unexpected character '#'",
+                "line 1, column 46: expected ';', found '2'",
+                "line 1, column 57: unterminated string constant",
+                "line 2, column 1: expected a term, found ';'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,496 @@
+use super::{Parse, Parser};
+use crate::ast::*;
+use crate::diagnostic::Diagnostic;
+use crate::token::{Keyword, TokenType};
+
+/// the binary operators from the loosest to the tightest binding, like in C: equality binds looser
+/// than the relational operators. Operators of a level still apply from left to right.
+const PRECEDENCE: [&[BinaryOp]; 6] = [
+    &[BinaryOp::Or],
+    &[BinaryOp::And],
+    &[BinaryOp::Eq],
+    &[BinaryOp::Lt, BinaryOp::Gt],
+    &[BinaryOp::Add, BinaryOp::Sub],
+    &[BinaryOp::Mul, BinaryOp::Div],
+];
+
+/// the operators of compound assignments, `let x += 1;`.
+const COMPOUND: [BinaryOp; 6] = [
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::And,
+    BinaryOp::Or,
+];
+
+/// the values of the flag a loop with `break` or `continue` keeps in a local: the statements
+/// after a jump are skipped until the flag is back to `GOING`.
+const GOING: u16 = 0;
+const CONTINUED: u16 = 1;
+const BROKEN: u16 = 2;
+
+/// what desugaring the statements of a subroutine needs to keep track of.
+#[derive(Debug, Default)]
+pub(super) struct Desugaring {
+    /// the loops around the statement being parsed, innermost last
+    loops: Vec<Loop>,
+    /// the flags and temporaries of the desugared statements, locals of the subroutine
+    pub(super) locals: Vec<VarDec>,
+}
+
+#[derive(Debug, Default)]
+struct Loop {
+    /// the flag of `GOING`, `CONTINUED` or `BROKEN`, once the body jumps
+    flag: Option<Identifier>,
+    breaks: bool,
+}
+
+/// the `--jack-ext` dialect, which the parser turns into standard jack as it goes so the rest
+/// of the compiler never sees it:
+/// - `for (init; condition; update) { ... }` is the init followed by a while loop, with any of
+///   the three left out. Init and update are assignments without `let` and `;`.
+/// - `break;` and `continue;` set a flag local of the loop, the statements after them only
+///   run while it's unset.
+/// - `else if (...) { ... }` is an if statement in the else block.
+/// - `'a'` is the integer constant 97.
+/// - `let a[i] += x;` is `let a[i] = a[i] + x;` with `i` evaluated once, for `+ - * / & |`.
+/// - operators bind by `PRECEDENCE` instead of applying from left to right, the parse tree has
+///   parentheses where they're needed.
+impl Parser<'_> {
+    pub(super) fn extended_statement(&mut self) -> Parse<Vec<Statement>> {
+        match self.peek().map(|token| token.t) {
+            Some(TokenType::Keyword(Keyword::Let)) => {
+                let start = self.expect_keyword(Keyword::Let)?;
+                let mut statements = self.assignment()?;
+                let end = self.expect_symbol(';')?;
+                if let Some(Statement::Let { span, .. }) = statements.last_mut() {
+                    *span = start.to(end);
+                }
+                Ok(statements)
+            }
+            Some(TokenType::Keyword(Keyword::While)) => {
+                let start = self.expect_keyword(Keyword::While)?;
+                let condition = self.condition()?;
+                let (body, end, jumps) = self.loop_body()?;
+                Ok(desugar_loop(
+                    condition,
+                    body,
+                    Vec::new(),
+                    jumps,
+                    start.to(end),
+                ))
+            }
+            Some(TokenType::Keyword(Keyword::For)) => self.for_statement(),
+            Some(TokenType::Keyword(Keyword::Break | Keyword::Continue)) => self.jump(),
+            _ => self.statement().map(|statement| vec![statement]),
+        }
+    }
+
+    /// `name ([index])? (= | op=) expression`, the statements it desugars to.
+    fn assignment(&mut self) -> Parse<Vec<Statement>> {
+        let target = self.identifier()?;
+        let index = match self.eat_symbol('[') {
+            true => {
+                let index = self.expression()?;
+                self.expect_symbol(']')?;
+                Some(index)
+            }
+            false => None,
+        };
+        let op = self.compound_operator();
+        if op.is_none() {
+            self.expect_symbol('=')?;
+        }
+        let value = self.expression()?;
+        let span = target.span.to(value.span);
+        let Some(op) = op else {
+            return Ok(vec![Statement::Let {
+                target,
+                index,
+                value,
+                span,
+            }]);
+        };
+
+        let mut statements = Vec::new();
+        let index = match index {
+            Some(index)
+                if index.rest.is_empty()
+                    && matches!(index.first, Term::Integer(..) | Term::Variable(_)) =>
+            {
+                Some(index)
+            }
+            Some(index) => {
+                let temporary = self.hidden_local("index", index.span);
+                statements.push(assign(&temporary, index));
+                Some(single(Term::Variable(temporary)))
+            }
+            None => None,
+        };
+        let current = match &index {
+            Some(index) => Term::Index(target.clone(), Box::new(index.clone()), target.span),
+            None => Term::Variable(target.clone()),
+        };
+        let value = Expression {
+            first: current,
+            rest: vec![(op, into_term(value))],
+            span,
+        };
+        statements.push(Statement::Let {
+            target,
+            index,
+            value,
+            span,
+        });
+        Ok(statements)
+    }
+
+    /// skips `op=` of a compound assignment, the operator and the equals sign without space
+    /// between them.
+    fn compound_operator(&mut self) -> Option<BinaryOp> {
+        let (operator, equals) = (self.peek()?, self.tokens.get(self.pos + 1)?);
+        let TokenType::Symbol(symbol) = operator.t else {
+            return None;
+        };
+        let op = BinaryOp::from_symbol(symbol).filter(|op| COMPOUND.contains(op))?;
+        if equals.t != TokenType::Symbol('=') || equals.start != operator.end {
+            return None;
+        }
+        self.pos += 2;
+        Some(op)
+    }
+
+    fn for_statement(&mut self) -> Parse<Vec<Statement>> {
+        let start = self.expect_keyword(Keyword::For)?;
+        self.expect_symbol('(')?;
+        let mut statements = match self.is_symbol(';') {
+            true => Vec::new(),
+            false => self.assignment()?,
+        };
+        self.expect_symbol(';')?;
+        let condition = match self.is_symbol(';') {
+            true => single(Term::Keyword(KeywordConstant::True, start)),
+            false => self.expression()?,
+        };
+        self.expect_symbol(';')?;
+        let update = match self.is_symbol(')') {
+            true => Vec::new(),
+            false => self.assignment()?,
+        };
+        self.expect_symbol(')')?;
+        let (body, end, jumps) = self.loop_body()?;
+        statements.extend(desugar_loop(condition, body, update, jumps, start.to(end)));
+        Ok(statements)
+    }
+
+    /// `break;` or `continue;`, which set the flag of the innermost loop.
+    fn jump(&mut self) -> Parse<Vec<Statement>> {
+        let (keyword, start) = self.keyword()?;
+        let span = start.to(self.expect_symbol(';')?);
+        let Some(innermost) = self.desugaring.loops.len().checked_sub(1) else {
+            // the statement itself is fine, parsing goes on after it
+            let message = format!("'{}' outside of a loop", keyword);
+            self.errors.push(Diagnostic::new(span, message));
+            return Ok(Vec::new());
+        };
+        let flag = match self.desugaring.loops[innermost].flag.clone() {
+            Some(flag) => flag,
+            None => {
+                let flag = self.hidden_local("jump", span);
+                self.desugaring.loops[innermost].flag = Some(flag.clone());
+                flag
+            }
+        };
+        let value = match keyword {
+            Keyword::Break => {
+                self.desugaring.loops[innermost].breaks = true;
+                BROKEN
+            }
+            _ => CONTINUED,
+        };
+        Ok(vec![assign(&flag, single(integer(value, span)))])
+    }
+
+    /// the block of a loop, and the span of its closing brace.
+    fn loop_body(&mut self) -> Parse<(Vec<Statement>, Span, Loop)> {
+        self.desugaring.loops.push(Loop::default());
+        let body = self.block();
+        let jumps = self.desugaring.loops.pop().unwrap_or_default();
+        body.map(|(body, end)| (body, end, jumps))
+    }
+
+    /// a local for the desugared code, `$` keeps it apart from the names of the program.
+    fn hidden_local(&mut self, name: &str, span: Span) -> Identifier {
+        let identifier = Identifier {
+            name: format!("${}{}", name, self.desugaring.locals.len()),
+            span,
+        };
+        self.desugaring.locals.push(VarDec {
+            ty: Type::Int,
+            names: vec![identifier.clone()],
+            span,
+        });
+        identifier
+    }
+
+    /// the expression from the operators of `PRECEDENCE[level]` on, operands binding tighter
+    /// are parenthesized.
+    pub(super) fn ranked_expression(&mut self, level: usize) -> Parse<Expression> {
+        let Some(ops) = PRECEDENCE.get(level) else {
+            return self.term().map(single);
+        };
+        let first = self.ranked_expression(level + 1)?;
+        let mut rest = Vec::new();
+        while let Some(TokenType::Symbol(symbol)) = self.peek().map(|token| token.t) {
+            let Some(op) = BinaryOp::from_symbol(symbol).filter(|op| ops.contains(op)) else {
+                break;
+            };
+            self.next();
+            rest.push((op, into_term(self.ranked_expression(level + 1)?)));
+        }
+        let Some((_, last)) = rest.last() else {
+            return Ok(first);
+        };
+        Ok(Expression {
+            span: first.span.to(last.span()),
+            first: into_term(first),
+            rest,
+        })
+    }
+}
+
+/// the while loop running the body and then the update. A loop that jumps resets its flag
+/// every round and skips what follows a jump, one that breaks also checks the condition
+/// itself so the condition isn't evaluated once more after the break:
+///
+/// ```text
+/// let flag = 0;
+/// while (flag < 2) {
+///     if (condition) { let flag = 0; body; if (flag < 2) { update } }
+///     else { let flag = 2; }
+/// }
+/// ```
+fn desugar_loop(
+    condition: Expression,
+    mut body: Vec<Statement>,
+    update: Vec<Statement>,
+    jumps: Loop,
+    span: Span,
+) -> Vec<Statement> {
+    let Some(flag) = jumps.flag else {
+        body.extend(update);
+        return vec![Statement::While {
+            condition,
+            body,
+            span,
+        }];
+    };
+    let mut round = vec![assign(&flag, single(integer(GOING, span)))];
+    round.extend(guard(body, &flag));
+    if !jumps.breaks {
+        round.extend(update);
+        return vec![Statement::While {
+            condition,
+            body: round,
+            span,
+        }];
+    }
+    if !update.is_empty() {
+        round.push(Statement::If {
+            condition: compare(&flag, BinaryOp::Lt, BROKEN),
+            then: update,
+            otherwise: None,
+            span,
+        });
+    }
+    vec![
+        assign(&flag, single(integer(GOING, span))),
+        Statement::While {
+            condition: compare(&flag, BinaryOp::Lt, BROKEN),
+            body: vec![Statement::If {
+                condition,
+                then: round,
+                otherwise: Some(vec![assign(&flag, single(integer(BROKEN, span)))]),
+                span,
+            }],
+            span,
+        },
+    ]
+}
+
+/// wraps the statements after one that can set the flag in `if (flag = 0) { ... }`, in the
+/// blocks of if statements too. Loops inside have flags of their own.
+fn guard(statements: Vec<Statement>, flag: &Identifier) -> Vec<Statement> {
+    let mut guarded = Vec::with_capacity(statements.len());
+    let mut statements = statements.into_iter();
+    while let Some(statement) = statements.next() {
+        let statement = match statement {
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+                span,
+            } => Statement::If {
+                condition,
+                then: guard(then, flag),
+                otherwise: otherwise.map(|otherwise| guard(otherwise, flag)),
+                span,
+            },
+            statement => statement,
+        };
+        let jumps = sets(&statement, flag);
+        let span = statement.span();
+        guarded.push(statement);
+        let rest = match jumps {
+            true => statements.by_ref().collect::<Vec<Statement>>(),
+            false => continue,
+        };
+        if !rest.is_empty() {
+            guarded.push(Statement::If {
+                condition: compare(flag, BinaryOp::Eq, GOING),
+                then: guard(rest, flag),
+                otherwise: None,
+                span,
+            });
+        }
+    }
+    guarded
+}
+
+fn sets(statement: &Statement, flag: &Identifier) -> bool {
+    match statement {
+        Statement::Let { target, .. } => target.name == flag.name,
+        Statement::If {
+            then, otherwise, ..
+        } => then
+            .iter()
+            .chain(otherwise.iter().flatten())
+            .any(|statement| sets(statement, flag)),
+        _ => false,
+    }
+}
+
+fn single(term: Term) -> Expression {
+    Expression {
+        span: term.span(),
+        first: term,
+        rest: Vec::new(),
+    }
+}
+
+/// the expression as a term, parenthesized unless it is one already.
+fn into_term(expression: Expression) -> Term {
+    match expression.rest.is_empty() {
+        true => expression.first,
+        false => {
+            let span = expression.span;
+            Term::Parenthesized(Box::new(expression), span)
+        }
+    }
+}
+
+fn integer(value: u16, span: Span) -> Term {
+    Term::Integer(value, span)
+}
+
+fn assign(target: &Identifier, value: Expression) -> Statement {
+    Statement::Let {
+        target: target.clone(),
+        index: None,
+        span: target.span.to(value.span),
+        value,
+    }
+}
+
+/// `flag op value`.
+fn compare(flag: &Identifier, op: BinaryOp, value: u16) -> Expression {
+    Expression {
+        first: Term::Variable(flag.clone()),
+        rest: vec![(op, integer(value, flag.span))],
+        span: flag.span,
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn parse(src: &str) -> Result<Class, String> {
+        Parser::with_extensions(src, true).into_class()
+    }
+
+    /// the statements of `f` in a class with the body.
+    fn statements(body: &str) -> Vec<Statement> {
+        let src = format!(
+            "class A {{ function void f() {{ var int i, x; var Array a; {body} return; }} }}"
+        );
+        let mut class = parse(&src).unwrap();
+        let mut statements = class.subroutines.remove(0).statements;
+        statements.pop();
+        statements
+    }
+
+    #[test]
+    fn test_precedence_parenthesizes() {
+        let mut parser = Parser::with_extensions("1 | 2 + 3 * 4 - 5 < 6", true);
+        let expression = parser.expression().unwrap();
+        // 1 | (((2 + (3 * 4)) - 5) < 6)
+        assert_eq!(expression.rest.len(), 1);
+        let Term::Parenthesized(comparison, _) = &expression.rest[0].1 else {
+            panic!("expected parentheses around the comparison");
+        };
+        assert_eq!(comparison.rest[0].0, BinaryOp::Lt);
+        let Term::Parenthesized(sum, _) = &comparison.first else {
+            panic!("expected parentheses around the sum");
+        };
+        assert_eq!(sum.rest.len(), 2);
+        assert!(matches!(sum.rest[0].1, Term::Parenthesized(..)));
+        assert_eq!(expression.span, Span::new(0, 21));
+    }
+
+    #[test]
+    fn test_desugars_to_standard_statements() {
+        let for_loop = statements("for (i = 0; i < 3; i += 1) { if (i = 1) { continue; } else if (x) { break; } let x = 1; }");
+        // init, reset of the flag, while loop
+        assert_eq!(for_loop.len(), 3);
+        let Statement::While { body, .. } = &for_loop[2] else {
+            panic!("expected a while loop");
+        };
+        let Statement::If { then, .. } = &body[0] else {
+            panic!("expected the condition of the for loop");
+        };
+        // reset, the jumping if, the guarded rest and the update
+        assert_eq!(then.len(), 4);
+        let Statement::If { otherwise, .. } = &then[1] else {
+            panic!("expected an if statement");
+        };
+        assert!(matches!(otherwise.as_deref(), Some([Statement::If { .. }])));
+
+        let compound = statements("let a[i + 1] -= 'a';");
+        assert_eq!(compound.len(), 2);
+        assert!(matches!(&compound[0], Statement::Let { target, .. } if target.name == "$index0"));
+    }
+
+    #[test]
+    fn test_hidden_locals_are_declared() {
+        let class = parse("class A { function void f() { var int i; while (true) { while (i) { break; } continue; } let i += 1; let i -= 1; return; } }").unwrap();
+        let locals = class.subroutines[0]
+            .locals
+            .iter()
+            .flat_map(|local| &local.names)
+            .map(|name| name.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(locals, vec!["i", "$jump0", "$jump1"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("class A { function void f() { break; let x = 1 } }").unwrap_err(),
+            "line 1, column 31: 'break' outside of a loop\nline 1, column 48: expected ';', found '}'"
+        );
+        // an operator and an equals sign apart are no compound assignment
+        assert!(parse("class A { function void f() { let x + = 1; return; } }").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::ast::Type;
+use std::collections::HashMap;
+use vm_translator_rust::parser::Segment;
+
+/// where a variable lives, statics and fields belong to the class, arguments and locals to the
+/// subroutine being compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Static,
+    Field,
+    Argument,
+    Local,
+}
+
+impl Kind {
+    /// the vm segment holding variables of this kind, fields are reached through `this`.
+    pub fn segment(&self) -> Segment {
+        match self {
+            Kind::Static => Segment::Static,
+            Kind::Field => Segment::This,
+            Kind::Argument => Segment::Argument,
+            Kind::Local => Segment::Local,
+        }
+    }
+
+    fn is_class_scope(&self) -> bool {
+        matches!(self, Kind::Static | Kind::Field)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub ty: Type,
+    pub kind: Kind,
+    /// the index in the kind's segment
+    pub index: u16,
+}
+
+/// the variables in scope, subroutine variables shadow the class variables of the same name.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    class: HashMap<String, Symbol>,
+    subroutine: HashMap<String, Symbol>,
+    counts: [u16; 4],
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// forgets the arguments and locals of the previous subroutine.
+    pub fn start_subroutine(&mut self) {
+        self.subroutine.clear();
+        self.counts[Kind::Argument as usize] = 0;
+        self.counts[Kind::Local as usize] = 0;
+    }
+
+    /// adds a variable at the next index of its kind, returns false if the scope already has a
+    /// variable of that name.
+    pub fn define(&mut self, name: &str, ty: Type, kind: Kind) -> bool {
+        let index = self.counts[kind as usize];
+        let scope = match kind.is_class_scope() {
+            true => &mut self.class,
+            false => &mut self.subroutine,
+        };
+        if scope.contains_key(name) {
+            return false;
+        }
+        scope.insert(name.to_string(), Symbol { ty, kind, index });
+        self.counts[kind as usize] += 1;
+        true
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.subroutine.get(name).or_else(|| self.class.get(name))
+    }
+
+    /// the variables in scope, the ones a subroutine variable shadows left out.
+    pub fn visible(&self) -> impl Iterator<Item = (&str, &Symbol)> {
+        let shadowed = |name: &String| self.subroutine.contains_key(name);
+        self.subroutine
+            .iter()
+            .chain(self.class.iter().filter(move |(name, _)| !shadowed(name)))
+            .map(|(name, symbol)| (name.as_str(), symbol))
+    }
+
+    /// how many variables of the kind are defined.
+    pub fn count(&self, kind: Kind) -> u16 {
+        self.counts[kind as usize]
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn test_scopes() {
+        let mut symbols = SymbolTable::new();
+        assert!(symbols.define("x", Type::Int, Kind::Field));
+        assert!(symbols.define("y", Type::Int, Kind::Field));
+        assert!(symbols.define("count", Type::Int, Kind::Static));
+        assert!(!symbols.define("x", Type::Char, Kind::Static));
+
+        symbols.start_subroutine();
+        symbols.define("this", Type::Int, Kind::Argument);
+        symbols.define("x", Type::Boolean, Kind::Argument);
+        assert_eq!(symbols.get("x").unwrap().kind, Kind::Argument);
+        assert_eq!(symbols.get("x").unwrap().index, 1);
+        assert_eq!(symbols.get("y").unwrap().index, 1);
+
+        let mut visible = symbols
+            .visible()
+            .map(|(name, symbol)| (name, symbol.kind))
+            .collect::<Vec<_>>();
+        visible.sort_by_key(|(name, _)| *name);
+        assert_eq!(
+            visible,
+            vec![
+                ("count", Kind::Static),
+                ("this", Kind::Argument),
+                ("x", Kind::Argument),
+                ("y", Kind::Field)
+            ]
+        );
+
+        symbols.start_subroutine();
+        assert_eq!(symbols.get("x").unwrap().kind, Kind::Field);
+        assert_eq!(symbols.count(Kind::Field), 2);
+        assert_eq!(symbols.count(Kind::Argument), 0);
+    }
+}
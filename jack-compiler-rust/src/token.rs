@@ -0,0 +1,482 @@
+use crate::ast::Span;
+use crate::diagnostic::Diagnostic;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    Class,
+    Constructor,
+    Function,
+    Method,
+    Field,
+    Static,
+    Var,
+    Int,
+    Char,
+    Boolean,
+    Void,
+    True,
+    False,
+    Null,
+    This,
+    Let,
+    Do,
+    If,
+    Else,
+    While,
+    Return,
+    /// the keywords of the `--jack-ext` dialect, plain identifiers in standard jack
+    For,
+    Break,
+    Continue,
+}
+
+impl Keyword {
+    pub const ALL: [Keyword; 21] = [
+        Keyword::Class,
+        Keyword::Constructor,
+        Keyword::Function,
+        Keyword::Method,
+        Keyword::Field,
+        Keyword::Static,
+        Keyword::Var,
+        Keyword::Int,
+        Keyword::Char,
+        Keyword::Boolean,
+        Keyword::Void,
+        Keyword::True,
+        Keyword::False,
+        Keyword::Null,
+        Keyword::This,
+        Keyword::Let,
+        Keyword::Do,
+        Keyword::If,
+        Keyword::Else,
+        Keyword::While,
+        Keyword::Return,
+    ];
+
+    pub const EXTENSIONS: [Keyword; 3] = [Keyword::For, Keyword::Break, Keyword::Continue];
+
+    pub fn parse(word: &str) -> Option<Keyword> {
+        Keyword::ALL
+            .into_iter()
+            .find(|keyword| keyword.name() == word)
+    }
+
+    /// the keyword of the `--jack-ext` dialect, which has a few more.
+    pub fn parse_extended(word: &str) -> Option<Keyword> {
+        Keyword::parse(word).or_else(|| {
+            Keyword::EXTENSIONS
+                .into_iter()
+                .find(|keyword| keyword.name() == word)
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Keyword::Class => "class",
+            Keyword::Constructor => "constructor",
+            Keyword::Function => "function",
+            Keyword::Method => "method",
+            Keyword::Field => "field",
+            Keyword::Static => "static",
+            Keyword::Var => "var",
+            Keyword::Int => "int",
+            Keyword::Char => "char",
+            Keyword::Boolean => "boolean",
+            Keyword::Void => "void",
+            Keyword::True => "true",
+            Keyword::False => "false",
+            Keyword::Null => "null",
+            Keyword::This => "this",
+            Keyword::Let => "let",
+            Keyword::Do => "do",
+            Keyword::If => "if",
+            Keyword::Else => "else",
+            Keyword::While => "while",
+            Keyword::Return => "return",
+            Keyword::For => "for",
+            Keyword::Break => "break",
+            Keyword::Continue => "continue",
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+pub const SYMBOLS: &str = "{}()[].,;+-*/&|<>=~";
+
+/// the largest integer constant, negative numbers are negated constants.
+pub const MAX_INTEGER: u16 = 32767;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    /// `// ...` up to the end of the line
+    Line,
+    /// `/* ... */`
+    Block,
+    /// `/** ... */`, documenting the declaration after it
+    Doc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenType {
+    Keyword(Keyword),
+    Symbol(char),
+    IntegerConstant(u16),
+    /// the span includes the quotes, see `Token::text`
+    StringConstant,
+    /// `'a'` of the `--jack-ext` dialect, the code of the character. The span includes the
+    /// quotes too.
+    CharConstant(u16),
+    Identifier,
+    Comment(CommentKind),
+}
+
+/// a token and the byte range of the source it spans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub t: TokenType,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub fn new(t: TokenType, start: usize, end: usize) -> Self {
+        Self { t, start, end }
+    }
+
+    /// the source of the token, without the quotes of a string or character constant.
+    pub fn text<'a>(&self, src: &'a str) -> &'a str {
+        match self.t {
+            TokenType::StringConstant | TokenType::CharConstant(_) => {
+                &src[self.start + 1..self.end - 1]
+            }
+            _ => &src[self.start..self.end],
+        }
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.t, TokenType::Comment(_))
+    }
+}
+
+/// the 1 based line and column of a byte offset, for error messages.
+pub fn line_column(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset.min(src.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
+
+/// splits jack source into tokens, comments included so tools like a formatter can keep them.
+/// Whitespace is skipped, the spans still tell where it was. After an error the iterator goes
+/// on with the rest of the source.
+pub struct Tokenizer<'a> {
+    src: &'a str,
+    pos: usize,
+    /// the `--jack-ext` dialect, with character constants and the `Keyword::EXTENSIONS`
+    extensions: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            extensions: false,
+        }
+    }
+
+    pub fn with_extensions(mut self, extensions: bool) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// all the tokens of the source, or the first error with its line and column.
+    pub fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+        Tokenizer::new(src).tokens()
+    }
+
+    /// the rest of the tokens, or the first error with its line and column.
+    pub fn tokens(self) -> Result<Vec<Token>, String> {
+        let src = self.src;
+        self.collect::<Result<Vec<Token>, Diagnostic>>()
+            .map_err(|diagnostic| diagnostic.render(src))
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, Diagnostic> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let Some(c) = self.peek() else {
+            return Ok(None);
+        };
+        let t = if self.rest().starts_with("//") {
+            self.advance_while(|c| c != '\n');
+            TokenType::Comment(CommentKind::Line)
+        } else if self.rest().starts_with("/*") {
+            self.block_comment()?
+        } else if SYMBOLS.contains(c) {
+            self.advance();
+            TokenType::Symbol(c)
+        } else if c == '"' {
+            self.string_constant()?;
+            TokenType::StringConstant
+        } else if c == '\'' && self.extensions {
+            TokenType::CharConstant(self.char_constant()?)
+        } else if c.is_ascii_digit() {
+            self.advance_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            let text = &self.src[start..self.pos];
+            match text.parse::<u16>() {
+                Ok(value) if value <= MAX_INTEGER => TokenType::IntegerConstant(value),
+                _ if text.bytes().all(|b| b.is_ascii_digit()) => {
+                    return Err(self.error(
+                        start,
+                        &format!("integer constant {} is larger than {}", text, MAX_INTEGER),
+                    ))
+                }
+                _ => return Err(self.error(start, &format!("invalid integer constant {}", text))),
+            }
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            self.advance_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            let word = &self.src[start..self.pos];
+            let keyword = match self.extensions {
+                true => Keyword::parse_extended(word),
+                false => Keyword::parse(word),
+            };
+            match keyword {
+                Some(keyword) => TokenType::Keyword(keyword),
+                None => TokenType::Identifier,
+            }
+        } else {
+            self.advance();
+            return Err(self.error(start, &format!("unexpected character {:?}", c)));
+        };
+        Ok(Some(Token::new(t, start, self.pos)))
+    }
+
+    fn block_comment(&mut self) -> Result<TokenType, Diagnostic> {
+        let start = self.pos;
+        // `/**/` is an empty block comment, not the start of a doc comment
+        let kind = match self.rest().starts_with("/**") && !self.rest().starts_with("/**/") {
+            true => CommentKind::Doc,
+            false => CommentKind::Block,
+        };
+        match self.src[start + 2..].find("*/") {
+            Some(end) => {
+                self.pos = start + 2 + end + 2;
+                Ok(TokenType::Comment(kind))
+            }
+            None => {
+                self.pos = self.src.len();
+                Err(self.error(start, "unterminated comment"))
+            }
+        }
+    }
+
+    /// string constants can't span lines or contain a double quote.
+    fn string_constant(&mut self) -> Result<(), Diagnostic> {
+        let start = self.pos;
+        self.advance();
+        self.advance_while(|c| c != '"' && c != '\n');
+        if self.peek() != Some('"') {
+            return Err(self.error(start, "unterminated string constant"));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    /// a single printable ascii character between single quotes, `'''` is the quote.
+    fn char_constant(&mut self) -> Result<u16, Diagnostic> {
+        let start = self.pos;
+        self.advance();
+        let c = match self.peek() {
+            Some('\n') | None => return Err(self.error(start, "unterminated character constant")),
+            Some(c) => c,
+        };
+        self.advance();
+        if self.peek() != Some('\'') {
+            self.advance_while(|c| c != '\'' && c != '\n');
+            return match self.peek() {
+                Some('\'') => {
+                    self.advance();
+                    Err(self.error(start, "a character constant holds a single character"))
+                }
+                _ => Err(self.error(start, "unterminated character constant")),
+            };
+        }
+        self.advance();
+        match c {
+            ' '..='~' => Ok(c as u16),
+            _ => Err(self.error(start, &format!("{:?} is not a jack character", c))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.advance_while(char::is_whitespace);
+    }
+
+    fn advance_while(&mut self, predicate: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&predicate) {
+            self.advance();
+        }
+    }
+
+    fn advance(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// an error about the source from `start` to where the tokenizer stopped.
+    fn error(&self, start: usize, message: &str) -> Diagnostic {
+        Diagnostic::new(Span::new(start, self.pos), message)
+    }
+}
+
+impl Iterator for Tokenizer<'_> {
+    type Item = Result<Token, Diagnostic>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn types(src: &str) -> Vec<TokenType> {
+        Tokenizer::tokenize(src)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.t)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_statement() {
+        let src = "let x = a[2] + \"hi there\";";
+        let tokens = Tokenizer::tokenize(src).unwrap();
+        assert_eq!(
+            tokens.iter().map(|token| token.t).collect::<Vec<_>>(),
+            vec![
+                TokenType::Keyword(Keyword::Let),
+                TokenType::Identifier,
+                TokenType::Symbol('='),
+                TokenType::Identifier,
+                TokenType::Symbol('['),
+                TokenType::IntegerConstant(2),
+                TokenType::Symbol(']'),
+                TokenType::Symbol('+'),
+                TokenType::StringConstant,
+                TokenType::Symbol(';'),
+            ]
+        );
+        assert_eq!(tokens[1].text(src), "x");
+        assert_eq!(tokens[8].text(src), "hi there");
+        assert_eq!((tokens[8].start, tokens[8].end), (15, 25));
+    }
+
+    #[test]
+    fn test_comments() {
+        let src = "// line\n/* block */ /** doc */ /**/ x/2";
+        assert_eq!(
+            types(src),
+            vec![
+                TokenType::Comment(CommentKind::Line),
+                TokenType::Comment(CommentKind::Block),
+                TokenType::Comment(CommentKind::Doc),
+                TokenType::Comment(CommentKind::Block),
+                TokenType::Identifier,
+                TokenType::Symbol('/'),
+                TokenType::IntegerConstant(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keywords_and_identifiers() {
+        assert_eq!(
+            types("classy class _x1 do"),
+            vec![
+                TokenType::Identifier,
+                TokenType::Keyword(Keyword::Class),
+                TokenType::Identifier,
+                TokenType::Keyword(Keyword::Do),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |src| Tokenizer::tokenize(src).unwrap_err();
+        assert_eq!(
+            error("let s = \"open\n"),
+            "line 1, column 9: unterminated string constant"
+        );
+        assert_eq!(
+            error("x\n  /* never"),
+            "line 2, column 3: unterminated comment"
+        );
+        assert_eq!(
+            error("32768"),
+            "line 1, column 1: integer constant 32768 is larger than 32767"
+        );
+        assert_eq!(
+            error("12ab"),
+            "line 1, column 1: invalid integer constant 12ab"
+        );
+        assert_eq!(error("a # b"), "line 1, column 3: unexpected character '#'");
+    }
+
+    #[test]
+    fn test_extensions() {
+        let extended = |src| {
+            Tokenizer::new(src)
+                .with_extensions(true)
+                .tokens()
+                .map(|tokens| tokens.into_iter().map(|token| token.t).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            extended("for 'a' ''' break"),
+            Ok(vec![
+                TokenType::Keyword(Keyword::For),
+                TokenType::CharConstant(97),
+                TokenType::CharConstant(39),
+                TokenType::Keyword(Keyword::Break),
+            ])
+        );
+        assert_eq!(types("for continue"), vec![TokenType::Identifier; 2]);
+        assert!(Tokenizer::tokenize("'a'").is_err());
+        assert_eq!(
+            extended("'ab'").unwrap_err(),
+            "line 1, column 1: a character constant holds a single character"
+        );
+        assert_eq!(
+            extended("x = 'a").unwrap_err(),
+            "line 1, column 5: unterminated character constant"
+        );
+    }
+
+    #[test]
+    fn test_continues_after_errors() {
+        let results = Tokenizer::new("a # 99999 b").collect::<Vec<_>>();
+        assert_eq!(results.len(), 4);
+        assert!(results[1].is_err() && results[2].is_err());
+        assert_eq!(results[3], Ok(Token::new(TokenType::Identifier, 10, 11)));
+    }
+}
@@ -0,0 +1,359 @@
+use crate::ast::*;
+use crate::token::{Token, TokenType};
+
+/// the reference xml files have windows line endings.
+const NEWLINE: &str = "\r\n";
+
+/// the `<tokens>` document of the course's tokenizer tests, one element per token with comments
+/// left out.
+pub fn tokens_xml(src: &str, tokens: &[Token]) -> String {
+    let mut out = format!("<tokens>{}", NEWLINE);
+    for token in tokens.iter().filter(|token| !token.is_comment()) {
+        out.push_str(&token_element(src, token));
+        out.push_str(NEWLINE);
+    }
+    out.push_str("</tokens>");
+    out.push_str(NEWLINE);
+    out
+}
+
+/// `<keyword> class </keyword>` and the like.
+pub fn token_element(src: &str, token: &Token) -> String {
+    let tag = match token.t {
+        TokenType::Keyword(_) => "keyword",
+        TokenType::Symbol(_) => "symbol",
+        TokenType::IntegerConstant(_) => "integerConstant",
+        TokenType::StringConstant => "stringConstant",
+        TokenType::CharConstant(_) => "charConstant",
+        TokenType::Identifier => "identifier",
+        TokenType::Comment(_) => "comment",
+    };
+    format!("<{}> {} </{}>", tag, escape(token.text(src)), tag)
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// the parse tree of the class in the xml format of the course's parser tests: one element per
+/// grammar rule holding the tokens it spans, indented by two spaces per level.
+pub fn class_xml(class: &Class) -> String {
+    let mut writer = XmlWriter::default();
+    writer.class(class);
+    writer.out
+}
+
+#[derive(Default)]
+struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn class(&mut self, class: &Class) {
+        self.open("class");
+        self.keyword("class");
+        self.identifier(&class.name);
+        self.symbol('{');
+        for var in &class.vars {
+            self.open("classVarDec");
+            self.keyword(match var.kind {
+                ClassVarKind::Static => "static",
+                ClassVarKind::Field => "field",
+            });
+            self.ty(&var.ty);
+            self.names(&var.names);
+            self.close("classVarDec");
+        }
+        for subroutine in &class.subroutines {
+            self.subroutine(subroutine);
+        }
+        self.symbol('}');
+        self.close("class");
+    }
+
+    fn subroutine(&mut self, subroutine: &Subroutine) {
+        self.open("subroutineDec");
+        self.keyword(match subroutine.kind {
+            SubroutineKind::Constructor => "constructor",
+            SubroutineKind::Function => "function",
+            SubroutineKind::Method => "method",
+        });
+        match &subroutine.return_type {
+            Some(ty) => self.ty(ty),
+            None => self.keyword("void"),
+        }
+        self.identifier(&subroutine.name);
+        self.symbol('(');
+        self.open("parameterList");
+        for (i, parameter) in subroutine.parameters.iter().enumerate() {
+            if i > 0 {
+                self.symbol(',');
+            }
+            self.ty(&parameter.ty);
+            self.identifier(&parameter.name);
+        }
+        self.close("parameterList");
+        self.symbol(')');
+        self.open("subroutineBody");
+        self.symbol('{');
+        for local in &subroutine.locals {
+            self.open("varDec");
+            self.keyword("var");
+            self.ty(&local.ty);
+            self.names(&local.names);
+            self.close("varDec");
+        }
+        self.statements(&subroutine.statements);
+        self.symbol('}');
+        self.close("subroutineBody");
+        self.close("subroutineDec");
+    }
+
+    /// the names of a declaration separated by commas, and its semicolon.
+    fn names(&mut self, names: &[Identifier]) {
+        for (i, name) in names.iter().enumerate() {
+            if i > 0 {
+                self.symbol(',');
+            }
+            self.identifier(name);
+        }
+        self.symbol(';');
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        match ty {
+            Type::Int => self.keyword("int"),
+            Type::Char => self.keyword("char"),
+            Type::Boolean => self.keyword("boolean"),
+            Type::Class(name) => self.identifier(name),
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        self.open("statements");
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.close("statements");
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let {
+                target,
+                index,
+                value,
+                ..
+            } => {
+                self.open("letStatement");
+                self.keyword("let");
+                self.identifier(target);
+                if let Some(index) = index {
+                    self.symbol('[');
+                    self.expression(index);
+                    self.symbol(']');
+                }
+                self.symbol('=');
+                self.expression(value);
+                self.symbol(';');
+                self.close("letStatement");
+            }
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                self.open("ifStatement");
+                self.keyword("if");
+                self.condition(condition);
+                self.block(then);
+                if let Some(otherwise) = otherwise {
+                    self.keyword("else");
+                    self.block(otherwise);
+                }
+                self.close("ifStatement");
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.open("whileStatement");
+                self.keyword("while");
+                self.condition(condition);
+                self.block(body);
+                self.close("whileStatement");
+            }
+            Statement::Do { call, .. } => {
+                self.open("doStatement");
+                self.keyword("do");
+                self.call(call);
+                self.symbol(';');
+                self.close("doStatement");
+            }
+            Statement::Return { value, .. } => {
+                self.open("returnStatement");
+                self.keyword("return");
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+                self.symbol(';');
+                self.close("returnStatement");
+            }
+        }
+    }
+
+    fn condition(&mut self, condition: &Expression) {
+        self.symbol('(');
+        self.expression(condition);
+        self.symbol(')');
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.symbol('{');
+        self.statements(statements);
+        self.symbol('}');
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        self.open("expression");
+        self.term(&expression.first);
+        for (op, term) in &expression.rest {
+            self.symbol(op.symbol());
+            self.term(term);
+        }
+        self.close("expression");
+    }
+
+    fn term(&mut self, term: &Term) {
+        self.open("term");
+        match term {
+            Term::Integer(value, _) => self.leaf("integerConstant", &value.to_string()),
+            Term::String(value, _) => self.leaf("stringConstant", value),
+            Term::Keyword(constant, _) => self.keyword(match constant {
+                KeywordConstant::True => "true",
+                KeywordConstant::False => "false",
+                KeywordConstant::Null => "null",
+                KeywordConstant::This => "this",
+            }),
+            Term::Variable(name) => self.identifier(name),
+            Term::Index(name, index, _) => {
+                self.identifier(name);
+                self.symbol('[');
+                self.expression(index);
+                self.symbol(']');
+            }
+            Term::Call(call) => self.call(call),
+            Term::Parenthesized(expression, _) => {
+                self.symbol('(');
+                self.expression(expression);
+                self.symbol(')');
+            }
+            Term::Unary(op, term, _) => {
+                self.symbol(op.symbol());
+                self.term(term);
+            }
+        }
+        self.close("term");
+    }
+
+    fn call(&mut self, call: &SubroutineCall) {
+        if let Some(receiver) = &call.receiver {
+            self.identifier(receiver);
+            self.symbol('.');
+        }
+        self.identifier(&call.name);
+        self.symbol('(');
+        self.open("expressionList");
+        for (i, argument) in call.arguments.iter().enumerate() {
+            if i > 0 {
+                self.symbol(',');
+            }
+            self.expression(argument);
+        }
+        self.close("expressionList");
+        self.symbol(')');
+    }
+
+    fn keyword(&mut self, keyword: &str) {
+        self.leaf("keyword", keyword);
+    }
+
+    fn symbol(&mut self, symbol: char) {
+        self.leaf("symbol", &symbol.to_string());
+    }
+
+    fn identifier(&mut self, identifier: &Identifier) {
+        self.leaf("identifier", &identifier.name);
+    }
+
+    fn leaf(&mut self, tag: &str, text: &str) {
+        self.line(&format!("<{}> {} </{}>", tag, escape(text), tag));
+    }
+
+    fn open(&mut self, tag: &str) {
+        self.line(&format!("<{}>", tag));
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.line(&format!("</{}>", tag));
+    }
+
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(line);
+        self.out.push_str(NEWLINE);
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::token::Tokenizer;
+
+    #[test]
+    fn test_tokens_xml() {
+        let src = "if (x < 1) { // less\n  do Output.printString(\"a&b\"); }";
+        let tokens = Tokenizer::tokenize(src).unwrap();
+        let xml = tokens_xml(src, &tokens);
+        let lines = xml.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0], "<tokens>");
+        assert_eq!(lines[1], "<keyword> if </keyword>");
+        assert_eq!(lines[4], "<symbol> &lt; </symbol>");
+        assert_eq!(lines[5], "<integerConstant> 1 </integerConstant>");
+        assert_eq!(lines[13], "<stringConstant> a&amp;b </stringConstant>");
+        assert_eq!(lines.last(), Some(&"</tokens>"));
+    }
+
+    #[test]
+    fn test_class_xml() {
+        let class = Parser::parse("class A { function void f() { return -x; } }").unwrap();
+        let xml = class_xml(&class);
+        let lines = xml.split("\r\n").collect::<Vec<&str>>();
+        assert_eq!(lines[0], "<class>");
+        assert_eq!(lines[4], "  <subroutineDec>");
+        assert_eq!(lines[9], "    <parameterList>");
+        assert_eq!(lines[10], "    </parameterList>");
+        assert_eq!(lines[15], "        <returnStatement>");
+        assert_eq!(lines[18], "            <term>");
+        assert_eq!(lines[19], "              <symbol> - </symbol>");
+        assert_eq!(lines[20], "              <term>");
+        assert_eq!(lines[21], "                <identifier> x </identifier>");
+        assert!(xml.ends_with("</class>\r\n"));
+    }
+}
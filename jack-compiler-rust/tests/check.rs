@@ -0,0 +1,47 @@
+use jack_compiler_rust::check::check_program;
+use jack_compiler_rust::code::read_sources;
+use jack_compiler_rust::parser::Parser;
+
+mod common;
+
+use common::project_dir;
+
+const PROGRAMS: [&str; 17] = [
+    "10/ArrayTest",
+    "10/Square",
+    "11/Average",
+    "11/ComplexArrays",
+    "11/ConvertToBin",
+    "11/Pong",
+    "11/Seven",
+    "11/Square",
+    "12/ArrayTest",
+    "12/KeyboardTest",
+    "12/MathTest",
+    "12/MemoryTest",
+    "12/MemoryTest/MemoryDiag",
+    "12/OutputTest",
+    "12/ScreenTest",
+    "12/StringTest",
+    "12/SysTest",
+];
+
+/// the course's programs are correct, nothing in them may be reported. ExpressionLessSquare
+/// only tests the parser, its expressions are placeholders.
+#[test]
+fn test_course_programs_are_clean() {
+    for program in PROGRAMS {
+        let sources = read_sources(&project_dir(program)).unwrap();
+        let classes = sources
+            .iter()
+            .map(|(_, src)| Parser::parse(src).unwrap())
+            .collect::<Vec<_>>();
+        for (diagnostics, (file_name, src)) in check_program(&classes).iter().zip(&sources) {
+            let rendered = diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.render(src))
+                .collect::<Vec<String>>();
+            assert!(rendered.is_empty(), "{program}/{file_name}: {rendered:#?}");
+        }
+    }
+}
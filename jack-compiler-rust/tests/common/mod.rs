@@ -0,0 +1,23 @@
+// each test binary only uses part of the helpers
+#![allow(dead_code)]
+
+use jack_compiler_rust::code::{emit_sources, read_sources, Emit};
+use std::path::{Path, PathBuf};
+
+/// the directory of a program of the course's projects, like `11/Pong`.
+pub fn project_dir(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../__project-files")
+        .join(relative)
+}
+
+/// the vm files of the compiled program, named like the compiler writes them.
+pub fn compile(program: &str) -> Vec<(String, String)> {
+    let sources = read_sources(&project_dir(program)).unwrap();
+    let outputs = emit_sources(&sources, Emit::Vm, false).unwrap();
+    sources
+        .iter()
+        .zip(outputs)
+        .map(|((file_name, _), vm)| (Emit::Vm.output_name(file_name), vm))
+        .collect()
+}
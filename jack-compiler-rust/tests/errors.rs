@@ -0,0 +1,22 @@
+use std::process::Command;
+
+/// a program that doesn't compile fails the run, with the error on stderr.
+#[test]
+fn test_failed_compile_exits_with_an_error() {
+    let dir = std::env::temp_dir().join(format!("jack-errors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("Main.jack"),
+        "class Main { function void main() { let x = ; return; } }",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_jack-compiler-rust"))
+        .arg(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("[err] "), "{stderr}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,186 @@
+use emulator::ram::Ram;
+use jack_compiler_rust::code::{emit_source, emit_sources, read_sources, Emit};
+use vm_emulator::program::Program;
+use vm_emulator::vm::{Vm, MEMORY_SIZE};
+
+mod common;
+
+use common::project_dir;
+
+/// compiles the class in the `--jack-ext` dialect and runs its `Sys.init` on the vm emulator,
+/// whose native OS serves multiplication and division. The statements of `init` store their
+/// results from RAM[8000] on, which is returned up to `results`.
+fn run(init: &str, results: usize) -> Vec<i16> {
+    let src = format!(
+        "class Sys {{
+  function void init() {{
+    var Array ram;
+    var int i, j, x;
+    let ram = 0;
+{init}
+    return;
+  }}
+  {}
+}}",
+        HELPERS
+    );
+    let vm = emit_source(&src, Emit::Vm, true).unwrap();
+    let files = [("Sys.vm".to_string(), vm)];
+    let mut vm = Vm::new(
+        Program::from_sources(&files).unwrap(),
+        Ram::new(MEMORY_SIZE),
+    );
+    vm.bootstrap();
+    vm.run(1_000_000).unwrap();
+    assert!(vm.is_halted(), "still running");
+    (8000..8000 + results)
+        .map(|address| vm.ram().read(address))
+        .collect()
+}
+
+const HELPERS: &str = "
+  function int sign(int x) {
+    if (x < 0) { return -1; }
+    else if (x = 0) { return 0; }
+    else { return 1; }
+  }
+
+  function int next() {
+    var Array ram;
+    let ram = 0;
+    let ram[7999] += 1;
+    return ram[7999];
+  }";
+
+#[test]
+fn test_for_loops() {
+    let results = run(
+        "    for (i = 0; i < 10; i += 1) { let ram[8000] += i; }
+    let ram[8001] = i;
+    for (; j < 3;) { let j += 1; }
+    let ram[8002] = j;
+    for (i = 0; i < 3; i += 1) {
+      for (j = i; j < 3; j += 1) { let x += 1; }
+    }
+    let ram[8003] = x;",
+        4,
+    );
+    assert_eq!(results, vec![45, 10, 3, 6]);
+}
+
+#[test]
+fn test_break_and_continue() {
+    let results = run(
+        "    for (i = 0; i < 100; i += 1) {
+      if (i = 10) { break; }
+      if ((i & 1) = 1) { continue; }
+      let ram[8000] += i;
+    }
+    let ram[8001] = i;
+    let i = 0;
+    while (true) {
+      let i += 1;
+      for (j = 0; ; j += 1) {
+        if (j = i) { break; }
+        let x += 1;
+      }
+      if (i < 4) { continue; }
+      break;
+      let ram[8002] = -1;
+    }
+    let ram[8003] = x;",
+        4,
+    );
+    // the even numbers below 10, the loop left at 10 without its update, 1 + 2 + 3 + 4 rounds
+    // of the inner loop
+    assert_eq!(results, vec![20, 10, 0, 10]);
+}
+
+#[test]
+fn test_else_if() {
+    let results = run(
+        "    let ram[8000] = Sys.sign(-5);
+    let ram[8001] = Sys.sign(0);
+    let ram[8002] = Sys.sign(7);",
+        3,
+    );
+    assert_eq!(results, vec![-1, 0, 1]);
+}
+
+#[test]
+fn test_character_constants() {
+    let results = run(
+        "    let ram[8000] = 'A';
+    let ram[8001] = '0' + 7;
+    let ram[8002] = ''';
+    let ram[8003] = ' ';",
+        4,
+    );
+    assert_eq!(results, vec![65, 55, 39, 32]);
+}
+
+#[test]
+fn test_compound_assignment() {
+    let results = run(
+        "    let x = 6;
+    let x += 4;
+    let x -= 1;
+    let x *= 3 + 1;
+    let x /= 2;
+    let ram[8000] = x;
+    let x |= 1;
+    let x &= 7;
+    let ram[8001] = x;
+    let ram[8003] = 5;
+    // the index is evaluated once, Sys.next counts its calls in RAM[7999]
+    let ram[8001 + Sys.next()] -= 2;
+    let ram[8004] = ram[7999];",
+        5,
+    );
+    assert_eq!(results, vec![18, 3, -2, 5, 1]);
+}
+
+#[test]
+fn test_operator_precedence() {
+    let results = run(
+        "    let ram[8000] = 2 + 3 * 4;
+    let ram[8001] = 10 - 4 - 3;
+    let ram[8002] = 100 / 10 / 5;
+    let ram[8003] = 1 + 2 = 3 & (4 < 5);
+    let ram[8004] = 1 < 2 | 3 < 2 & 1 = 1;
+    let ram[8005] = -2 * 3 + 8 / 2 * 3;
+    let ram[8006] = 1 < 2 = 4 < 3;
+    let ram[8007] = 3 = 3 < 5;",
+        8,
+    );
+    assert_eq!(results, vec![14, 3, 2, -1, -1, 6, 0, 0]);
+}
+
+/// the extensions aren't standard jack.
+#[test]
+fn test_standard_jack_rejects_extensions() {
+    for statement in [
+        "for (i = 0; i < 3; i += 1) { }",
+        "while (true) { break; }",
+        "let i += 1;",
+        "let i = 'a';",
+        "if (true) { } else if (false) { }",
+    ] {
+        let src = format!("class A {{ function void f() {{ var int i; {statement} return; }} }}");
+        assert!(emit_source(&src, Emit::Vm, false).is_err(), "{statement}");
+        assert!(emit_source(&src, Emit::Vm, true).is_ok(), "{statement}");
+    }
+}
+
+/// programs that don't lean on the order of jack's operators compile to the same vm code.
+#[test]
+fn test_standard_programs_compile_the_same() {
+    for program in ["Seven", "ConvertToBin", "Square", "Average", "Pong"] {
+        let sources = read_sources(&project_dir(&format!("11/{program}"))).unwrap();
+        assert_eq!(
+            emit_sources(&sources, Emit::Vm, true).unwrap(),
+            emit_sources(&sources, Emit::Vm, false).unwrap(),
+            "{program}"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use jack_compiler_rust::code::{emit_source, emit_sources, read_sources, Emit};
+use jack_compiler_rust::format::{format_files, format_source};
+use jack_compiler_rust::os::OS;
+use jack_compiler_rust::token::Tokenizer;
+use std::path::{Path, PathBuf};
+
+/// the programs of the course's projects 10 and 11.
+fn programs() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../__project-files");
+    let mut programs = Vec::new();
+    for project in ["10", "11"] {
+        for entry in std::fs::read_dir(root.join(project)).unwrap() {
+            programs.push(entry.unwrap().path());
+        }
+    }
+    programs.sort();
+    programs
+}
+
+/// the text of every comment, without the whitespace formatting may change.
+fn comments(src: &str) -> Vec<String> {
+    Tokenizer::tokenize(src)
+        .unwrap()
+        .iter()
+        .filter(|token| token.is_comment())
+        .map(|token| {
+            token
+                .text(src)
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// formatting changes neither the parse tree nor the comments, and formatting again changes
+/// nothing.
+#[test]
+fn test_formatting_keeps_programs() {
+    for dir in programs() {
+        let sources = read_sources(&dir).unwrap();
+        for (file_name, src) in &sources {
+            let formatted = format_source(src).unwrap();
+            assert_eq!(format_source(&formatted).unwrap(), formatted, "{file_name}");
+            assert_eq!(comments(&formatted), comments(src), "{file_name}");
+            assert_eq!(
+                emit_source(&formatted, Emit::Xml, false).unwrap(),
+                emit_source(src, Emit::Xml, false).unwrap(),
+                "{file_name}"
+            );
+        }
+    }
+}
+
+/// the formatted OS compiles to the bundled vm code.
+#[test]
+fn test_formatted_os_compiles_the_same() {
+    let mut files = OS
+        .iter()
+        .map(|(file_name, src)| (file_name.to_string(), src.to_string()))
+        .collect::<Vec<(String, String)>>();
+    files.push((
+        "Main.jack".to_string(),
+        "class Main { function void main() { return; } }".to_string(),
+    ));
+    let formatted = files
+        .iter()
+        .map(|(file_name, src)| (file_name.clone(), format_source(src).unwrap()))
+        .collect::<Vec<(String, String)>>();
+    assert_eq!(
+        emit_sources(&formatted, Emit::Vm, false).unwrap(),
+        emit_sources(&files, Emit::Vm, false).unwrap()
+    );
+    // the OS is written in the canonical style, but for the font table's aligned comments
+    for ((file_name, src), (_, formatted)) in files.iter().zip(&formatted) {
+        if file_name != "Output.jack" && file_name != "Main.jack" {
+            assert_eq!(src, formatted, "{file_name}");
+        }
+    }
+}
+
+#[test]
+fn test_format_files() {
+    let dir = std::env::temp_dir().join(format!("jackfmt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let messy = "class A { function void f() { return; } }";
+    std::fs::write(dir.join("A.jack"), messy).unwrap();
+    std::fs::write(dir.join("B.jack"), format_source(messy).unwrap()).unwrap();
+
+    assert_eq!(format_files(&dir, true).unwrap(), vec!["A.jack"]);
+    assert_eq!(std::fs::read_to_string(dir.join("A.jack")).unwrap(), messy);
+    assert_eq!(format_files(&dir, false).unwrap(), vec!["A.jack"]);
+    assert_eq!(format_files(&dir, true).unwrap(), Vec::<String>::new());
+    assert_eq!(
+        format_files(&dir.join("B.jack"), true).unwrap(),
+        Vec::<String>::new()
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,82 @@
+use assembler_rust::code::assemble;
+use emulator::chipset::Chipset;
+use emulator::ram::Ram;
+use vm_translator_rust::code::translate_sources_with_map;
+use vm_translator_rust::inline::DEFAULT_THRESHOLD;
+use vm_translator_rust::ir::IrOptions;
+use vm_translator_rust::os::link_os;
+
+mod common;
+
+const MEMORY_SIZE: usize = 32_768;
+const SCREEN: usize = 16384;
+const SCREEN_WORDS: usize = 8192;
+
+/// the games of project 11 and how often their main loop polls the keyboard before the
+/// measurement stops, without any key pressed.
+const BENCHMARKS: [(&str, usize); 2] = [("Pong", 10), ("Square", 10)];
+
+/// the vm files of the compiled game with the Jack OS linked in.
+fn compile(program: &str) -> Vec<(String, String)> {
+    let mut files = common::compile(&format!("11/{program}"));
+    link_os(&mut files);
+    files
+}
+
+/// the cycles the game takes until it polls the keyboard for the given time, and the screen
+/// it has drawn by then.
+fn measure(files: &[(String, String)], options: &IrOptions, polls: usize) -> (usize, Vec<i16>) {
+    let (assembly, map) = translate_sources_with_map(files, options).unwrap();
+    let key_pressed = map
+        .entries()
+        .iter()
+        .find(|entry| entry.location.function.as_deref() == Some("Keyboard.keyPressed"))
+        .unwrap()
+        .address as usize;
+    let mut rom = assemble(&assembly).unwrap();
+    assert!(rom.len() <= MEMORY_SIZE, "the program does not fit the ROM");
+    rom.resize(MEMORY_SIZE, 0);
+    let ram = Ram::new(MEMORY_SIZE);
+    let mut chipset = Chipset::new(rom, ram.clone());
+    let mut cycles = 0;
+    let mut polled = 0;
+    loop {
+        if chipset.pc() == key_pressed {
+            polled += 1;
+            if polled == polls {
+                break;
+            }
+        }
+        assert!(cycles < 100_000_000, "still running");
+        chipset.run_next_instruction();
+        cycles += 1;
+    }
+    let mut screen = vec![0; SCREEN_WORDS];
+    ram.copy_slice(SCREEN, &mut screen).unwrap();
+    (cycles, screen)
+}
+
+/// the inlined games draw the same screen in fewer cycles. The shared runtime keeps a game and
+/// the OS within the 32K words of ROM.
+#[test]
+fn test_inlining_saves_cycles() {
+    let plain = IrOptions {
+        shared_runtime: true,
+        ..IrOptions::default()
+    };
+    let inlined = IrOptions {
+        inline: Some(DEFAULT_THRESHOLD),
+        ..plain.clone()
+    };
+    for (program, polls) in BENCHMARKS {
+        let files = compile(program);
+        let (plain_cycles, plain_screen) = measure(&files, &plain, polls);
+        let (inlined_cycles, inlined_screen) = measure(&files, &inlined, polls);
+        assert!(plain_screen.iter().any(|word| *word != 0), "{program}");
+        assert!(plain_screen == inlined_screen, "{program}");
+        assert!(
+            inlined_cycles < plain_cycles,
+            "{program}: {plain_cycles} -> {inlined_cycles} cycles"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use jack_compiler_rust::lsp::json::Json;
+use jack_compiler_rust::lsp::serve;
+use std::path::Path;
+
+const MAIN: &str = "class Main {
+    function void main() {
+        var int a, b;
+        var Counter counter;
+        let counter = Counter.new();
+        let b = counter.add(a);
+        do Math.
+        return;
+    }
+}
+";
+
+const COUNTER: &str = "class Counter {
+    field int total;
+
+    constructor Counter new() {
+        let total = 0;
+        return this;
+    }
+
+    method int add(int n) {
+        let total = total + n;
+        return total;
+    }
+}
+";
+
+fn frame(message: Json) -> String {
+    let content = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+}
+
+fn request(id: usize, method: &str, params: Json) -> String {
+    frame(Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id.into()),
+        ("method", method.into()),
+        ("params", params),
+    ]))
+}
+
+fn notification(method: &str, params: Json) -> String {
+    frame(Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ]))
+}
+
+/// the params of a request about a position of a document.
+fn at(uri: &str, line: usize, character: usize) -> Json {
+    Json::object([
+        ("textDocument", Json::object([("uri", uri.into())])),
+        (
+            "position",
+            Json::object([("line", line.into()), ("character", character.into())]),
+        ),
+    ])
+}
+
+/// splits the output of the server into its messages.
+fn messages(output: &[u8]) -> Vec<Json> {
+    let mut output = std::str::from_utf8(output).unwrap();
+    let mut messages = Vec::new();
+    while let Some(rest) = output.strip_prefix("Content-Length: ") {
+        let (length, rest) = rest.split_once("\r\n\r\n").unwrap();
+        let (content, rest) = rest.split_at(length.parse().unwrap());
+        messages.push(Json::parse(content).unwrap());
+        output = rest;
+    }
+    assert!(output.is_empty());
+    messages
+}
+
+fn uri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+/// a session of an editor with Main.jack of a two class program open.
+#[test]
+fn test_session() {
+    let dir = std::env::temp_dir().join(format!("jack-lsp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("Main.jack"), MAIN).unwrap();
+    std::fs::write(dir.join("Counter.jack"), COUNTER).unwrap();
+    let main = uri(&dir.join("Main.jack"));
+    let counter = uri(&dir.join("Counter.jack"));
+
+    let open = Json::object([(
+        "textDocument",
+        Json::object([
+            ("uri", main.as_str().into()),
+            ("languageId", "jack".into()),
+            ("version", 1.into()),
+            ("text", MAIN.into()),
+        ]),
+    )]);
+    let mut rename = at(&main, 2, 16);
+    if let Json::Object(members) = &mut rename {
+        members.push(("newName".to_string(), "first".into()));
+    }
+    let input = [
+        request(1, "initialize", Json::object([])),
+        notification("initialized", Json::object([])),
+        notification("textDocument/didOpen", open),
+        request(2, "textDocument/definition", at(&main, 5, 26)),
+        request(3, "textDocument/hover", at(&main, 5, 12)),
+        request(4, "textDocument/completion", at(&main, 6, 16)),
+        request(5, "textDocument/rename", rename),
+        request(6, "textDocument/formatting", Json::object([])),
+        request(7, "shutdown", Json::Null),
+        notification("exit", Json::Null),
+    ]
+    .concat();
+    let mut output = Vec::new();
+    serve(&mut input.as_bytes(), &mut output).unwrap();
+    let messages = messages(&output);
+    let response = |id: usize| {
+        messages
+            .iter()
+            .find(|message| message.get("id") == Some(&id.into()))
+            .unwrap()
+    };
+
+    assert!(response(1)
+        .at(&["result", "capabilities", "renameProvider"])
+        .is_some());
+
+    // every file of the directory gets its diagnostics, the unfinished call is an error
+    let published = messages
+        .iter()
+        .filter(|message| message.get("method") == Some(&"textDocument/publishDiagnostics".into()))
+        .map(|message| {
+            let uri = message
+                .at(&["params", "uri"])
+                .and_then(Json::as_str)
+                .unwrap();
+            let diagnostics = match message.at(&["params", "diagnostics"]) {
+                Some(Json::Array(diagnostics)) => diagnostics.len(),
+                _ => panic!("no diagnostics"),
+            };
+            (uri.to_string(), diagnostics)
+        })
+        .collect::<Vec<(String, usize)>>();
+    assert_eq!(published, vec![(counter.clone(), 0), (main.clone(), 1)]);
+
+    // `add` of `counter.add(a)` is in the other class
+    let definition = response(2).get("result").unwrap();
+    assert_eq!(definition.get("uri"), Some(&counter.as_str().into()));
+    assert_eq!(
+        definition.at(&["range", "start"]).unwrap().to_string(),
+        r#"{"line":8,"character":15}"#
+    );
+
+    assert_eq!(
+        response(3).at(&["result", "contents", "value"]),
+        Some(&"```jack\nint b\n```\n\nlocal 1".into())
+    );
+
+    let labels = match response(4).get("result") {
+        Some(Json::Array(items)) => items
+            .iter()
+            .map(|item| item.get("label").and_then(Json::as_str).unwrap())
+            .collect::<Vec<&str>>(),
+        _ => panic!("no completions"),
+    };
+    assert!(labels.contains(&"multiply") && labels.contains(&"sqrt"));
+
+    // `a` is declared, then passed to `add`
+    let edits = match response(5).at(&["result", "changes", main.as_str()]) {
+        Some(Json::Array(edits)) => edits
+            .iter()
+            .map(|edit| edit.at(&["range", "start"]).unwrap().to_string())
+            .collect::<Vec<String>>(),
+        _ => panic!("no edits"),
+    };
+    assert_eq!(
+        edits,
+        vec![
+            r#"{"line":2,"character":16}"#,
+            r#"{"line":5,"character":28}"#
+        ]
+    );
+
+    assert_eq!(
+        response(6).at(&["error", "code"]),
+        Some(&Json::Number(-32601.0))
+    );
+    assert_eq!(response(7).get("result"), Some(&Json::Null));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,30 @@
+use jack_compiler_rust::code::{emit_source, read_sources, Emit};
+
+mod common;
+
+use common::project_dir;
+
+const PROGRAMS: [&str; 3] = ["ArrayTest", "ExpressionLessSquare", "Square"];
+
+/// compares the output for every class of the project-10 programs byte for byte.
+fn check_outputs(emit: Emit) {
+    for program in PROGRAMS {
+        let dir = project_dir(&format!("10/{program}"));
+        for (file_name, src) in read_sources(&dir).unwrap() {
+            let expected_path = dir.join(emit.output_name(&file_name));
+            let expected = std::fs::read_to_string(&expected_path).unwrap();
+            let actual = emit_source(&src, emit, false).unwrap();
+            assert!(actual == expected, "{:?} differs", expected_path);
+        }
+    }
+}
+
+#[test]
+fn test_tokens_match_expected_xml() {
+    check_outputs(Emit::Tokens);
+}
+
+#[test]
+fn test_parse_trees_match_expected_xml() {
+    check_outputs(Emit::Xml);
+}
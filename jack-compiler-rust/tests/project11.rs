@@ -0,0 +1,106 @@
+use emulator::os::{Os, KEYBOARD, SCREEN};
+use emulator::ram::Ram;
+use vm_emulator::program::Program;
+use vm_emulator::vm::{Vm, MEMORY_SIZE};
+
+mod common;
+
+use common::compile;
+
+/// the OS functions the native implementation lacks, and a `Sys.init` that runs `Main.main`
+/// and halts like the real one.
+const SYS: &str = "function Sys.init 0
+call Main.main 0
+pop temp 0
+call Sys.halt 0
+function Keyboard.readInt 0
+push constant 3
+return
+";
+
+/// compiles and checks every class of the program and loads it in the vm emulator.
+fn load(program: &str) -> Vm {
+    let mut files = compile(&format!("11/{program}"));
+    files.insert(0, ("Sys.vm".to_string(), SYS.to_string()));
+    let mut vm = Vm::new(
+        Program::from_sources(&files).unwrap(),
+        Ram::new(MEMORY_SIZE),
+    );
+    vm.bootstrap();
+    vm
+}
+
+/// runs the program to the end, failing if it doesn't halt in time.
+fn run(vm: &mut Vm) {
+    vm.run(10_000_000).unwrap();
+    assert!(vm.is_halted(), "still running after {} steps", vm.steps());
+}
+
+/// the screen after printing the lines with the native OS, the way the programs print them.
+fn printed(lines: &[&str]) -> Vec<i16> {
+    let ram = Ram::new(MEMORY_SIZE);
+    let mut os = Os::new();
+    for line in lines {
+        for c in line.chars() {
+            os.call(&ram, "Output.printChar", &[c as i16]).unwrap();
+        }
+        os.call(&ram, "Output.println", &[]).unwrap();
+    }
+    screen(&ram)
+}
+
+fn screen(ram: &Ram) -> Vec<i16> {
+    (SCREEN..KEYBOARD)
+        .map(|address| ram.read(address))
+        .collect()
+}
+
+/// the programs print their results, `Keyboard.readInt` gives 3 for every number Average asks.
+#[test]
+fn test_printed_results() {
+    let programs: [(&str, &[&str]); 3] = [
+        ("Seven", &["7"]),
+        ("Average", &["The average is 3"]),
+        (
+            "ComplexArrays",
+            &[
+                "Test 1: expected result: 5; actual result: 5",
+                "Test 2: expected result: 40; actual result: 40",
+                "Test 3: expected result: 0; actual result: 0",
+                "Test 4: expected result: 77; actual result: 77",
+                "Test 5: expected result: 110; actual result: 110",
+            ],
+        ),
+    ];
+    for (program, lines) in programs {
+        let mut vm = load(program);
+        run(&mut vm);
+        assert!(screen(vm.ram()) == printed(lines), "{program}");
+    }
+}
+
+/// without key presses Pong's bat never moves and the game is over once the ball misses it.
+#[test]
+fn test_pong_halts() {
+    let mut vm = load("Pong");
+    run(&mut vm);
+}
+
+#[test]
+fn test_convert_to_bin() {
+    let mut vm = load("ConvertToBin");
+    vm.ram().write(8000, 0b0010_0000_0000_0101);
+    run(&mut vm);
+    let bits = (8001..=8016)
+        .map(|address| vm.ram().read(address))
+        .collect::<Vec<i16>>();
+    assert_eq!(bits, vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0]);
+}
+
+/// the square waits for key presses forever, it only has to keep running without errors.
+#[test]
+fn test_square_runs() {
+    let mut vm = load("Square");
+    vm.run(1_000_000).unwrap();
+    assert!(!vm.is_halted());
+}
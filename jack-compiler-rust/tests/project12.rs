@@ -0,0 +1,286 @@
+use assembler_rust::code::assemble;
+use emulator::chipset::Chipset;
+use emulator::native::NativeCalls;
+use emulator::os::Os;
+use emulator::ram::Ram;
+use jack_compiler_rust::code::{emit_sources, Emit};
+use jack_compiler_rust::os::OS;
+use vm_emulator::program::{Instruction, Program};
+use vm_emulator::vm::{Vm, MEMORY_SIZE};
+use vm_translator_rust::code::{assemble_sources, translate_sources_with_map};
+use vm_translator_rust::ir::IrOptions;
+use vm_translator_rust::os::link_os;
+
+mod common;
+
+use common::project_dir;
+
+const SCREEN: usize = 16384;
+const SCREEN_WORDS: usize = 8192;
+const KEYBOARD: usize = 24576;
+const CYCLES: usize = 5_000_000;
+
+/// starts a program on the native OS of the vm emulator instead of the Jack one.
+const NATIVE_SYS: &str = "function Sys.init 0
+call Memory.init 0
+pop temp 0
+call Output.init 0
+pop temp 0
+call Screen.init 0
+pop temp 0
+call Main.main 0
+pop temp 0
+call Sys.halt 0
+";
+
+/// the vm files of the compiled test of project 12.
+fn compile(program: &str) -> Vec<(String, String)> {
+    common::compile(&format!("12/{program}"))
+}
+
+/// compiles the program and loads it in the vm emulator, with the Jack OS linked in like the
+/// translator does or with the native one. The classes with functions the native OS lacks, like
+/// the reading ones of `Keyboard`, come from the Jack OS then.
+fn load(program: &str, jack_os: bool) -> Vm {
+    let mut files = compile(program);
+    match jack_os {
+        true => link_os(&mut files),
+        false => {
+            let missing = files
+                .iter()
+                .flat_map(|(_, src)| src.lines())
+                .filter_map(|line| line.strip_prefix("call "))
+                .filter_map(|call| call.split(' ').next())
+                .filter(|function| !Os::implements(function))
+                .filter_map(|function| function.split_once('.'))
+                .map(|(class, _)| format!("{class}.vm"))
+                .collect::<Vec<String>>();
+            for (file_name, src) in vm_translator_rust::os::OS {
+                if missing.iter().any(|missing| missing == file_name) {
+                    files.push((file_name.to_string(), src.to_string()));
+                }
+            }
+            files.push(("Sys.vm".to_string(), NATIVE_SYS.to_string()));
+        }
+    }
+    let mut vm = Vm::new(
+        Program::from_sources(&files).unwrap(),
+        Ram::new(MEMORY_SIZE),
+    );
+    vm.bootstrap();
+    vm
+}
+
+/// types the keys for a program polling `Keyboard.keyPressed`: each key is held for a few polls
+/// and released for a few more, so `Keyboard.readChar` sees it go down and up again. Newlines
+/// and backspaces are typed as the Hack keys.
+struct Typist {
+    keys: Vec<i16>,
+    polls: usize,
+}
+
+impl Typist {
+    const POLLS: usize = 3;
+
+    fn new(text: &str) -> Self {
+        let keys = text
+            .chars()
+            .map(|c| match c {
+                '\n' => 128,
+                '\u{8}' => 129,
+                c => c as i16,
+            })
+            .collect();
+        Self { keys, polls: 0 }
+    }
+
+    /// puts the key of this poll in the keyboard register, right before the program reads it.
+    fn poll(&mut self, ram: &Ram) {
+        let held = self.polls % (2 * Self::POLLS) < Self::POLLS;
+        let key = self
+            .keys
+            .get(self.polls / (2 * Self::POLLS))
+            .filter(|_| held);
+        ram.write(KEYBOARD, key.copied().unwrap_or(0));
+        self.polls += 1;
+    }
+}
+
+/// runs the program until it halts, the Jack `Sys.halt` loops forever.
+fn run(vm: &mut Vm, typist: &mut Typist) {
+    let halted = |vm: &Vm| {
+        vm.is_halted()
+            || vm
+                .call_stack()
+                .last()
+                .is_some_and(|frame| frame.function == "Sys.halt")
+    };
+    while !halted(vm) {
+        assert!(vm.steps() < 20_000_000, "still running");
+        if let Some(Instruction::Call { name, .. }) = vm.current() {
+            if name == "Keyboard.keyPressed" {
+                typist.poll(vm.ram());
+            }
+        }
+        vm.step().unwrap();
+    }
+}
+
+/// the values of the .cmp file of the test, which lists RAM[8000] onwards.
+fn expected(program: &str) -> Vec<i16> {
+    let cmp = std::fs::read_to_string(
+        project_dir(&format!("12/{program}")).join(format!("{program}.cmp")),
+    );
+    cmp.unwrap()
+        .lines()
+        .nth(1)
+        .unwrap()
+        .split('|')
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+/// the OS bundled with the translator is the compiled one of this crate.
+#[test]
+fn test_bundled_os_is_up_to_date() {
+    let mut files = OS
+        .iter()
+        .map(|(file_name, src)| (file_name.to_string(), src.to_string()))
+        .collect::<Vec<(String, String)>>();
+    // Sys.init runs the program's Main.main
+    let main = "class Main { function void main() { return; } }";
+    files.push(("Main.jack".to_string(), main.to_string()));
+    let outputs = emit_sources(&files, Emit::Vm, false).unwrap();
+    for (((file_name, _), vm), (vm_name, vm_src)) in
+        files.iter().zip(outputs).zip(vm_translator_rust::os::OS)
+    {
+        assert_eq!(Emit::Vm.output_name(file_name), vm_name);
+        assert!(
+            vm_src == vm.as_str(),
+            "{vm_name} differs from the compiled {file_name}"
+        );
+    }
+}
+
+#[test]
+fn test_ram_results() {
+    for program in ["MathTest", "MemoryTest", "ArrayTest"] {
+        let mut vm = load(program, true);
+        run(&mut vm, &mut Typist::new(""));
+        let expected = expected(program);
+        let actual = (8000..8000 + expected.len())
+            .map(|address| vm.ram().read(address))
+            .collect::<Vec<i16>>();
+        assert_eq!(actual, expected, "{program}");
+    }
+}
+
+/// the translated program with the OS linked in fits in ROM and runs on the CPU too.
+#[test]
+fn test_math_on_the_cpu() {
+    let mut files = compile("MathTest");
+    link_os(&mut files);
+    let mut rom = assemble_sources(&files, &IrOptions::default()).unwrap();
+    rom.resize(MEMORY_SIZE, 0);
+    let ram = Ram::new(MEMORY_SIZE);
+    let mut chipset = Chipset::new(rom, ram.clone());
+    for _ in 0..CYCLES {
+        chipset.run_next_instruction();
+    }
+    let expected = expected("MathTest");
+    let actual = (8000..8000 + expected.len())
+        .map(|address| ram.read(address))
+        .collect::<Vec<i16>>();
+    assert_eq!(actual, expected);
+}
+
+/// runs the translated program on the CPU until it reaches `Sys.halt`, with the OS served
+/// natively or as hack code. Returns the RAM and the number of cycles it took.
+fn run_on_cpu(program: &str, native: bool, typist: &mut Typist) -> (Ram, usize) {
+    let mut files = compile(program);
+    link_os(&mut files);
+    // the shared runtime keeps the bigger tests within the 32K words of ROM
+    let options = IrOptions {
+        shared_runtime: true,
+        ..IrOptions::default()
+    };
+    let (assembly, map) = translate_sources_with_map(&files, &options).unwrap();
+    let halt = map
+        .entries()
+        .iter()
+        .find(|entry| entry.location.function.as_deref() == Some("Sys.halt"))
+        .unwrap()
+        .address as usize;
+    let key_pressed = map
+        .entries()
+        .iter()
+        .find(|entry| entry.location.function.as_deref() == Some("Keyboard.keyPressed"))
+        .map(|entry| entry.address as usize);
+    let mut rom = assemble(&assembly).unwrap();
+    assert!(rom.len() <= MEMORY_SIZE, "{program} does not fit the ROM");
+    rom.resize(MEMORY_SIZE, 0);
+    let ram = Ram::new(MEMORY_SIZE);
+    let mut chipset = Chipset::new(rom, ram.clone());
+    if native {
+        chipset = chipset.with_native_calls(NativeCalls::new(&map));
+    }
+    let mut cycles = 0;
+    while chipset.pc() != halt {
+        assert!(cycles < 100_000_000, "{program} still running");
+        if key_pressed == Some(chipset.pc()) {
+            typist.poll(&ram);
+        }
+        chipset.run_next_instruction();
+        cycles += 1;
+    }
+    (ram, cycles)
+}
+
+/// native OS calls give the same results as the hack code in a fraction of the cycles.
+#[test]
+fn test_native_os_calls() {
+    for program in ["MathTest", "OutputTest"] {
+        let (native, native_cycles) = run_on_cpu(program, true, &mut Typist::new(""));
+        let (hack, hack_cycles) = run_on_cpu(program, false, &mut Typist::new(""));
+        for address in (8000..8014).chain(SCREEN..SCREEN + SCREEN_WORDS) {
+            assert_eq!(native.read(address), hack.read(address), "{program}");
+        }
+        assert!(native_cycles * 10 < hack_cycles, "{program}");
+    }
+}
+
+/// the tests with pictures as results, which the course only shows as screenshots, draw the
+/// same screen with the Jack OS in the vm emulator, on the CPU and with the native OS. The ones
+/// waiting for keys get them typed, and only halt once they have checked what they read.
+#[test]
+fn test_screens_match_native_os() {
+    let programs = [
+        ("StringTest", ""),
+        ("OutputTest", ""),
+        ("ScreenTest", ""),
+        ("SysTest", "a"),
+        ("KeyboardTest", " 3JAX\u{8}CK\n-32124\u{8}3\n"),
+    ];
+    for (program, keys) in programs {
+        let screen = |ram: &Ram| {
+            (SCREEN..SCREEN + SCREEN_WORDS)
+                .map(|address| ram.read(address))
+                .collect::<Vec<i16>>()
+        };
+        let vm_screens = [true, false].map(|jack_os| {
+            let mut vm = load(program, jack_os);
+            run(&mut vm, &mut Typist::new(keys));
+            screen(vm.ram())
+        });
+        let (ram, cycles) = run_on_cpu(program, false, &mut Typist::new(keys));
+        assert!(vm_screens[0].iter().any(|word| *word != 0), "{program}");
+        assert!(vm_screens[0] == vm_screens[1], "{program}");
+        assert!(screen(&ram) == vm_screens[1], "{program} on the CPU");
+        if program == "SysTest" {
+            // Sys.wait(2000) counts down from 50 for every millisecond
+            assert!(cycles > 2000 * 50 * 10, "{program} did not wait");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Decimal,
+    Hex,
+    Binary,
+    Text,
+}
+
+/// an output-list column format, `%D2.6.2` is a decimal value right aligned in 6 characters
+/// with 2 spaces on each side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+    pub radix: Radix,
+    pub left: usize,
+    pub width: usize,
+    pub right: usize,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            radix: Radix::Decimal,
+            left: 1,
+            width: 6,
+            right: 1,
+        }
+    }
+}
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Format, String> {
+        let invalid = || format!("invalid format {:?}", format);
+        let rest = format.strip_prefix('%').ok_or_else(invalid)?;
+        let mut chars = rest.chars();
+        let radix = match chars.next() {
+            Some('D') => Radix::Decimal,
+            Some('X') => Radix::Hex,
+            Some('B') => Radix::Binary,
+            Some('S') => Radix::Text,
+            _ => return Err(invalid()),
+        };
+        let sizes = chars
+            .as_str()
+            .split('.')
+            .map(|size| size.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| invalid())?;
+        match sizes[..] {
+            [left, width, right] => Ok(Format {
+                radix,
+                left,
+                width,
+                right,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.left + self.width + self.right
+    }
+
+    /// the column header, the variable name centered in the full column width and cut off when
+    /// it doesn't fit.
+    pub fn header(&self, name: &str) -> String {
+        let total = self.total();
+        if name.len() >= total {
+            return name[..total].to_string();
+        }
+        let left = (total - name.len()) / 2;
+        let right = total - name.len() - left;
+        format!("{}{}{}", " ".repeat(left), name, " ".repeat(right))
+    }
+
+    pub fn value(&self, value: i16) -> String {
+        let text = match self.radix {
+            Radix::Decimal => format!("{:>width$}", value, width = self.width),
+            Radix::Hex => format!("{:0>width$X}", value as u16, width = self.width),
+            Radix::Binary => {
+                let bits = format!("{:016b}", value as u16);
+                let start = bits.len().saturating_sub(self.width);
+                format!("{:0>width$}", &bits[start..], width = self.width)
+            }
+            Radix::Text => format!("{:<width$}", value, width = self.width),
+        };
+        format!(
+            "{}{}{}",
+            " ".repeat(self.left),
+            text,
+            " ".repeat(self.right)
+        )
+    }
+}
+
+/// joins the cells of an output line.
+pub fn row(cells: &[String]) -> String {
+    format!("|{}|", cells.join("|"))
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn test_decimal_columns() {
+        let format = Format::parse("%D2.6.2").unwrap();
+        assert_eq!(format.header("RAM[0]"), "  RAM[0]  ");
+        assert_eq!(format.value(257), "     257  ");
+        let format = Format::parse("%D1.6.1").unwrap();
+        assert_eq!(format.header("RAM[0]"), " RAM[0] ");
+        assert_eq!(format.value(-2), "     -2 ");
+        let format = Format::parse("%D2.6.1").unwrap();
+        assert_eq!(format.header("RAM[17000]"), "RAM[17000");
+        assert_eq!(
+            row(&[format.value(100), format.value(-18000)]),
+            "|     100 |  -18000 |"
+        );
+    }
+
+    #[test]
+    fn test_binary_and_hex_columns() {
+        let format = Format::parse("%B1.16.1").unwrap();
+        assert_eq!(format.value(-1), " 1111111111111111 ");
+        assert_eq!(format.value(5), " 0000000000000101 ");
+        let format = Format::parse("%X1.4.1").unwrap();
+        assert_eq!(format.value(-2), " FFFE ");
+    }
+
+    #[test]
+    fn test_invalid_formats() {
+        assert!(Format::parse("%Q1.6.1").is_err());
+        assert!(Format::parse("%D1.6").is_err());
+        assert!(Format::parse("D1.6.1").is_err());
+    }
+}
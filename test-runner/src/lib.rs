@@ -0,0 +1,4 @@
+pub mod format;
+pub mod runner;
+pub mod script;
+pub mod target;
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use test_runner::runner::run_script;
+
+fn main() {
+    let scripts = std::env::args()
+        .skip(1)
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+    if scripts.is_empty() {
+        println!("usage: test-runner <script.tst>...");
+        std::process::exit(2);
+    }
+
+    let mut failures = 0;
+    for script in &scripts {
+        match run_script(script) {
+            Ok(report) => {
+                for echo in &report.echoes {
+                    println!("[echo] {echo}");
+                }
+                match &report.mismatch {
+                    None if report.compared => {
+                        println!(
+                            "[ok] {}: end of script - comparison ended successfully",
+                            script.display()
+                        )
+                    }
+                    None => println!("[ok] {}: end of script", script.display()),
+                    Some(mismatch) => {
+                        failures += 1;
+                        println!(
+                            "[fail] {}: comparison failure at line {}",
+                            script.display(),
+                            mismatch.line
+                        );
+                        println!("  expected: {}", mismatch.expected);
+                        println!("  actual:   {}", mismatch.actual);
+                    }
+                }
+            }
+            Err(e) => {
+                failures += 1;
+                println!("[err] {e}");
+            }
+        }
+    }
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::format::row;
+use crate::script::{parse, Command, OutputColumn};
+use crate::target::{load, Target};
+use std::path::{Path, PathBuf};
+
+/// the first output line that doesn't match the compare file, lines are numbered from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub line: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub output: Vec<String>,
+    pub echoes: Vec<String>,
+    pub mismatch: Option<Mismatch>,
+    pub compared: bool,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.mismatch.is_none()
+    }
+}
+
+struct Runner {
+    dir: PathBuf,
+    target: Option<Box<dyn Target>>,
+    output_file: Option<PathBuf>,
+    compare_to: Option<PathBuf>,
+    columns: Vec<OutputColumn>,
+    report: Report,
+}
+
+/// runs a test script: files it names are relative to its directory, the output is written to
+/// its `output-file` and checked against its `compare-to` file.
+pub fn run_script(path: &Path) -> Result<Report, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let commands = parse(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+    let mut runner = Runner {
+        dir,
+        target: None,
+        output_file: None,
+        compare_to: None,
+        columns: Vec::new(),
+        report: Report::default(),
+    };
+    runner.execute_all(&commands)?;
+    runner.finish()
+}
+
+impl Runner {
+    fn execute_all(&mut self, commands: &[Command]) -> Result<(), String> {
+        for command in commands {
+            self.execute(command)?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, command: &Command) -> Result<(), String> {
+        match command {
+            Command::Load(file) => self.target = Some(load(&self.dir, file.as_deref())?),
+            Command::OutputFile(file) => self.output_file = Some(self.dir.join(file)),
+            Command::CompareTo(file) => self.compare_to = Some(self.dir.join(file)),
+            Command::OutputList(columns) => {
+                self.columns = columns.clone();
+                let header = columns
+                    .iter()
+                    .map(|c| c.format.header(&c.variable.to_string()))
+                    .collect::<Vec<String>>();
+                self.report.output.push(row(&header));
+            }
+            Command::Set(variable, value) => self.target()?.set(variable, *value)?,
+            Command::Repeat(count, body) => {
+                for _ in 0..*count {
+                    self.execute_all(body)?;
+                }
+            }
+            Command::Output => {
+                let target = self.target.as_deref().ok_or("no program loaded")?;
+                let values = self
+                    .columns
+                    .iter()
+                    .map(|c| target.get(&c.variable).map(|v| c.format.value(v)))
+                    .collect::<Result<Vec<String>, String>>()?;
+                self.report.output.push(row(&values));
+            }
+            Command::TickTock => self.target()?.tick_tock()?,
+            Command::VmStep => self.target()?.vm_step()?,
+            Command::Echo(text) => self.report.echoes.push(text.clone()),
+            Command::ClearEcho => {}
+        }
+        Ok(())
+    }
+
+    fn target(&mut self) -> Result<&mut Box<dyn Target>, String> {
+        self.target
+            .as_mut()
+            .ok_or_else(|| "no program loaded".to_string())
+    }
+
+    fn finish(mut self) -> Result<Report, String> {
+        if let Some(path) = &self.output_file {
+            let mut output = self.report.output.join("\n");
+            output.push('\n');
+            std::fs::write(path, output).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        if let Some(path) = &self.compare_to {
+            let expected =
+                std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            self.report.mismatch = compare(&self.report.output, &expected);
+            self.report.compared = true;
+        }
+        Ok(self.report)
+    }
+}
+
+/// compares the output with the compare file line by line, a `*` in the compare file matches
+/// any character.
+pub fn compare(output: &[String], expected: &str) -> Option<Mismatch> {
+    let expected = expected
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .collect::<Vec<&str>>();
+    let expected_len = expected
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |last| last + 1);
+
+    for i in 0..output.len().max(expected_len) {
+        let actual = output.get(i).map_or("", |line| line.as_str());
+        let wanted = expected.get(i).copied().unwrap_or("");
+        if !line_matches(actual, wanted) {
+            return Some(Mismatch {
+                line: i + 1,
+                expected: wanted.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+    }
+    None
+}
+
+fn line_matches(actual: &str, expected: &str) -> bool {
+    actual.chars().count() == expected.chars().count()
+        && actual
+            .chars()
+            .zip(expected.chars())
+            .all(|(a, e)| e == '*' || a == e)
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let output = vec!["|  RAM[0]  |".to_string(), "|     257  |".to_string()];
+        assert_eq!(compare(&output, "|  RAM[0]  |\r\n|     257  |\r\n"), None);
+        assert_eq!(compare(&output, "|  RAM[0]  |\n|     2**  |\n\n"), None);
+        assert_eq!(
+            compare(&output, "|  RAM[0]  |\n|     258  |\n"),
+            Some(Mismatch {
+                line: 2,
+                expected: "|     258  |".to_string(),
+                actual: "|     257  |".to_string(),
+            })
+        );
+        assert_eq!(compare(&output, "|  RAM[0]  |\n").map(|m| m.line), Some(2));
+    }
+}
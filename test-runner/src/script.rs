@@ -0,0 +1,288 @@
+use crate::format::Format;
+use std::fmt;
+
+/// a simulator variable: `RAM[256]`, `sp`, `local[2]`, `PC`...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    pub index: Option<usize>,
+}
+
+impl Variable {
+    pub fn parse(word: &str) -> Result<Variable, String> {
+        let Some(open) = word.find('[') else {
+            return Ok(Variable {
+                name: word.to_string(),
+                index: None,
+            });
+        };
+        let index = word[open + 1..]
+            .strip_suffix(']')
+            .and_then(|index| index.parse::<usize>().ok())
+            .ok_or_else(|| format!("invalid variable {:?}", word))?;
+        Ok(Variable {
+            name: word[..open].to_string(),
+            index: Some(index),
+        })
+    }
+}
+
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "{}[{}]", self.name, index),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputColumn {
+    pub variable: Variable,
+    pub format: Format,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Load(Option<String>),
+    OutputFile(String),
+    CompareTo(String),
+    OutputList(Vec<OutputColumn>),
+    Set(Variable, i16),
+    Repeat(u64, Vec<Command>),
+    Output,
+    TickTock,
+    VmStep,
+    Echo(String),
+    ClearEcho,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    End,
+    Open,
+    Close,
+}
+
+/// parses a nand2tetris test script. Commands end with `,`, `;` or `!`, which only matter to
+/// the interactive simulators, so they are all treated as separators.
+pub fn parse(source: &str) -> Result<Vec<Command>, String> {
+    let tokens = tokenize(source)?;
+    let mut pos = 0;
+    let commands = parse_commands(&tokens, &mut pos)?;
+    match tokens.get(pos) {
+        None => Ok(commands),
+        Some(_) => Err("unexpected '}'".to_string()),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            ',' | ';' | '!' => tokens.push(Token::End),
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|c| *c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => previous = c,
+                        None => return Err("unterminated comment".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !",;!{}\"".contains(*c))
+                {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_commands(tokens: &[Token], pos: &mut usize) -> Result<Vec<Command>, String> {
+    let mut commands = Vec::new();
+    while let Some(token) = tokens.get(*pos) {
+        *pos += 1;
+        let word = match token {
+            Token::End => continue,
+            Token::Close => {
+                *pos -= 1;
+                break;
+            }
+            Token::Word(word) => word.as_str(),
+            other => return Err(format!("expected a command, found {:?}", other)),
+        };
+        let command = match word {
+            "load" => Command::Load(optional_word(tokens, pos)),
+            "output-file" => Command::OutputFile(expect_word(tokens, pos, word)?),
+            "compare-to" => Command::CompareTo(expect_word(tokens, pos, word)?),
+            "output-list" => {
+                let mut columns = Vec::new();
+                while let Some(column) = optional_word(tokens, pos) {
+                    columns.push(parse_column(&column)?);
+                }
+                Command::OutputList(columns)
+            }
+            "set" => {
+                let variable = Variable::parse(&expect_word(tokens, pos, word)?)?;
+                let value = parse_value(&expect_word(tokens, pos, word)?)?;
+                Command::Set(variable, value)
+            }
+            "repeat" => {
+                let count = expect_word(tokens, pos, word)?;
+                let count = count
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid repeat count {:?}", count))?;
+                if tokens.get(*pos) != Some(&Token::Open) {
+                    return Err("expected '{' after repeat".to_string());
+                }
+                *pos += 1;
+                let body = parse_commands(tokens, pos)?;
+                if tokens.get(*pos) != Some(&Token::Close) {
+                    return Err("expected '}' to close repeat".to_string());
+                }
+                *pos += 1;
+                Command::Repeat(count, body)
+            }
+            "output" => Command::Output,
+            "ticktock" => Command::TickTock,
+            "vmstep" => Command::VmStep,
+            "echo" => match tokens.get(*pos) {
+                Some(Token::Text(text)) => {
+                    *pos += 1;
+                    Command::Echo(text.clone())
+                }
+                _ => return Err("echo expects a quoted string".to_string()),
+            },
+            "clear-echo" => Command::ClearEcho,
+            other => return Err(format!("unsupported command {:?}", other)),
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+fn optional_word(tokens: &[Token], pos: &mut usize) -> Option<String> {
+    match tokens.get(*pos) {
+        Some(Token::Word(word)) => {
+            *pos += 1;
+            Some(word.clone())
+        }
+        _ => None,
+    }
+}
+
+fn expect_word(tokens: &[Token], pos: &mut usize, command: &str) -> Result<String, String> {
+    optional_word(tokens, pos).ok_or_else(|| format!("{} is missing an argument", command))
+}
+
+/// `RAM[0]%D2.6.2`, the format defaults to `%D1.6.1`.
+fn parse_column(word: &str) -> Result<OutputColumn, String> {
+    let (variable, format) = match word.find('%') {
+        Some(percent) => (&word[..percent], Format::parse(&word[percent..])?),
+        None => (word, Format::default()),
+    };
+    Ok(OutputColumn {
+        variable: Variable::parse(variable)?,
+        format,
+    })
+}
+
+/// decimal values, or `%D`, `%X` and `%B` prefixed ones.
+fn parse_value(word: &str) -> Result<i16, String> {
+    let invalid = || format!("invalid value {:?}", word);
+    let (radix, digits) = match word.get(..2) {
+        Some("%D") => (10, &word[2..]),
+        Some("%X") => (16, &word[2..]),
+        Some("%B") => (2, &word[2..]),
+        _ => (10, word),
+    };
+    if radix == 10 {
+        return digits.parse::<i16>().map_err(|_| invalid());
+    }
+    u16::from_str_radix(digits, radix)
+        .map(|value| value as i16)
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let source = "// a comment\nload SimpleAdd.asm,\noutput-file SimpleAdd.out,\nset RAM[0] 256, /* block */\nrepeat 60 {\n  ticktock;\n}\noutput-list RAM[0]%D2.6.2 sp;\noutput;\n";
+        let commands = parse(source).unwrap();
+        assert_eq!(commands.len(), 6);
+        assert_eq!(
+            commands[0],
+            Command::Load(Some("SimpleAdd.asm".to_string()))
+        );
+        assert_eq!(
+            commands[2],
+            Command::Set(
+                Variable {
+                    name: "RAM".to_string(),
+                    index: Some(0)
+                },
+                256
+            )
+        );
+        assert_eq!(commands[3], Command::Repeat(60, vec![Command::TickTock]));
+        match &commands[4] {
+            Command::OutputList(columns) => {
+                assert_eq!(columns.len(), 2);
+                assert_eq!(columns[0].format.width, 6);
+                assert_eq!(columns[1].variable.to_string(), "sp");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(commands[5], Command::Output);
+    }
+
+    #[test]
+    fn test_parse_load_without_file_and_echo() {
+        let commands =
+            parse("echo \"some text, with a comma\";\nload,\nset argument[1] %XFFFF;").unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                Command::Echo("some text, with a comma".to_string()),
+                Command::Load(None),
+                Command::Set(Variable::parse("argument[1]").unwrap(), -1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("repeat 3 { vmstep;").is_err());
+        assert!(parse("while RAM[0] = 0 { vmstep; }").is_err());
+        assert!(parse("set RAM[x] 1;").is_err());
+        assert!(parse("set RAM[0] 40000;").is_err());
+    }
+}
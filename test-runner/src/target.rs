@@ -0,0 +1,169 @@
+use crate::script::Variable;
+use assembler_rust::code::CodeGenerator;
+use emulator::chipset::Chipset;
+use emulator::ram::Ram;
+use std::path::Path;
+use vm_emulator::program::Program;
+use vm_emulator::vm::{Vm, MEMORY_SIZE};
+use vm_translator_rust::code::read_sources;
+
+/// what a test script drives: the CPU running a hack program or the VM interpreter.
+pub trait Target {
+    fn set(&mut self, variable: &Variable, value: i16) -> Result<(), String>;
+    fn get(&self, variable: &Variable) -> Result<i16, String>;
+
+    fn tick_tock(&mut self) -> Result<(), String> {
+        Err("ticktock needs a .asm or .hack program to be loaded".to_string())
+    }
+
+    fn vm_step(&mut self) -> Result<(), String> {
+        Err("vmstep needs a .vm program to be loaded".to_string())
+    }
+}
+
+/// loads the program named by a `load` command, relative to the script's directory. Without a
+/// file name every .vm file of the directory is loaded.
+pub fn load(dir: &Path, file: Option<&str>) -> Result<Box<dyn Target>, String> {
+    let path = file.map_or(dir.to_path_buf(), |file| dir.join(file));
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    match extension {
+        Some("asm") => {
+            let source = read_file(&path)?;
+            let mut code = CodeGenerator::new(&source);
+            code.generate()
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            CpuTarget::new(&code.take_code()).map(|target| Box::new(target) as Box<dyn Target>)
+        }
+        Some("hack") => {
+            CpuTarget::new(&read_file(&path)?).map(|target| Box::new(target) as Box<dyn Target>)
+        }
+        _ => {
+            let files = read_sources(&path)?;
+            let program = Program::from_sources(&files)?;
+            let vm = Vm::new(program, Ram::new(MEMORY_SIZE));
+            Ok(Box::new(VmTarget { vm }))
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn ram_address(variable: &Variable) -> Result<usize, String> {
+    match variable.index {
+        Some(index) if index < MEMORY_SIZE => Ok(index),
+        _ => Err(format!("invalid RAM address {}", variable)),
+    }
+}
+
+fn unknown(variable: &Variable) -> String {
+    format!("unknown variable {}", variable)
+}
+
+pub struct CpuTarget {
+    chipset: Chipset,
+    ram: Ram,
+    time: u64,
+}
+
+impl CpuTarget {
+    /// the program is the binary text produced by the assembler, one instruction per line.
+    pub fn new(binary: &str) -> Result<CpuTarget, String> {
+        let mut rom = binary
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                u16::from_str_radix(line, 2).map_err(|_| format!("invalid instruction {:?}", line))
+            })
+            .collect::<Result<Vec<u16>, String>>()?;
+        if rom.len() > MEMORY_SIZE {
+            return Err("program does not fit in ROM".to_string());
+        }
+        rom.resize(MEMORY_SIZE, 0);
+        let ram = Ram::new(MEMORY_SIZE);
+        Ok(CpuTarget {
+            chipset: Chipset::new(rom, ram.clone()),
+            ram,
+            time: 0,
+        })
+    }
+}
+
+impl Target for CpuTarget {
+    fn set(&mut self, variable: &Variable, value: i16) -> Result<(), String> {
+        match variable.name.as_str() {
+            "RAM" => self.ram.write(ram_address(variable)?, value),
+            _ => return Err(format!("cannot set {} on the CPU", variable)),
+        }
+        Ok(())
+    }
+
+    fn get(&self, variable: &Variable) -> Result<i16, String> {
+        match variable.name.as_str() {
+            "RAM" => Ok(self.ram.read(ram_address(variable)?)),
+            "PC" => Ok(self.chipset.pc() as i16),
+            "A" | "ARegister" => Ok(self.chipset.a_register() as i16),
+            "D" | "DRegister" => Ok(self.chipset.d_register()),
+            "time" => Ok(self.time as i16),
+            _ => Err(unknown(variable)),
+        }
+    }
+
+    fn tick_tock(&mut self) -> Result<(), String> {
+        self.chipset.run_next_instruction();
+        self.time += 1;
+        Ok(())
+    }
+}
+
+pub struct VmTarget {
+    vm: Vm,
+}
+
+impl VmTarget {
+    /// `sp`, `local`, `argument`, `this` and `that` are the pointers themselves, with an index
+    /// they address the segment. `temp[i]` and `pointer[i]` are fixed locations.
+    fn address(&self, variable: &Variable) -> Result<usize, String> {
+        let pointer = match variable.name.as_str() {
+            "RAM" => return ram_address(variable),
+            "sp" => 0,
+            "local" => 1,
+            "argument" => 2,
+            "this" => 3,
+            "that" => 4,
+            "pointer" => return Ok(3 + variable.index.ok_or_else(|| unknown(variable))?),
+            "temp" => return Ok(5 + variable.index.ok_or_else(|| unknown(variable))?),
+            _ => return Err(unknown(variable)),
+        };
+        match variable.index {
+            None => Ok(pointer),
+            Some(_) if pointer == 0 => Err(unknown(variable)),
+            Some(index) => {
+                let address = self.vm.ram().read(pointer) as usize + index;
+                if address >= MEMORY_SIZE {
+                    return Err(format!("{} is out of memory", variable));
+                }
+                Ok(address)
+            }
+        }
+    }
+}
+
+impl Target for VmTarget {
+    fn set(&mut self, variable: &Variable, value: i16) -> Result<(), String> {
+        let address = self.address(variable)?;
+        self.vm.ram().write(address, value);
+        Ok(())
+    }
+
+    fn get(&self, variable: &Variable) -> Result<i16, String> {
+        let address = self.address(variable)?;
+        Ok(self.vm.ram().read(address))
+    }
+
+    fn vm_step(&mut self) -> Result<(), String> {
+        self.vm.step()
+    }
+}
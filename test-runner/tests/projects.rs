@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use test_runner::runner::run_script;
+use vm_translator_rust::code::translate;
+use vm_translator_rust::ir::IrOptions;
+
+const PROGRAMS: [&str; 11] = [
+    "7/StackArithmetic/SimpleAdd",
+    "7/StackArithmetic/StackTest",
+    "7/MemoryAccess/BasicTest",
+    "7/MemoryAccess/PointerTest",
+    "7/MemoryAccess/StaticTest",
+    "8/ProgramFlow/BasicLoop",
+    "8/ProgramFlow/FibonacciSeries",
+    "8/FunctionCalls/SimpleFunction",
+    "8/FunctionCalls/NestedCall",
+    "8/FunctionCalls/FibonacciElement",
+    "8/FunctionCalls/StaticsTest",
+];
+
+/// copies the program's directory into a scratch directory so the `.out` files and the
+/// translated `.asm` don't end up in the project files.
+fn scratch_copy(test: &str, program: &str) -> PathBuf {
+    let source = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../__project-files")
+        .join(program);
+    let name = source.file_name().unwrap().to_str().unwrap().to_string();
+    let dir = std::env::temp_dir()
+        .join(format!("test-runner-{}-{}", std::process::id(), test))
+        .join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    for entry in std::fs::read_dir(&source).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            std::fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+        }
+    }
+    dir
+}
+
+fn script(dir: &Path, suffix: &str) -> PathBuf {
+    let name = dir.file_name().unwrap().to_str().unwrap();
+    dir.join(format!("{name}{suffix}.tst"))
+}
+
+#[test]
+fn test_cpu_scripts_pass_on_translated_programs() {
+    for program in PROGRAMS {
+        let dir = scratch_copy("cpu", program);
+        translate(dir.clone(), &IrOptions::default()).unwrap();
+        let report = run_script(&script(&dir, "")).unwrap();
+        assert!(report.compared, "{program}");
+        assert_eq!(report.mismatch, None, "{program}");
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn test_vm_scripts_pass() {
+    for program in PROGRAMS {
+        let dir = scratch_copy("vm", program);
+        let report = run_script(&script(&dir, "VME")).unwrap();
+        assert!(report.compared, "{program}");
+        assert_eq!(report.mismatch, None, "{program}");
+        let out = std::fs::read_to_string(dir.join(format!(
+            "{}.out",
+            Path::new(program).file_name().unwrap().to_str().unwrap()
+        )))
+        .unwrap();
+        assert_eq!(out.lines().count(), report.output.len());
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn test_exit_code_reports_mismatch() {
+    let dir = scratch_copy("exit-code", "7/StackArithmetic/SimpleAdd");
+    let binary = env!("CARGO_BIN_EXE_test-runner");
+
+    let status = Command::new(binary)
+        .arg(script(&dir, "VME"))
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+
+    std::fs::write(
+        dir.join("SimpleAdd.cmp"),
+        "|  RAM[0]  | RAM[256] |\n|     257  |      16  |\n",
+    )
+    .unwrap();
+    let output = Command::new(binary)
+        .arg(script(&dir, "VME"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("comparison failure at line 2"), "{stdout}");
+    std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
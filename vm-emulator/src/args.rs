@@ -0,0 +1,36 @@
+use std::{env::args, path::PathBuf};
+
+/// runs are capped so a program stuck in its final `goto` loop still ends.
+pub const DEFAULT_STEPS: u64 = 10_000_000;
+
+pub struct VmArgs {
+    pub src: PathBuf,
+    pub steps: u64,
+    pub trace: bool,
+}
+
+impl VmArgs {
+    /// a single positional argument, the .vm file or a directory of .vm files, followed by any
+    /// options.
+    pub fn parse() -> Result<VmArgs, String> {
+        let mut src = None;
+        let mut steps = DEFAULT_STEPS;
+        let mut trace = false;
+        let mut iter = args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--steps" => {
+                    let value = iter.next().ok_or("--steps expects a number")?;
+                    steps = value
+                        .parse()
+                        .map_err(|_| format!("invalid step count {}", value))?;
+                }
+                "--trace" => trace = true,
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+                _ => src = Some(PathBuf::from(arg)),
+            }
+        }
+        let src = src.ok_or("missing source file")?;
+        Ok(VmArgs { src, steps, trace })
+    }
+}
@@ -0,0 +1,53 @@
+use emulator::ram::Ram;
+use vm_emulator::args::VmArgs;
+use vm_emulator::program::Program;
+use vm_emulator::vm::{Vm, MEMORY_SIZE};
+use vm_translator_rust::code::read_sources;
+
+fn main() {
+    let args = match VmArgs::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("[err] {e}");
+            eprintln!("usage: vm-emulator <source file | directory> [--steps <n>] [--trace]");
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = run(&args) {
+        // parse and runtime errors end the run with a status scripts can tell apart
+        eprintln!("[err] {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &VmArgs) -> Result<(), String> {
+    let files = read_sources(&args.src)?;
+    let program = Program::from_sources(&files)?;
+    let mut vm = Vm::new(program, Ram::new(MEMORY_SIZE));
+    vm.bootstrap();
+
+    while !vm.is_halted() && vm.steps() < args.steps {
+        if args.trace {
+            if let Some(instruction) = vm.current() {
+                println!("{:>8} {:<24} {}", vm.steps(), vm.location(), instruction);
+            }
+        }
+        vm.step()?;
+    }
+
+    if vm.is_halted() {
+        println!("[info] halted after {} steps", vm.steps());
+    } else {
+        println!("[info] stopped after {} steps", vm.steps());
+    }
+    for frame in vm.call_stack().iter().rev() {
+        println!(
+            "[info]   at {} args {:?} locals {:?}",
+            frame.function,
+            frame.arguments(vm.ram()),
+            frame.locals(vm.ram())
+        );
+    }
+    println!("[info] stack {:?}", vm.stack());
+    Ok(())
+}
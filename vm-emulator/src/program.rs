@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::fmt;
+pub use vm_translator_rust::parser::Segment;
+use vm_translator_rust::parser::{VmCommand, VmParser};
+
+/// the first address of the static variables, every file gets its own block after it.
+pub const STATIC_BASE: usize = 16;
+
+/// a vm command with its labels resolved to instruction indexes. Labels themselves take no
+/// step, like in the reference VM emulator, so they don't appear in the program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Add,
+    Sub,
+    Neg,
+    Eq,
+    Gt,
+    Lt,
+    And,
+    Or,
+    Not,
+    Push(Segment, u16),
+    Pop(Segment, u16),
+    Goto(usize),
+    IfGoto(usize),
+    Function { name: String, locals: u16 },
+    Call { name: String, args: u16 },
+    Return,
+    Mul,
+    Div,
+    Mod,
+    Shl,
+    Shr,
+    Dup,
+    Swap,
+    Constant(i16),
+    Move((Segment, u16), (Segment, u16)),
+    IsZero,
+    Inc,
+    Dec,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Add => write!(f, "add"),
+            Instruction::Sub => write!(f, "sub"),
+            Instruction::Neg => write!(f, "neg"),
+            Instruction::Eq => write!(f, "eq"),
+            Instruction::Gt => write!(f, "gt"),
+            Instruction::Lt => write!(f, "lt"),
+            Instruction::And => write!(f, "and"),
+            Instruction::Or => write!(f, "or"),
+            Instruction::Not => write!(f, "not"),
+            Instruction::Push(segment, index) => write!(f, "push {} {}", segment.name(), index),
+            Instruction::Pop(segment, index) => write!(f, "pop {} {}", segment.name(), index),
+            Instruction::Goto(target) => write!(f, "goto {}", target),
+            Instruction::IfGoto(target) => write!(f, "if-goto {}", target),
+            Instruction::Function { name, locals } => write!(f, "function {} {}", name, locals),
+            Instruction::Call { name, args } => write!(f, "call {} {}", name, args),
+            Instruction::Return => write!(f, "return"),
+            Instruction::Mul => write!(f, "mul"),
+            Instruction::Div => write!(f, "div"),
+            Instruction::Mod => write!(f, "mod"),
+            Instruction::Shl => write!(f, "shl"),
+            Instruction::Shr => write!(f, "shr"),
+            Instruction::Dup => write!(f, "dup"),
+            Instruction::Swap => write!(f, "swap"),
+            Instruction::Constant(value) => write!(f, "constant {}", value),
+            Instruction::Move((from, i), (to, j)) => {
+                write!(f, "move {} {} {} {}", from.name(), i, to.name(), j)
+            }
+            Instruction::IsZero => write!(f, "is-zero"),
+            Instruction::Inc => write!(f, "inc"),
+            Instruction::Dec => write!(f, "dec"),
+        }
+    }
+}
+
+/// a loaded vm program: the instructions of all its files one after the other, the file every
+/// instruction comes from (statics are per file) and where every function starts.
+#[derive(Debug)]
+pub struct Program {
+    pub files: Vec<String>,
+    pub instructions: Vec<Instruction>,
+    locations: Vec<usize>,
+    static_bases: Vec<usize>,
+    functions: HashMap<String, usize>,
+}
+
+impl Program {
+    /// parses the (file name, source) pairs of a program.
+    pub fn from_sources(files: &[(String, String)]) -> Result<Program, String> {
+        let mut programs = Vec::with_capacity(files.len());
+        for (file_name, source) in files {
+            let commands = parse(source).map_err(|e| format!("{file_name}: {e}"))?;
+            programs.push((file_name.as_str(), commands));
+        }
+        Program::from_commands(&programs)
+    }
+
+    /// builds a program from already parsed (file name, commands) pairs, this includes the
+    /// commands produced by the optimizer.
+    pub fn from_commands(files: &[(&str, Vec<VmCommand>)]) -> Result<Program, String> {
+        let mut program = Program {
+            files: Vec::with_capacity(files.len()),
+            instructions: Vec::new(),
+            locations: Vec::new(),
+            static_bases: Vec::with_capacity(files.len()),
+            functions: HashMap::new(),
+        };
+
+        // first pass, find where every function and label lands.
+        let mut labels = HashMap::new();
+        let mut position = 0;
+        for (file_name, commands) in files {
+            let mut scope = file_name.to_string();
+            for command in commands {
+                match command {
+                    VmCommand::Label(label) => {
+                        let key = format!("{}${}", scope, label);
+                        if labels.insert(key, position).is_some() {
+                            return Err(format!("{file_name}: duplicate label {label}"));
+                        }
+                    }
+                    VmCommand::Function { name, .. } => {
+                        scope = name.to_string();
+                        if program.functions.insert(scope.clone(), position).is_some() {
+                            return Err(format!("{file_name}: duplicate function {name}"));
+                        }
+                        position += 1;
+                    }
+                    _ => position += 1,
+                }
+            }
+        }
+
+        // second pass, lower the commands with resolved jump targets.
+        let mut next_static = STATIC_BASE;
+        for (file, (file_name, commands)) in files.iter().enumerate() {
+            let mut scope = file_name.to_string();
+            let mut statics = 0;
+            for command in commands {
+                let target = |label: &str| {
+                    labels
+                        .get(&format!("{}${}", scope, label))
+                        .copied()
+                        .ok_or_else(|| format!("{file_name}: unknown label {label} in {scope}"))
+                };
+                let instruction = match command {
+                    VmCommand::Add => Instruction::Add,
+                    VmCommand::Sub => Instruction::Sub,
+                    VmCommand::Neg => Instruction::Neg,
+                    VmCommand::Eq => Instruction::Eq,
+                    VmCommand::Gt => Instruction::Gt,
+                    VmCommand::Lt => Instruction::Lt,
+                    VmCommand::And => Instruction::And,
+                    VmCommand::Or => Instruction::Or,
+                    VmCommand::Not => Instruction::Not,
+                    VmCommand::Push { segment, index } => Instruction::Push(*segment, *index),
+                    VmCommand::Pop { segment, index } => {
+                        if *segment == Segment::Constant {
+                            return Err(format!("{file_name}: cannot pop into constant"));
+                        }
+                        Instruction::Pop(*segment, *index)
+                    }
+                    VmCommand::Label(_) => continue,
+                    VmCommand::Goto(label) => Instruction::Goto(target(label)?),
+                    VmCommand::IfGoto(label) => Instruction::IfGoto(target(label)?),
+                    VmCommand::Function { name, locals } => {
+                        scope = name.to_string();
+                        Instruction::Function {
+                            name: name.to_string(),
+                            locals: *locals,
+                        }
+                    }
+                    VmCommand::Call { name, args } => Instruction::Call {
+                        name: name.to_string(),
+                        args: *args,
+                    },
+                    VmCommand::Return => Instruction::Return,
+                    VmCommand::Mul => Instruction::Mul,
+                    VmCommand::Div => Instruction::Div,
+                    VmCommand::Mod => Instruction::Mod,
+                    VmCommand::Shl => Instruction::Shl,
+                    VmCommand::Shr => Instruction::Shr,
+                    VmCommand::Dup => Instruction::Dup,
+                    VmCommand::Swap => Instruction::Swap,
+                    VmCommand::Constant(value) => Instruction::Constant(*value),
+                    VmCommand::Move((from, i), (to, j)) => {
+                        Instruction::Move((*from, *i), (*to, *j))
+                    }
+                    VmCommand::IsZero => Instruction::IsZero,
+                    VmCommand::Inc => Instruction::Inc,
+                    VmCommand::Dec => Instruction::Dec,
+                };
+                statics = statics.max(static_count(&instruction));
+                program.instructions.push(instruction);
+                program.locations.push(file);
+            }
+            program.files.push(file_name.to_string());
+            program.static_bases.push(next_static);
+            next_static += statics;
+        }
+
+        Ok(program)
+    }
+
+    /// the index of the first instruction of the function, if the program defines it.
+    pub fn function(&self, name: &str) -> Option<usize> {
+        self.functions.get(name).copied()
+    }
+
+    /// programs with a `Sys.init` start there, everything else starts at the first instruction.
+    pub fn entry(&self) -> usize {
+        self.function("Sys.init").unwrap_or(0)
+    }
+
+    /// the file the instruction at `pc` comes from.
+    pub fn file(&self, pc: usize) -> Option<&str> {
+        self.locations
+            .get(pc)
+            .map(|file| self.files[*file].as_str())
+    }
+
+    /// the address of a static variable of the file the instruction at `pc` comes from.
+    pub fn static_address(&self, pc: usize, index: u16) -> usize {
+        self.static_bases[self.locations[pc]] + index as usize
+    }
+}
+
+/// parses every command of a source, the extension commands included. Unlike the translator
+/// a syntax error is not silently the end of the program.
+fn parse(source: &str) -> Result<Vec<VmCommand<'_>>, String> {
+    let commands = VmParser::with_extensions(source).commands()?;
+    Ok(commands.into_iter().map(|(command, _)| command).collect())
+}
+
+/// the number of static variables the instruction needs its file to have.
+fn static_count(instruction: &Instruction) -> usize {
+    let count = |segment: &Segment, index: &u16| match segment {
+        Segment::Static => *index as usize + 1,
+        _ => 0,
+    };
+    match instruction {
+        Instruction::Push(segment, index) | Instruction::Pop(segment, index) => {
+            count(segment, index)
+        }
+        Instruction::Move((from, i), (to, j)) => count(from, i).max(count(to, j)),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn program(files: &[(&str, &str)]) -> Result<Program, String> {
+        let files = files
+            .iter()
+            .map(|(name, source)| (name.to_string(), source.to_string()))
+            .collect::<Vec<_>>();
+        Program::from_sources(&files)
+    }
+
+    #[test]
+    fn test_labels_resolve_to_next_instruction() {
+        let program = program(&[(
+            "Main.vm",
+            "function Main.main 0\nlabel LOOP\npush constant 1\nif-goto END\ngoto LOOP\nlabel END\nreturn\n",
+        )])
+        .unwrap();
+        assert_eq!(
+            program.instructions,
+            vec![
+                Instruction::Function {
+                    name: "Main.main".to_string(),
+                    locals: 0
+                },
+                Instruction::Push(Segment::Constant, 1),
+                Instruction::IfGoto(4),
+                Instruction::Goto(1),
+                Instruction::Return,
+            ]
+        );
+        assert_eq!(program.entry(), 0);
+    }
+
+    #[test]
+    fn test_labels_are_scoped_to_functions() {
+        let result = program(&[(
+            "Main.vm",
+            "function Main.a 0\nlabel LOOP\nreturn\nfunction Main.b 0\ngoto LOOP\n",
+        )]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_statics_are_per_file() {
+        let program = program(&[
+            ("A.vm", "function A.f 0\npush static 2\nreturn\n"),
+            ("B.vm", "function Sys.init 0\npop static 0\n"),
+        ])
+        .unwrap();
+        assert_eq!(program.static_address(1, 2), 16 + 2);
+        assert_eq!(program.static_address(4, 0), 16 + 3);
+        assert_eq!(program.entry(), 3);
+        assert_eq!(program.file(4), Some("B.vm"));
+    }
+
+    #[test]
+    fn test_syntax_errors_are_reported() {
+        assert!(program(&[("A.vm", "push constant 1\npush nowhere 2\n")]).is_err());
+        assert!(program(&[("A.vm", "pop constant 1\n")]).is_err());
+    }
+}
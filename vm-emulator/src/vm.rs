@@ -0,0 +1,531 @@
+use crate::program::{Instruction, Program, Segment};
+use emulator::os::Os;
+use emulator::ram::Ram;
+use vm_translator_rust::optimize::{shift_left, shift_right};
+
+pub const MEMORY_SIZE: usize = 32_768;
+pub const STACK_BASE: usize = 256;
+
+const SP: usize = 0;
+const LCL: usize = 1;
+const ARG: usize = 2;
+const THIS: usize = 3;
+const THAT: usize = 4;
+const TEMP: usize = 5;
+
+/// an entry of the call stack, mirroring the frame the program keeps in RAM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    /// where execution continues after `return`, `None` for the function the program started in.
+    pub return_address: Option<usize>,
+    pub arguments: usize,
+    pub arg_base: usize,
+    pub locals: usize,
+    pub local_base: usize,
+}
+
+impl Frame {
+    pub fn arguments(&self, ram: &Ram) -> Vec<i16> {
+        (0..self.arguments)
+            .map(|i| ram.read(self.arg_base + i))
+            .collect()
+    }
+
+    pub fn locals(&self, ram: &Ram) -> Vec<i16> {
+        (0..self.locals)
+            .map(|i| ram.read(self.local_base + i))
+            .collect()
+    }
+}
+
+/// interprets a vm program on the hack memory layout: the stack, segment pointers and call
+/// frames live in RAM exactly where the translated program would put them. Calls to functions
+/// the program doesn't define are served by the native OS implementation.
+#[derive(Debug)]
+pub struct Vm {
+    program: Program,
+    ram: Ram,
+    os: Os,
+    pc: usize,
+    call_stack: Vec<Frame>,
+    /// set by `call` so the next `function` completes the callee's frame instead of starting
+    /// a new one.
+    entering: bool,
+    steps: u64,
+    halted: bool,
+}
+
+impl Vm {
+    pub fn new(program: Program, ram: Ram) -> Self {
+        let pc = program.entry();
+        Self {
+            program,
+            ram,
+            os: Os::new(),
+            pc,
+            call_stack: Vec::new(),
+            entering: false,
+            steps: 0,
+            halted: false,
+        }
+    }
+
+    /// sets up the stack like the translator's bootstrap code would before `Sys.init` runs.
+    pub fn bootstrap(&mut self) {
+        for pointer in [SP, LCL, ARG] {
+            self.ram.write(pointer, STACK_BASE as i16);
+        }
+    }
+
+    pub fn ram(&self) -> &Ram {
+        &self.ram
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// the instruction the next step executes.
+    pub fn current(&self) -> Option<&Instruction> {
+        self.program.instructions.get(self.pc)
+    }
+
+    /// the active calls, the innermost one last.
+    pub fn call_stack(&self) -> &[Frame] {
+        &self.call_stack
+    }
+
+    /// the values on the working stack of the current function.
+    pub fn stack(&self) -> Vec<i16> {
+        let base = self
+            .call_stack
+            .last()
+            .map_or(STACK_BASE, |frame| frame.local_base + frame.locals);
+        let sp = self.ram.read(SP).max(0) as usize;
+        (base.min(sp)..sp).map(|i| self.ram.read(i)).collect()
+    }
+
+    /// runs until the program halts or `max_steps` steps were executed, returns the number of
+    /// steps taken.
+    pub fn run(&mut self, max_steps: u64) -> Result<u64, String> {
+        let start = self.steps;
+        while !self.halted && self.steps - start < max_steps {
+            self.step()?;
+        }
+        Ok(self.steps - start)
+    }
+
+    /// executes a single instruction.
+    pub fn step(&mut self) -> Result<(), String> {
+        if self.halted {
+            return Ok(());
+        }
+        let Some(instruction) = self.program.instructions.get(self.pc).cloned() else {
+            self.halted = true;
+            return Ok(());
+        };
+        self.steps += 1;
+        self.execute(instruction)
+            .map_err(|e| format!("{} ({}): {}", self.location(), self.steps, e))
+    }
+
+    /// the file and function of the current instruction, for error messages.
+    pub fn location(&self) -> String {
+        let file = self.program.file(self.pc).unwrap_or("<end>");
+        match self.call_stack.last() {
+            Some(frame) => format!("{}:{}", file, frame.function),
+            None => file.to_string(),
+        }
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> Result<(), String> {
+        let mut next = self.pc + 1;
+        match instruction {
+            Instruction::Add => self.binary(|a, b| a.wrapping_add(b))?,
+            Instruction::Sub => self.binary(|a, b| a.wrapping_sub(b))?,
+            Instruction::And => self.binary(|a, b| a & b)?,
+            Instruction::Or => self.binary(|a, b| a | b)?,
+            Instruction::Eq => self.binary(|a, b| as_bool(a == b))?,
+            // the translated code compares by the sign of x - y, which overflows for operands
+            // far apart, and a program has to give the same results here as on the CPU
+            Instruction::Gt => self.binary(|a, b| as_bool(a.wrapping_sub(b) > 0))?,
+            Instruction::Lt => self.binary(|a, b| as_bool(a.wrapping_sub(b) < 0))?,
+            Instruction::Neg => self.unary(|a| a.wrapping_neg())?,
+            Instruction::Not => self.unary(|a| !a)?,
+            Instruction::IsZero => self.unary(|a| as_bool(a == 0))?,
+            Instruction::Inc => self.unary(|a| a.wrapping_add(1))?,
+            Instruction::Dec => self.unary(|a| a.wrapping_sub(1))?,
+            Instruction::Mul => self.binary(|a, b| a.wrapping_mul(b))?,
+            Instruction::Div => self.division(|a, b| a.wrapping_div(b))?,
+            Instruction::Mod => self.division(|a, b| a.wrapping_rem(b))?,
+            Instruction::Shl => self.binary(shift_left)?,
+            Instruction::Shr => self.binary(shift_right)?,
+            Instruction::Dup => {
+                let value = self.pop()?;
+                self.push(value)?;
+                self.push(value)?;
+            }
+            Instruction::Swap => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(b)?;
+                self.push(a)?;
+            }
+            Instruction::Constant(value) => self.push(value)?,
+            Instruction::Push(segment, index) => {
+                let value = self.load(segment, index)?;
+                self.push(value)?;
+            }
+            Instruction::Pop(segment, index) => {
+                let value = self.pop()?;
+                self.store(segment, index, value)?;
+            }
+            Instruction::Move(from, to) => {
+                let value = self.load(from.0, from.1)?;
+                self.store(to.0, to.1, value)?;
+            }
+            Instruction::Goto(target) => next = target,
+            Instruction::IfGoto(target) => {
+                if self.pop()? != 0 {
+                    next = target;
+                }
+            }
+            Instruction::Function { name, locals } => self.function(name, locals)?,
+            Instruction::Call { name, args } => next = self.call(name, args)?,
+            Instruction::Return => match self.return_from_function()? {
+                Some(address) => next = address,
+                None => self.halted = true,
+            },
+        }
+        self.pc = next;
+        Ok(())
+    }
+
+    fn function(&mut self, name: String, locals: u16) -> Result<(), String> {
+        let local_base = self.ram.read(LCL) as usize;
+        match self.call_stack.last_mut() {
+            Some(frame) if self.entering => {
+                frame.local_base = local_base;
+                frame.locals = locals as usize;
+            }
+            _ => self.call_stack.push(Frame {
+                function: name,
+                return_address: None,
+                arguments: 0,
+                arg_base: self.ram.read(ARG) as usize,
+                locals: locals as usize,
+                local_base,
+            }),
+        }
+        self.entering = false;
+        for _ in 0..locals {
+            self.push(0)?;
+        }
+        Ok(())
+    }
+
+    /// pushes the frame and jumps to the function, or runs the native implementation in place
+    /// when the program doesn't define it. Returns the next pc.
+    fn call(&mut self, name: String, args: u16) -> Result<usize, String> {
+        let Some(target) = self.program.function(&name) else {
+            return self.call_native(&name, args);
+        };
+
+        let return_address = self.pc + 1;
+        self.push(return_address as i16)?;
+        for pointer in [LCL, ARG, THIS, THAT] {
+            let value = self.ram.read(pointer);
+            self.push(value)?;
+        }
+        let sp = self.ram.read(SP);
+        let arg_base = sp - 5 - args as i16;
+        self.ram.write(ARG, arg_base);
+        self.ram.write(LCL, sp);
+        self.call_stack.push(Frame {
+            function: name,
+            return_address: Some(return_address),
+            arguments: args as usize,
+            arg_base: arg_base.max(0) as usize,
+            locals: 0,
+            local_base: sp as usize,
+        });
+        self.entering = true;
+        Ok(target)
+    }
+
+    fn call_native(&mut self, name: &str, args: u16) -> Result<usize, String> {
+        if !Os::implements(name) {
+            return Err(format!("call to undefined function {}", name));
+        }
+        let mut values = vec![0; args as usize];
+        for value in values.iter_mut().rev() {
+            *value = self.pop()?;
+        }
+        let result = self.os.call(&self.ram, name, &values)?;
+        self.push(result)?;
+        if self.os.is_halted() {
+            self.halted = true;
+        }
+        Ok(self.pc + 1)
+    }
+
+    /// restores the caller's segments from the frame in RAM, returns `None` when the function
+    /// the program started in returns.
+    fn return_from_function(&mut self) -> Result<Option<usize>, String> {
+        let frame = self.ram.read(LCL);
+        let value = self.pop()?;
+        let arg = self.ram.read(ARG);
+        self.write_at(arg, value)?;
+        self.ram.write(SP, arg.wrapping_add(1));
+        for (offset, pointer) in [THAT, THIS, ARG, LCL].into_iter().enumerate() {
+            let saved = self.read_at(frame.wrapping_sub(offset as i16 + 1))?;
+            self.ram.write(pointer, saved);
+        }
+        Ok(self.call_stack.pop().and_then(|frame| frame.return_address))
+    }
+
+    fn load(&self, segment: Segment, index: u16) -> Result<i16, String> {
+        if segment == Segment::Constant {
+            return Ok(index as i16);
+        }
+        let address = self.address(segment, index)?;
+        Ok(self.ram.read(address))
+    }
+
+    fn store(&self, segment: Segment, index: u16, value: i16) -> Result<(), String> {
+        if segment == Segment::Constant {
+            return Err("cannot pop into constant".to_string());
+        }
+        let address = self.address(segment, index)?;
+        self.ram.write(address, value);
+        Ok(())
+    }
+
+    fn address(&self, segment: Segment, index: u16) -> Result<usize, String> {
+        let index = index as i16;
+        let address = match segment {
+            Segment::Local => self.ram.read(LCL).wrapping_add(index),
+            Segment::Argument => self.ram.read(ARG).wrapping_add(index),
+            Segment::This => self.ram.read(THIS).wrapping_add(index),
+            Segment::That => self.ram.read(THAT).wrapping_add(index),
+            Segment::Pointer if index < 2 => THIS as i16 + index,
+            Segment::Temp if index < 8 => TEMP as i16 + index,
+            Segment::Static => self.program.static_address(self.pc, index as u16) as i16,
+            _ => return Err(format!("{} {} is out of bounds", segment.name(), index)),
+        };
+        check_address(address)
+    }
+
+    fn binary(&mut self, op: impl Fn(i16, i16) -> i16) -> Result<(), String> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+        self.push(op(a, b))
+    }
+
+    fn division(&mut self, op: impl Fn(i16, i16) -> i16) -> Result<(), String> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+        if b == 0 {
+            return Err("division by zero".to_string());
+        }
+        self.push(op(a, b))
+    }
+
+    fn unary(&mut self, op: impl Fn(i16) -> i16) -> Result<(), String> {
+        let a = self.pop()?;
+        self.push(op(a))
+    }
+
+    fn push(&mut self, value: i16) -> Result<(), String> {
+        let sp = self.ram.read(SP);
+        self.write_at(sp, value)?;
+        self.ram.write(SP, sp.wrapping_add(1));
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<i16, String> {
+        let sp = self.ram.read(SP).wrapping_sub(1);
+        self.ram.write(SP, sp);
+        self.read_at(sp)
+    }
+
+    fn read_at(&self, address: i16) -> Result<i16, String> {
+        Ok(self.ram.read(check_address(address)?))
+    }
+
+    fn write_at(&self, address: i16, value: i16) -> Result<(), String> {
+        self.ram.write(check_address(address)?, value);
+        Ok(())
+    }
+}
+
+fn check_address(address: i16) -> Result<usize, String> {
+    if address < 0 {
+        return Err(format!("illegal memory address {}", address));
+    }
+    Ok(address as usize)
+}
+
+fn as_bool(value: bool) -> i16 {
+    if value {
+        -1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn vm(files: &[(&str, &str)]) -> Vm {
+        let files = files
+            .iter()
+            .map(|(name, source)| (name.to_string(), source.to_string()))
+            .collect::<Vec<_>>();
+        let program = Program::from_sources(&files).unwrap();
+        let mut vm = Vm::new(program, Ram::new(MEMORY_SIZE));
+        vm.bootstrap();
+        vm
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut vm = vm(&[(
+            "Main.vm",
+            "push constant 7\npush constant 8\nadd\npush constant 20\nlt\nnot\npush constant 3\nneg\n",
+        )]);
+        assert_eq!(vm.run(100), Ok(8));
+        assert!(vm.is_halted());
+        assert_eq!(vm.stack(), vec![0, -3]);
+    }
+
+    /// gt and lt of operands more than 32767 apart, like `optimize::fold_binary` of the
+    /// translator folds them.
+    #[test]
+    fn test_overflowing_comparisons() {
+        for (x, y, gt, lt) in [
+            (32767, -1, 0, -1),
+            (-32768, 1, -1, 0),
+            (1, -32768, 0, -1),
+            (-32768, 32767, -1, 0),
+            (-2, 32767, -1, 0),
+            (5, 3, -1, 0),
+        ] {
+            let source = format!("{}{}gt\n{}{}lt\n", push(x), push(y), push(x), push(y));
+            let mut vm = vm(&[("Main.vm", &source)]);
+            vm.run(100).unwrap();
+            assert_eq!(vm.stack(), vec![gt, lt], "{x} {y}");
+        }
+    }
+
+    /// pushes any value, constants only go up to 32767.
+    fn push(value: i16) -> String {
+        match value {
+            -32768 => "push constant 32767\nneg\npush constant 1\nsub\n".to_string(),
+            value if value < 0 => format!("push constant {}\nneg\n", -value),
+            value => format!("push constant {value}\n"),
+        }
+    }
+
+    #[test]
+    fn test_extension_commands() {
+        let mut division = vm(&[("Main.vm", "push constant 1\npush constant 0\ndiv\n")]);
+        assert!(division.run(100).unwrap_err().contains("division by zero"));
+
+        let mut vm = vm(&[(
+            "Main.vm",
+            "push constant 7\nneg\npush constant 2\ndiv\npush constant 7\nneg\npush constant 2\nmod\npush constant 300\npush constant 300\nmul\npush constant 1\npush constant 15\nshl\npush constant 3\nshr\npush constant 1\npush constant 2\nswap\ndup\n",
+        )]);
+        assert_eq!(vm.run(100), Ok(20));
+        assert_eq!(vm.stack(), vec![-3, -1, 24464, -4096, 2, 1, 1]);
+    }
+
+    #[test]
+    fn test_single_step() {
+        let mut vm = vm(&[("Main.vm", "push constant 2\npop temp 3\n")]);
+        assert_eq!(vm.current(), Some(&Instruction::Push(Segment::Constant, 2)));
+        vm.step().unwrap();
+        assert_eq!(vm.stack(), vec![2]);
+        assert_eq!(
+            vm.current().map(|i| i.to_string()),
+            Some("pop temp 3".to_string())
+        );
+        vm.step().unwrap();
+        assert_eq!(vm.ram().read(8), 2);
+        assert_eq!(vm.steps(), 2);
+    }
+
+    #[test]
+    fn test_call_stack() {
+        let mut vm = vm(&[(
+            "Sys.vm",
+            "function Sys.init 0\npush constant 4\npush constant 5\ncall Sys.add 2\nlabel HALT\ngoto HALT\nfunction Sys.add 1\npush argument 0\npush argument 1\nadd\nreturn\n",
+        )]);
+        vm.run(5).unwrap();
+        let functions = vm
+            .call_stack()
+            .iter()
+            .map(|frame| frame.function.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(functions, vec!["Sys.init", "Sys.add"]);
+        let frame = &vm.call_stack()[1];
+        assert_eq!(frame.arguments(vm.ram()), vec![4, 5]);
+        assert_eq!(frame.locals(vm.ram()), vec![0]);
+        assert_eq!(frame.return_address, Some(4));
+
+        vm.run(4).unwrap();
+        assert_eq!(vm.call_stack().len(), 1);
+        assert_eq!(vm.stack(), vec![9]);
+        assert_eq!(vm.pc(), 4);
+    }
+
+    #[test]
+    fn test_native_os_calls() {
+        let mut vm = vm(&[(
+            "Sys.vm",
+            "function Sys.init 0\npush constant 6\npush constant 7\ncall Math.multiply 2\npush constant 3\ncall String.new 1\npush constant 72\ncall String.appendChar 2\ncall String.length 1\ncall Sys.halt 0\n",
+        )]);
+        vm.run(100).unwrap();
+        assert!(vm.is_halted());
+        assert_eq!(vm.stack(), vec![42, 1, 0]);
+    }
+
+    #[test]
+    fn test_statics_and_pointers() {
+        let mut vm = vm(&[(
+            "Main.vm",
+            "push constant 3000\npop pointer 0\npush constant 11\npop this 2\npush this 2\npop static 1\n",
+        )]);
+        vm.run(100).unwrap();
+        assert_eq!(vm.ram().read(3002), 11);
+        assert_eq!(vm.ram().read(17), 11);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut vm = vm(&[("Main.vm", "call Main.missing 0\n")]);
+        assert!(vm.step().is_err());
+        let mut vm = vm_without_bootstrap("push temp 8\n");
+        assert!(vm.step().is_err());
+    }
+
+    fn vm_without_bootstrap(source: &str) -> Vm {
+        let program =
+            Program::from_sources(&[("Main.vm".to_string(), source.to_string())]).unwrap();
+        Vm::new(program, Ram::new(MEMORY_SIZE))
+    }
+}
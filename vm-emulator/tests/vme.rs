@@ -0,0 +1,157 @@
+use emulator::ram::Ram;
+use std::path::{Path, PathBuf};
+use test_runner::script::{parse, Command, Variable};
+use vm_emulator::program::Program;
+use vm_emulator::vm::{Vm, MEMORY_SIZE};
+use vm_translator_rust::code::read_sources;
+
+/// the parts of a `*VME.tst` script these tests care about: the initial RAM values, how many
+/// `vmstep`s to take and which addresses end up in the `.cmp` file.
+struct TestScript {
+    setup: Vec<(usize, i16)>,
+    steps: u64,
+    outputs: Vec<usize>,
+}
+
+fn project_dir(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../__project-files")
+        .join(relative)
+}
+
+/// reads the script with the parser of the test runner.
+fn read_test_script(path: &Path) -> TestScript {
+    let source = std::fs::read_to_string(path).unwrap();
+    let mut script = TestScript {
+        setup: Vec::new(),
+        steps: 0,
+        outputs: Vec::new(),
+    };
+    let mut pointers = [0usize; 5];
+    for command in parse(&source).unwrap() {
+        match command {
+            Command::Set(variable, value) => {
+                let address = address(&variable, &pointers);
+                if address < pointers.len() {
+                    pointers[address] = value as usize;
+                }
+                script.setup.push((address, value));
+            }
+            Command::Repeat(count, _) => script.steps = count,
+            Command::OutputList(columns) => {
+                let addresses = columns
+                    .iter()
+                    .map(|column| address(&column.variable, &pointers));
+                script.outputs.extend(addresses);
+            }
+            _ => {}
+        }
+    }
+    script
+}
+
+/// `sp`, `local`, `argument`, `this` and `that` name the pointers, `segment[i]` the i-th entry
+/// of the segment as set up so far.
+fn address(variable: &Variable, pointers: &[usize; 5]) -> usize {
+    let pointer = match variable.name.as_str() {
+        "RAM" => return variable.index.unwrap(),
+        "sp" => 0,
+        "local" => 1,
+        "argument" => 2,
+        "this" => 3,
+        "that" => 4,
+        other => panic!("unknown variable {other}"),
+    };
+    match variable.index {
+        Some(index) => pointers[pointer] + index,
+        None => pointer,
+    }
+}
+
+fn read_expected(path: &Path) -> Vec<i16> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .skip(1)
+        .step_by(2)
+        .flat_map(|line| {
+            line.split('|')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.parse::<i16>().unwrap())
+                .collect::<Vec<i16>>()
+        })
+        .collect()
+}
+
+fn check_against_cmp(program: &str) {
+    let dir = project_dir(program);
+    let name = dir.file_name().unwrap().to_str().unwrap().to_string();
+    let script = read_test_script(&dir.join(format!("{name}VME.tst")));
+    let expected = read_expected(&dir.join(format!("{name}.cmp")));
+
+    let files = read_sources(&dir).unwrap();
+    let ram = Ram::new(MEMORY_SIZE);
+    for (address, value) in &script.setup {
+        ram.write(*address, *value);
+    }
+    let mut vm = Vm::new(Program::from_sources(&files).unwrap(), ram);
+    vm.run(script.steps).unwrap();
+
+    let actual = script
+        .outputs
+        .iter()
+        .map(|address| vm.ram().read(*address))
+        .collect::<Vec<i16>>();
+    assert_eq!(actual, expected, "{program} after {} steps", script.steps);
+}
+
+#[test]
+fn test_project7_programs() {
+    for program in [
+        "7/StackArithmetic/SimpleAdd",
+        "7/StackArithmetic/StackTest",
+        "7/MemoryAccess/BasicTest",
+        "7/MemoryAccess/PointerTest",
+        "7/MemoryAccess/StaticTest",
+    ] {
+        check_against_cmp(program);
+    }
+}
+
+#[test]
+fn test_project8_programs() {
+    for program in [
+        "8/ProgramFlow/BasicLoop",
+        "8/ProgramFlow/FibonacciSeries",
+        "8/FunctionCalls/SimpleFunction",
+        "8/FunctionCalls/NestedCall",
+        "8/FunctionCalls/FibonacciElement",
+        "8/FunctionCalls/StaticsTest",
+    ] {
+        check_against_cmp(program);
+    }
+}
+
+/// parse and runtime errors end the run with a failing status.
+#[test]
+fn test_errors_exit_with_an_error() {
+    let dir = std::env::temp_dir().join(format!("vme-errors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, source) in [
+        ("Parse.vm", "push constant 1\nfrobnicate\n"),
+        ("Run.vm", "push constant 1\ncall Main.missing 0\n"),
+    ] {
+        let path = dir.join(name);
+        std::fs::write(&path, source).unwrap();
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_vm-emulator"))
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{name}");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("[err] "), "{name}: {stderr}");
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
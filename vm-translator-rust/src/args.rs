@@ -1,37 +1,124 @@
+use crate::code::{read_sources, Emit};
+use crate::inline::DEFAULT_THRESHOLD;
+use crate::ir::IrOptions;
+use crate::os::link_os;
+use crate::stats::StatsFormat;
+use std::io::Read;
 use std::{env::args, path::PathBuf};
 
+/// the source given as `-` is read from stdin.
+pub const STDIN: &str = "-";
+
 pub struct AssemblerArgs {
     pub src: PathBuf,
+    /// the file name of a single source, and with it the namespace of its statics
+    pub name: Option<String>,
+    pub options: IrOptions,
+    pub size_report: bool,
+    pub source_map: bool,
+    pub verify: bool,
+    pub emit: Emit,
+    pub stats: Option<StatsFormat>,
+    /// whether a directory gets the OS classes it calls linked in, see `os::link_os`
+    pub os: bool,
 }
 
 impl AssemblerArgs {
     pub fn parse() -> Result<AssemblerArgs, String> {
-        let mut args = args();
-        args.next();
-        let src_str = args.next().ok_or("missing source file")?;
-        let src = AssemblerArgs::validate_src(src_str.to_string())?;
-        Ok(AssemblerArgs { src })
+        let mut src = None;
+        let mut options = IrOptions::default();
+        let mut size_report = false;
+        let mut source_map = false;
+        let mut verify = false;
+        let mut emit = Emit::default();
+        let mut stats = None;
+        let mut name = None;
+        let mut os = true;
+        for arg in args().skip(1) {
+            match arg.as_str() {
+                "--shared-runtime" => options.shared_runtime = true,
+                "--size-report" => size_report = true,
+                "--source-map" => source_map = true,
+                "--verify" => verify = true,
+                "--ext" => options.extensions = true,
+                "--cache-top" => options.cache_top = true,
+                "--no-os" => os = false,
+                "--stats" => stats = Some(StatsFormat::Table),
+                flag if flag.starts_with("--stats=") => {
+                    stats = Some(StatsFormat::parse(&flag["--stats=".len()..])?)
+                }
+                "--inline" => options.inline = Some(DEFAULT_THRESHOLD),
+                flag if flag.starts_with("--inline=") => {
+                    let threshold = flag["--inline=".len()..]
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid inline threshold {}", flag))?;
+                    options.inline = Some(threshold);
+                }
+                flag if flag.starts_with("--name=") => {
+                    name = Some(flag["--name=".len()..].to_string())
+                }
+                flag if flag.starts_with("--emit=") => {
+                    emit = Emit::parse(&flag["--emit=".len()..])?
+                }
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+                _ => src = Some(arg),
+            }
+        }
+        let src_str = src.ok_or("missing source file")?;
+        let src = AssemblerArgs::validate_src(src_str)?;
+        if src.as_os_str() == STDIN && source_map {
+            return Err("the source map is written next to the source file".to_string());
+        }
+        Ok(AssemblerArgs {
+            src,
+            name,
+            options,
+            size_report,
+            source_map,
+            verify,
+            emit,
+            stats,
+            os,
+        })
     }
 
-    /// args for the program is a single positional argument: the source file
-    /// The source file has two main requirements:
-    /// 1. It must begin with a capital letter.
-    /// 2. Its extension must be .vm
+    /// args for the program is a single positional argument: the source file, a directory of
+    /// .vm files or `-` for stdin, followed by any options. Files can have any name, the
+    /// namespace of their statics is derived from it by `ir::namespace`.
     fn validate_src(src: String) -> Result<PathBuf, String> {
         let src = PathBuf::from(src);
-        if src.extension().is_none() {
-            return Err("source file must have an extension".to_string());
+        if src.as_os_str() != STDIN && !src.exists() {
+            return Err(format!("no such file or directory {:?}", src));
         }
-        if src.extension().unwrap() != "vm" {
-            return Err("source file must have a .vm extension".to_string());
+        Ok(src)
+    }
+
+    pub fn is_stdin(&self) -> bool {
+        self.src.as_os_str() == STDIN
+    }
+
+    /// the (file name, source) pairs to translate. Stdin is named `Stdin` unless `--name` says
+    /// otherwise, which also renames a single source file. A directory is a whole program, so
+    /// it's linked with the OS unless `--no-os` is given.
+    pub fn sources(&self) -> Result<Vec<(String, String)>, String> {
+        if self.is_stdin() {
+            let mut source = String::new();
+            std::io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|e| format!("{e}"))?;
+            let name = self.name.clone().unwrap_or_else(|| "Stdin".to_string());
+            return Ok(vec![(name, source)]);
         }
-        if let Some(file_name) = src.file_name() {
-            if let Some(file_name) = file_name.to_str() {
-                if !file_name.chars().next().unwrap().is_uppercase() {
-                    return Err("source file must begin with a capital letter".to_string());
-                }
+        let mut sources = read_sources(&self.src)?;
+        if self.os && self.src.is_dir() {
+            link_os(&mut sources);
+        }
+        if let Some(name) = &self.name {
+            if sources.len() != 1 {
+                return Err("--name needs a single source file".to_string());
             }
+            sources[0].0 = name.clone();
         }
-        Ok(src)
+        Ok(sources)
     }
 }
@@ -1,270 +1,126 @@
-use crate::parser::VmCommand;
+use crate::ir::AsmIr;
+use assembler_rust::source_map::{SourceEntry, SourceMap};
 
-static LOGICAL_OR_MATH_TEMPLATE: &str = r#"// MATH or LOGICAL {operation}
-@SP
-AM=M-1
-D=M
-A=A-1
-M={operation}
-"#;
+const INDENT: &str = "    ";
 
-static NEG: &str = r#"// neg
-@SP
-A=M-1
-M=-M
-"#;
-
-static NOT: &str = r#"// not
-@SP
-A=M-1
-M=!M
-"#;
-
-static GT_LT_TEMPLATE: &str = r#"// OPERATION IS LOGICAL {jump_condition}
-@SP
-AM=M-1
-D=M
-A=A-1
-D=M-D
-@{jump_condition}_TRUE_{postfix}
-D;{jump_condition}
-@SP
-A=M-1
-M=0
-@{jump_condition}_END_{postfix}
-0;JMP
-({jump_condition}_TRUE_{postfix})
-@SP
-A=M-1
-M=-1
-({jump_condition}_END_{postfix})
-"#;
-
-static PUSH_REF_TEMPLATE: &str = r#"// PUSH {segment} {index}
-@{index}
-D=A
-@{segment}
-A=D+M
-D=M
-@SP
-A=M
-M=D
-@SP
-M=M+1
-"#;
-
-static PUSH_DIRECT_TEMPLATE: &str = r#"// PUSH {address}
-@{address}
-{target}
-@SP
-A=M
-M=D
-@SP
-M=M+1
-"#;
-
-static POP_REF_TEMPLATE: &str = r#"// POP {segment} {index}
-@{index}
-D=A
-@{segment}
-D=D+M
-@R13
-M=D
-@SP
-AM=M-1
-D=M
-@R13
-A=M
-M=D
-"#;
-
-static POP_DIRECT_TEMPLATE: &str = r#"// POP {address}
-@SP
-AM=M-1
-D=M
-@{address}
-M=D
-"#;
-
-pub struct AsmTranslator {
-    condition_counter: u16,
+/// Pretty-printer for lowered `AsmIr`. Each vm command is introduced by its comment after a blank
+/// line, labels are kept flush left and the instructions are indented under them, so the output
+/// reads like hand written assembly while assembling to exactly the same program.
+pub fn print(commands: &[AsmIr]) -> String {
+    print_with_source_map(commands).0
 }
 
-impl AsmTranslator {
-    pub fn new() -> Self {
-        Self {
-            condition_counter: 0,
-        }
-    }
-
-    pub fn to_asm(&mut self, command: VmCommand, postfix: &str) -> Result<String, String> {
+/// `print`, along with the map from the ROM address of every instruction to the vm command it
+/// was lowered from. Instructions before the first `AsmIr::Source` marker, like the bootstrap
+/// and the shared runtime, are left out of the map.
+pub fn print_with_source_map(commands: &[AsmIr]) -> (String, SourceMap) {
+    let mut out = String::new();
+    let mut map = SourceMap::new();
+    let mut location = None;
+    let mut line = 1;
+    let mut address = 0;
+    for command in commands {
         match command {
-            VmCommand::Add => {
-                Ok(self.custom_str_replace(LOGICAL_OR_MATH_TEMPLATE, &[("{operation}", "D+M")]))
-            }
-            VmCommand::Sub => {
-                Ok(self.custom_str_replace(LOGICAL_OR_MATH_TEMPLATE, &[("{operation}", "M-D")]))
-            }
-            VmCommand::Neg => Ok(NEG.to_string()),
-            VmCommand::Gt => {
-                let unique_postfix = format!("{}_{}", postfix, self.condition_counter);
-                self.condition_counter += 1;
-                Ok(self.custom_str_replace(
-                    GT_LT_TEMPLATE,
-                    &[("{postfix}", &unique_postfix), ("{jump_condition}", "JGT")],
-                ))
-            }
-            VmCommand::Lt => {
-                let unique_postfix = format!("{}_{}", postfix, self.condition_counter);
-                self.condition_counter += 1;
-                Ok(self.custom_str_replace(
-                    GT_LT_TEMPLATE,
-                    &[("{postfix}", &unique_postfix), ("{jump_condition}", "JLT")],
-                ))
-            }
-            VmCommand::Eq => {
-                let unique_postfix = format!("{}_{}", postfix, self.condition_counter);
-                self.condition_counter += 1;
-                Ok(self.custom_str_replace(
-                    GT_LT_TEMPLATE,
-                    &[("{postfix}", &unique_postfix), ("{jump_condition}", "JEQ")],
-                ))
-            }
-
-            VmCommand::And => {
-                Ok(self.custom_str_replace(LOGICAL_OR_MATH_TEMPLATE, &[("{operation}", "D&M")]))
-            }
-
-            VmCommand::Or => {
-                Ok(self.custom_str_replace(LOGICAL_OR_MATH_TEMPLATE, &[("{operation}", "D|M")]))
-            }
-
-            VmCommand::Not => Ok(NOT.to_string()),
-
-            VmCommand::Push { segment, index } => self.assemble_push(segment, index, postfix),
-
-            VmCommand::Pop { segment, index } => self.assemble_pop(segment, index, postfix),
-
-            _ => Err("Not implemented".to_string()),
-        }
-    }
-
-    fn assemble_push(&self, segment: &str, index: u16, postfix: &str) -> Result<String, String> {
-        match segment {
-            "constant" => Ok(self.custom_str_replace(
-                PUSH_DIRECT_TEMPLATE,
-                &[("{address}", &index.to_string()), ("{target}", "D=A")],
-            )),
-
-            "static" => Ok(self.custom_str_replace(
-                PUSH_DIRECT_TEMPLATE,
-                &[
-                    ("{address}", &format!("{}.{}", postfix, index)),
-                    ("{target}", "D=M"),
-                ],
-            )),
-
-            "pointer" if index == 0 => Ok(self.custom_str_replace(
-                PUSH_DIRECT_TEMPLATE,
-                &[("{address}", "THIS"), ("{target}", "D=M")],
-            )),
-
-            "pointer" if index == 1 => Ok(self.custom_str_replace(
-                PUSH_DIRECT_TEMPLATE,
-                &[("{address}", "THAT"), ("{target}", "D=M")],
-            )),
-
-            "temp" => Ok(self.custom_str_replace(
-                PUSH_DIRECT_TEMPLATE,
-                &[
-                    ("{address}", &format!("{}", 5 + index)),
-                    ("{target}", "D=M"),
-                ],
-            )),
-
-            _ => {
-                let reference = self.resolve_reference(segment)?;
-                Ok(self.custom_str_replace(
-                    PUSH_REF_TEMPLATE,
-                    &[("{segment}", &reference), ("{index}", &index.to_string())],
-                ))
-            }
-        }
-    }
-
-    fn assemble_pop(&self, segment: &str, index: u16, postfix: &str) -> Result<String, String> {
-        match segment {
-            "static" => Ok(self.custom_str_replace(
-                POP_DIRECT_TEMPLATE,
-                &[("{address}", &format!("{}.{}", postfix, index))],
-            )),
-
-            "pointer" if index == 0 => {
-                Ok(self.custom_str_replace(POP_DIRECT_TEMPLATE, &[("{address}", "THIS")]))
+            AsmIr::Comment(comment) => {
+                if !out.is_empty() {
+                    out.push('\n');
+                    line += 1;
+                }
+                out.push_str(comment);
+                line += comment.matches('\n').count();
             }
-
-            "pointer" if index == 1 => {
-                Ok(self.custom_str_replace(POP_DIRECT_TEMPLATE, &[("{address}", "THAT")]))
+            AsmIr::Source(source) => location = Some(source),
+            AsmIr::Label(_) => {
+                out.push_str(&command.to_string());
+                line += 1;
             }
-
-            "temp" => Ok(self.custom_str_replace(
-                POP_DIRECT_TEMPLATE,
-                &[("{address}", &format!("R{}", 5 + index))],
-            )),
-
             _ => {
-                let reference = self.resolve_reference(segment)?;
-                Ok(self.custom_str_replace(
-                    POP_REF_TEMPLATE,
-                    &[("{segment}", &reference), ("{index}", &index.to_string())],
-                ))
-            }
-        }
-    }
-
-    fn resolve_reference(&self, segment: &str) -> Result<String, String> {
-        match segment {
-            "local" => Ok("LCL".to_string()),
-            "argument" => Ok("ARG".to_string()),
-            "this" => Ok("THIS".to_string()),
-            "that" => Ok("THAT".to_string()),
-            _ => Err("Invalid segment".to_string()),
-        }
-    }
-
-    fn custom_str_replace(&self, template: &str, replacements: &[(&str, &str)]) -> String {
-        let mut result = String::with_capacity(template.len());
-        let mut idx = 0;
-        while idx < template.len() {
-            let mut found = false;
-            for (find, replace) in replacements {
-                if template[idx..].starts_with(find) {
-                    result.push_str(replace);
-                    idx += find.len();
-                    found = true;
-                    break;
+                for instruction in command.to_string().lines() {
+                    if let Some(location) = location {
+                        map.push(SourceEntry {
+                            address,
+                            asm_line: line,
+                            location: location.clone(),
+                        });
+                    }
+                    out.push_str(INDENT);
+                    out.push_str(instruction);
+                    out.push('\n');
+                    line += 1;
+                    address += 1;
                 }
             }
-            if !found {
-                result.push(template.chars().nth(idx).unwrap());
-                idx += 1;
-            }
         }
-        result
     }
+    (out, map)
 }
 
 #[cfg(test)]
 mod unit {
+    use super::*;
+    use crate::ir::{Comp, Dest, Jump};
+    use assembler_rust::source_map::SourceLocation;
+
+    #[test]
+    fn test_print() {
+        let commands = vec![
+            AsmIr::Comment("// Push constant 7\n".to_string()),
+            AsmIr::LoadConstant(7),
+            AsmIr::Push,
+            AsmIr::Comment("// Label LOOP\n".to_string()),
+            AsmIr::Label("Main.main$LOOP".to_string()),
+            AsmIr::TopAssign(Dest::Mem, Comp::NotMem),
+            AsmIr::Jump("Main.main$LOOP".to_string(), Jump::Jmp),
+        ];
+        let expected = "// Push constant 7
+    @7
+    D=A
+    @SP
+    A=M
+    M=D
+    @SP
+    M=M+1
+
+// Label LOOP
+(Main.main$LOOP)
+    @SP
+    A=M-1
+    M=!M
+    @Main.main$LOOP
+    D;JMP
+";
+        assert_eq!(print(&commands), expected);
+    }
 
     #[test]
-    fn test_vm_command_to_asm() {
-        use super::*;
-        let command = VmCommand::Push {
-            segment: "constant",
-            index: 32000,
+    fn test_print_with_source_map() {
+        let location = |line: usize, command: &str| SourceLocation {
+            file: "Main.vm".to_string(),
+            line,
+            function: Some("Main.main".to_string()),
+            command: command.to_string(),
         };
-        let mut translator = AsmTranslator::new();
-        println!("{:?}", translator.to_asm(command, "test"));
+        let commands = vec![
+            AsmIr::LoadConstant(256),
+            AsmIr::Source(location(2, "push constant 7")),
+            AsmIr::Comment("// Push constant 7\n".to_string()),
+            AsmIr::LoadConstant(7),
+            AsmIr::Source(location(3, "label LOOP")),
+            AsmIr::Comment("// Label LOOP\n".to_string()),
+            AsmIr::Label("Main.main$LOOP".to_string()),
+            AsmIr::Source(location(4, "goto LOOP")),
+            AsmIr::Jump("Main.main$LOOP".to_string(), Jump::Jmp),
+        ];
+        let (asm, map) = print_with_source_map(&commands);
+        assert_eq!(asm, print(&commands));
+        let entries = map
+            .entries()
+            .iter()
+            .map(|e| (e.address, e.asm_line, e.location.line))
+            .collect::<Vec<(u16, usize, usize)>>();
+        assert_eq!(entries, vec![(2, 5, 2), (3, 6, 2), (4, 10, 4), (5, 11, 4)]);
+        let lines = asm.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[4], "    @7");
+        assert_eq!(lines[9], "    @Main.main$LOOP");
     }
 }
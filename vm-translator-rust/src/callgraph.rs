@@ -0,0 +1,136 @@
+use crate::parser::VmCommand;
+use std::collections::{HashMap, HashSet};
+
+/// the functions every function of a program calls. Vm code has no indirect calls, so a function
+/// that can't be reached through the graph from the entry point is never run.
+#[derive(Debug, Default)]
+pub struct CallGraph<'a> {
+    calls: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> CallGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds the functions defined by the commands of a file. Calls made outside of a function
+    /// aren't part of the graph.
+    pub fn add<'b, I>(&mut self, commands: I)
+    where
+        I: IntoIterator<Item = &'b VmCommand<'a>>,
+        'a: 'b,
+    {
+        let mut function = None;
+        for command in commands {
+            match command {
+                VmCommand::Function { name, .. } => {
+                    self.calls.entry(name).or_default();
+                    function = Some(*name);
+                }
+                VmCommand::Call { name, .. } => {
+                    if let Some(caller) = function {
+                        let callees = self.calls.entry(caller).or_default();
+                        if !callees.contains(name) {
+                            callees.push(name);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn defines(&self, function: &str) -> bool {
+        self.calls.contains_key(function)
+    }
+
+    pub fn callees(&self, function: &str) -> &[&'a str] {
+        self.calls.get(function).map_or(&[], |callees| callees)
+    }
+
+    /// the functions `root` calls directly or indirectly, including itself.
+    pub fn reachable(&self, root: &'a str) -> HashSet<&'a str> {
+        let mut reached = HashSet::from([root]);
+        let mut pending = vec![root];
+        while let Some(function) = pending.pop() {
+            for callee in self.callees(function) {
+                if reached.insert(callee) {
+                    pending.push(callee);
+                }
+            }
+        }
+        reached
+    }
+}
+
+/// the name and commands of a function.
+pub type Function<'a, T> = (&'a str, Vec<(VmCommand<'a>, T)>);
+
+/// splits off the functions `keep` rejects, returning them as (name, commands) pairs in the order
+/// they were defined. Commands before the first function of the file are always kept.
+pub fn remove_functions<'a, T>(
+    commands: Vec<(VmCommand<'a>, T)>,
+    keep: impl Fn(&str) -> bool,
+) -> (Vec<(VmCommand<'a>, T)>, Vec<Function<'a, T>>) {
+    let mut kept = Vec::with_capacity(commands.len());
+    let mut removed: Vec<Function<T>> = Vec::new();
+    let mut removing = false;
+    for command in commands {
+        if let VmCommand::Function { name, .. } = command.0 {
+            removing = !keep(name);
+            if removing {
+                removed.push((name, Vec::new()));
+            }
+        }
+        match removed.last_mut() {
+            Some((_, function)) if removing => function.push(command),
+            _ => kept.push(command),
+        }
+    }
+    (kept, removed)
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+    use crate::parser::VmParser;
+
+    fn parse(source: &str) -> Vec<VmCommand<'_>> {
+        let mut parser = VmParser::new(source);
+        let mut commands = Vec::new();
+        while let Ok(command) = parser.next_command() {
+            commands.push(command);
+        }
+        commands
+    }
+
+    const SOURCE: &str = "function Sys.init 0\ncall Main.main 0\nlabel END\ngoto END\nfunction Main.main 0\ncall Main.helper 0\ncall Main.main 0\nreturn\nfunction Main.helper 0\npush constant 0\nreturn\nfunction Main.unused 0\ncall Main.helper 0\nreturn\n";
+
+    #[test]
+    fn test_reachable() {
+        let commands = parse(SOURCE);
+        let mut graph = CallGraph::new();
+        graph.add(&commands);
+        assert_eq!(graph.callees("Main.main"), ["Main.helper", "Main.main"]);
+        let mut reached = graph
+            .reachable("Sys.init")
+            .into_iter()
+            .collect::<Vec<&str>>();
+        reached.sort();
+        assert_eq!(reached, ["Main.helper", "Main.main", "Sys.init"]);
+    }
+
+    #[test]
+    fn test_remove_functions() {
+        let source = format!("push constant 1\n{}", SOURCE);
+        let commands = parse(&source)
+            .into_iter()
+            .map(|command| (command, ()))
+            .collect::<Vec<_>>();
+        let (kept, removed) = remove_functions(commands, |name| name != "Main.unused");
+        assert_eq!(kept.len(), 12);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].0, "Main.unused");
+        assert_eq!(removed[0].1.len(), 3);
+    }
+}
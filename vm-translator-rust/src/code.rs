@@ -1,47 +1,419 @@
-use crate::asm::AsmTranslator;
-use crate::ir::IrParser;
-use crate::parser::VmParser;
-use std::path::PathBuf;
-
-pub fn translate(path: PathBuf) -> Result<(), String> {
-    let source = read_file(&path)?;
-    let file_name = get_file_name(&path)?;
-    let mut parser = VmParser::new(&source);
-    let mut ir_parser = IrParser::new(&file_name);
-    while let Ok(command) = parser.next_command() {
+use crate::asm;
+use crate::callgraph::{remove_functions, CallGraph};
+use crate::inline::Inliner;
+use crate::ir::{AsmIr, IrOptions, IrParser, Pass};
+use crate::optimize::optimize_located;
+use crate::parser::{VmCommand, VmParser};
+use crate::program::VmProgram;
+use assembler_rust::code::{assemble, hack_text};
+use assembler_rust::source_map::{SourceLocation, SourceMap};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// what the translator writes for a program.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Emit {
+    /// hack assembly, a .asm file
+    #[default]
+    Asm,
+    /// the assembled ROM image, a .hack file
+    Hack,
+}
+
+impl Emit {
+    pub fn parse(emit: &str) -> Result<Emit, String> {
+        match emit {
+            "asm" => Ok(Emit::Asm),
+            "hack" => Ok(Emit::Hack),
+            other => Err(format!(
+                "unknown output kind {}, expected asm or hack",
+                other
+            )),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Emit::Asm => "asm",
+            Emit::Hack => "hack",
+        }
+    }
+}
+
+/// translates a single .vm file, or every .vm file of a directory, into one .asm file. The
+/// output is written next to the source file, or inside the directory using its name.
+pub fn translate(path: PathBuf, options: &IrOptions) -> Result<(), String> {
+    translate_to(path, options, Emit::Asm, false)
+}
+
+/// `translate`, writing the output `emit` asks for. With `source_map` the map from ROM
+/// addresses back to the vm commands is written next to it with a .map extension.
+pub fn translate_to(
+    path: PathBuf,
+    options: &IrOptions,
+    emit: Emit,
+    source_map: bool,
+) -> Result<(), String> {
+    let files = read_sources(&path)?;
+    translate_files_to(&path, &files, options, emit, source_map)
+}
+
+/// `translate_to` for sources that were already read, the output is written where it would be
+/// for the sources at the path.
+pub fn translate_files_to(
+    path: &Path,
+    files: &[(String, String)],
+    options: &IrOptions,
+    emit: Emit,
+    source_map: bool,
+) -> Result<(), String> {
+    let (output, map) = emit_sources(files, options, emit)?;
+    let output_path = output_path(path)?.with_extension(emit.extension());
+    write_file(&output_path, output)?;
+    if source_map {
+        write_file(&output_path.with_extension("map"), map.to_string())?;
+    }
+    Ok(())
+}
+
+/// the text of the output `emit` asks for, along with the source map of the program.
+pub fn emit_sources(
+    files: &[(String, String)],
+    options: &IrOptions,
+    emit: Emit,
+) -> Result<(String, SourceMap), String> {
+    let (assembly, map) = translate_sources_with_map(files, options)?;
+    let output = match emit {
+        Emit::Asm => assembly,
+        Emit::Hack => hack_text(&assemble(&assembly)?),
+    };
+    Ok((output, map))
+}
+
+/// translates the (file name, source) pairs of a program and assembles it, the words of the
+/// ROM image.
+pub fn assemble_sources(
+    files: &[(String, String)],
+    options: &IrOptions,
+) -> Result<Vec<u16>, String> {
+    assemble(&translate_sources(files, options)?)
+}
+
+/// `assemble_sources` for a program built in memory.
+pub fn assemble_program(program: &VmProgram, options: &IrOptions) -> Result<Vec<u16>, String> {
+    assemble(&translate_program(program, options)?)
+}
+
+/// translates the vm source of a single file to hack assembly, the file name is used as the
+/// namespace of its static variables and labels.
+pub fn translate_source(
+    source: &str,
+    file_name: &str,
+    options: &IrOptions,
+) -> Result<String, String> {
+    translate_sources(&[(file_name.to_string(), source.to_string())], options)
+}
+
+/// translates the (file name, source) pairs of a program into a single assembly program.
+pub fn translate_sources(
+    files: &[(String, String)],
+    options: &IrOptions,
+) -> Result<String, String> {
+    let commands = lower_sources(files, options)?;
+    Ok(asm::print(&commands))
+}
+
+/// `translate_sources`, along with the map from ROM addresses back to the vm commands.
+pub fn translate_sources_with_map(
+    files: &[(String, String)],
+    options: &IrOptions,
+) -> Result<(String, SourceMap), String> {
+    let commands = lower_sources(files, options)?;
+    Ok(asm::print_with_source_map(&commands))
+}
+
+/// `translate`, also writing the source map next to the .asm file with a .map extension.
+pub fn translate_with_source_map(path: PathBuf, options: &IrOptions) -> Result<(), String> {
+    translate_to(path, options, Emit::Asm, true)
+}
+
+/// a function left out of the program because nothing reachable from `Sys.init` calls it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EliminatedFunction {
+    pub file: String,
+    pub name: String,
+    /// the hack instructions its code would have taken
+    pub instructions: usize,
+}
+
+/// the optimized commands of a file, with the line each one comes from.
+type Located<'a> = Vec<(VmCommand<'a>, usize)>;
+
+/// (file name, source, commands) of a file, or (file name, function name, commands) of a
+/// function removed from it.
+type Unit<'a> = (&'a str, &'a str, Located<'a>);
+
+/// lowers the (file name, source) pairs of a program to `AsmIr`. When one of the files defines
+/// `Sys.init` the program starts with the bootstrap code calling it, and the functions it can't
+/// reach are left out. With `options.inline` small functions are inlined first, so the ones
+/// no longer called are left out as well. The code of every command is preceded by an `AsmIr::Source` marker with
+/// its location.
+pub fn lower_sources(
+    files: &[(String, String)],
+    options: &IrOptions,
+) -> Result<Vec<AsmIr>, String> {
+    lower_units(parse_sources(files, options)?, options)
+}
+
+/// translates a program built in memory, see `lower_program`.
+pub fn translate_program(program: &VmProgram, options: &IrOptions) -> Result<String, String> {
+    Ok(asm::print(&lower_program(program, options)?))
+}
+
+/// `lower_sources` for a program built in memory. Source locations refer to the lines of the
+/// program's serialized text, `VmProgram::sources`.
+pub fn lower_program(program: &VmProgram, options: &IrOptions) -> Result<Vec<AsmIr>, String> {
+    let texts = program
+        .files
+        .iter()
+        .map(|file| file.to_string())
+        .collect::<Vec<String>>();
+    let units = program
+        .files
+        .iter()
+        .zip(&texts)
+        .map(|(file, text)| {
+            let commands = file.commands().into_iter().zip(1..).collect();
+            (file.name.as_str(), text.as_str(), commands)
+        })
+        .collect();
+    lower_units(units, options)
+}
+
+fn lower_units<'a>(units: Vec<Unit<'a>>, options: &IrOptions) -> Result<Vec<AsmIr>, String> {
+    let mut labels = HashSet::new();
+    let (programs, eliminated) = prepare(units, options, &mut labels);
+    let mut ir_parser = IrParser::with_options("Bootstrap", options.clone());
+    if eliminated.is_some() {
+        ir_parser.bootstrap()?;
+    }
+    for (file_name, source, commands) in programs {
+        ir_parser.set_filename(file_name);
+        lower_file(&mut ir_parser, file_name, source, commands)?;
+    }
+    if options.skip_pass != Some(Pass::Fusion) {
+        ir_parser.optimize();
+    }
+    let mut commands = ir_parser.runtime();
+    commands.append(&mut ir_parser.commands);
+    Ok(commands)
+}
+
+/// the functions `lower_sources` leaves out of the program, in the order they are defined.
+pub fn eliminated_functions(
+    files: &[(String, String)],
+    options: &IrOptions,
+) -> Result<Vec<EliminatedFunction>, String> {
+    let mut labels = HashSet::new();
+    let (_, eliminated) = prepare(parse_sources(files, options)?, options, &mut labels);
+    let mut functions = Vec::new();
+    for (file_name, name, commands) in eliminated.unwrap_or_default() {
+        let mut ir_parser = IrParser::with_options(file_name, options.clone());
+        lower_file(&mut ir_parser, file_name, "", commands)?;
+        ir_parser.optimize();
+        functions.push(EliminatedFunction {
+            file: file_name.to_string(),
+            name: name.to_string(),
+            instructions: count_instructions(&asm::print(&ir_parser.commands)),
+        });
+    }
+    Ok(functions)
+}
+
+/// parses every file with the line of each command, the extension commands only with
+/// `options.extensions`.
+fn parse_sources<'a>(
+    files: &'a [(String, String)],
+    options: &IrOptions,
+) -> Result<Vec<Unit<'a>>, String> {
+    let mut programs = Vec::with_capacity(files.len());
+    for (file_name, source) in files {
+        let mut parser = if options.extensions {
+            VmParser::with_extensions(source)
+        } else {
+            VmParser::new(source)
+        };
+        let commands = parser
+            .commands()
+            .map_err(|e| format!("{}: {}", file_name, e))?;
+        programs.push((file_name.as_str(), source.as_str(), commands));
+    }
+    Ok(programs)
+}
+
+/// inlines small functions of the parsed files when the options ask for it and optimizes the
+/// result. Programs with a `Sys.init` also get the functions it can't reach removed, `None`
+/// when there is no `Sys.init`. The labels of inlined code are kept in `labels`.
+fn prepare<'a>(
+    mut programs: Vec<Unit<'a>>,
+    options: &IrOptions,
+    labels: &'a mut HashSet<String>,
+) -> (Vec<Unit<'a>>, Option<Vec<Unit<'a>>>) {
+    if let Some(threshold) = options.inline {
+        let files = programs
+            .iter()
+            .map(|(file_name, _, commands)| (*file_name, &commands[..]))
+            .collect::<Vec<(&str, &[(VmCommand, usize)])>>();
+        let inliner = Inliner::new(&files, threshold);
+        for (file_name, commands) in &files {
+            labels.extend(inliner.labels(file_name, commands));
+        }
+        let labels: &'a HashSet<String> = labels;
+        programs = programs
+            .iter()
+            .map(|(file_name, source, commands)| {
+                let commands = inliner.inline(file_name, commands.clone(), labels);
+                (*file_name, *source, commands)
+            })
+            .collect();
+    }
+
+    let mut graph = CallGraph::new();
+    for (_, _, commands) in &programs {
+        graph.add(commands.iter().map(|(command, _)| command));
+    }
+    let peephole = options.skip_pass != Some(Pass::Peephole);
+    let optimized = |units: Vec<Unit<'a>>| {
+        units
+            .into_iter()
+            .map(|(file, name, commands)| match peephole {
+                true => (file, name, optimize_located(commands)),
+                false => (file, name, commands),
+            })
+            .collect::<Vec<Unit>>()
+    };
+    if !graph.defines("Sys.init") {
+        return (optimized(programs), None);
+    }
+
+    let live = graph.reachable("Sys.init");
+    let keep_all = options.skip_pass == Some(Pass::DeadCode);
+    let mut kept = Vec::with_capacity(programs.len());
+    let mut eliminated = Vec::new();
+    for (file_name, source, commands) in programs {
+        let (commands, removed) =
+            remove_functions(commands, |name| keep_all || live.contains(name));
+        kept.push((file_name, source, commands));
+        eliminated.extend(
+            removed
+                .into_iter()
+                .map(|(name, commands)| (file_name, name, commands)),
+        );
+    }
+    (optimized(kept), Some(optimized(eliminated)))
+}
+
+fn lower_file<'a>(
+    ir_parser: &mut IrParser<'a>,
+    file_name: &str,
+    source: &str,
+    commands: Located<'a>,
+) -> Result<(), String> {
+    let lines = source.lines().collect::<Vec<&str>>();
+    let mut function = None;
+    for (command, line) in commands {
+        if let VmCommand::Function { name, .. } = command {
+            function = Some(name.to_string());
+        }
+        let text = lines.get(line - 1).copied().unwrap_or_default();
+        ir_parser.source(SourceLocation {
+            file: file_name.to_string(),
+            line,
+            function: function.clone(),
+            command: text
+                .split("//")
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        });
         ir_parser.parse(command)?;
     }
-    let output_path = output_path(&path);
-    ir_parser.optimize();
-    let assembly = ir_parser
-        .commands
-        .into_iter()
-        .map(|c| c.to_string())
-        .collect::<Vec<String>>()
-        .join("");
-    write_file(&output_path, assembly)?;
+    ir_parser.flush()?;
     Ok(())
 }
 
-fn output_path(original: &PathBuf) -> PathBuf {
-    let mut path = original.clone();
+/// the number of hack instructions in an assembly program, labels and comments take no space
+/// in ROM.
+pub fn count_instructions(assembly: &str) -> usize {
+    assembly
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('('))
+        .count()
+}
+
+/// the ROM size of the program with inlined comparisons, calls and returns, and with the
+/// shared runtime subroutines.
+pub fn size_report(files: &[(String, String)]) -> Result<(usize, usize), String> {
+    let inline = translate_sources(files, &IrOptions::default())?;
+    let shared = translate_sources(
+        files,
+        &IrOptions {
+            shared_runtime: true,
+            ..IrOptions::default()
+        },
+    )?;
+    Ok((count_instructions(&inline), count_instructions(&shared)))
+}
+
+/// reads the file, or all the .vm files of the directory sorted by name, as (file name, source)
+/// pairs.
+pub fn read_sources(path: &Path) -> Result<Vec<(String, String)>, String> {
+    if !path.is_dir() {
+        return Ok(vec![(get_file_name(path)?, read_file(path)?)]);
+    }
+
+    let mut paths = std::fs::read_dir(path)
+        .map_err(|e| format!("{e}"))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "vm"))
+        .collect::<Vec<PathBuf>>();
+    if paths.is_empty() {
+        return Err(format!("no .vm files found in {:?}", path));
+    }
+    paths.sort();
+
+    let mut sources = Vec::with_capacity(paths.len());
+    for path in paths {
+        sources.push((get_file_name(&path)?, read_file(&path)?));
+    }
+    Ok(sources)
+}
+
+fn output_path(original: &Path) -> Result<PathBuf, String> {
+    if original.is_dir() {
+        let name = get_file_name(original)?;
+        return Ok(original.join(name).with_extension("asm"));
+    }
+    let mut path = original.to_path_buf();
     path.set_extension("asm");
-    path
+    Ok(path)
 }
 
-fn read_file(path: &PathBuf) -> Result<String, String> {
+fn read_file(path: &Path) -> Result<String, String> {
     let src = std::fs::read_to_string(path).map_err(|e| format!("{e}"))?;
     Ok(src)
 }
 
-fn write_file(path: &PathBuf, content: String) -> Result<(), String> {
+fn write_file(path: &Path, content: String) -> Result<(), String> {
     std::fs::write(path, content).map_err(|e| format!("{e}"))
 }
 
-fn get_file_name(path: &PathBuf) -> Result<String, String> {
+fn get_file_name(path: &Path) -> Result<String, String> {
     let file_name = path
         .file_name()
-        .and_then(|f| f.to_str().and_then(|s| Some(s.to_string())))
+        .and_then(|f| f.to_str().map(|s| s.to_string()))
         .ok_or("invalid file name structure")?;
     Ok(file_name)
 }
@@ -0,0 +1,477 @@
+use crate::parser::{Segment, VmCommand};
+use crate::verify;
+use std::collections::{HashMap, HashSet};
+
+/// the size of the functions `--inline` inlines when no threshold is given.
+pub const DEFAULT_THRESHOLD: usize = 8;
+
+/// a function that can be inlined: it calls no other function, is short enough and returns with
+/// only the return value on the stack.
+struct Callee<'a> {
+    file: &'a str,
+    locals: u16,
+    /// the commands after `function`, ending with a `return`
+    body: Vec<VmCommand<'a>>,
+    /// how many argument slots the body uses
+    arguments: u16,
+    uses_static: bool,
+    /// the `pointer` entries the body writes, they are restored after the inlined code
+    pointers: Vec<u16>,
+}
+
+impl Callee<'_> {
+    /// the caller locals an inlined copy takes at a call passing `args` arguments.
+    fn slots(&self, args: u16) -> u16 {
+        args.max(self.arguments) + self.locals + self.pointers.len() as u16
+    }
+
+    fn returns_early(&self) -> bool {
+        self.body[..self.body.len() - 1].contains(&VmCommand::Return)
+    }
+
+    fn labels(&self) -> impl Iterator<Item = &str> + '_ {
+        self.body.iter().filter_map(|command| match command {
+            VmCommand::Label(label) => Some(*label),
+            _ => None,
+        })
+    }
+}
+
+/// Replaces calls to small leaf functions with a copy of their body. The arguments and locals
+/// of the copy live in fresh locals appended to the caller's frame, the arguments are popped
+/// into them from the stack and the locals are cleared, so the copy behaves like the call
+/// without saving and restoring a frame. Pointers the callee sets are restored afterwards.
+///
+/// Labels of the copies are renamed to `{callee}${label}.{site}`, and early returns jump to
+/// `{callee}.return.{site}`, where the site counts the inlined calls of the caller. Those names
+/// are made up front by `labels` so the inlined commands can borrow them.
+pub struct Inliner<'a> {
+    callees: HashMap<&'a str, Callee<'a>>,
+}
+
+impl<'a> Inliner<'a> {
+    /// finds the functions of at most `threshold` commands, not counting `function`, that call
+    /// no other function and return with only the return value on the stack. `Sys.init` is
+    /// never inlined.
+    pub fn new<T>(files: &[(&'a str, &[(VmCommand<'a>, T)])], threshold: usize) -> Self {
+        let mut callees = HashMap::new();
+        for (file, commands) in files {
+            let mut rest = *commands;
+            while let Some(start) = rest
+                .iter()
+                .position(|(c, _)| matches!(c, VmCommand::Function { .. }))
+            {
+                let end = rest[start + 1..]
+                    .iter()
+                    .position(|(c, _)| matches!(c, VmCommand::Function { .. }))
+                    .map_or(rest.len(), |end| start + 1 + end);
+                if let VmCommand::Function { name, locals } = rest[start].0 {
+                    let body = rest[start + 1..end]
+                        .iter()
+                        .map(|(command, _)| command.clone())
+                        .collect::<Vec<VmCommand>>();
+                    if let Some(callee) = Self::callee(file, locals, body, threshold) {
+                        if name != "Sys.init" {
+                            callees.insert(name, callee);
+                        }
+                    }
+                }
+                rest = &rest[end..];
+            }
+        }
+        Inliner { callees }
+    }
+
+    fn callee(
+        file: &'a str,
+        locals: u16,
+        body: Vec<VmCommand<'a>>,
+        threshold: usize,
+    ) -> Option<Callee<'a>> {
+        // the inlined copy leaves whatever the body has on the stack at `return` in place of
+        // the return value, so that has to be the return value alone
+        if body.len() > threshold
+            || body.last() != Some(&VmCommand::Return)
+            || !verify::balanced(&body)
+        {
+            return None;
+        }
+        let mut arguments = 0;
+        let mut uses_static = false;
+        let mut pointers = Vec::new();
+        for command in &body {
+            match command {
+                VmCommand::Call { .. } => return None,
+                VmCommand::Push { segment, index } | VmCommand::Pop { segment, index } => {
+                    match *segment {
+                        Segment::Argument => arguments = arguments.max(index + 1),
+                        Segment::Static => uses_static = true,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+            if let VmCommand::Pop {
+                segment: Segment::Pointer,
+                index,
+            } = command
+            {
+                if !pointers.contains(index) {
+                    pointers.push(*index);
+                }
+            }
+        }
+        Some(Callee {
+            file,
+            locals,
+            body,
+            arguments,
+            uses_static,
+            pointers,
+        })
+    }
+
+    /// the callee a command of `file` inlines, only calls made inside a function are inlined.
+    /// Statics belong to the file, so a callee using them is only inlined in its own file.
+    fn inlined(
+        &self,
+        file: &str,
+        caller: Option<&str>,
+        command: &VmCommand,
+    ) -> Option<(&'a str, &Callee<'a>)> {
+        let VmCommand::Call { name, .. } = command else {
+            return None;
+        };
+        caller?;
+        let (name, callee) = self.callees.get_key_value(*name)?;
+        if callee.uses_static && callee.file != file {
+            return None;
+        }
+        Some((*name, callee))
+    }
+
+    /// the renamed labels of every copy inlined into the commands of `file`.
+    pub fn labels<T>(&self, file: &str, commands: &[(VmCommand, T)]) -> HashSet<String> {
+        let mut labels = HashSet::new();
+        let mut caller = None;
+        let mut site = 0;
+        for (command, _) in commands {
+            if let VmCommand::Function { name, .. } = command {
+                caller = Some(*name);
+                site = 0;
+            }
+            if let Some((name, callee)) = self.inlined(file, caller, command) {
+                labels.extend(callee.labels().map(|label| label_name(name, label, site)));
+                if callee.returns_early() {
+                    labels.insert(return_name(name, site));
+                }
+                site += 1;
+            }
+        }
+        labels
+    }
+
+    /// inlines the calls of `file`, inlined commands take the tag of the call they replace.
+    pub fn inline<'b, T: Copy>(
+        &self,
+        file: &str,
+        commands: Vec<(VmCommand<'b>, T)>,
+        labels: &'b HashSet<String>,
+    ) -> Vec<(VmCommand<'b>, T)>
+    where
+        'a: 'b,
+    {
+        // the locals every caller needs for the largest of its inlined copies
+        let mut slots: HashMap<&str, u16> = HashMap::new();
+        let mut caller = None;
+        for (command, _) in &commands {
+            if let VmCommand::Function { name, .. } = command {
+                caller = Some(*name);
+            }
+            if let (Some(caller), Some((_, callee))) = (caller, self.inlined(file, caller, command))
+            {
+                let VmCommand::Call { args, .. } = command else {
+                    unreachable!()
+                };
+                let needed = slots.entry(caller).or_default();
+                *needed = (*needed).max(callee.slots(*args));
+            }
+        }
+
+        let mut inlined = Vec::with_capacity(commands.len());
+        let mut caller = None;
+        let mut base = 0;
+        let mut site = 0;
+        for (command, tag) in commands {
+            if let VmCommand::Function { name, locals } = command {
+                caller = Some(name);
+                base = locals;
+                site = 0;
+                let extra = slots.get(name).copied().unwrap_or(0);
+                inlined.push((
+                    VmCommand::Function {
+                        name,
+                        locals: locals + extra,
+                    },
+                    tag,
+                ));
+                continue;
+            }
+            match (self.inlined(file, caller, &command), &command) {
+                (Some((name, callee)), VmCommand::Call { args, .. }) => {
+                    let copy = InlinedCopy {
+                        name,
+                        callee,
+                        base,
+                        args: *args,
+                        site,
+                        labels,
+                    };
+                    inlined.extend(copy.commands().into_iter().map(|c| (c, tag)));
+                    site += 1;
+                }
+                _ => inlined.push((command, tag)),
+            }
+        }
+        inlined
+    }
+}
+
+/// one inlined copy of a callee, its slots start at the caller local `base`.
+struct InlinedCopy<'c, 'a, 'b> {
+    name: &'a str,
+    callee: &'c Callee<'a>,
+    base: u16,
+    args: u16,
+    site: usize,
+    labels: &'b HashSet<String>,
+}
+
+impl<'a: 'b, 'b> InlinedCopy<'_, 'a, 'b> {
+    fn commands(&self) -> Vec<VmCommand<'b>> {
+        let callee = self.callee;
+        let arguments = self.args.max(callee.arguments);
+        let local = |index: u16| self.base + arguments + index;
+        let saved = |slot: usize| self.base + arguments + callee.locals + slot as u16;
+
+        let mut commands = Vec::with_capacity(callee.body.len() + 2 * arguments as usize);
+        for index in (0..self.args).rev() {
+            commands.push(pop(Segment::Local, self.base + index));
+        }
+        for index in 0..callee.locals {
+            commands.push(push(Segment::Constant, 0));
+            commands.push(pop(Segment::Local, local(index)));
+        }
+        for (slot, pointer) in callee.pointers.iter().enumerate() {
+            commands.push(push(Segment::Pointer, *pointer));
+            commands.push(pop(Segment::Local, saved(slot)));
+        }
+
+        let last = callee.body.len() - 1;
+        for (i, command) in callee.body.iter().enumerate() {
+            let command = match command {
+                VmCommand::Push {
+                    segment: Segment::Argument,
+                    index,
+                } => push(Segment::Local, self.base + index),
+                VmCommand::Pop {
+                    segment: Segment::Argument,
+                    index,
+                } => pop(Segment::Local, self.base + index),
+                VmCommand::Push {
+                    segment: Segment::Local,
+                    index,
+                } => push(Segment::Local, local(*index)),
+                VmCommand::Pop {
+                    segment: Segment::Local,
+                    index,
+                } => pop(Segment::Local, local(*index)),
+                VmCommand::Label(label) => VmCommand::Label(self.label(label)),
+                VmCommand::Goto(label) => VmCommand::Goto(self.label(label)),
+                VmCommand::IfGoto(label) => VmCommand::IfGoto(self.label(label)),
+                VmCommand::Return if i == last => continue,
+                VmCommand::Return => VmCommand::Goto(self.find(&return_name(self.name, self.site))),
+                other => other.clone(),
+            };
+            commands.push(command);
+        }
+        if callee.returns_early() {
+            commands.push(VmCommand::Label(
+                self.find(&return_name(self.name, self.site)),
+            ));
+        }
+
+        for (slot, pointer) in callee.pointers.iter().enumerate() {
+            commands.push(push(Segment::Local, saved(slot)));
+            commands.push(pop(Segment::Pointer, *pointer));
+        }
+        commands
+    }
+
+    fn label(&self, label: &str) -> &'b str {
+        self.find(&label_name(self.name, label, self.site))
+    }
+
+    fn find(&self, name: &str) -> &'b str {
+        self.labels
+            .get(name)
+            .map(String::as_str)
+            .expect("inlined labels are made before inlining")
+    }
+}
+
+fn label_name(callee: &str, label: &str, site: usize) -> String {
+    format!("{}${}.{}", callee, label, site)
+}
+
+fn return_name(callee: &str, site: usize) -> String {
+    format!("{}.return.{}", callee, site)
+}
+
+fn push(segment: Segment, index: u16) -> VmCommand<'static> {
+    VmCommand::Push { segment, index }
+}
+
+fn pop(segment: Segment, index: u16) -> VmCommand<'static> {
+    VmCommand::Pop { segment, index }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+    use crate::parser::VmParser;
+
+    fn parse(source: &str) -> Vec<(VmCommand<'_>, usize)> {
+        let mut parser = VmParser::new(source);
+        let mut commands = Vec::new();
+        while let Ok(command) = parser.next_command() {
+            commands.push((command, parser.line()));
+        }
+        commands
+    }
+
+    #[test]
+    fn test_inline_getter() {
+        let source = "function Main.main 1\npush local 0\ncall Main.getX 1\nreturn\nfunction Main.getX 0\npush argument 0\npop pointer 0\npush this 0\nreturn\n";
+        let commands = parse(source);
+        let inliner = Inliner::new(&[("Main.vm", &commands[..])], 4);
+        let labels = inliner.labels("Main.vm", &commands);
+        let inlined = inliner.inline("Main.vm", commands, &labels);
+        assert_eq!(
+            inlined[..10],
+            vec![
+                (
+                    VmCommand::Function {
+                        name: "Main.main",
+                        locals: 3
+                    },
+                    1
+                ),
+                (push(Segment::Local, 0), 2),
+                (pop(Segment::Local, 1), 3),
+                (push(Segment::Pointer, 0), 3),
+                (pop(Segment::Local, 2), 3),
+                (push(Segment::Local, 1), 3),
+                (pop(Segment::Pointer, 0), 3),
+                (push(Segment::This, 0), 3),
+                (push(Segment::Local, 2), 3),
+                (pop(Segment::Pointer, 0), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_renames_labels_and_locals() {
+        let source = "function Main.main 0\npush constant 1\ncall Main.abs 1\npush constant 2\ncall Main.abs 1\nadd\nreturn\nfunction Main.abs 1\npush argument 0\npop local 0\npush local 0\npush constant 0\nlt\nif-goto NEG\npush local 0\nreturn\nlabel NEG\npush local 0\nneg\nreturn\n";
+        let commands = parse(source);
+        let inliner = Inliner::new(&[("Main.vm", &commands[..])], 12);
+        let labels = inliner.labels("Main.vm", &commands);
+        let inlined = inliner.inline("Main.vm", commands, &labels);
+        // only the caller, the callee itself is left as it is
+        let end = inlined
+            .iter()
+            .position(|(c, _)| {
+                matches!(
+                    c,
+                    VmCommand::Function {
+                        name: "Main.abs",
+                        ..
+                    }
+                )
+            })
+            .unwrap();
+        let commands = &inlined[..end];
+        assert_eq!(
+            commands[0].0,
+            VmCommand::Function {
+                name: "Main.main",
+                locals: 2
+            }
+        );
+        let jumps = commands
+            .iter()
+            .filter_map(|(command, _)| match command {
+                VmCommand::Label(label) | VmCommand::Goto(label) | VmCommand::IfGoto(label) => {
+                    Some(label.to_string())
+                }
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(
+            jumps,
+            [
+                "Main.abs$NEG.0",
+                "Main.abs.return.0",
+                "Main.abs$NEG.0",
+                "Main.abs.return.0",
+                "Main.abs$NEG.1",
+                "Main.abs.return.1",
+                "Main.abs$NEG.1",
+                "Main.abs.return.1",
+            ]
+        );
+        // the locals of the copies are cleared, the argument goes to local 0 and `local 0` of
+        // the callee to local 1
+        assert!(commands.contains(&(pop(Segment::Local, 1), 3)));
+        assert!(!commands
+            .iter()
+            .any(|(command, _)| matches!(command, VmCommand::Call { .. })));
+    }
+
+    #[test]
+    fn test_skips_non_leaf_and_foreign_statics() {
+        let source = "function Main.main 0\ncall Main.outer 0\ncall Other.get 0\nadd\nreturn\nfunction Main.outer 0\ncall Main.main 0\nreturn\n";
+        let other = "function Other.get 0\npush static 0\nreturn\n";
+        let commands = parse(source);
+        let other_commands = parse(other);
+        let inliner = Inliner::new(
+            &[
+                ("Main.vm", &commands[..]),
+                ("Other.vm", &other_commands[..]),
+            ],
+            10,
+        );
+        let labels = inliner.labels("Main.vm", &commands);
+        let inlined = inliner.inline("Main.vm", commands.clone(), &labels);
+        assert_eq!(inlined, commands);
+    }
+
+    /// a callee leaving more than the return value on the stack would leave the extra values to
+    /// the caller once inlined, it is called instead.
+    #[test]
+    fn test_skips_unbalanced_returns() {
+        let source = "function Main.main 0\ncall Main.pair 0\ncall Main.one 0\nadd\nreturn\nfunction Main.pair 0\npush constant 1\npush constant 2\nreturn\nfunction Main.one 0\npush constant 1\nreturn\n";
+        let commands = parse(source);
+        let inliner = Inliner::new(&[("Main.vm", &commands[..])], 10);
+        let labels = inliner.labels("Main.vm", &commands);
+        let inlined = inliner.inline("Main.vm", commands, &labels);
+        let calls = inlined
+            .iter()
+            .filter_map(|(command, _)| match command {
+                VmCommand::Call { name, .. } => Some(*name),
+                _ => None,
+            })
+            .collect::<Vec<&str>>();
+        assert_eq!(calls, ["Main.pair"]);
+    }
+}
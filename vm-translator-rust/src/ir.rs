@@ -1,4 +1,8 @@
-use crate::parser::VmCommand;
+use crate::parser::{Segment, VmCommand};
+use assembler_rust::source_map::SourceLocation;
+use std::fmt;
+
+mod cached;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Comp {
@@ -23,6 +27,7 @@ pub enum Comp {
     DataPlusAddr,
     DataPlusMem,
     DataMinusAddr,
+    DataMinusMem,
     AddrMinusData,
     MemMinusData,
     DataAndAddr,
@@ -55,6 +60,7 @@ impl Comp {
             Comp::DataPlusAddr => "D+A",
             Comp::DataPlusMem => "D+M",
             Comp::DataMinusAddr => "D-A",
+            Comp::DataMinusMem => "D-M",
             Comp::AddrMinusData => "A-D",
             Comp::MemMinusData => "M-D",
             Comp::DataAndAddr => "D&A",
@@ -131,28 +137,40 @@ pub enum AsmIr {
     Label(String),            // Label
     Assign(Dest, Comp),       // Dest = Comp - this will be used in optimization steps,
     Address(String),          // @Address
+    JumpIf(Comp, Jump),       // Comp;Jump to the address already in A
     Comment(String),          // Comments
+    Source(SourceLocation),   // Where the following instructions come from, prints nothing
 }
 
-impl AsmIr {
-    pub fn to_string(self) -> String {
-        match self {
+impl fmt::Display for AsmIr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let asm = match self {
             AsmIr::Push => Self::push(),
             AsmIr::Pop => Self::pop(),
             AsmIr::DecAssign(dest, comp) => Self::dec_assign(dest, comp),
             AsmIr::TopAssign(dest, comp) => Self::top_assign(dest, comp),
-            AsmIr::LoadOffset(segment, offset) => Self::load_offset(segment, offset),
-            AsmIr::LoadConstant(value) => Self::load_constant(value),
+            AsmIr::LoadOffset(segment, offset) => Self::load_offset(segment, *offset),
+            AsmIr::LoadConstant(value) => Self::load_constant(*value),
             AsmIr::LoadAddress(address) => Self::load_address(address),
             AsmIr::WriteToAddress(address) => Self::write_to_address(address),
-            AsmIr::StoreOffset(segment, offset) => Self::store_offset(segment, offset),
+            AsmIr::StoreOffset(segment, offset) => Self::store_offset(segment, *offset),
             AsmIr::DerefWrite(address) => Self::deref_write(address),
             AsmIr::Jump(label, jump) => Self::jump(label, jump),
             AsmIr::Label(label) => Self::label(label),
             AsmIr::Assign(dest, comp) => Self::assign(dest, comp),
             AsmIr::Address(address) => Self::address(address),
-            AsmIr::Comment(comment) => comment,
-        }
+            AsmIr::JumpIf(comp, jump) => Self::jump_if(comp, jump),
+            AsmIr::Comment(comment) => comment.clone(),
+            AsmIr::Source(_) => String::new(),
+        };
+        f.write_str(&asm)
+    }
+}
+
+impl AsmIr {
+    /// comments and source markers, which take no space in ROM.
+    pub fn is_annotation(&self) -> bool {
+        matches!(self, AsmIr::Comment(_) | AsmIr::Source(_))
     }
 
     fn push() -> String {
@@ -163,15 +181,15 @@ impl AsmIr {
         "@SP\nAM=M-1\nD=M\n".to_string()
     }
 
-    fn dec_assign(dest: Dest, comp: Comp) -> String {
+    fn dec_assign(dest: &Dest, comp: &Comp) -> String {
         format!("A=A-1\n{}={}\n", dest.as_str(), comp.as_str())
     }
 
-    fn top_assign(dest: Dest, comp: Comp) -> String {
+    fn top_assign(dest: &Dest, comp: &Comp) -> String {
         format!("@SP\nA=M-1\n{}={}\n", dest.as_str(), comp.as_str())
     }
 
-    fn load_offset(segment: String, offset: u16) -> String {
+    fn load_offset(segment: &str, offset: u16) -> String {
         format!("@{}\nD=A\n@{}\nA=D+M\nD=M\n", offset, segment)
     }
 
@@ -179,55 +197,252 @@ impl AsmIr {
         format!("@{}\nD=A\n", value)
     }
 
-    fn load_address(address: String) -> String {
+    fn load_address(address: &str) -> String {
         format!("@{}\nD=M\n", address)
     }
 
-    fn write_to_address(address: String) -> String {
+    fn write_to_address(address: &str) -> String {
         format!("@{}\nM=D\n", address)
     }
 
-    fn store_offset(segment: String, offset: u16) -> String {
+    fn store_offset(segment: &str, offset: u16) -> String {
         format!("@{}\nD=A\n@{}\nD=D+M\n@R13\nM=D\n", offset, segment)
     }
 
-    pub fn deref_write(address: String) -> String {
+    pub fn deref_write(address: &str) -> String {
         format!("@{}\nA=M\nM=D\n", address)
     }
 
-    fn jump(label: String, jump: Jump) -> String {
+    fn jump(label: &str, jump: &Jump) -> String {
         format!("@{}\nD;{}\n", label, jump.as_str())
     }
 
-    fn label(label: String) -> String {
+    fn label(label: &str) -> String {
         format!("({})\n", label)
     }
 
-    fn assign(dest: Dest, comp: Comp) -> String {
+    fn assign(dest: &Dest, comp: &Comp) -> String {
         format!("{}={}\n", dest.as_str(), comp.as_str())
     }
 
-    fn address(address: String) -> String {
+    fn address(address: &str) -> String {
         format!("@{}\n", address)
     }
+
+    fn jump_if(comp: &Comp, jump: &Jump) -> String {
+        format!("{};{}\n", comp.as_str(), jump.as_str())
+    }
+}
+
+/// the segment pointers saved in a call frame, in the order they are pushed.
+const FRAME: [&str; 4] = ["LCL", "ARG", "THIS", "THAT"];
+
+// entry points of the shared runtime subroutines. The return address is passed in D, `call`
+// also expects the argument count in R13 and the function address in R14.
+const CALL_ROUTINE: &str = "__CALL";
+const RETURN_ROUTINE: &str = "__RETURN";
+const RUNTIME_START: &str = "__START";
+
+/// the extension commands lowered to loops, with the shared subroutine each one jumps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arithmetic {
+    Mul,
+    Div,
+    Mod,
+    Shl,
+    Shr,
+}
+
+impl Arithmetic {
+    const ALL: [Arithmetic; 5] = [
+        Arithmetic::Mul,
+        Arithmetic::Div,
+        Arithmetic::Mod,
+        Arithmetic::Shl,
+        Arithmetic::Shr,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Arithmetic::Mul => "MUL",
+            Arithmetic::Div => "DIV",
+            Arithmetic::Mod => "MOD",
+            Arithmetic::Shl => "SHL",
+            Arithmetic::Shr => "SHR",
+        }
+    }
+
+    fn routine(&self) -> &'static str {
+        match self {
+            Arithmetic::Mul => "__MUL",
+            Arithmetic::Div => "__DIV",
+            Arithmetic::Mod => "__MOD",
+            Arithmetic::Shl => "__SHL",
+            Arithmetic::Shr => "__SHR",
+        }
+    }
+}
+
+/// where the words of a segment are.
+enum Location {
+    /// a fixed address or symbol
+    Direct(String),
+    /// an offset from the address held by the pointer
+    Offset(&'static str),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IrOptions {
+    /// lower comparisons, `call` and `return` to jumps into subroutines shared by the whole
+    /// program instead of inlining them at every use, trading cycles for ROM size.
+    pub shared_runtime: bool,
+    /// inline functions of at most this many commands that call no other function, see
+    /// `inline::Inliner`.
+    pub inline: Option<usize>,
+    /// accept the extension commands `mul`, `div`, `mod`, `shl`, `shr`, `dup` and `swap`.
+    pub extensions: bool,
+    /// keep the value on top of the stack in D between vm commands, see `cached`.
+    pub cache_top: bool,
+    /// leave one of the optimization passes out, `stats` uses it to measure what each saves.
+    pub skip_pass: Option<Pass>,
+}
+
+/// the optimization passes that always run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    /// constant folding and the other rewrites of `optimize`
+    Peephole,
+    /// leaving out the functions `Sys.init` can't reach
+    DeadCode,
+    /// fusing a push with the pop right after it, `IrParser::optimize`
+    Fusion,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 3] = [Pass::Peephole, Pass::DeadCode, Pass::Fusion];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::Peephole => "peephole",
+            Pass::DeadCode => "dead code",
+            Pass::Fusion => "push/pop fusion",
+        }
+    }
+}
+
+/// the hack symbol prefix of the statics of a file: its name without a .vm extension, with
+/// the characters symbols can't contain replaced by `_`. `Foo.vm` gives `Foo` and its static 3
+/// is `Foo.3`.
+pub fn namespace(file_name: &str) -> String {
+    let stem = file_name.strip_suffix(".vm").unwrap_or(file_name);
+    let mut namespace = stem
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' | '$' | ':' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    if namespace.is_empty() || namespace.starts_with(|c: char| c.is_ascii_digit()) {
+        namespace.insert(0, '_');
+    }
+    namespace
 }
 
 pub struct IrParser<'a> {
     pub commands: Vec<AsmIr>,
     conditional_counter: u16,
-    filename: &'a str,
+    return_counter: u16,
+    /// the prefix of statics and labels outside of functions, see `namespace`
+    namespace: String,
+    function: Option<&'a str>,
+    options: IrOptions,
+    routines: Vec<&'static str>,
+    /// with `options.cache_top`, whether the top of the stack is in D instead of RAM[SP - 1]
+    cached: bool,
+    /// with `options.cache_top`, a value pushed on top of the stack that is not loaded yet
+    pending: Option<cached::Operand>,
 }
 
 impl<'a> IrParser<'a> {
-    pub fn new(filename: &'a str) -> Self {
+    pub fn new(filename: &str) -> Self {
+        Self::with_options(filename, IrOptions::default())
+    }
+
+    pub fn with_options(filename: &str, options: IrOptions) -> Self {
         IrParser {
             commands: Vec::new(),
             conditional_counter: 0,
-            filename,
+            return_counter: 0,
+            namespace: namespace(filename),
+            function: None,
+            options,
+            routines: Vec::new(),
+            cached: false,
+            pending: None,
         }
     }
 
-    pub fn parse(&mut self, command: VmCommand) -> Result<(), String> {
+    /// switches to the next file of a program, statics and labels outside of a function are
+    /// namespaced by the file name.
+    pub fn set_filename(&mut self, filename: &str) {
+        self.namespace = namespace(filename);
+        self.function = None;
+    }
+
+    /// marks the instructions lowered from now on as coming from `location`.
+    pub fn source(&mut self, location: SourceLocation) {
+        self.commands.push(AsmIr::Source(location));
+    }
+
+    /// initializes the stack pointer and hands control to `Sys.init`
+    pub fn bootstrap(&mut self) -> Result<(), String> {
+        self.comment("Bootstrap");
+        self.commands.push(AsmIr::LoadConstant(256));
+        self.commands.push(AsmIr::WriteToAddress("SP".to_string()));
+        self.parse(VmCommand::Call {
+            name: "Sys.init",
+            args: 0,
+        })
+    }
+
+    /// the shared subroutines used so far, preceded by a jump over them. This is meant to be
+    /// placed at the very start of the program.
+    pub fn runtime(&mut self) -> Vec<AsmIr> {
+        if self.routines.is_empty() {
+            return Vec::new();
+        }
+        let body = std::mem::take(&mut self.commands);
+        self.commands
+            .push(AsmIr::Jump(RUNTIME_START.to_string(), Jump::Jmp));
+        for routine in self.routines.clone() {
+            self.comment(&format!("Runtime {}", routine));
+            self.commands.push(AsmIr::Label(routine.to_string()));
+            match routine {
+                CALL_ROUTINE => self.call_sequence(),
+                RETURN_ROUTINE => self.return_sequence(),
+                _ => match Arithmetic::ALL.iter().find(|op| op.routine() == routine) {
+                    Some(op) => {
+                        self.commands.push(AsmIr::WriteToAddress("R15".to_string()));
+                        self.arithmetic_sequence(*op, routine);
+                        self.jump_to_register("R15");
+                    }
+                    None => self.comparison_routine(routine),
+                },
+            }
+        }
+        self.commands.push(AsmIr::Label(RUNTIME_START.to_string()));
+        std::mem::replace(&mut self.commands, body)
+    }
+
+    pub fn parse(&mut self, command: VmCommand<'a>) -> Result<(), String> {
+        if self.options.cache_top {
+            return self.parse_cached(command);
+        }
+        self.lower(command)
+    }
+
+    /// lowers the command with the whole stack in RAM.
+    fn lower(&mut self, command: VmCommand<'a>) -> Result<(), String> {
         match command {
             VmCommand::Add => {
                 self.comment("Add");
@@ -277,7 +492,68 @@ impl<'a> IrParser<'a> {
             VmCommand::Eq => self.push_comparison("JEQ", Jump::Jeq),
             VmCommand::Push { segment, index } => self.push(segment, index),
             VmCommand::Pop { segment, index } => self.pop(segment, index),
-            _ => Err("Not implemented".to_string()),
+            VmCommand::Label(label) => {
+                self.comment(&format!("Label {}", label));
+                let label = self.scoped_label(label);
+                self.commands.push(AsmIr::Label(label));
+                Ok(())
+            }
+            VmCommand::Goto(label) => {
+                self.comment(&format!("Goto {}", label));
+                let label = self.scoped_label(label);
+                self.commands.push(AsmIr::Jump(label, Jump::Jmp));
+                Ok(())
+            }
+            VmCommand::IfGoto(label) => {
+                self.comment(&format!("IfGoto {}", label));
+                let label = self.scoped_label(label);
+                self.commands.push(AsmIr::Pop);
+                self.commands.push(AsmIr::Jump(label, Jump::Jne));
+                Ok(())
+            }
+            VmCommand::Function { name, locals } => self.function(name, locals),
+            VmCommand::Call { name, args } => self.call(name, args),
+            VmCommand::Return => self.return_from_function(),
+            VmCommand::Constant(value) => self.constant(value),
+            VmCommand::Move(from, to) => self.move_value(from, to),
+            VmCommand::IsZero => self.is_zero(),
+            VmCommand::Inc => {
+                self.comment("Inc");
+                self.commands
+                    .push(AsmIr::TopAssign(Dest::Mem, Comp::MemPlusOne));
+                Ok(())
+            }
+            VmCommand::Dec => {
+                self.comment("Dec");
+                self.commands
+                    .push(AsmIr::TopAssign(Dest::Mem, Comp::MemMinusOne));
+                Ok(())
+            }
+            VmCommand::Mul => self.arithmetic(Arithmetic::Mul),
+            VmCommand::Div => self.arithmetic(Arithmetic::Div),
+            VmCommand::Mod => self.arithmetic(Arithmetic::Mod),
+            VmCommand::Shl => self.arithmetic(Arithmetic::Shl),
+            VmCommand::Shr => self.arithmetic(Arithmetic::Shr),
+            VmCommand::Dup => {
+                self.comment("Dup");
+                self.commands.push(AsmIr::TopAssign(Dest::Data, Comp::Mem));
+                self.commands.push(AsmIr::Push);
+                Ok(())
+            }
+            VmCommand::Swap => {
+                self.comment("Swap");
+                self.commands.push(AsmIr::TopAssign(Dest::Data, Comp::Mem));
+                self.commands.push(AsmIr::WriteToAddress("R13".to_string()));
+                self.stack_slot(-2);
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Addr, Comp::AddrPlusOne));
+                self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Data));
+                self.commands.push(AsmIr::LoadAddress("R13".to_string()));
+                self.stack_slot(-2);
+                self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Data));
+                Ok(())
+            }
         }
     }
 
@@ -286,58 +562,50 @@ impl<'a> IrParser<'a> {
     /// This one optimization reduced the stacktest.vm program from 321 instructions to 249 and still passes the tests
     /// If this holds across the board we may see a 20% performance improvement across all assembly!
     ///
-    /// Known issues:
-    /// Currently the comments appear completely out of order unfortunately. I'm not sure how to fix this yet.
+    /// Comments and source markers between the fused push and pop are moved after the fused
+    /// instructions, so those are attributed to the command that pushed.
     ///
     pub fn optimize(&mut self) {
-        let mut optimized: Vec<AsmIr> = Vec::new();
-        let mut iter = self.commands.iter().peekable();
-        while let Some(ir) = iter.next() {
-            if let Some(mut next) = iter.peek() {
-                let mut comment = None;
-                match next {
-                    AsmIr::Comment(_) => {
-                        println!("comment found!");
-                        comment = iter.next();
-                        next = iter.peek().unwrap();
-                    }
-                    _ => {}
-                };
-
-                match (ir, next) {
-                    (AsmIr::Push, AsmIr::Pop) => {
-                        println!("Optimizing push/pop");
-                        iter.next();
-                        optimized.push(AsmIr::Address("SP".to_string()));
-                        optimized.push(AsmIr::Assign(Dest::Addr, Comp::Mem));
-                        if comment.is_some() {
-                            optimized.push(comment.unwrap().clone());
-                        }
-                    }
-                    _ => {
-                        optimized.push(ir.clone());
-                        if comment.is_some() {
-                            optimized.push(comment.unwrap().clone());
-                        }
-                    }
+        let commands = std::mem::take(&mut self.commands);
+        let mut optimized: Vec<AsmIr> = Vec::with_capacity(commands.len());
+        let mut i = 0;
+        while i < commands.len() {
+            let next = (i + 1..commands.len()).find(|&j| !commands[j].is_annotation());
+            if let (AsmIr::Push, Some(j)) = (&commands[i], next) {
+                if commands[j] == AsmIr::Pop {
+                    optimized.push(AsmIr::Address("SP".to_string()));
+                    optimized.push(AsmIr::Assign(Dest::Addr, Comp::Mem));
+                    optimized.extend_from_slice(&commands[i + 1..j]);
+                    i = j + 1;
+                    continue;
                 }
-            } else {
-                optimized.push(ir.clone());
             }
+            optimized.push(commands[i].clone());
+            i += 1;
         }
         self.commands = optimized;
     }
 
     fn push_comparison(&mut self, condition: &str, jmp: Jump) -> Result<(), String> {
         self.comment(&format!("Comparison {}", condition));
+        if self.options.shared_runtime {
+            let routine = match jmp {
+                Jump::Jgt => "__JGT",
+                Jump::Jlt => "__JLT",
+                _ => "__JEQ",
+            };
+            self.call_routine(routine);
+            return Ok(());
+        }
+
         let if_true = format!(
             "{}_TRUE_{}.{}",
-            condition, self.filename, self.conditional_counter
+            condition, self.namespace, self.conditional_counter
         );
 
         let end = format!(
             "{}_END_{}.{}",
-            condition, self.filename, self.conditional_counter
+            condition, self.namespace, self.conditional_counter
         );
 
         self.conditional_counter += 1;
@@ -354,117 +622,583 @@ impl<'a> IrParser<'a> {
         Ok(())
     }
 
-    fn push(&mut self, segment: &str, index: u16) -> Result<(), String> {
-        self.comment(&format!("Push {} {}", segment, index));
-        match segment {
-            "constant" => {
-                self.commands.push(AsmIr::LoadConstant(index));
-                self.commands.push(AsmIr::Push);
-                Ok(())
-            }
+    /// the body of a shared comparison, the return address is kept in R15 while comparing.
+    fn comparison_routine(&mut self, routine: &str) {
+        let jmp = match routine {
+            "__JGT" => Jump::Jgt,
+            "__JLT" => Jump::Jlt,
+            _ => Jump::Jeq,
+        };
+        let end = format!("{}_END", routine);
+        self.commands.push(AsmIr::WriteToAddress("R15".to_string()));
+        self.commands.push(AsmIr::Pop);
+        self.commands
+            .push(AsmIr::DecAssign(Dest::Data, Comp::MemMinusData));
+        self.commands.push(AsmIr::Assign(Dest::Mem, Comp::NegOne));
+        self.commands.push(AsmIr::Jump(end.clone(), jmp));
+        self.commands.push(AsmIr::TopAssign(Dest::Mem, Comp::Zero));
+        self.commands.push(AsmIr::Label(end));
+        self.jump_to_register("R15");
+    }
 
-            "static" => {
-                self.commands
-                    .push(AsmIr::LoadAddress(format!("{}.{}", self.filename, index)));
-                self.commands.push(AsmIr::Push);
-                Ok(())
-            }
+    /// the arithmetic extension commands are loops, either inlined or in a shared subroutine.
+    fn arithmetic(&mut self, op: Arithmetic) -> Result<(), String> {
+        let name = op.name();
+        self.comment(&format!("{}{}", &name[..1], name[1..].to_lowercase()));
+        if self.options.shared_runtime {
+            self.call_routine(op.routine());
+            return Ok(());
+        }
+        let prefix = format!("{}_{}.{}", name, self.namespace, self.conditional_counter);
+        self.conditional_counter += 1;
+        self.arithmetic_sequence(op, &prefix);
+        Ok(())
+    }
 
-            "pointer" if index == 0 => {
-                self.commands.push(AsmIr::LoadAddress("THIS".to_string()));
-                self.commands.push(AsmIr::Push);
-                Ok(())
+    /// replaces x and y on top of the stack with the result of the operation. R13 and R14 and
+    /// the two words above the stack are used as scratch space, labels start with `prefix`.
+    /// Dividing by zero ends in an endless loop, like `Sys.halt`.
+    fn arithmetic_sequence(&mut self, op: Arithmetic, prefix: &str) {
+        let label = |name: &str| format!("{}_{}", prefix, name);
+        match op {
+            Arithmetic::Mul => {
+                // shift and add: every set bit of y adds x shifted by its position, y is cleared
+                // bit by bit so the loop ends after its highest set bit.
+                self.set_register("R13", Comp::Zero);
+                self.set_register("R14", Comp::One);
+                self.commands.push(AsmIr::Label(label("LOOP")));
+                self.stack_slot(-1);
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                self.commands.push(AsmIr::Jump(label("END"), Jump::Jeq));
+                self.commands.push(AsmIr::Address("R14".to_string()));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Data, Comp::DataAndMem));
+                self.commands.push(AsmIr::Jump(label("SKIP"), Jump::Jeq));
+                self.stack_slot(-1);
+                self.commands
+                    .push(AsmIr::Assign(Dest::Mem, Comp::MemMinusData));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Addr, Comp::AddrMinusOne));
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                self.commands.push(AsmIr::Address("R13".to_string()));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Mem, Comp::DataPlusMem));
+                self.commands.push(AsmIr::Label(label("SKIP")));
+                self.double_slot(-2);
+                self.commands.push(AsmIr::Address("R14".to_string()));
+                self.double();
+                self.commands.push(AsmIr::Jump(label("LOOP"), Jump::Jmp));
+                self.commands.push(AsmIr::Label(label("END")));
             }
+            Arithmetic::Div | Arithmetic::Mod => {
+                self.stack_slot(-1);
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                self.commands.push(AsmIr::Jump(label("ZERO"), Jump::Jeq));
+                // the division works on |x| and |y|, the word above the stack remembers whether
+                // the result is negated: x < 0 for mod, x < 0 != y < 0 for div.
+                self.stack_slot(1);
+                self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Zero));
+                self.absolute_slot(-2, &label("XPOS"));
+                if op == Arithmetic::Div {
+                    self.absolute_slot(-1, &label("YPOS"));
+                } else {
+                    self.stack_slot(-1);
+                    self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                    self.commands.push(AsmIr::Jump(label("YPOS"), Jump::Jge));
+                    self.stack_slot(-1);
+                    self.commands.push(AsmIr::Assign(Dest::Mem, Comp::NegData));
+                    self.commands.push(AsmIr::Label(label("YPOS")));
+                }
+                // restoring division of |x| by |y|, one bit of |x| per round from the top. R13 is
+                // the remainder, R14 the quotient and the word on top of the stack counts down
+                // the 16 rounds.
+                self.set_register("R13", Comp::Zero);
+                self.set_register("R14", Comp::Zero);
+                self.commands.push(AsmIr::LoadConstant(16));
+                self.stack_slot(0);
+                self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Data));
+                self.commands.push(AsmIr::Label(label("LOOP")));
+                self.commands.push(AsmIr::Address("R13".to_string()));
+                self.double();
+                self.double_slot(-2);
+                self.commands.push(AsmIr::Jump(label("NOBIT"), Jump::Jge));
+                self.commands.push(AsmIr::Address("R13".to_string()));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Mem, Comp::MemPlusOne));
+                self.commands.push(AsmIr::Label(label("NOBIT")));
+                self.commands.push(AsmIr::Address("R14".to_string()));
+                self.double();
+                // the remainder is at most 0xFFFF and |y| at most 0x8000, so a remainder with the
+                // sign bit set is always large enough, otherwise remainder - |y| tells.
+                self.commands.push(AsmIr::LoadAddress("R13".to_string()));
+                self.commands.push(AsmIr::Jump(label("GE"), Jump::Jlt));
+                self.stack_slot(-1);
+                self.commands
+                    .push(AsmIr::Assign(Dest::Data, Comp::DataMinusMem));
+                self.commands.push(AsmIr::Jump(label("NEXT"), Jump::Jlt));
+                self.commands.push(AsmIr::Label(label("GE")));
+                self.commands.push(AsmIr::LoadAddress("R13".to_string()));
+                self.stack_slot(-1);
+                self.commands
+                    .push(AsmIr::Assign(Dest::Data, Comp::DataMinusMem));
+                self.commands.push(AsmIr::WriteToAddress("R13".to_string()));
+                self.commands.push(AsmIr::Address("R14".to_string()));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Mem, Comp::MemPlusOne));
+                self.commands.push(AsmIr::Label(label("NEXT")));
+                self.stack_slot(0);
+                self.commands
+                    .push(AsmIr::Assign(Dest::DataMem, Comp::MemMinusOne));
+                self.commands.push(AsmIr::Jump(label("LOOP"), Jump::Jne));
 
-            "pointer" if index == 1 => {
-                self.commands.push(AsmIr::LoadAddress("THAT".to_string()));
-                self.commands.push(AsmIr::Push);
-                Ok(())
-            }
-            "temp" => {
-                if index > 7 {
-                    return Err(format!("Invalid index for push temp, received {}", index));
+                if op == Arithmetic::Div {
+                    self.commands.push(AsmIr::LoadAddress("R14".to_string()));
+                    self.commands.push(AsmIr::WriteToAddress("R13".to_string()));
                 }
+                self.stack_slot(1);
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                self.commands.push(AsmIr::Jump(label("END"), Jump::Jeq));
+                self.commands.push(AsmIr::Address("R13".to_string()));
+                self.commands.push(AsmIr::Assign(Dest::Mem, Comp::NegMem));
+                self.commands.push(AsmIr::Jump(label("END"), Jump::Jmp));
+                self.commands.push(AsmIr::Label(label("ZERO")));
+                self.commands.push(AsmIr::Jump(label("ZERO"), Jump::Jmp));
+                self.commands.push(AsmIr::Label(label("END")));
+            }
+            Arithmetic::Shl | Arithmetic::Shr => {
+                // shifting by 16 or more, or a negative count, leaves only copies of the sign
+                // bit for shr and nothing for shl.
+                self.stack_slot(-1);
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                self.commands.push(AsmIr::Jump(label("ALL"), Jump::Jlt));
+                self.commands.push(AsmIr::Address("16".to_string()));
                 self.commands
-                    .push(AsmIr::LoadAddress(format!("R{}", 5 + index)));
-                self.commands.push(AsmIr::Push);
-                Ok(())
+                    .push(AsmIr::Assign(Dest::Data, Comp::DataMinusAddr));
+                self.commands.push(AsmIr::Jump(label("ALL"), Jump::Jge));
+                if op == Arithmetic::Shl {
+                    self.shift_left(&label);
+                } else {
+                    self.shift_right(&label);
+                }
+                self.commands.push(AsmIr::Jump(label("END"), Jump::Jmp));
+                self.commands.push(AsmIr::Label(label("ALL")));
+                self.set_register("R13", Comp::Zero);
+                if op == Arithmetic::Shr {
+                    self.stack_slot(-2);
+                    self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+                    self.commands.push(AsmIr::Jump(label("END"), Jump::Jge));
+                    self.set_register("R13", Comp::NegOne);
+                }
+                self.commands.push(AsmIr::Label(label("END")));
             }
+        }
+        // the result in R13 replaces x, y is popped. Mul leaves it there as well.
+        self.commands.push(AsmIr::LoadAddress("R13".to_string()));
+        self.commands.push(AsmIr::Address("SP".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::AddrMem, Comp::MemMinusOne));
+        self.commands
+            .push(AsmIr::Assign(Dest::Addr, Comp::AddrMinusOne));
+        self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Data));
+    }
+
+    /// R13 = x << y, for 0 <= y < 16: x is doubled y times.
+    fn shift_left(&mut self, label: &dyn Fn(&str) -> String) {
+        self.commands.push(AsmIr::Label(label("LOOP")));
+        self.stack_slot(-1);
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands.push(AsmIr::Jump(label("DONE"), Jump::Jeq));
+        self.stack_slot(-1);
+        self.commands
+            .push(AsmIr::Assign(Dest::Mem, Comp::DataMinusOne));
+        self.double_slot(-2);
+        self.commands.push(AsmIr::Jump(label("LOOP"), Jump::Jmp));
+        self.commands.push(AsmIr::Label(label("DONE")));
+        self.stack_slot(-2);
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands.push(AsmIr::WriteToAddress("R13".to_string()));
+    }
+
+    /// R13 = x >> y, for 0 <= y < 16: bit i + y of x is copied to bit i of the result, then
+    /// the top y bits are filled with the sign of x.
+    fn shift_right(&mut self, label: &dyn Fn(&str) -> String) {
+        // y becomes the mask of the source bit, 1 << y, and R14 the mask of the result bit
+        self.set_register("R14", Comp::One);
+        self.commands.push(AsmIr::Label(label("MASK")));
+        self.stack_slot(-1);
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands.push(AsmIr::Jump(label("MASKED"), Jump::Jeq));
+        self.stack_slot(-1);
+        self.commands
+            .push(AsmIr::Assign(Dest::Mem, Comp::DataMinusOne));
+        self.commands.push(AsmIr::Address("R14".to_string()));
+        self.double();
+        self.commands.push(AsmIr::Jump(label("MASK"), Jump::Jmp));
+        self.commands.push(AsmIr::Label(label("MASKED")));
+        self.commands.push(AsmIr::LoadAddress("R14".to_string()));
+        self.stack_slot(-1);
+        self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Data));
+        self.set_register("R14", Comp::One);
+        self.set_register("R13", Comp::Zero);
+
+        self.commands.push(AsmIr::Label(label("LOOP")));
+        self.stack_slot(-1);
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands.push(AsmIr::Jump(label("SIGN"), Jump::Jeq));
+        self.stack_slot(-2);
+        self.commands
+            .push(AsmIr::Assign(Dest::Data, Comp::DataAndMem));
+        self.commands.push(AsmIr::Jump(label("SKIP"), Jump::Jeq));
+        self.commands.push(AsmIr::LoadAddress("R14".to_string()));
+        self.commands.push(AsmIr::Address("R13".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::Mem, Comp::DataOrMem));
+        self.commands.push(AsmIr::Label(label("SKIP")));
+        self.double_slot(-1);
+        self.commands.push(AsmIr::Address("R14".to_string()));
+        self.double();
+        self.commands.push(AsmIr::Jump(label("LOOP"), Jump::Jmp));
+
+        // R14 is 1 << (16 - y) by now, and -R14 has the top y bits set
+        self.commands.push(AsmIr::Label(label("SIGN")));
+        self.stack_slot(-2);
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands.push(AsmIr::Jump(label("END"), Jump::Jge));
+        self.commands.push(AsmIr::Address("R14".to_string()));
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::NegMem));
+        self.commands.push(AsmIr::Address("R13".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::Mem, Comp::DataOrMem));
+    }
+
+    /// A = SP + offset, the words above the stack are free to use as scratch space.
+    fn stack_slot(&mut self, offset: i8) {
+        self.commands.push(AsmIr::Address("SP".to_string()));
+        match offset {
+            0 => self.commands.push(AsmIr::Assign(Dest::Addr, Comp::Mem)),
+            1 => self
+                .commands
+                .push(AsmIr::Assign(Dest::Addr, Comp::MemPlusOne)),
             _ => {
-                let reference = self.resolve_reference(segment)?;
-                self.commands.push(AsmIr::LoadOffset(reference, index));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Addr, Comp::MemMinusOne));
+                for _ in offset..-1 {
+                    self.commands
+                        .push(AsmIr::Assign(Dest::Addr, Comp::AddrMinusOne));
+                }
+            }
+        }
+    }
+
+    /// doubles the word A points at, D keeps the value it had.
+    fn double(&mut self) {
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands
+            .push(AsmIr::Assign(Dest::Mem, Comp::DataPlusMem));
+    }
+
+    fn double_slot(&mut self, offset: i8) {
+        self.stack_slot(offset);
+        self.double();
+    }
+
+    /// replaces the stack word with its absolute value, flipping the sign flag above the stack
+    /// when it was negative.
+    fn absolute_slot(&mut self, offset: i8, positive: &str) {
+        self.stack_slot(offset);
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands
+            .push(AsmIr::Jump(positive.to_string(), Jump::Jge));
+        self.stack_slot(offset);
+        self.commands.push(AsmIr::Assign(Dest::Mem, Comp::NegData));
+        self.stack_slot(1);
+        self.commands.push(AsmIr::Assign(Dest::Mem, Comp::NotMem));
+        self.commands.push(AsmIr::Label(positive.to_string()));
+    }
+
+    fn set_register(&mut self, register: &str, comp: Comp) {
+        self.commands.push(AsmIr::Address(register.to_string()));
+        self.commands.push(AsmIr::Assign(Dest::Mem, comp));
+    }
+
+    fn function(&mut self, name: &'a str, locals: u16) -> Result<(), String> {
+        self.comment(&format!("Function {} {}", name, locals));
+        self.function = Some(name);
+        self.commands.push(AsmIr::Label(name.to_string()));
+        if locals > 0 {
+            self.commands.push(AsmIr::Assign(Dest::Data, Comp::Zero));
+            for _ in 0..locals {
                 self.commands.push(AsmIr::Push);
-                Ok(())
             }
         }
+        Ok(())
     }
 
-    fn pop(&mut self, segment: &str, index: u16) -> Result<(), String> {
+    fn call(&mut self, name: &str, args: u16) -> Result<(), String> {
+        self.comment(&format!("Call {} {}", name, args));
+        if self.options.shared_runtime {
+            // R13 = args
+            let comp = match args {
+                0 => Comp::Zero,
+                1 => Comp::One,
+                _ => {
+                    self.commands.push(AsmIr::LoadConstant(args));
+                    Comp::Data
+                }
+            };
+            self.commands.push(AsmIr::Address("R13".to_string()));
+            self.commands.push(AsmIr::Assign(Dest::Mem, comp));
+            self.commands.push(AsmIr::Address(name.to_string()));
+            self.commands.push(AsmIr::Assign(Dest::Data, Comp::Addr));
+            self.commands.push(AsmIr::WriteToAddress("R14".to_string()));
+            self.call_routine(CALL_ROUTINE);
+            return Ok(());
+        }
+
+        let return_label = self.return_label();
+        self.commands.push(AsmIr::Address(return_label.clone()));
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Addr));
+        self.commands.push(AsmIr::Push);
+        for segment in FRAME {
+            self.commands.push(AsmIr::LoadAddress(segment.to_string()));
+            self.commands.push(AsmIr::Push);
+        }
+        // ARG = SP - 5 - args
+        self.commands.push(AsmIr::LoadAddress("SP".to_string()));
+        self.commands.push(AsmIr::Address((5 + args).to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::Data, Comp::DataMinusAddr));
+        self.commands.push(AsmIr::WriteToAddress("ARG".to_string()));
+        self.commands.push(AsmIr::LoadAddress("SP".to_string()));
+        self.commands.push(AsmIr::WriteToAddress("LCL".to_string()));
+        self.commands.push(AsmIr::Jump(name.to_string(), Jump::Jmp));
+        self.commands.push(AsmIr::Label(return_label));
+        Ok(())
+    }
+
+    /// the shared version of `call`, the return address is already on the stack by the time the
+    /// rest of the frame is pushed.
+    fn call_sequence(&mut self) {
+        self.commands.push(AsmIr::Push);
+        for segment in FRAME {
+            self.commands.push(AsmIr::LoadAddress(segment.to_string()));
+            self.commands.push(AsmIr::Push);
+        }
+        // ARG = SP - R13 - 5
+        self.commands.push(AsmIr::LoadAddress("SP".to_string()));
+        self.commands.push(AsmIr::Address("R13".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::Data, Comp::DataMinusMem));
+        self.commands.push(AsmIr::Address("5".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::Data, Comp::DataMinusAddr));
+        self.commands.push(AsmIr::WriteToAddress("ARG".to_string()));
+        self.commands.push(AsmIr::LoadAddress("SP".to_string()));
+        self.commands.push(AsmIr::WriteToAddress("LCL".to_string()));
+        self.jump_to_register("R14");
+    }
+
+    fn return_from_function(&mut self) -> Result<(), String> {
+        self.comment("Return");
+        if self.options.shared_runtime {
+            self.use_routine(RETURN_ROUTINE);
+            self.commands
+                .push(AsmIr::Jump(RETURN_ROUTINE.to_string(), Jump::Jmp));
+        } else {
+            self.return_sequence();
+        }
+        Ok(())
+    }
+
+    /// restores the caller's frame, R14 holds the frame address and R15 the return address.
+    fn return_sequence(&mut self) {
+        self.commands.push(AsmIr::LoadAddress("LCL".to_string()));
+        self.commands.push(AsmIr::WriteToAddress("R14".to_string()));
+        self.commands.push(AsmIr::Address("5".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::Addr, Comp::DataMinusAddr));
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+        self.commands.push(AsmIr::WriteToAddress("R15".to_string()));
+        // *ARG = pop(), SP = ARG + 1
+        self.commands.push(AsmIr::Pop);
+        self.commands.push(AsmIr::DerefWrite("ARG".to_string()));
+        self.commands.push(AsmIr::Address("ARG".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::Data, Comp::MemPlusOne));
+        self.commands.push(AsmIr::WriteToAddress("SP".to_string()));
+        for segment in FRAME.iter().rev() {
+            self.commands.push(AsmIr::Address("R14".to_string()));
+            self.commands
+                .push(AsmIr::Assign(Dest::AddrMem, Comp::MemMinusOne));
+            self.commands.push(AsmIr::Assign(Dest::Data, Comp::Mem));
+            self.commands
+                .push(AsmIr::WriteToAddress(segment.to_string()));
+        }
+        self.jump_to_register("R15");
+    }
+
+    /// jumps to a shared subroutine with the return address in D.
+    fn call_routine(&mut self, routine: &'static str) {
+        self.use_routine(routine);
+        let return_label = self.return_label();
+        self.commands.push(AsmIr::Address(return_label.clone()));
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Addr));
+        self.commands
+            .push(AsmIr::Jump(routine.to_string(), Jump::Jmp));
+        self.commands.push(AsmIr::Label(return_label));
+    }
+
+    fn use_routine(&mut self, routine: &'static str) {
+        if !self.routines.contains(&routine) {
+            self.routines.push(routine);
+        }
+    }
+
+    fn jump_to_register(&mut self, register: &str) {
+        self.commands.push(AsmIr::Address(register.to_string()));
+        self.commands.push(AsmIr::Assign(Dest::Addr, Comp::Mem));
+        self.commands.push(AsmIr::JumpIf(Comp::Zero, Jump::Jmp));
+    }
+
+    fn return_label(&mut self) -> String {
+        let label = format!("{}$ret.{}", self.scope(), self.return_counter);
+        self.return_counter += 1;
+        label
+    }
+
+    /// labels are local to the function they are declared in
+    fn scoped_label(&self, label: &str) -> String {
+        format!("{}${}", self.scope(), label)
+    }
+
+    fn scope(&self) -> &str {
+        self.function.unwrap_or(&self.namespace)
+    }
+
+    fn push(&mut self, segment: Segment, index: u16) -> Result<(), String> {
+        self.comment(&format!("Push {} {}", segment, index));
+        self.load(segment, index)?;
+        self.commands.push(AsmIr::Push);
+        Ok(())
+    }
+
+    fn pop(&mut self, segment: Segment, index: u16) -> Result<(), String> {
         self.comment(&format!("Pop {} {}", segment, index));
-        match segment {
-            "static" => {
+        match self.locate(segment, index)? {
+            Location::Direct(address) => {
                 self.commands.push(AsmIr::Pop);
-                self.commands.push(AsmIr::WriteToAddress(format!(
-                    "{}.{}",
-                    self.filename, index
-                )));
-                Ok(())
+                self.commands.push(AsmIr::WriteToAddress(address));
             }
-
-            "pointer" if index == 0 => {
-                self.commands.push(AsmIr::Pop);
+            Location::Offset(pointer) => {
                 self.commands
-                    .push(AsmIr::WriteToAddress("THIS".to_string()));
-                Ok(())
-            }
-
-            "pointer" if index == 1 => {
+                    .push(AsmIr::StoreOffset(pointer.to_string(), index)); // store the computed address to R13
                 self.commands.push(AsmIr::Pop);
-                self.commands
-                    .push(AsmIr::WriteToAddress("THAT".to_string()));
-                Ok(())
+                self.commands.push(AsmIr::DerefWrite("R13".to_string()));
             }
-            "temp" => {
-                if index > 7 {
-                    return Err(format!("Invalid index for pop temp, received {}", index));
-                }
-                self.commands.push(AsmIr::Pop);
-                self.commands
-                    .push(AsmIr::WriteToAddress(format!("R{}", 5 + index)));
-                Ok(())
+        }
+        Ok(())
+    }
+
+    fn constant(&mut self, value: i16) -> Result<(), String> {
+        self.comment(&format!("Constant {}", value));
+        self.load_value(value);
+        self.commands.push(AsmIr::Push);
+        Ok(())
+    }
+
+    /// loads any 16 bit value into the data register, `@` only takes 15 bit values.
+    fn load_value(&mut self, value: i16) {
+        match value {
+            0.. => self.commands.push(AsmIr::LoadConstant(value as u16)),
+            -1 => self.commands.push(AsmIr::Assign(Dest::Data, Comp::NegOne)),
+            i16::MIN => {
+                self.commands.push(AsmIr::Address(i16::MAX.to_string()));
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::NotAddr));
             }
             _ => {
-                let reference = self.resolve_reference(segment)?;
-                self.commands.push(AsmIr::StoreOffset(reference, index)); // store the computed address to R13
-                self.commands.push(AsmIr::Pop);
+                self.commands.push(AsmIr::Address((-value).to_string()));
+                self.commands.push(AsmIr::Assign(Dest::Data, Comp::NegAddr));
+            }
+        }
+    }
+
+    /// copies a value between two segments without touching the stack.
+    fn move_value(&mut self, from: (Segment, u16), to: (Segment, u16)) -> Result<(), String> {
+        self.comment(&format!("Move {} {} -> {} {}", from.0, from.1, to.0, to.1));
+        match self.locate(to.0, to.1)? {
+            Location::Direct(address) => {
+                self.load(from.0, from.1)?;
+                self.commands.push(AsmIr::WriteToAddress(address));
+            }
+            Location::Offset(pointer) => {
+                self.commands
+                    .push(AsmIr::StoreOffset(pointer.to_string(), to.1));
+                self.load(from.0, from.1)?;
                 self.commands.push(AsmIr::DerefWrite("R13".to_string()));
-                Ok(())
             }
         }
+        Ok(())
     }
 
-    fn comment(&mut self, comment: &str) {
-        self.commands
-            .push(AsmIr::Comment(format!("// {}\n", comment)));
+    fn is_zero(&mut self) -> Result<(), String> {
+        self.comment("IsZero");
+        let end = format!("IS_ZERO_{}.{}", self.namespace, self.conditional_counter);
+        self.conditional_counter += 1;
+        self.commands.push(AsmIr::TopAssign(Dest::Data, Comp::Mem));
+        self.commands.push(AsmIr::Assign(Dest::Mem, Comp::NegOne));
+        self.commands.push(AsmIr::Jump(end.clone(), Jump::Jeq));
+        self.commands.push(AsmIr::TopAssign(Dest::Mem, Comp::Zero));
+        self.commands.push(AsmIr::Label(end));
+        Ok(())
     }
 
-    fn resolve_reference(&self, segment: &str) -> Result<String, String> {
+    /// loads the value at segment[index] into the data register.
+    fn load(&mut self, segment: Segment, index: u16) -> Result<(), String> {
+        if segment == Segment::Constant {
+            self.commands.push(AsmIr::LoadConstant(index));
+            return Ok(());
+        }
+        match self.locate(segment, index)? {
+            Location::Direct(address) => self.commands.push(AsmIr::LoadAddress(address)),
+            Location::Offset(pointer) => self
+                .commands
+                .push(AsmIr::LoadOffset(pointer.to_string(), index)),
+        }
+        Ok(())
+    }
+
+    /// segments that map to a fixed address don't need any pointer arithmetic, the others are
+    /// an offset from their base pointer. The constant segment has no address to store into.
+    fn locate(&self, segment: Segment, index: u16) -> Result<Location, String> {
+        if let Some(pointer) = segment.base_pointer() {
+            return Ok(Location::Offset(pointer));
+        }
         match segment {
-            "local" => Ok("LCL".to_string()),
-            "argument" => Ok("ARG".to_string()),
-            "this" => Ok("THIS".to_string()),
-            "that" => Ok("THAT".to_string()),
-            _ => Err("Invalid segment".to_string()),
+            Segment::Static => Ok(Location::Direct(format!("{}.{}", self.namespace, index))),
+            Segment::Pointer if index == 0 => Ok(Location::Direct("THIS".to_string())),
+            Segment::Pointer if index == 1 => Ok(Location::Direct("THAT".to_string())),
+            Segment::Pointer => Err(format!("Invalid index for pointer, received {}", index)),
+            Segment::Temp if index > 7 => {
+                Err(format!("Invalid index for temp, received {}", index))
+            }
+            Segment::Temp => Ok(Location::Direct(format!("R{}", 5 + index))),
+            _ => Err("Invalid segment for pop, received constant".to_string()),
         }
     }
+
+    fn comment(&mut self, comment: &str) {
+        self.commands
+            .push(AsmIr::Comment(format!("// {}\n", comment)));
+    }
 }
 
 #[cfg(test)]
 mod unit {
     use super::*;
 
+    /// a comparison branches on the sign of x - y, which `optimize::fold_binary` mirrors.
     #[test]
-    fn test_push() {
+    fn test_comparison() {
         let name = "test.vm".to_string();
         let mut translator = IrParser::new(&name);
         let command = VmCommand::Gt;
@@ -478,6 +1212,36 @@ mod unit {
             .collect::<Vec<String>>()
             .join("");
 
-        println!("{}", as_string);
+        assert_eq!(
+            as_string,
+            "// Comparison JGT\n\
+             @SP\nAM=M-1\nD=M\nA=A-1\nD=M-D\n\
+             @JGT_TRUE_test.0\nD;JGT\n\
+             @SP\nA=M-1\nM=0\n\
+             @JGT_END_test.0\nD;JMP\n\
+             (JGT_TRUE_test.0)\n\
+             @SP\nA=M-1\nM=-1\n\
+             (JGT_END_test.0)\n"
+        );
+    }
+
+    #[test]
+    fn test_namespace() {
+        assert_eq!(namespace("Foo.vm"), "Foo");
+        assert_eq!(namespace("foo-bar.gen.vm"), "foo_bar.gen");
+        assert_eq!(namespace("Foo.vm.3"), "Foo.vm.3");
+        assert_eq!(namespace("3d vector"), "_3d_vector");
+        assert_eq!(namespace(""), "_");
+
+        let mut translator = IrParser::new("main.vm");
+        translator
+            .parse(VmCommand::Pop {
+                segment: Segment::Static,
+                index: 3,
+            })
+            .unwrap();
+        assert!(translator
+            .commands
+            .contains(&AsmIr::WriteToAddress("main.3".to_string())));
     }
 }
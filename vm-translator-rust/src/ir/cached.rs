@@ -0,0 +1,287 @@
+use super::{AsmIr, Comp, Dest, IrParser, Jump, Location};
+use crate::parser::{Segment, VmCommand};
+
+/// pops into a segment at a small offset from its pointer step the address up one by one,
+/// which is shorter than computing it below this index.
+const MAX_ADDRESS_STEPS: u16 = 10;
+
+/// a value pushed onto the stack but not loaded yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Operand {
+    Word(Segment, u16),
+    Value(i16),
+}
+
+impl<'a> IrParser<'a> {
+    /// lowers the command keeping the top of the stack out of RAM where the control flow
+    /// allows. A pushed value is only loaded once the next command needs it, so `push; add`
+    /// becomes a single `D=D+M`, and the value on top of the stack stays in D between
+    /// computations. Everything is spilled to RAM before labels, jumps, calls, returns and the
+    /// commands that expect the whole stack in RAM, which means every label is reached with
+    /// nothing cached.
+    pub(super) fn parse_cached(&mut self, command: VmCommand<'a>) -> Result<(), String> {
+        match command {
+            VmCommand::Push { segment, index } => {
+                self.comment(&format!("Push {} {}", segment, index));
+                self.defer(Operand::Word(segment, index))?;
+            }
+            VmCommand::Constant(value) => {
+                self.comment(&format!("Constant {}", value));
+                self.defer(Operand::Value(value))?;
+            }
+            VmCommand::Pop { segment, index } => {
+                self.comment(&format!("Pop {} {}", segment, index));
+                match self.pending.take() {
+                    Some(operand) => {
+                        self.spill_data();
+                        self.move_operand(operand, segment, index)?;
+                    }
+                    None => {
+                        self.fill()?;
+                        self.store_data(segment, index)?;
+                        self.cached = false;
+                    }
+                }
+            }
+            VmCommand::Add => self.binary_cached("Add", [Comp::DataPlusMem, Comp::DataPlusAddr])?,
+            VmCommand::Sub => {
+                self.binary_cached("Sub", [Comp::DataMinusMem, Comp::DataMinusAddr])?
+            }
+            VmCommand::And => self.binary_cached("And", [Comp::DataAndMem, Comp::DataAndAddr])?,
+            VmCommand::Or => self.binary_cached("Or", [Comp::DataOrMem, Comp::DataOrAddr])?,
+            VmCommand::Neg => self.unary_cached("Neg", Comp::NegData)?,
+            VmCommand::Not => self.unary_cached("Not", Comp::NotData)?,
+            VmCommand::Inc => self.unary_cached("Inc", Comp::DataPlusOne)?,
+            VmCommand::Dec => self.unary_cached("Dec", Comp::DataMinusOne)?,
+            VmCommand::Gt | VmCommand::Lt | VmCommand::Eq if !self.options.shared_runtime => {
+                let (condition, jump) = match command {
+                    VmCommand::Gt => ("JGT", Jump::Jgt),
+                    VmCommand::Lt => ("JLT", Jump::Jlt),
+                    _ => ("JEQ", Jump::Jeq),
+                };
+                self.binary_cached(
+                    &format!("Comparison {}", condition),
+                    [Comp::DataMinusMem, Comp::DataMinusAddr],
+                )?;
+                self.test_data(condition, jump);
+            }
+            VmCommand::IsZero => {
+                self.comment("IsZero");
+                self.fill()?;
+                self.test_data("IS_ZERO", Jump::Jeq);
+            }
+            VmCommand::IfGoto(label) => {
+                self.comment(&format!("IfGoto {}", label));
+                self.fill()?;
+                let label = self.scoped_label(label);
+                self.commands.push(AsmIr::Jump(label, Jump::Jne));
+                self.cached = false;
+            }
+            VmCommand::Dup => {
+                self.comment("Dup");
+                self.fill()?;
+                self.spill_data();
+                self.cached = true;
+            }
+            other => {
+                self.flush_cached()?;
+                self.lower(other)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// writes everything cached back to RAM, at the end of a file the code falls through to
+    /// whatever comes next.
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.flush_cached()
+    }
+
+    fn flush_cached(&mut self) -> Result<(), String> {
+        if self.pending.is_some() {
+            self.fill()?;
+        }
+        self.spill_data();
+        Ok(())
+    }
+
+    /// pushes an operand, the one pushed before it is loaded into D.
+    fn defer(&mut self, operand: Operand) -> Result<(), String> {
+        if self.pending.is_some() {
+            self.fill()?;
+        }
+        self.pending = Some(operand);
+        Ok(())
+    }
+
+    /// moves the top of the stack into D.
+    fn fill(&mut self) -> Result<(), String> {
+        match self.pending.take() {
+            Some(operand) => {
+                self.spill_data();
+                self.load_operand(operand)?;
+            }
+            None if !self.cached => self.commands.push(AsmIr::Pop),
+            None => {}
+        }
+        self.cached = true;
+        Ok(())
+    }
+
+    /// pushes a value cached in D onto the stack in RAM.
+    fn spill_data(&mut self) {
+        if !self.cached {
+            return;
+        }
+        self.commands.push(AsmIr::Address("SP".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::AddrMem, Comp::MemPlusOne));
+        self.commands
+            .push(AsmIr::Assign(Dest::Addr, Comp::AddrMinusOne));
+        self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Data));
+        self.cached = false;
+    }
+
+    fn load_operand(&mut self, operand: Operand) -> Result<(), String> {
+        match operand {
+            Operand::Word(segment, index) => self.load(segment, index)?,
+            Operand::Value(value) => self.load_value(value),
+        }
+        Ok(())
+    }
+
+    /// D = x op y. A pending y is used straight from memory or as a constant, `comps` are the
+    /// computations with y in M and in A.
+    fn binary_cached(&mut self, name: &str, comps: [Comp; 2]) -> Result<(), String> {
+        self.comment(name);
+        let [with_mem, with_addr] = comps;
+        if let Some(operand) = self.pending {
+            if let Some(in_memory) = self.operand_address(operand, true) {
+                // x is below the operand, in D or on top of the stack in RAM
+                if !self.cached {
+                    self.commands.push(AsmIr::Pop);
+                    self.cached = true;
+                }
+                self.operand_address(operand, false);
+                self.pending = None;
+                let comp = if in_memory { with_mem } else { with_addr };
+                self.commands.push(AsmIr::Assign(Dest::Data, comp));
+                return Ok(());
+            }
+        }
+        self.fill()?;
+        // y is in D and x on top of the stack, D-M and friends are turned around
+        let comp = match with_mem {
+            Comp::DataMinusMem => Comp::MemMinusData,
+            other => other,
+        };
+        self.commands.push(AsmIr::Address("SP".to_string()));
+        self.commands
+            .push(AsmIr::Assign(Dest::AddrMem, Comp::MemMinusOne));
+        self.commands.push(AsmIr::Assign(Dest::Data, comp));
+        Ok(())
+    }
+
+    /// points A at the operand without touching D, `Some(true)` when the operand is then in M
+    /// and `Some(false)` when it is A itself. Without `dry_run` the instructions are emitted.
+    fn operand_address(&mut self, operand: Operand, dry_run: bool) -> Option<bool> {
+        let mut instructions = Vec::new();
+        let in_memory = match operand {
+            Operand::Value(value) if value >= 0 => {
+                instructions.push(AsmIr::Address(value.to_string()));
+                false
+            }
+            Operand::Value(_) => return None,
+            Operand::Word(Segment::Constant, index) => {
+                instructions.push(AsmIr::Address(index.to_string()));
+                false
+            }
+            Operand::Word(segment, index) => match self.locate(segment, index).ok()? {
+                Location::Direct(address) => {
+                    instructions.push(AsmIr::Address(address));
+                    true
+                }
+                Location::Offset(pointer) if index < MAX_ADDRESS_STEPS => {
+                    instructions.push(AsmIr::Address(pointer.to_string()));
+                    instructions.push(AsmIr::Assign(Dest::Addr, Comp::Mem));
+                    for _ in 0..index {
+                        instructions.push(AsmIr::Assign(Dest::Addr, Comp::AddrPlusOne));
+                    }
+                    true
+                }
+                Location::Offset(_) => return None,
+            },
+        };
+        if !dry_run {
+            self.commands.extend(instructions);
+        }
+        Some(in_memory)
+    }
+
+    fn unary_cached(&mut self, name: &str, comp: Comp) -> Result<(), String> {
+        self.comment(name);
+        self.fill()?;
+        self.commands.push(AsmIr::Assign(Dest::Data, comp));
+        Ok(())
+    }
+
+    /// D = -1 when D satisfies the jump condition, 0 otherwise.
+    fn test_data(&mut self, condition: &str, jump: Jump) {
+        let suffix = format!("{}.{}", self.namespace, self.conditional_counter);
+        self.conditional_counter += 1;
+        let if_true = format!("{}_TRUE_{}", condition, suffix);
+        let end = format!("{}_END_{}", condition, suffix);
+        self.commands.push(AsmIr::Jump(if_true.clone(), jump));
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::Zero));
+        self.commands.push(AsmIr::Jump(end.clone(), Jump::Jmp));
+        self.commands.push(AsmIr::Label(if_true));
+        self.commands.push(AsmIr::Assign(Dest::Data, Comp::NegOne));
+        self.commands.push(AsmIr::Label(end));
+    }
+
+    /// copies a pending operand into segment[index], D is free.
+    fn move_operand(
+        &mut self,
+        operand: Operand,
+        segment: Segment,
+        index: u16,
+    ) -> Result<(), String> {
+        match operand {
+            Operand::Word(from, from_index) => {
+                self.move_value((from, from_index), (segment, index))
+            }
+            Operand::Value(value) => {
+                self.load_value(value);
+                self.store_data(segment, index)
+            }
+        }
+    }
+
+    /// stores D into segment[index]. R13 and R14 hold the value and the address when the
+    /// address has to be computed.
+    fn store_data(&mut self, segment: Segment, index: u16) -> Result<(), String> {
+        match self.locate(segment, index)? {
+            Location::Direct(address) => self.commands.push(AsmIr::WriteToAddress(address)),
+            Location::Offset(pointer) if index < MAX_ADDRESS_STEPS => {
+                self.commands.push(AsmIr::Address(pointer.to_string()));
+                self.commands.push(AsmIr::Assign(Dest::Addr, Comp::Mem));
+                for _ in 0..index {
+                    self.commands
+                        .push(AsmIr::Assign(Dest::Addr, Comp::AddrPlusOne));
+                }
+                self.commands.push(AsmIr::Assign(Dest::Mem, Comp::Data));
+            }
+            Location::Offset(pointer) => {
+                self.commands.push(AsmIr::WriteToAddress("R13".to_string()));
+                self.commands.push(AsmIr::LoadConstant(index));
+                self.commands.push(AsmIr::Address(pointer.to_string()));
+                self.commands
+                    .push(AsmIr::Assign(Dest::Data, Comp::DataPlusMem));
+                self.commands.push(AsmIr::WriteToAddress("R14".to_string()));
+                self.commands.push(AsmIr::LoadAddress("R13".to_string()));
+                self.commands.push(AsmIr::DerefWrite("R14".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,13 @@
 pub mod args;
 pub mod asm;
+pub mod callgraph;
 pub mod code;
+pub mod inline;
 pub mod ir;
+pub mod optimize;
+pub mod os;
 pub mod parser;
+pub mod program;
+pub mod stats;
 pub mod token;
+pub mod verify;
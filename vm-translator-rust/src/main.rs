@@ -1,14 +1,92 @@
 use vm_translator_rust::args::AssemblerArgs;
-use vm_translator_rust::code::translate;
+use vm_translator_rust::code::{
+    eliminated_functions, emit_sources, size_report, translate_files_to,
+};
+use vm_translator_rust::stats::{Stats, StatsFormat};
+use vm_translator_rust::verify::verify;
 
 fn main() {
     let program_args = AssemblerArgs::parse();
     if let Ok(args) = program_args {
-        if let Err(e) = translate(args.src) {
-            println!("[err] {e}")
+        // with the source on stdin the output goes to stdout, and everything else to stderr
+        let report = |message: String| match args.is_stdin() {
+            true => eprintln!("{message}"),
+            false => println!("{message}"),
+        };
+        // the first error ends the run, with a status scripts and CI can tell apart
+        let fail = |message: String| -> ! {
+            report(format!("[err] {message}"));
+            std::process::exit(1);
+        };
+        let files = args.sources().unwrap_or_else(|e| fail(e));
+        if args.verify {
+            let problems = verify(&files);
+            if !problems.is_empty() {
+                for problem in &problems {
+                    report(format!("[warn] {problem}"));
+                }
+                // buggy compiler output has to fail the build that produced it
+                fail(format!("verification found {} problems", problems.len()));
+            }
+            report("[info] verification passed".to_string());
+        }
+        let translated = match args.is_stdin() {
+            true => emit_sources(&files, &args.options, args.emit).map(|(output, _)| {
+                print!("{output}");
+            }),
+            false => {
+                translate_files_to(&args.src, &files, &args.options, args.emit, args.source_map)
+            }
+        };
+        if let Err(e) = translated {
+            fail(e);
+        }
+        // json statistics are the only output, so they can be piped into other tools
+        if let Some(StatsFormat::Json) = args.stats {
+            match Stats::collect(&files, &args.options) {
+                Ok(stats) => report(stats.json()),
+                Err(e) => fail(e),
+            }
+            return;
+        }
+        match eliminated_functions(&files, &args.options) {
+            Ok(eliminated) if !eliminated.is_empty() => {
+                for function in &eliminated {
+                    report(format!(
+                        "[info] eliminated {} ({}, {} instructions)",
+                        function.name, function.file, function.instructions
+                    ));
+                }
+                report(format!(
+                    "[info] eliminated {} unreachable functions, saving {} instructions",
+                    eliminated.len(),
+                    eliminated.iter().map(|f| f.instructions).sum::<usize>()
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => fail(e),
+        }
+        if args.size_report {
+            match size_report(&files) {
+                Ok((inline, shared)) => {
+                    report(format!("[info] inline runtime: {} instructions", inline));
+                    report(format!(
+                        "[info] shared runtime: {} instructions ({:+} instructions)",
+                        shared,
+                        shared as i64 - inline as i64
+                    ));
+                }
+                Err(e) => fail(e),
+            }
+        }
+        if let Some(StatsFormat::Table) = args.stats {
+            match Stats::collect(&files, &args.options) {
+                Ok(stats) => report(stats.table().trim_end().to_string()),
+                Err(e) => fail(e),
+            }
         }
     } else {
-        println!("usage: assembler <source file>");
-        println!("reminder that the source file must begin with a capital letter and have a .vm extension");
+        println!("usage: assembler <source file | directory | -> [--name=NAME] [--shared-runtime] [--size-report] [--source-map] [--verify] [--inline[=N]] [--ext] [--cache-top] [--no-os] [--emit=asm|hack] [--stats[=table|json]]");
+        println!("the source is read from stdin when it is -, and the output written to stdout");
     }
 }
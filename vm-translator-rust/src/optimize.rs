@@ -0,0 +1,316 @@
+use crate::parser::{Segment, VmCommand};
+
+/// Peephole optimizer that works on vm commands before they are lowered by the `IrParser`.
+/// Every command is pushed onto the output and then the tail of the output is reduced for as
+/// long as one of the rewrite rules applies, so chains like
+/// `push constant 57; push constant 31; push constant 53; add; sub` collapse into a single push.
+///
+/// The rewrites are:
+/// - constant arithmetic and logic is folded into a single constant
+/// - `push x; pop y` becomes a direct `Move` between the two memory locations
+/// - `push constant 0; eq` becomes an `IsZero` test of the top of the stack
+/// - `push constant 1; add` and `push constant 1; sub` become `Inc` and `Dec`
+pub fn optimize(commands: Vec<VmCommand>) -> Vec<VmCommand> {
+    let located = commands.into_iter().map(|command| (command, ())).collect();
+    optimize_located(located)
+        .into_iter()
+        .map(|(command, _)| command)
+        .collect()
+}
+
+/// `optimize` for commands tagged with where they come from, like their source line. A
+/// rewritten command keeps the tag of the first command it replaces.
+pub fn optimize_located<T: Copy>(commands: Vec<(VmCommand, T)>) -> Vec<(VmCommand, T)> {
+    let mut optimized = Vec::with_capacity(commands.len());
+    for command in commands {
+        optimized.push(command);
+        while reduce(&mut optimized) {}
+    }
+    optimized
+}
+
+/// tries to rewrite the last commands of the output, returns true if something changed.
+fn reduce<T: Copy>(commands: &mut Vec<(VmCommand, T)>) -> bool {
+    let len = commands.len();
+
+    if len >= 3 {
+        let lhs = as_constant(&commands[len - 3].0);
+        let rhs = as_constant(&commands[len - 2].0);
+        if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+            if let Some(value) = fold_binary(&commands[len - 1].0, lhs, rhs) {
+                replace_tail(commands, 3, constant(value));
+                return true;
+            }
+        }
+    }
+
+    if len >= 2 {
+        if let Some(value) = as_constant(&commands[len - 2].0) {
+            let replacement = match (value, &commands[len - 1].0) {
+                (_, VmCommand::Neg) => Some(constant(value.wrapping_neg())),
+                (_, VmCommand::Not) => Some(constant(!value)),
+                (0, VmCommand::Eq) => Some(VmCommand::IsZero),
+                (1, VmCommand::Add) => Some(VmCommand::Inc),
+                (1, VmCommand::Sub) => Some(VmCommand::Dec),
+                _ => None,
+            };
+            if let Some(replacement) = replacement {
+                replace_tail(commands, 2, replacement);
+                return true;
+            }
+        }
+
+        if let (
+            VmCommand::Push {
+                segment: from,
+                index: from_index,
+            },
+            VmCommand::Pop {
+                segment: to,
+                index: to_index,
+            },
+        ) = (&commands[len - 2].0, &commands[len - 1].0)
+        {
+            let replacement = VmCommand::Move((*from, *from_index), (*to, *to_index));
+            replace_tail(commands, 2, replacement);
+            return true;
+        }
+    }
+
+    false
+}
+
+fn replace_tail<'a, T: Copy>(
+    commands: &mut Vec<(VmCommand<'a>, T)>,
+    count: usize,
+    replacement: VmCommand<'a>,
+) {
+    let tag = commands[commands.len() - count].1;
+    commands.truncate(commands.len() - count);
+    commands.push((replacement, tag));
+}
+
+fn as_constant(command: &VmCommand) -> Option<i16> {
+    match command {
+        VmCommand::Push {
+            segment: Segment::Constant,
+            index,
+        } => Some(*index as i16),
+        VmCommand::Constant(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// values that fit in a `push constant` stay a regular push so they can still be fused with a
+/// following pop.
+fn constant<'a>(value: i16) -> VmCommand<'a> {
+    if value >= 0 {
+        VmCommand::Push {
+            segment: Segment::Constant,
+            index: value as u16,
+        }
+    } else {
+        VmCommand::Constant(value)
+    }
+}
+
+fn fold_binary(command: &VmCommand, lhs: i16, rhs: i16) -> Option<i16> {
+    let value = match command {
+        VmCommand::Add => lhs.wrapping_add(rhs),
+        VmCommand::Sub => lhs.wrapping_sub(rhs),
+        VmCommand::And => lhs & rhs,
+        VmCommand::Or => lhs | rhs,
+        VmCommand::Eq => as_bool(lhs == rhs),
+        // the lowered comparisons branch on the sign of x - y, which overflows for operands
+        // far apart, and folding has to give what the unfolded code computes
+        VmCommand::Gt => as_bool(lhs.wrapping_sub(rhs) > 0),
+        VmCommand::Lt => as_bool(lhs.wrapping_sub(rhs) < 0),
+        VmCommand::Mul => lhs.wrapping_mul(rhs),
+        VmCommand::Div if rhs != 0 => lhs.wrapping_div(rhs),
+        VmCommand::Mod if rhs != 0 => lhs.wrapping_rem(rhs),
+        VmCommand::Shl => shift_left(lhs, rhs),
+        VmCommand::Shr => shift_right(lhs, rhs),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// shifting by a negative count or by 16 and more shifts every bit out.
+pub fn shift_left(value: i16, count: i16) -> i16 {
+    if (0..16).contains(&count) {
+        value << count
+    } else {
+        0
+    }
+}
+
+/// an arithmetic shift, only copies of the sign bit are left for counts outside 0..16.
+pub fn shift_right(value: i16, count: i16) -> i16 {
+    if (0..16).contains(&count) {
+        value >> count
+    } else {
+        value >> 15
+    }
+}
+
+fn as_bool(value: bool) -> i16 {
+    if value {
+        -1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+    use crate::parser::VmParser;
+
+    fn parse(source: &str) -> Vec<VmCommand<'_>> {
+        let commands = VmParser::new(source).commands().unwrap();
+        commands.into_iter().map(|(command, _)| command).collect()
+    }
+
+    #[test]
+    fn test_fold_constant_arithmetic() {
+        let commands = parse("push constant 7\npush constant 8\nadd\n");
+        assert_eq!(
+            optimize(commands),
+            vec![VmCommand::Push {
+                segment: Segment::Constant,
+                index: 15
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fold_chain_to_negative_constant() {
+        // 57 & -(31 + 53 - 112) = 57 & 28 = 24, 24 | 82 = 90, !90 = -91
+        let commands = parse(
+            "push constant 57\npush constant 31\npush constant 53\nadd\npush constant 112\nsub\nneg\nand\npush constant 82\nor\nnot\n",
+        );
+        assert_eq!(optimize(commands), vec![VmCommand::Constant(-91)]);
+    }
+
+    #[test]
+    fn test_fold_comparisons() {
+        let commands = parse(
+            "push constant 892\npush constant 891\nlt\npush constant 891\npush constant 892\nlt\n",
+        );
+        assert_eq!(
+            optimize(commands),
+            vec![
+                VmCommand::Push {
+                    segment: Segment::Constant,
+                    index: 0
+                },
+                VmCommand::Constant(-1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fold_overflowing_comparisons() {
+        // x - y overflows for all but the last pair, so the lowered code sees the opposite sign
+        for (lhs, rhs, gt, lt) in [
+            (32767, -1, 0, -1),
+            (-32768, 1, -1, 0),
+            (1, -32768, 0, -1),
+            (-32768, 32767, -1, 0),
+            (-2, 32767, -1, 0),
+            (5, 3, -1, 0),
+        ] {
+            assert_eq!(
+                fold_binary(&VmCommand::Gt, lhs, rhs),
+                Some(gt),
+                "{lhs} > {rhs}"
+            );
+            assert_eq!(
+                fold_binary(&VmCommand::Lt, lhs, rhs),
+                Some(lt),
+                "{lhs} < {rhs}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuse_push_pop() {
+        let commands = parse("push local 0\npop local 1\npush constant 10\npop static 3\n");
+        assert_eq!(
+            optimize(commands),
+            vec![
+                VmCommand::Move((Segment::Local, 0), (Segment::Local, 1)),
+                VmCommand::Move((Segment::Constant, 10), (Segment::Static, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_zero_inc_dec() {
+        let commands = parse(
+            "push local 0\npush constant 0\neq\npush argument 1\npush constant 1\nadd\npush constant 1\nsub\n",
+        );
+        assert_eq!(
+            optimize(commands),
+            vec![
+                VmCommand::Push {
+                    segment: Segment::Local,
+                    index: 0
+                },
+                VmCommand::IsZero,
+                VmCommand::Push {
+                    segment: Segment::Argument,
+                    index: 1
+                },
+                VmCommand::Inc,
+                VmCommand::Dec,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rewrites_keep_first_location() {
+        let commands = vec![
+            (
+                VmCommand::Push {
+                    segment: Segment::Local,
+                    index: 0,
+                },
+                1,
+            ),
+            (
+                VmCommand::Pop {
+                    segment: Segment::Local,
+                    index: 1,
+                },
+                2,
+            ),
+            (
+                VmCommand::Push {
+                    segment: Segment::Constant,
+                    index: 7,
+                },
+                3,
+            ),
+            (
+                VmCommand::Push {
+                    segment: Segment::Constant,
+                    index: 8,
+                },
+                4,
+            ),
+            (VmCommand::Add, 5),
+        ];
+        let lines = optimize_located(commands)
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect::<Vec<usize>>();
+        assert_eq!(lines, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_leaves_non_constant_arithmetic() {
+        let commands = parse("push local 0\npush constant 8\nadd\n");
+        assert_eq!(optimize(commands.clone()), commands);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+/// the Jack OS compiled to vm code, as (file name, source) pairs. The Jack sources live in
+/// `jack-compiler-rust/os`.
+pub const OS: [(&str, &str); 8] = [
+    ("Array.vm", include_str!("../os/Array.vm")),
+    ("Keyboard.vm", include_str!("../os/Keyboard.vm")),
+    ("Math.vm", include_str!("../os/Math.vm")),
+    ("Memory.vm", include_str!("../os/Memory.vm")),
+    ("Output.vm", include_str!("../os/Output.vm")),
+    ("Screen.vm", include_str!("../os/Screen.vm")),
+    ("String.vm", include_str!("../os/String.vm")),
+    ("Sys.vm", include_str!("../os/Sys.vm")),
+];
+
+/// adds the OS classes the program calls without defining them, and the ones those call in
+/// turn. A program with a `Main.main` but no `Sys.init` gets `Sys` too, which initializes the
+/// OS and runs it. Programs that bring their own classes keep them.
+pub fn link_os(files: &mut Vec<(String, String)>) {
+    let functions = files
+        .iter()
+        .flat_map(|(_, src)| names(src, "function"))
+        .collect::<HashSet<String>>();
+    let classes = functions
+        .iter()
+        .map(|function| class(function).to_string())
+        .collect::<HashSet<String>>();
+    let mut wanted = files
+        .iter()
+        .flat_map(|(_, src)| names(src, "call"))
+        .collect::<Vec<String>>();
+    if functions.contains("Main.main") && !functions.contains("Sys.init") {
+        wanted.push("Sys.init".to_string());
+    }
+
+    let mut linked = HashSet::new();
+    while let Some(function) = wanted.pop() {
+        let class = class(&function);
+        if classes.contains(class) || linked.contains(class) {
+            continue;
+        }
+        let Some((_, src)) = OS
+            .iter()
+            .find(|(file_name, _)| self::class(file_name) == class)
+        else {
+            continue;
+        };
+        linked.insert(class.to_string());
+        wanted.extend(names(src, "call"));
+    }
+    for (file_name, src) in OS {
+        if linked.contains(class(file_name)) {
+            files.push((file_name.to_string(), src.to_string()));
+        }
+    }
+}
+
+/// the function names following every `keyword` command of the source.
+fn names(src: &str, keyword: &str) -> Vec<String> {
+    src.lines()
+        .filter_map(|line| {
+            let mut words = line.split("//").next()?.split_whitespace();
+            match words.next() {
+                Some(word) if word == keyword => words.next().map(|name| name.to_string()),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn class(function: &str) -> &str {
+    function.split('.').next().unwrap_or(function)
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn file_names(files: &[(String, String)]) -> Vec<&str> {
+        files.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_links_called_classes() {
+        let mut files = vec![(
+            "Foo.vm".to_string(),
+            "function Foo.bar 0\npush constant 2\ncall Math.sqrt 1 // root\nreturn\n".to_string(),
+        )];
+        link_os(&mut files);
+        // Math.sqrt reports errors with Sys.error, and Sys.init initializes every class
+        assert_eq!(files.len(), 9);
+        assert_eq!(
+            file_names(&files)[..3],
+            ["Foo.vm", "Array.vm", "Keyboard.vm"]
+        );
+    }
+
+    #[test]
+    fn test_links_sys_for_main() {
+        let mut files = vec![(
+            "Main.vm".to_string(),
+            "function Main.main 0\npush constant 0\nreturn\n".to_string(),
+        )];
+        link_os(&mut files);
+        assert_eq!(files.len(), 9);
+        assert_eq!(files[8].0, "Sys.vm");
+    }
+
+    #[test]
+    fn test_keeps_defined_classes() {
+        let sys = "function Sys.init 0\ncall Main.main 0\nlabel END\ngoto END\n";
+        let mut files = vec![
+            (
+                "Main.vm".to_string(),
+                "function Main.main 0\ncall Math.abs 1\nreturn\n".to_string(),
+            ),
+            ("Sys.vm".to_string(), sys.to_string()),
+            (
+                "Math.vm".to_string(),
+                "function Math.abs 0\nreturn\n".to_string(),
+            ),
+        ];
+        link_os(&mut files);
+        assert_eq!(file_names(&files), vec!["Main.vm", "Sys.vm", "Math.vm"]);
+    }
+}
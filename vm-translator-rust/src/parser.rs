@@ -1,8 +1,68 @@
 use crate::token::{Token, TokenType, Tokenizer};
+use std::fmt;
 
 const MAX_ADDRESS: u16 = 2_u16.pow(15) - 1;
 
-#[derive(Debug, PartialEq)]
+/// the memory segments `push` and `pop` address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Constant,
+    Local,
+    Argument,
+    This,
+    That,
+    Pointer,
+    Temp,
+    Static,
+}
+
+impl Segment {
+    pub fn parse(segment: &str) -> Result<Segment, String> {
+        match segment {
+            "constant" => Ok(Segment::Constant),
+            "local" => Ok(Segment::Local),
+            "argument" => Ok(Segment::Argument),
+            "this" => Ok(Segment::This),
+            "that" => Ok(Segment::That),
+            "pointer" => Ok(Segment::Pointer),
+            "temp" => Ok(Segment::Temp),
+            "static" => Ok(Segment::Static),
+            other => Err(format!("Invalid segment: {:?}", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Segment::Constant => "constant",
+            Segment::Local => "local",
+            Segment::Argument => "argument",
+            Segment::This => "this",
+            Segment::That => "that",
+            Segment::Pointer => "pointer",
+            Segment::Temp => "temp",
+            Segment::Static => "static",
+        }
+    }
+
+    /// the register holding the base address of the segment, for the segments that move.
+    pub fn base_pointer(&self) -> Option<&'static str> {
+        match self {
+            Segment::Local => Some("LCL"),
+            Segment::Argument => Some("ARG"),
+            Segment::This => Some("THIS"),
+            Segment::That => Some("THAT"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum VmCommand<'a> {
     Add,
     Sub,
@@ -13,14 +73,82 @@ pub enum VmCommand<'a> {
     And,
     Or,
     Not,
-    Push { segment: &'a str, index: u16 },
-    Pop { segment: &'a str, index: u16 },
+    Push { segment: Segment, index: u16 },
+    Pop { segment: Segment, index: u16 },
+    Label(&'a str),
+    Goto(&'a str),
+    IfGoto(&'a str),
+    Function { name: &'a str, locals: u16 },
+    Call { name: &'a str, args: u16 },
+    Return,
+    // Extension commands, only parsed by `VmParser::with_extensions`.
+    Mul,  // x * y
+    Div,  // x / y, rounded towards zero
+    Mod,  // x % y, with the sign of x
+    Shl,  // x << y
+    Shr,  // x >> y, the sign bit is copied
+    Dup,  // pushes a copy of the top of the stack
+    Swap, // swaps the two values on top of the stack
+    // The commands below are never produced by the parser, they are what
+    // `optimize::optimize` rewrites common command sequences into.
+    Constant(i16), // push a folded value, which may not fit in `push constant`
+    Move((Segment, u16), (Segment, u16)), // push (segment, index); pop (segment, index)
+    IsZero,        // push constant 0; eq
+    Inc,           // push constant 1; add
+    Dec,           // push constant 1; sub
+}
+
+/// the vm text of the command. The commands produced by the optimizer are written as the
+/// plain commands they stand for, which can take more than one line.
+impl fmt::Display for VmCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmCommand::Add => f.write_str("add"),
+            VmCommand::Sub => f.write_str("sub"),
+            VmCommand::Neg => f.write_str("neg"),
+            VmCommand::Eq => f.write_str("eq"),
+            VmCommand::Gt => f.write_str("gt"),
+            VmCommand::Lt => f.write_str("lt"),
+            VmCommand::And => f.write_str("and"),
+            VmCommand::Or => f.write_str("or"),
+            VmCommand::Not => f.write_str("not"),
+            VmCommand::Push { segment, index } => write!(f, "push {} {}", segment, index),
+            VmCommand::Pop { segment, index } => write!(f, "pop {} {}", segment, index),
+            VmCommand::Label(label) => write!(f, "label {}", label),
+            VmCommand::Goto(label) => write!(f, "goto {}", label),
+            VmCommand::IfGoto(label) => write!(f, "if-goto {}", label),
+            VmCommand::Function { name, locals } => write!(f, "function {} {}", name, locals),
+            VmCommand::Call { name, args } => write!(f, "call {} {}", name, args),
+            VmCommand::Return => f.write_str("return"),
+            VmCommand::Mul => f.write_str("mul"),
+            VmCommand::Div => f.write_str("div"),
+            VmCommand::Mod => f.write_str("mod"),
+            VmCommand::Shl => f.write_str("shl"),
+            VmCommand::Shr => f.write_str("shr"),
+            VmCommand::Dup => f.write_str("dup"),
+            VmCommand::Swap => f.write_str("swap"),
+            VmCommand::Constant(value) => match *value {
+                0.. => write!(f, "push constant {}", value),
+                i16::MIN => write!(f, "push constant {}\nnot", i16::MAX),
+                _ => write!(f, "push constant {}\nneg", -value),
+            },
+            VmCommand::Move((from, i), (to, j)) => {
+                write!(f, "push {} {}\npop {} {}", from, i, to, j)
+            }
+            VmCommand::IsZero => f.write_str("push constant 0\neq"),
+            VmCommand::Inc => f.write_str("push constant 1\nadd"),
+            VmCommand::Dec => f.write_str("push constant 1\nsub"),
+        }
+    }
 }
 
 pub struct VmParser<'a> {
     tokens: Vec<Token>,
     pos: usize,
     source: &'a str,
+    line: usize,
+    scanned: usize,
+    extensions: bool,
 }
 
 impl<'a> VmParser<'a> {
@@ -30,20 +158,62 @@ impl<'a> VmParser<'a> {
             tokens,
             source,
             pos: 0,
+            line: 1,
+            scanned: 0,
+            extensions: false,
         }
     }
 
+    /// a parser that also accepts the extension commands `mul`, `div`, `mod`, `shl`, `shr`,
+    /// `dup` and `swap`.
+    pub fn with_extensions(source: &'a str) -> Self {
+        Self {
+            extensions: true,
+            ..Self::new(source)
+        }
+    }
+
+    /// the line, counted from 1, of the command returned by the last `next_command`.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
     pub fn is_done(&self) -> bool {
         self.pos >= self.tokens.len()
     }
 
-    pub fn next_command(&mut self) -> Result<VmCommand, String> {
-        let _ = self.skip_unnecessary_tokens();
+    /// every command of the source with its line, the first invalid command is an error.
+    pub fn commands(&mut self) -> Result<Vec<(VmCommand<'a>, usize)>, String> {
+        let mut commands = Vec::new();
+        while !self.at_end() {
+            let command = self
+                .next_command()
+                .map_err(|e| format!("line {}: {}", self.line, e))?;
+            commands.push((command, self.line));
+        }
+        Ok(commands)
+    }
+
+    /// whether only whitespace and comments are left.
+    pub fn at_end(&mut self) -> bool {
+        self.skip_unnecessary_tokens();
+        self.is_done()
+    }
+
+    /// skips the rest of the line, to carry on parsing after an invalid command.
+    pub fn skip_line(&mut self) {
+        self.skip_while(|t| t.token_type != TokenType::Newline);
+    }
+
+    pub fn next_command(&mut self) -> Result<VmCommand<'a>, String> {
+        self.skip_unnecessary_tokens();
         let toke = self.next_token()?;
+        self.line += self.source[self.scanned..toke.start].matches('\n').count();
+        self.scanned = toke.start;
         self.match_command(toke)
     }
 
-    pub fn match_command(&mut self, command_toke: Token) -> Result<VmCommand, String> {
+    pub fn match_command(&mut self, command_toke: Token) -> Result<VmCommand<'a>, String> {
         match &self.source[command_toke.start..command_toke.end] {
             "add" => Ok(VmCommand::Add),
             "sub" => Ok(VmCommand::Sub),
@@ -54,38 +224,84 @@ impl<'a> VmParser<'a> {
             "and" => Ok(VmCommand::And),
             "or" => Ok(VmCommand::Or),
             "not" => Ok(VmCommand::Not),
-            "push" => return self.match_push(),
-            "pop" => return self.match_pop(),
-            other => Err(format!("Invalid command: {}", other)),
+            "push" => self.match_push(),
+            "pop" => self.match_pop(),
+            "label" => Ok(VmCommand::Label(self.match_name()?)),
+            "goto" => Ok(VmCommand::Goto(self.match_name()?)),
+            "if-goto" => Ok(VmCommand::IfGoto(self.match_name()?)),
+            "function" => {
+                let name = self.match_name()?;
+                let locals = self.match_index()?;
+                Ok(VmCommand::Function { name, locals })
+            }
+            "call" => {
+                let name = self.match_name()?;
+                let args = self.match_index()?;
+                Ok(VmCommand::Call { name, args })
+            }
+            "return" => Ok(VmCommand::Return),
+            other => match (self.extensions, Self::match_extension(other)) {
+                (true, Some(command)) => Ok(command),
+                (false, Some(_)) => Err(format!(
+                    "Invalid command: {} is an extension command, enable them with --ext",
+                    other
+                )),
+                (_, None) => Err(format!("Invalid command: {}", other)),
+            },
+        }
+    }
+
+    fn match_extension(command: &str) -> Option<VmCommand<'a>> {
+        match command {
+            "mul" => Some(VmCommand::Mul),
+            "div" => Some(VmCommand::Div),
+            "mod" => Some(VmCommand::Mod),
+            "shl" => Some(VmCommand::Shl),
+            "shr" => Some(VmCommand::Shr),
+            "dup" => Some(VmCommand::Dup),
+            "swap" => Some(VmCommand::Swap),
+            _ => None,
         }
     }
 
-    fn match_push(&mut self) -> Result<VmCommand, String> {
+    fn match_push(&mut self) -> Result<VmCommand<'a>, String> {
         let segment = self.match_segment()?;
         let index = self.match_index()?;
         Ok(VmCommand::Push { segment, index })
     }
 
-    fn match_pop(&mut self) -> Result<VmCommand, String> {
+    fn match_pop(&mut self) -> Result<VmCommand<'a>, String> {
         let segment = self.match_segment()?;
         let index = self.match_index()?;
+        if segment == Segment::Constant {
+            return Err("Invalid segment for pop, received constant".to_string());
+        }
         Ok(VmCommand::Pop { segment, index })
     }
 
-    fn match_segment(&mut self) -> Result<&'a str, String> {
-        let _ = self.skip_while(|t| t.token_type == TokenType::WhiteSpace);
+    fn match_segment(&mut self) -> Result<Segment, String> {
+        self.skip_while(|t| t.token_type == TokenType::WhiteSpace);
         let segment_toke = self.next_token()?;
-        let segment = &self.source[segment_toke.start..segment_toke.end];
-        match segment {
-            "argument" | "local" | "static" | "constant" | "this" | "that" | "pointer" | "temp" => {
-                Ok(segment)
-            }
-            other => Err(format!("Invalid segment: {:?}", other)),
+        Segment::parse(&self.source[segment_toke.start..segment_toke.end])
+    }
+
+    /// labels and function names follow the same rules as hack symbols, a sequence of letters,
+    /// digits, '_', '.', '$' and ':' that doesn't begin with a digit.
+    fn match_name(&mut self) -> Result<&'a str, String> {
+        self.skip_while(|t| t.token_type == TokenType::WhiteSpace);
+        let name_toke = self.next_token()?;
+        let name = &self.source[name_toke.start..name_toke.end];
+        let valid = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.$:".contains(c));
+        match name.chars().next() {
+            Some(c) if valid && !c.is_ascii_digit() => Ok(name),
+            _ => Err(format!("Invalid name: {:?}", name)),
         }
     }
 
     fn match_index(&mut self) -> Result<u16, String> {
-        let _ = self.skip_while(|t| t.token_type == TokenType::WhiteSpace);
+        self.skip_while(|t| t.token_type == TokenType::WhiteSpace);
         let index_toke = self.next_token()?;
         let index = &self.source[index_toke.start..index_toke.end];
         match index.parse::<u16>() {
@@ -131,7 +347,6 @@ impl<'a> VmParser<'a> {
 }
 
 #[cfg(test)]
-
 mod unit {
     use super::*;
 
@@ -143,7 +358,7 @@ mod unit {
         assert_eq!(
             command,
             VmCommand::Push {
-                segment: "constant",
+                segment: Segment::Constant,
                 index: 7
             }
         );
@@ -151,7 +366,7 @@ mod unit {
         assert_eq!(
             command,
             VmCommand::Push {
-                segment: "constant",
+                segment: Segment::Constant,
                 index: 8
             }
         );
@@ -178,4 +393,104 @@ mod unit {
         let result = parser.next_command();
         assert_eq!(result, Err("Invalid command: xor".to_string()));
     }
+
+    #[test]
+    fn test_parser_functions() {
+        let source =
+            "function Main.fib 2\nlabel LOOP\nif-goto END\ngoto LOOP\ncall Main.fib 1\nreturn\n";
+        let mut parser = VmParser::new(source);
+        assert_eq!(
+            parser.next_command().unwrap(),
+            VmCommand::Function {
+                name: "Main.fib",
+                locals: 2
+            }
+        );
+        assert_eq!(parser.next_command().unwrap(), VmCommand::Label("LOOP"));
+        assert_eq!(parser.next_command().unwrap(), VmCommand::IfGoto("END"));
+        assert_eq!(parser.next_command().unwrap(), VmCommand::Goto("LOOP"));
+        assert_eq!(
+            parser.next_command().unwrap(),
+            VmCommand::Call {
+                name: "Main.fib",
+                args: 1
+            }
+        );
+        assert_eq!(parser.next_command().unwrap(), VmCommand::Return);
+    }
+
+    #[test]
+    fn test_parser_extensions() {
+        let source = "mul\ndiv\nmod\nshl\nshr\ndup\nswap\n";
+        let mut parser = VmParser::with_extensions(source);
+        let mut commands = Vec::new();
+        while let Ok(command) = parser.next_command() {
+            commands.push(command);
+        }
+        assert_eq!(
+            commands,
+            vec![
+                VmCommand::Mul,
+                VmCommand::Div,
+                VmCommand::Mod,
+                VmCommand::Shl,
+                VmCommand::Shr,
+                VmCommand::Dup,
+                VmCommand::Swap
+            ]
+        );
+        let mut parser = VmParser::new(source);
+        assert_eq!(
+            parser.next_command(),
+            Err("Invalid command: mul is an extension command, enable them with --ext".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parser_line() {
+        let source = "// header\n\npush constant 7 // seven\n  add\n\n\nneg";
+        let mut parser = VmParser::new(source);
+        parser.next_command().unwrap();
+        assert_eq!(parser.line(), 3);
+        parser.next_command().unwrap();
+        assert_eq!(parser.line(), 4);
+        parser.next_command().unwrap();
+        assert_eq!(parser.line(), 7);
+    }
+
+    #[test]
+    fn test_parser_segments() {
+        let mut parser = VmParser::new("pop constant 1\n");
+        assert_eq!(
+            parser.next_command(),
+            Err("Invalid segment for pop, received constant".to_string())
+        );
+        let mut parser = VmParser::new("push heap 1\n");
+        assert_eq!(
+            parser.next_command(),
+            Err("Invalid segment: \"heap\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let source = "function Main.f 2\nlabel LOOP\npush argument 1\npop that 0\ncall Main.g 1\nif-goto LOOP\nreturn";
+        let mut parser = VmParser::new(source);
+        let commands = parser.commands().unwrap();
+        let text = commands
+            .iter()
+            .map(|(command, _)| command.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_eq!(text, source);
+        assert_eq!(VmCommand::Constant(-5).to_string(), "push constant 5\nneg");
+    }
+
+    #[test]
+    fn test_parser_error_invalid_name() {
+        let source = "label 1LOOP\n";
+        let mut parser = VmParser::new(source);
+        let result = parser.next_command();
+        assert_eq!(result, Err("Invalid name: \"1LOOP\"".to_string()));
+    }
 }
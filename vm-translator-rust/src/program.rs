@@ -0,0 +1,285 @@
+use crate::parser::{Segment, VmCommand, VmParser};
+use std::fmt;
+
+/// the commands that compute on the top of the stack, including the extension commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Sub,
+    Neg,
+    Eq,
+    Gt,
+    Lt,
+    And,
+    Or,
+    Not,
+    Mul,
+    Div,
+    Mod,
+    Shl,
+    Shr,
+    Dup,
+    Swap,
+}
+
+impl Operation {
+    pub fn command(&self) -> VmCommand<'static> {
+        match self {
+            Operation::Add => VmCommand::Add,
+            Operation::Sub => VmCommand::Sub,
+            Operation::Neg => VmCommand::Neg,
+            Operation::Eq => VmCommand::Eq,
+            Operation::Gt => VmCommand::Gt,
+            Operation::Lt => VmCommand::Lt,
+            Operation::And => VmCommand::And,
+            Operation::Or => VmCommand::Or,
+            Operation::Not => VmCommand::Not,
+            Operation::Mul => VmCommand::Mul,
+            Operation::Div => VmCommand::Div,
+            Operation::Mod => VmCommand::Mod,
+            Operation::Shl => VmCommand::Shl,
+            Operation::Shr => VmCommand::Shr,
+            Operation::Dup => VmCommand::Dup,
+            Operation::Swap => VmCommand::Swap,
+        }
+    }
+
+    fn from_command(command: &VmCommand) -> Option<Operation> {
+        let operation = match command {
+            VmCommand::Add => Operation::Add,
+            VmCommand::Sub => Operation::Sub,
+            VmCommand::Neg => Operation::Neg,
+            VmCommand::Eq => Operation::Eq,
+            VmCommand::Gt => Operation::Gt,
+            VmCommand::Lt => Operation::Lt,
+            VmCommand::And => Operation::And,
+            VmCommand::Or => Operation::Or,
+            VmCommand::Not => Operation::Not,
+            VmCommand::Mul => Operation::Mul,
+            VmCommand::Div => Operation::Div,
+            VmCommand::Mod => Operation::Mod,
+            VmCommand::Shl => Operation::Shl,
+            VmCommand::Shr => Operation::Shr,
+            VmCommand::Dup => Operation::Dup,
+            VmCommand::Swap => Operation::Swap,
+            _ => return None,
+        };
+        Some(operation)
+    }
+}
+
+/// a command inside a function, owning its names so programs can be built in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Push(Segment, u16),
+    Pop(Segment, u16),
+    Operation(Operation),
+    Label(String),
+    Goto(String),
+    IfGoto(String),
+    Call { name: String, args: u16 },
+    Return,
+}
+
+impl Statement {
+    pub fn command(&self) -> VmCommand<'_> {
+        match self {
+            Statement::Push(segment, index) => VmCommand::Push {
+                segment: *segment,
+                index: *index,
+            },
+            Statement::Pop(segment, index) => VmCommand::Pop {
+                segment: *segment,
+                index: *index,
+            },
+            Statement::Operation(operation) => operation.command(),
+            Statement::Label(label) => VmCommand::Label(label),
+            Statement::Goto(label) => VmCommand::Goto(label),
+            Statement::IfGoto(label) => VmCommand::IfGoto(label),
+            Statement::Call { name, args } => VmCommand::Call { name, args: *args },
+            Statement::Return => VmCommand::Return,
+        }
+    }
+
+    /// the statement of a parsed command, `function` commands and the commands produced by the
+    /// optimizer have none.
+    pub fn from_command(command: &VmCommand) -> Option<Statement> {
+        let statement = match command {
+            VmCommand::Push { segment, index } => Statement::Push(*segment, *index),
+            VmCommand::Pop { segment, index } => Statement::Pop(*segment, *index),
+            VmCommand::Label(label) => Statement::Label(label.to_string()),
+            VmCommand::Goto(label) => Statement::Goto(label.to_string()),
+            VmCommand::IfGoto(label) => Statement::IfGoto(label.to_string()),
+            VmCommand::Call { name, args } => Statement::Call {
+                name: name.to_string(),
+                args: *args,
+            },
+            VmCommand::Return => Statement::Return,
+            other => Statement::Operation(Operation::from_command(other)?),
+        };
+        Some(statement)
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.command().fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VmFunction {
+    pub name: String,
+    pub locals: u16,
+    pub body: Vec<Statement>,
+}
+
+impl VmFunction {
+    pub fn new(name: &str, locals: u16) -> Self {
+        Self {
+            name: name.to_string(),
+            locals,
+            body: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, statement: Statement) {
+        self.body.push(statement);
+    }
+}
+
+/// one .vm file. Statics and labels outside of functions are namespaced by its name, which
+/// includes the .vm extension like the file names given to `code::translate_sources`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VmFile {
+    pub name: String,
+    /// the commands before the first function, only test programs have these
+    pub preamble: Vec<Statement>,
+    pub functions: Vec<VmFunction>,
+}
+
+impl VmFile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            preamble: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// the commands of the file in order, the nth command is on line n of its text.
+    pub fn commands(&self) -> Vec<VmCommand<'_>> {
+        let mut commands = self
+            .preamble
+            .iter()
+            .map(Statement::command)
+            .collect::<Vec<VmCommand>>();
+        for function in &self.functions {
+            commands.push(VmCommand::Function {
+                name: &function.name,
+                locals: function.locals,
+            });
+            commands.extend(function.body.iter().map(Statement::command));
+        }
+        commands
+    }
+}
+
+/// the vm text of the file, one command per line.
+impl fmt::Display for VmFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for command in self.commands() {
+            writeln!(f, "{}", command)?;
+        }
+        Ok(())
+    }
+}
+
+/// a whole vm program as a tree of files and functions, which tools like a compiler can build
+/// and hand to `code::translate_program` without going through .vm text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VmProgram {
+    pub files: Vec<VmFile>,
+}
+
+impl VmProgram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// parses the (file name, source) pairs of a program, the extension commands only when
+    /// `extensions` is set.
+    pub fn parse(files: &[(String, String)], extensions: bool) -> Result<VmProgram, String> {
+        let mut program = VmProgram::new();
+        for (file_name, source) in files {
+            let mut parser = if extensions {
+                VmParser::with_extensions(source)
+            } else {
+                VmParser::new(source)
+            };
+            let commands = parser
+                .commands()
+                .map_err(|e| format!("{}: {}", file_name, e))?;
+            let mut file = VmFile::new(file_name);
+            for (command, _) in &commands {
+                if let VmCommand::Function { name, locals } = command {
+                    file.functions.push(VmFunction::new(name, *locals));
+                    continue;
+                }
+                let statement = Statement::from_command(command)
+                    .ok_or_else(|| format!("{}: unexpected command {}", file_name, command))?;
+                match file.functions.last_mut() {
+                    Some(function) => function.push(statement),
+                    None => file.preamble.push(statement),
+                }
+            }
+            program.files.push(file);
+        }
+        Ok(program)
+    }
+
+    /// the (file name, source) pairs of the program, the inverse of `parse`.
+    pub fn sources(&self) -> Vec<(String, String)> {
+        self.files
+            .iter()
+            .map(|file| (file.name.clone(), file.to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    const SOURCE: &str = "function Main.main 1\npush constant 3\npop local 0\nlabel LOOP\npush local 0\nif-goto LOOP\ncall Main.helper 0\nreturn\nfunction Main.helper 0\npush argument 0\nneg\nreturn\n";
+
+    #[test]
+    fn test_parse_and_serialize() {
+        let files = vec![("Main.vm".to_string(), SOURCE.to_string())];
+        let program = VmProgram::parse(&files, false).unwrap();
+        let main = &program.files[0];
+        assert_eq!(main.functions.len(), 2);
+        assert_eq!(main.functions[0].body[1], Statement::Pop(Segment::Local, 0));
+        assert_eq!(
+            main.functions[1].body[1],
+            Statement::Operation(Operation::Neg)
+        );
+        assert_eq!(program.sources(), files);
+    }
+
+    #[test]
+    fn test_build_in_memory() {
+        let mut function = VmFunction::new("Main.double", 0);
+        function.push(Statement::Push(Segment::Argument, 0));
+        function.push(Statement::Operation(Operation::Dup));
+        function.push(Statement::Operation(Operation::Add));
+        function.push(Statement::Return);
+        let mut file = VmFile::new("Main.vm");
+        file.functions.push(function);
+        assert_eq!(
+            file.to_string(),
+            "function Main.double 0\npush argument 0\ndup\nadd\nreturn\n"
+        );
+        assert_eq!(file.commands()[2], VmCommand::Dup);
+    }
+}
@@ -0,0 +1,388 @@
+use crate::code::{count_instructions, translate_sources, translate_sources_with_map};
+use crate::ir::{IrOptions, Pass};
+use crate::parser::{VmCommand, VmParser};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// how `--stats` prints the statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsFormat {
+    Table,
+    Json,
+}
+
+impl StatsFormat {
+    pub fn parse(format: &str) -> Result<StatsFormat, String> {
+        match format {
+            "table" => Ok(StatsFormat::Table),
+            "json" => Ok(StatsFormat::Json),
+            other => Err(format!(
+                "unknown stats format {}, expected table or json",
+                other
+            )),
+        }
+    }
+}
+
+/// vm commands and the hack instructions they were lowered to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Count {
+    pub commands: usize,
+    pub instructions: usize,
+}
+
+impl Count {
+    fn add(&mut self, other: Count) {
+        self.commands += other.commands;
+        self.instructions += other.instructions;
+    }
+}
+
+/// a function, or the commands before the first function of a file when `name` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionStats {
+    pub name: Option<String>,
+    pub total: Count,
+    /// by the first word of the command as written, like `push` or `if-goto`
+    pub by_command: BTreeMap<String, Count>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStats {
+    pub name: String,
+    pub total: Count,
+    pub functions: Vec<FunctionStats>,
+}
+
+/// the size of the program with one optimization left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassStats {
+    pub name: &'static str,
+    pub without: usize,
+    /// instructions the pass saves, negative when it costs some
+    pub saved: i64,
+}
+
+/// where the ROM of a translated program goes. Instructions are attributed to the vm command
+/// they were lowered from through the source map, the bootstrap and the shared runtime
+/// subroutines belong to no command and are counted as `runtime`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub files: Vec<FileStats>,
+    pub runtime: usize,
+    pub total: Count,
+    pub passes: Vec<PassStats>,
+}
+
+impl Stats {
+    /// translates the (file name, source) pairs of a program with the options, and once more
+    /// without each optimization pass that applies to them.
+    pub fn collect(sources: &[(String, String)], options: &IrOptions) -> Result<Stats, String> {
+        let mut files = Vec::with_capacity(sources.len());
+        for (file_name, source) in sources {
+            let mut parser = if options.extensions {
+                VmParser::with_extensions(source)
+            } else {
+                VmParser::new(source)
+            };
+            let commands = parser
+                .commands()
+                .map_err(|e| format!("{}: {}", file_name, e))?;
+            let mut file = FileStats {
+                name: file_name.clone(),
+                total: Count::default(),
+                functions: Vec::new(),
+            };
+            for (command, _) in commands {
+                match command {
+                    VmCommand::Function { name, .. } => {
+                        file.functions.push(FunctionStats::new(Some(name)))
+                    }
+                    _ if file.functions.is_empty() => file.functions.push(FunctionStats::new(None)),
+                    _ => {}
+                }
+                let line = command.to_string();
+                if let Some(function) = file.functions.last_mut() {
+                    function.count(keyword(&line), 1, 0);
+                }
+            }
+            files.push(file);
+        }
+
+        let (assembly, map) = translate_sources_with_map(sources, options)?;
+        let mut mapped = 0;
+        for entry in map.entries() {
+            let location = &entry.location;
+            let index = match files.iter().position(|file| file.name == location.file) {
+                Some(index) => index,
+                None => continue,
+            };
+            let function = files[index].function(location.function.as_deref());
+            function.count(keyword(&location.command), 0, 1);
+            mapped += 1;
+        }
+
+        let mut total = Count::default();
+        for file in &mut files {
+            for function in &mut file.functions {
+                for count in function.by_command.values() {
+                    function.total.add(*count);
+                }
+                file.total.add(function.total);
+            }
+            total.add(file.total);
+        }
+        let instructions = count_instructions(&assembly);
+        total.instructions = instructions;
+
+        let mut passes = Vec::new();
+        for (name, without) in alternatives(options) {
+            let without = count_instructions(&translate_sources(sources, &without)?);
+            passes.push(PassStats {
+                name,
+                without,
+                saved: without as i64 - instructions as i64,
+            });
+        }
+        Ok(Stats {
+            files,
+            runtime: instructions - mapped,
+            total,
+            passes,
+        })
+    }
+
+    /// an indented table of files, their functions and their command types, followed by the
+    /// optimization passes.
+    pub fn table(&self) -> String {
+        let mut out = String::new();
+        let row = |out: &mut String, name: &str, count: &Count| {
+            let _ = writeln!(
+                out,
+                "{:<40} {:>9} {:>13}",
+                name, count.commands, count.instructions
+            );
+        };
+        let _ = writeln!(
+            out,
+            "{:<40} {:>9} {:>13}",
+            "file / function / command", "commands", "instructions"
+        );
+        for file in &self.files {
+            row(&mut out, &file.name, &file.total);
+            for function in &file.functions {
+                let name = function.name.as_deref().unwrap_or("-");
+                row(&mut out, &format!("  {}", name), &function.total);
+                for (command, count) in &function.by_command {
+                    row(&mut out, &format!("    {}", command), count);
+                }
+            }
+        }
+        let _ = writeln!(out, "{:<40} {:>9} {:>13}", "runtime", "", self.runtime);
+        row(&mut out, "total", &self.total);
+        if !self.passes.is_empty() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "{:<40} {:>9} {:>13}", "pass", "without", "saved");
+            for pass in &self.passes {
+                let _ = writeln!(
+                    out,
+                    "{:<40} {:>9} {:>13}",
+                    pass.name, pass.without, pass.saved
+                );
+            }
+        }
+        out
+    }
+
+    /// the statistics as a json object, for tracking them over time.
+    pub fn json(&self) -> String {
+        let count = |count: &Count| {
+            format!(
+                "\"commands\": {}, \"instructions\": {}",
+                count.commands, count.instructions
+            )
+        };
+        let mut files = Vec::new();
+        for file in &self.files {
+            let mut functions = Vec::new();
+            for function in &file.functions {
+                let name = match &function.name {
+                    Some(name) => json_string(name),
+                    None => "null".to_string(),
+                };
+                let by_command = function
+                    .by_command
+                    .iter()
+                    .map(|(command, c)| format!("{}: {{{}}}", json_string(command), count(c)))
+                    .collect::<Vec<String>>();
+                functions.push(format!(
+                    "{{\"name\": {}, {}, \"by_command\": {{{}}}}}",
+                    name,
+                    count(&function.total),
+                    by_command.join(", ")
+                ));
+            }
+            files.push(format!(
+                "{{\"name\": {}, {}, \"functions\": [{}]}}",
+                json_string(&file.name),
+                count(&file.total),
+                functions.join(", ")
+            ));
+        }
+        let passes = self
+            .passes
+            .iter()
+            .map(|pass| {
+                format!(
+                    "{{\"name\": {}, \"without\": {}, \"saved\": {}}}",
+                    json_string(pass.name),
+                    pass.without,
+                    pass.saved
+                )
+            })
+            .collect::<Vec<String>>();
+        format!(
+            "{{\"files\": [{}], \"runtime\": {}, {}, \"passes\": [{}]}}",
+            files.join(", "),
+            self.runtime,
+            count(&self.total),
+            passes.join(", ")
+        )
+    }
+}
+
+impl FileStats {
+    /// the named function, or the commands outside of functions when `name` is `None`.
+    fn function(&mut self, name: Option<&str>) -> &mut FunctionStats {
+        let index = self
+            .functions
+            .iter()
+            .position(|function| function.name.as_deref() == name);
+        match index {
+            Some(index) => &mut self.functions[index],
+            None => {
+                let function = FunctionStats::new(name);
+                // commands before the first function come first
+                let index = if name.is_none() {
+                    0
+                } else {
+                    self.functions.len()
+                };
+                self.functions.insert(index, function);
+                &mut self.functions[index]
+            }
+        }
+    }
+}
+
+impl FunctionStats {
+    fn new(name: Option<&str>) -> Self {
+        Self {
+            name: name.map(|name| name.to_string()),
+            total: Count::default(),
+            by_command: BTreeMap::new(),
+        }
+    }
+
+    fn count(&mut self, command: &str, commands: usize, instructions: usize) {
+        let count = self.by_command.entry(command.to_string()).or_default();
+        count.add(Count {
+            commands,
+            instructions,
+        });
+    }
+}
+
+/// the options with each pass that applies to them left out.
+fn alternatives(options: &IrOptions) -> Vec<(&'static str, IrOptions)> {
+    let mut alternatives = Pass::ALL
+        .iter()
+        .map(|pass| {
+            let options = IrOptions {
+                skip_pass: Some(*pass),
+                ..options.clone()
+            };
+            (pass.name(), options)
+        })
+        .collect::<Vec<(&'static str, IrOptions)>>();
+    if options.inline.is_some() {
+        let without = IrOptions {
+            inline: None,
+            ..options.clone()
+        };
+        alternatives.push(("inline", without));
+    }
+    if options.cache_top {
+        let without = IrOptions {
+            cache_top: false,
+            ..options.clone()
+        };
+        alternatives.push(("cache top", without));
+    }
+    if options.shared_runtime {
+        let without = IrOptions {
+            shared_runtime: false,
+            ..options.clone()
+        };
+        alternatives.push(("shared runtime", without));
+    }
+    alternatives
+}
+
+fn keyword(command: &str) -> &str {
+    command.split_whitespace().next().unwrap_or_default()
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    const SOURCE: &str = "push constant 1\npop temp 0\nfunction Main.main 0\npush constant 2\npush constant 3\nadd\nreturn\n";
+
+    #[test]
+    fn test_counts_commands_by_function() {
+        let files = vec![("Main.vm".to_string(), SOURCE.to_string())];
+        let stats = Stats::collect(&files, &IrOptions::default()).unwrap();
+        let main = &stats.files[0];
+        assert_eq!(main.functions.len(), 2);
+        assert_eq!(main.functions[0].name, None);
+        assert_eq!(main.functions[0].total.commands, 2);
+        let function = &main.functions[1];
+        assert_eq!(function.name.as_deref(), Some("Main.main"));
+        assert_eq!(function.by_command["push"].commands, 2);
+        assert_eq!(function.total.commands, 5);
+        // the folded constant is lowered from the first push
+        assert!(function.by_command["push"].instructions > 0);
+        assert_eq!(function.by_command["add"].instructions, 0);
+        assert_eq!(
+            main.total.instructions + stats.runtime,
+            stats.total.instructions
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let files = vec![("Main\"s.vm".to_string(), "push constant 1\n".to_string())];
+        let stats = Stats::collect(&files, &IrOptions::default()).unwrap();
+        let json = stats.json();
+        assert!(json.starts_with("{\"files\": [{\"name\": \"Main\\\"s.vm\""));
+        assert!(json.contains("\"name\": null"));
+        assert!(json.contains("{\"name\": \"peephole\", \"without\": "));
+    }
+}
@@ -67,17 +67,11 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn is_comment(&self) -> bool {
-        match self.slice_n(2) {
-            "//" => true,
-            _ => false,
-        }
+        matches!(self.slice_n(2), "//")
     }
 
     fn is_newline(&self) -> bool {
-        match self.current_slice().chars().next() {
-            Some('\n') => true,
-            _ => false,
-        }
+        matches!(self.current_slice().chars().next(), Some('\n'))
     }
 
     fn is_whitespace(&self) -> bool {
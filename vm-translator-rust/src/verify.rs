@@ -0,0 +1,328 @@
+use crate::parser::{Segment, VmCommand, VmParser};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// something `verify` found wrong with the vm code, lines are counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub file: String,
+    pub line: usize,
+    pub function: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// the commands of one function, or the commands before the first function of a file.
+struct Unit<'a> {
+    file: &'a str,
+    function: Option<(&'a str, u16)>,
+    commands: Vec<(VmCommand<'a>, usize)>,
+}
+
+/// a `call f m` command, where it is and how many arguments it passes.
+struct CallSite<'a> {
+    file: &'a str,
+    line: usize,
+    args: u16,
+}
+
+/// checks the (file name, source) pairs of a program without running it. The stack depth at
+/// every command of a function is computed along its control flow, starting from 0 at the
+/// `function` command, to find:
+/// - commands that pop more values than the stack holds
+/// - labels reached with different stack depths
+/// - `return` with anything but the return value on the stack
+/// - commands that don't parse, like `pop constant`
+/// - `local` indices beyond the count declared by `function f n`
+/// - `argument` indices beyond what one of the `call f m` of the function passes
+pub fn verify(files: &[(String, String)]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let units = files
+        .iter()
+        .flat_map(|(file, source)| split_units(file, source, &mut problems))
+        .collect::<Vec<Unit>>();
+
+    let mut call_sites: HashMap<&str, Vec<CallSite>> = HashMap::new();
+    for unit in &units {
+        for (command, line) in &unit.commands {
+            if let VmCommand::Call { name, args } = command {
+                call_sites.entry(name).or_default().push(CallSite {
+                    file: unit.file,
+                    line: *line,
+                    args: *args,
+                });
+            }
+        }
+    }
+
+    for unit in &units {
+        let calls = unit
+            .function
+            .and_then(|(name, _)| call_sites.get(name))
+            .map_or(&[][..], |calls| calls.as_slice());
+        check_segments(unit, calls, &mut problems);
+        check_stack(unit, &mut problems);
+    }
+    let file_index = |file: &str| files.iter().position(|(name, _)| name == file);
+    problems.sort_by_key(|problem| (file_index(&problem.file), problem.line));
+    problems
+}
+
+/// splits the file into units, the commands that don't parse are reported and left out.
+fn split_units<'a>(file: &'a str, source: &'a str, problems: &mut Vec<Problem>) -> Vec<Unit<'a>> {
+    let mut parser = VmParser::with_extensions(source);
+    let mut units = vec![Unit {
+        file,
+        function: None,
+        commands: Vec::new(),
+    }];
+    while !parser.at_end() {
+        let command = match parser.next_command() {
+            Ok(command) => command,
+            Err(message) => {
+                problems.push(Problem {
+                    file: file.to_string(),
+                    line: parser.line(),
+                    function: units
+                        .last()
+                        .unwrap()
+                        .function
+                        .map(|(name, _)| name.to_string()),
+                    message,
+                });
+                parser.skip_line();
+                continue;
+            }
+        };
+        if let VmCommand::Function { name, locals } = command {
+            units.push(Unit {
+                file,
+                function: Some((name, locals)),
+                commands: Vec::new(),
+            });
+        }
+        units
+            .last_mut()
+            .unwrap()
+            .commands
+            .push((command, parser.line()));
+    }
+    units.retain(|unit| !unit.commands.is_empty());
+    units
+}
+
+fn check_segments(unit: &Unit, calls: &[CallSite], problems: &mut Vec<Problem>) {
+    // the call passing the fewest arguments bounds the argument indices
+    let fewest = calls.iter().min_by_key(|call| call.args);
+    for (command, line) in &unit.commands {
+        let (segment, index) = match command {
+            VmCommand::Push { segment, index } | VmCommand::Pop { segment, index } => {
+                (*segment, *index)
+            }
+            _ => continue,
+        };
+        match (segment, unit.function, fewest) {
+            (Segment::Local, Some((name, locals)), _) if index >= locals => {
+                let message = format!(
+                    "local {} is beyond the {} locals declared by {}",
+                    index, locals, name
+                );
+                report(problems, unit, *line, message);
+            }
+            (Segment::Argument, Some((name, _)), Some(call)) if index >= call.args => {
+                let message = format!(
+                    "argument {} is not passed by the call to {} on {}:{}, which passes {}",
+                    index, name, call.file, call.line, call.args
+                );
+                report(problems, unit, *line, message);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_stack(unit: &Unit, problems: &mut Vec<Problem>) {
+    let commands = &unit.commands;
+    let labels = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (command, _))| match command {
+            VmCommand::Label(label) => Some((*label, i)),
+            _ => None,
+        })
+        .collect::<HashMap<&str, usize>>();
+
+    let mut depths: Vec<Option<usize>> = vec![None; commands.len()];
+    let mut reported_joins = HashSet::new();
+    let mut pending = vec![0];
+    depths[0] = Some(0);
+    while let Some(i) = pending.pop() {
+        let depth = depths[i].unwrap();
+        let (command, line) = &commands[i];
+        let (pops, pushes) = stack_effect(command);
+        if depth < pops {
+            let message = format!(
+                "stack underflow, the command takes {} values but the stack holds {}",
+                pops, depth
+            );
+            report(problems, unit, *line, message);
+        }
+        let after = depth.saturating_sub(pops) + pushes;
+
+        let mut successors = Vec::with_capacity(2);
+        match command {
+            VmCommand::Return => {
+                if depth != 1 {
+                    let message = format!("return with a stack depth of {}, expected 1", depth);
+                    report(problems, unit, *line, message);
+                }
+            }
+            VmCommand::Goto(label) | VmCommand::IfGoto(label) => {
+                match labels.get(label) {
+                    Some(target) => successors.push(*target),
+                    None => report(problems, unit, *line, format!("undefined label {}", label)),
+                }
+                if matches!(command, VmCommand::IfGoto(_)) {
+                    successors.push(i + 1);
+                }
+            }
+            _ => successors.push(i + 1),
+        }
+
+        for next in successors.into_iter().filter(|&next| next < commands.len()) {
+            match depths[next] {
+                None => {
+                    depths[next] = Some(after);
+                    pending.push(next);
+                }
+                Some(known) if known != after && reported_joins.insert(next) => {
+                    let message = format!(
+                        "inconsistent stack depth, reached with both {} and {}",
+                        known, after
+                    );
+                    report(problems, unit, commands[next].1, message);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// whether the commands of a function body, after its `function` command, pass the stack
+/// checks of `verify`: no underflow, consistent depths at labels and only the return value on
+/// the stack at every `return`.
+pub(crate) fn balanced(body: &[VmCommand]) -> bool {
+    let unit = Unit {
+        file: "",
+        function: None,
+        commands: body.iter().cloned().zip(1..).collect(),
+    };
+    let mut problems = Vec::new();
+    if !unit.commands.is_empty() {
+        check_stack(&unit, &mut problems);
+    }
+    problems.is_empty()
+}
+
+/// how many values the command takes from the stack and how many it puts back.
+fn stack_effect(command: &VmCommand) -> (usize, usize) {
+    match command {
+        VmCommand::Add
+        | VmCommand::Sub
+        | VmCommand::Eq
+        | VmCommand::Gt
+        | VmCommand::Lt
+        | VmCommand::And
+        | VmCommand::Or
+        | VmCommand::Mul
+        | VmCommand::Div
+        | VmCommand::Mod
+        | VmCommand::Shl
+        | VmCommand::Shr => (2, 1),
+        VmCommand::Dup => (1, 2),
+        VmCommand::Swap => (2, 2),
+        VmCommand::Neg | VmCommand::Not | VmCommand::IsZero | VmCommand::Inc | VmCommand::Dec => {
+            (1, 1)
+        }
+        VmCommand::Push { .. } | VmCommand::Constant(_) => (0, 1),
+        VmCommand::Pop { .. } | VmCommand::IfGoto(_) | VmCommand::Return => (1, 0),
+        VmCommand::Call { args, .. } => (*args as usize, 1),
+        VmCommand::Label(_) | VmCommand::Goto(_) | VmCommand::Function { .. } => (0, 0),
+        VmCommand::Move(_, _) => (0, 0),
+    }
+}
+
+fn report(problems: &mut Vec<Problem>, unit: &Unit, line: usize, message: String) {
+    problems.push(Problem {
+        file: unit.file.to_string(),
+        line,
+        function: unit.function.map(|(name, _)| name.to_string()),
+        message,
+    });
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    fn problems(source: &str) -> Vec<String> {
+        verify(&[("Main.vm".to_string(), source.to_string())])
+            .into_iter()
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_function() {
+        let source = "function Main.max 0\npush argument 0\npush argument 1\ngt\nif-goto A\npush argument 1\ngoto END\nlabel A\npush argument 0\nlabel END\nreturn\n";
+        assert_eq!(problems(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_underflow_and_return_depth() {
+        let source = "function Main.f 0\npush constant 1\nadd\npush constant 2\nreturn\n";
+        assert_eq!(
+            problems(source),
+            vec![
+                "Main.vm:3: stack underflow, the command takes 2 values but the stack holds 1",
+                "Main.vm:5: return with a stack depth of 2, expected 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_join() {
+        let source = "function Main.f 0\npush constant 0\nif-goto END\npush constant 1\nlabel END\npush constant 2\nreturn\n";
+        let problems = problems(source);
+        assert_eq!(
+            problems[0],
+            "Main.vm:5: inconsistent stack depth, reached with both 0 and 1"
+        );
+    }
+
+    #[test]
+    fn test_segments() {
+        let source = "function Main.f 1\npush local 1\npop constant 0\npush argument 1\nreturn\nfunction Main.g 0\npush constant 1\ncall Main.f 1\nreturn\n";
+        assert_eq!(
+            problems(source),
+            vec![
+                "Main.vm:2: local 1 is beyond the 1 locals declared by Main.f",
+                "Main.vm:3: Invalid segment for pop, received constant",
+                "Main.vm:4: argument 1 is not passed by the call to Main.f on Main.vm:8, which passes 1",
+                // the invalid pop is left out, so it leaves its value on the stack
+                "Main.vm:5: return with a stack depth of 2, expected 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_label() {
+        let problems = problems("function Main.f 0\ngoto LOOP\n");
+        assert_eq!(problems, vec!["Main.vm:2: undefined label LOOP"]);
+    }
+}
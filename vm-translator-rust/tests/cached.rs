@@ -0,0 +1,74 @@
+mod common;
+
+use common::{
+    check_against_cmp, cycles_to_halt, program_file, read_test_script, translate_program,
+};
+use vm_translator_rust::code::count_instructions;
+use vm_translator_rust::ir::IrOptions;
+
+const PROGRAMS: [&str; 11] = [
+    "7/StackArithmetic/SimpleAdd",
+    "7/StackArithmetic/StackTest",
+    "7/MemoryAccess/BasicTest",
+    "7/MemoryAccess/PointerTest",
+    "7/MemoryAccess/StaticTest",
+    "8/ProgramFlow/BasicLoop",
+    "8/ProgramFlow/FibonacciSeries",
+    "8/FunctionCalls/SimpleFunction",
+    "8/FunctionCalls/NestedCall",
+    "8/FunctionCalls/FibonacciElement",
+    "8/FunctionCalls/StaticsTest",
+];
+
+fn cached(shared_runtime: bool) -> IrOptions {
+    IrOptions {
+        shared_runtime,
+        cache_top: true,
+        ..IrOptions::default()
+    }
+}
+
+#[test]
+fn test_cached_top_matches_cmp() {
+    for shared_runtime in [false, true] {
+        for program in PROGRAMS {
+            let assembly = translate_program(program, &cached(shared_runtime));
+            check_against_cmp(&assembly, program, 2);
+        }
+    }
+}
+
+/// caching the top of the stack in D takes fewer instructions and cycles on every program.
+#[test]
+fn test_cached_top_benchmark() {
+    println!(
+        "{:<34} {:>8} {:>8} {:>8} {:>8}",
+        "program", "size", "cached", "cycles", "cached"
+    );
+    let (mut cycles, mut cached_cycles) = (0, 0);
+    for program in PROGRAMS {
+        let plain = translate_program(program, &IrOptions::default());
+        let cached = translate_program(program, &cached(false));
+        let script = read_test_script(&program_file(program, "tst"));
+        let max = script.cycles * 2;
+        let plain_cycles = cycles_to_halt(&plain, &script.setup, max).unwrap();
+        let fast_cycles = cycles_to_halt(&cached, &script.setup, max).unwrap();
+        println!(
+            "{:<34} {:>8} {:>8} {:>8} {:>8}",
+            program,
+            count_instructions(&plain),
+            count_instructions(&cached),
+            plain_cycles,
+            fast_cycles
+        );
+        assert!(
+            count_instructions(&cached) < count_instructions(&plain),
+            "{}",
+            program
+        );
+        assert!(fast_cycles < plain_cycles, "{}", program);
+        cycles += plain_cycles;
+        cached_cycles += fast_cycles;
+    }
+    println!("total cycles {} -> {}", cycles, cached_cycles);
+}
@@ -0,0 +1,177 @@
+// each test binary only uses part of the helpers
+#![allow(dead_code)]
+
+use emulator::chipset::Chipset;
+use emulator::ram::Ram;
+use std::path::{Path, PathBuf};
+use vm_translator_rust::code::translate_sources;
+use vm_translator_rust::ir::IrOptions;
+
+const MEMORY_SIZE: usize = 32_768;
+
+/// the parts of a nand2tetris `.tst` script these tests care about: the initial RAM values,
+/// how many cycles to run and which addresses end up in the `.cmp` file.
+pub struct TestScript {
+    pub setup: Vec<(usize, i16)>,
+    pub cycles: usize,
+    pub outputs: Vec<usize>,
+}
+
+pub fn project_dir(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../__project-files")
+        .join(relative)
+}
+
+/// translates every .vm file in the program's directory as one program.
+pub fn translate_program(program: &str, options: &IrOptions) -> String {
+    translate_sources(&program_sources(program), options).unwrap()
+}
+
+/// the (file name, source) pairs of every .vm file in the program's directory.
+pub fn program_sources(program: &str) -> Vec<(String, String)> {
+    let mut paths = std::fs::read_dir(project_dir(program))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "vm"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    paths
+        .iter()
+        .map(|p| {
+            let name = p.file_name().unwrap().to_str().unwrap().to_string();
+            (name, std::fs::read_to_string(p).unwrap())
+        })
+        .collect()
+}
+
+/// the test script and compare file of a program are named after its directory.
+pub fn program_file(program: &str, extension: &str) -> PathBuf {
+    let dir = project_dir(program);
+    let name = dir.file_name().unwrap().to_str().unwrap().to_string();
+    dir.join(name).with_extension(extension)
+}
+
+pub fn read_test_script(path: &Path) -> TestScript {
+    let source = std::fs::read_to_string(path).unwrap();
+    let source = source
+        .lines()
+        .map(|line| line.split("//").next().unwrap())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let words = source
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<&str>>();
+
+    let mut script = TestScript {
+        setup: Vec::new(),
+        cycles: 0,
+        outputs: Vec::new(),
+    };
+    let mut iter = words.into_iter().peekable();
+    while let Some(word) = iter.next() {
+        match word {
+            "set" => {
+                let address = ram_address(iter.next().unwrap()).unwrap();
+                let value = iter.next().unwrap().parse::<i16>().unwrap();
+                script.setup.push((address, value));
+            }
+            "repeat" => script.cycles += iter.next().unwrap().parse::<usize>().unwrap(),
+            "output-list" => {
+                while let Some(address) = iter.peek().and_then(|w| ram_address(w)) {
+                    script.outputs.push(address);
+                    iter.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    script
+}
+
+fn ram_address(word: &str) -> Option<usize> {
+    let inner = word.strip_prefix("RAM[")?;
+    let end = inner.find(']')?;
+    inner[..end].parse().ok()
+}
+
+/// compare files alternate between a header row and a row of values, long output lists are
+/// split over several header/value pairs.
+pub fn read_expected(path: &Path) -> Vec<i16> {
+    let source = std::fs::read_to_string(path).unwrap();
+    source
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .skip(1)
+        .step_by(2)
+        .flat_map(|row| row.split('|'))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<i16>().unwrap())
+        .collect()
+}
+
+pub fn assemble(assembly: &str) -> Vec<u16> {
+    assembler_rust::code::assemble(assembly).unwrap()
+}
+
+/// runs the assembly on the emulator's chipset and returns the RAM afterwards.
+pub fn run(assembly: &str, setup: &[(usize, i16)], cycles: usize) -> Ram {
+    let mut rom = assemble(assembly);
+    assert!(rom.len() < MEMORY_SIZE, "program does not fit in ROM");
+    rom.resize(MEMORY_SIZE, 0);
+    let ram = Ram::new(MEMORY_SIZE);
+    for (address, value) in setup {
+        ram.write(*address, *value);
+    }
+    let mut chipset = Chipset::new(rom, ram.clone());
+    for _ in 0..cycles {
+        chipset.run_next_instruction();
+    }
+    ram
+}
+
+/// runs the assembly the way the program's test script would and checks the results against
+/// its compare file. Slower code generation modes can be given more cycles than the script.
+pub fn check_against_cmp(assembly: &str, program: &str, cycle_factor: usize) {
+    let tst = program_file(program, "tst");
+    let script = read_test_script(&tst);
+    let expected = read_expected(&program_file(program, "cmp"));
+    let ram = run(assembly, &script.setup, script.cycles * cycle_factor);
+    let actual = script
+        .outputs
+        .iter()
+        .map(|address| ram.read(*address))
+        .collect::<Vec<i16>>();
+    assert_eq!(actual, expected, "{:?}", tst);
+}
+
+/// how many cycles the program takes to halt, within `max` cycles. A program halts by running
+/// past its last instruction or by reaching a jump to itself, `(END) @END 0;JMP`.
+pub fn cycles_to_halt(assembly: &str, setup: &[(usize, i16)], max: usize) -> Option<usize> {
+    let program = assemble(assembly);
+    let length = program.len();
+    let mut rom = program.clone();
+    rom.resize(MEMORY_SIZE, 0);
+    let ram = Ram::new(MEMORY_SIZE);
+    for (address, value) in setup {
+        ram.write(*address, *value);
+    }
+    let mut chipset = Chipset::new(rom, ram);
+    for cycle in 0..max {
+        let pc = chipset.pc();
+        if pc >= length {
+            return Some(cycle);
+        }
+        let jumps_to_itself = program[pc] as usize == pc
+            && program
+                .get(pc + 1)
+                .is_some_and(|next| next & 0b1110_0000_0000_0111 == 0b1110_0000_0000_0111);
+        if jumps_to_itself {
+            return Some(cycle);
+        }
+        chipset.run_next_instruction();
+    }
+    None
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{assemble, check_against_cmp, program_sources};
+use vm_translator_rust::code::{
+    count_instructions, eliminated_functions, lower_sources, translate_sources,
+};
+use vm_translator_rust::ir::IrOptions;
+
+const UNUSED: &str = "// never called
+function Unused.twice 0
+push argument 0
+push argument 0
+add
+return
+function Unused.caller 0
+push constant 3
+call Unused.twice 1
+return
+";
+
+fn with_unused_file(program: &str) -> Vec<(String, String)> {
+    let mut files = program_sources(program);
+    files.push(("Unused.vm".to_string(), UNUSED.to_string()));
+    files
+}
+
+#[test]
+fn test_reachable_programs_keep_every_function() {
+    for program in [
+        "8/FunctionCalls/NestedCall",
+        "8/FunctionCalls/FibonacciElement",
+        "8/FunctionCalls/StaticsTest",
+    ] {
+        let files = program_sources(program);
+        let options = IrOptions::default();
+        assert_eq!(eliminated_functions(&files, &options).unwrap(), vec![]);
+    }
+}
+
+#[test]
+fn test_unreachable_functions_are_eliminated() {
+    let program = "8/FunctionCalls/StaticsTest";
+    let options = IrOptions::default();
+    let files = with_unused_file(program);
+    let eliminated = eliminated_functions(&files, &options).unwrap();
+    let names = eliminated
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(names, ["Unused.twice", "Unused.caller"]);
+    assert!(eliminated
+        .iter()
+        .all(|f| f.file == "Unused.vm" && f.instructions > 0));
+
+    let assembly = translate_sources(&files, &options).unwrap();
+    assert!(!assembly.contains("Unused."));
+    assert_eq!(
+        assembly,
+        translate_sources(&program_sources(program), &options).unwrap()
+    );
+    check_against_cmp(&assembly, program, 1);
+}
+
+#[test]
+fn test_saved_instructions_match_the_removed_code() {
+    let options = IrOptions::default();
+    let files = with_unused_file("8/FunctionCalls/StaticsTest");
+    let saved: usize = eliminated_functions(&files, &options)
+        .unwrap()
+        .iter()
+        .map(|f| f.instructions)
+        .sum();
+
+    // without Sys.init nothing is eliminated, so the unused file is translated on its own
+    let alone = [("Unused.vm".to_string(), UNUSED.to_string())];
+    assert_eq!(eliminated_functions(&alone, &options).unwrap(), vec![]);
+    let commands = lower_sources(&alone, &options).unwrap();
+    assert_eq!(
+        assemble(&vm_translator_rust::asm::print(&commands)).len(),
+        saved
+    );
+    assert_eq!(
+        count_instructions(&translate_sources(&alone, &options).unwrap()),
+        saved
+    );
+}
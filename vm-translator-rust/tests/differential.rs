@@ -0,0 +1,152 @@
+mod common;
+
+use common::{
+    assemble, program_file, program_sources, project_dir, read_expected, read_test_script,
+};
+use emulator::chipset::Chipset;
+use emulator::ram::Ram;
+use std::path::{Path, PathBuf};
+use vm_translator_rust::code::translate_sources;
+use vm_translator_rust::ir::IrOptions;
+
+const MEMORY_SIZE: usize = 32_768;
+
+const PROGRAMS: [&str; 11] = [
+    "7/StackArithmetic/SimpleAdd",
+    "7/StackArithmetic/StackTest",
+    "7/MemoryAccess/BasicTest",
+    "7/MemoryAccess/PointerTest",
+    "7/MemoryAccess/StaticTest",
+    "8/ProgramFlow/BasicLoop",
+    "8/ProgramFlow/FibonacciSeries",
+    "8/FunctionCalls/SimpleFunction",
+    "8/FunctionCalls/NestedCall",
+    "8/FunctionCalls/FibonacciElement",
+    "8/FunctionCalls/StaticsTest",
+];
+
+/// the output of the template `AsmTranslator` the `AsmIr` back-end replaced, one `.asm` per
+/// program. The templates only knew arithmetic, push and pop, the function and flow commands
+/// of project 8 are lowered by the `IrParser` of the same revision, unoptimized.
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/differential")
+}
+
+fn oracle(program: &Path) -> String {
+    let name = program.file_name().unwrap().to_str().unwrap();
+    std::fs::read_to_string(fixtures().join(name).with_extension("asm")).unwrap()
+}
+
+/// the RAM after running the program until it runs past its last instruction or jumps to
+/// itself, and the highest stack pointer it reached.
+fn execute(assembly: &str, setup: &[(usize, i16)], max_cycles: usize) -> (Vec<i16>, usize) {
+    let program = assemble(assembly);
+    let mut rom = program.clone();
+    rom.resize(MEMORY_SIZE, 0);
+    let ram = Ram::new(MEMORY_SIZE);
+    for (address, value) in setup {
+        ram.write(*address, *value);
+    }
+    let mut chipset = Chipset::new(rom, ram.clone());
+    let mut max_sp = ram.read(0) as usize;
+    for _ in 0..max_cycles {
+        let pc = chipset.pc();
+        let jumps_to_itself = program.get(pc) == Some(&(pc as u16))
+            && program
+                .get(pc + 1)
+                .is_some_and(|next| next & 0b1110_0000_0000_0111 == 0b1110_0000_0000_0111);
+        if pc >= program.len() || jumps_to_itself {
+            break;
+        }
+        chipset.run_next_instruction();
+        max_sp = max_sp.max(ram.read(0) as u16 as usize);
+    }
+    let mut memory = vec![0; MEMORY_SIZE];
+    ram.copy_slice(0, &mut memory).unwrap();
+    (memory, max_sp)
+}
+
+/// the addresses both programs must agree on: all of RAM but the R13-R15 scratch registers,
+/// the stack above the final stack pointer that either program used, and the return addresses
+/// saved in the frames still on the stack, which point into two different ROMs.
+fn observable(memory: &[i16], dead_stack_end: usize, has_frames: bool) -> Vec<usize> {
+    let sp = memory[0] as usize;
+    let mut return_addresses = Vec::new();
+    let mut lcl = memory[1] as usize;
+    while has_frames && lcl >= 261 && lcl <= sp {
+        return_addresses.push(lcl - 5);
+        let caller = memory[lcl - 4] as usize;
+        if caller >= lcl {
+            break;
+        }
+        lcl = caller;
+    }
+    (0..MEMORY_SIZE)
+        .filter(|address| !(13..16).contains(address))
+        .filter(|address| !(sp..dead_stack_end).contains(address))
+        .filter(|address| !return_addresses.contains(address))
+        .collect()
+}
+
+/// runs the retired templates and the `AsmIr` back-end side by side, both match the compare
+/// file and leave the same RAM behind.
+fn compare(name: &str, oracle: &str, files: &[(String, String)], setup: &[(usize, i16)]) {
+    let max_cycles = 1_000_000;
+    let (old, old_sp) = execute(oracle, setup, max_cycles);
+    let has_frames = files
+        .iter()
+        .any(|(_, source)| source.contains("function Sys.init"));
+    for shared_runtime in [false, true] {
+        let options = IrOptions {
+            shared_runtime,
+            ..IrOptions::default()
+        };
+        let assembly = translate_sources(files, &options).unwrap();
+        let (new, new_sp) = execute(&assembly, setup, max_cycles);
+        assert_eq!(old[0], new[0], "{} SP", name);
+        for address in observable(&old, old_sp.max(new_sp), has_frames) {
+            assert_eq!(
+                old[address], new[address],
+                "{} RAM[{}], shared runtime {}",
+                name, address, shared_runtime
+            );
+        }
+    }
+}
+
+#[test]
+fn test_same_behaviour_as_templates() {
+    for program in PROGRAMS {
+        let script = read_test_script(&program_file(program, "tst"));
+        let oracle = oracle(&project_dir(program));
+        // the oracle itself passes the course's test
+        let (memory, _) = execute(&oracle, &script.setup, script.cycles);
+        let actual = script
+            .outputs
+            .iter()
+            .map(|address| memory[*address])
+            .collect::<Vec<i16>>();
+        assert_eq!(
+            actual,
+            read_expected(&program_file(program, "cmp")),
+            "{}",
+            program
+        );
+
+        compare(program, &oracle, &program_sources(program), &script.setup);
+    }
+}
+
+/// the templates popped temp i into `R{5+i}` and pushed it from `{5+i}`, the back-end must
+/// use the same registers.
+#[test]
+fn test_temp_registers_as_templates() {
+    let dir = fixtures().join("TempTest");
+    let source = std::fs::read_to_string(dir.join("TempTest.vm")).unwrap();
+    let files = vec![("TempTest.vm".to_string(), source)];
+    let oracle = oracle(&dir);
+    let (memory, _) = execute(&oracle, &[(0, 256)], 10_000);
+    assert_eq!(&memory[5..13], &[206, 101, 102, 7, 104, 105, 106, 107]);
+    assert_eq!(&memory[16..18], &[112, -1]);
+    compare("TempTest", &oracle, &files, &[(0, 256)]);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{assemble, program_sources, project_dir};
+use vm_translator_rust::code::{assemble_sources, translate_sources, translate_to, Emit};
+use vm_translator_rust::ir::IrOptions;
+
+#[test]
+fn test_assemble_sources_matches_assembler() {
+    let files = program_sources("8/FunctionCalls/FibonacciElement");
+    let options = IrOptions::default();
+    let words = assemble_sources(&files, &options).unwrap();
+    assert_eq!(
+        words,
+        assemble(&translate_sources(&files, &options).unwrap())
+    );
+}
+
+#[test]
+fn test_emit_hack_writes_rom_image() {
+    let dir = std::env::temp_dir().join(format!("emit-hack-{}", std::process::id()));
+    let program = dir.join("SimpleFunction");
+    std::fs::create_dir_all(&program).unwrap();
+    let source = project_dir("8/FunctionCalls/SimpleFunction/SimpleFunction.vm");
+    std::fs::copy(source, program.join("SimpleFunction.vm")).unwrap();
+
+    translate_to(program.clone(), &IrOptions::default(), Emit::Hack, true).unwrap();
+    let output = program.join("SimpleFunction.hack");
+    let hack = std::fs::read_to_string(&output).unwrap();
+    let words = hack
+        .lines()
+        .map(|line| u16::from_str_radix(line, 2).unwrap())
+        .collect::<Vec<u16>>();
+    let files = program_sources("8/FunctionCalls/SimpleFunction");
+    assert_eq!(
+        words,
+        assemble_sources(&files, &IrOptions::default()).unwrap()
+    );
+    assert!(program.join("SimpleFunction.map").exists());
+    assert!(!program.join("SimpleFunction.asm").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_emit_parse() {
+    assert_eq!(Emit::parse("asm"), Ok(Emit::Asm));
+    assert_eq!(Emit::parse("hack"), Ok(Emit::Hack));
+    assert!(Emit::parse("bin").is_err());
+}
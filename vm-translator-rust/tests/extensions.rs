@@ -0,0 +1,113 @@
+mod common;
+
+use common::run;
+use vm_translator_rust::code::translate_source;
+use vm_translator_rust::ir::IrOptions;
+use vm_translator_rust::optimize::{shift_left, shift_right};
+
+const VALUES: [i16; 14] = [
+    -32768, -32767, -1000, -7, -2, -1, 0, 1, 2, 3, 7, 16, 255, 32767,
+];
+
+/// runs the assembly with x in temp 0 and y in temp 1, returning temp 2, temp 3 and the stack
+/// pointer afterwards.
+fn evaluate(assembly: &str, x: i16, y: i16) -> (i16, i16, i16) {
+    let ram = run(assembly, &[(0, 256), (5, x), (6, y)], 2_000);
+    (ram.read(7), ram.read(8), ram.read(0))
+}
+
+/// the expected result of an operation, `None` where the hack code never finishes.
+type Semantics = fn(i16, i16) -> Option<i16>;
+
+fn options(shared_runtime: bool) -> IrOptions {
+    IrOptions {
+        shared_runtime,
+        extensions: true,
+        ..IrOptions::default()
+    }
+}
+
+#[test]
+fn test_arithmetic_matches_rust() {
+    let operations: [(&str, Semantics); 5] = [
+        ("mul", |x, y| Some(x.wrapping_mul(y))),
+        ("div", |x, y| (y != 0).then(|| x.wrapping_div(y))),
+        ("mod", |x, y| (y != 0).then(|| x.wrapping_rem(y))),
+        ("shl", |x, y| Some(shift_left(x, y))),
+        ("shr", |x, y| Some(shift_right(x, y))),
+    ];
+    for shared_runtime in [false, true] {
+        for (command, expected) in operations {
+            let source = format!(
+                "push temp 0\npush temp 1\n{}\npop temp 2\nlabel END\ngoto END\n",
+                command
+            );
+            let assembly = translate_source(&source, "Main.vm", &options(shared_runtime)).unwrap();
+            for x in VALUES {
+                for y in VALUES {
+                    let Some(expected) = expected(x, y) else {
+                        continue;
+                    };
+                    let (result, _, sp) = evaluate(&assembly, x, y);
+                    assert_eq!(
+                        (result, sp),
+                        (expected, 256),
+                        "{} {} {}, shared runtime: {}",
+                        x,
+                        command,
+                        y,
+                        shared_runtime
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_division_by_zero_halts() {
+    for command in ["div", "mod"] {
+        let source = format!("push temp 0\npush temp 1\n{}\npop temp 2\n", command);
+        let assembly = translate_source(&source, "Main.vm", &options(false)).unwrap();
+        assert_eq!(evaluate(&assembly, 5, 0), (0, 0, 258));
+    }
+}
+
+#[test]
+fn test_dup_and_swap() {
+    let source = "push temp 0\npush temp 1\nswap\npop temp 2\npop temp 3\npush temp 0\ndup\nadd\npop temp 1\nlabel END\ngoto END\n";
+    for cache_top in [false, true] {
+        let options = IrOptions {
+            cache_top,
+            ..options(false)
+        };
+        let assembly = translate_source(source, "Main.vm", &options).unwrap();
+        let ram = run(&assembly, &[(0, 256), (5, 3), (6, -4)], 200);
+        assert_eq!((ram.read(7), ram.read(8)), (3, -4));
+        assert_eq!((ram.read(6), ram.read(0)), (6, 256));
+    }
+}
+
+#[test]
+fn test_extensions_need_the_flag() {
+    let error = translate_source(
+        "push constant 6\npush constant 7\nmul\n",
+        "Main.vm",
+        &IrOptions::default(),
+    )
+    .unwrap_err();
+    assert!(error.contains("enable them with --ext"), "{}", error);
+}
+
+#[test]
+fn test_constant_operands_are_folded() {
+    let assembly = translate_source(
+        "push constant 6\npush constant 7\nmul\npop temp 0\n",
+        "Main.vm",
+        &options(false),
+    )
+    .unwrap();
+    assert!(!assembly.contains("MUL"));
+    let ram = run(&assembly, &[(0, 256)], 100);
+    assert_eq!(ram.read(5), 42);
+}
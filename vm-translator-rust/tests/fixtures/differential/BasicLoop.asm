@@ -0,0 +1,130 @@
+// PUSH 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP LCL 0
+@0
+D=A
+@LCL
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// Label LOOP
+(BasicLoop.vm$LOOP)
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH LCL 0
+@0
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// POP LCL 0
+@0
+D=A
+@LCL
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// POP ARG 0
+@0
+D=A
+@ARG
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// IfGoto LOOP
+@SP
+AM=M-1
+D=M
+@BasicLoop.vm$LOOP
+D;JNE
+// PUSH LCL 0
+@0
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
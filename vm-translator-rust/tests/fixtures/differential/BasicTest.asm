@@ -0,0 +1,233 @@
+// PUSH 10
+@10
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP LCL 0
+@0
+D=A
+@LCL
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 21
+@21
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 22
+@22
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP ARG 2
+@2
+D=A
+@ARG
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// POP ARG 1
+@1
+D=A
+@ARG
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 36
+@36
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THIS 6
+@6
+D=A
+@THIS
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 42
+@42
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 45
+@45
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT 5
+@5
+D=A
+@THAT
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// POP THAT 2
+@2
+D=A
+@THAT
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 510
+@510
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R11
+@SP
+AM=M-1
+D=M
+@R11
+M=D
+// PUSH LCL 0
+@0
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH THAT 5
+@5
+D=A
+@THAT
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// PUSH ARG 1
+@1
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// PUSH THIS 6
+@6
+D=A
+@THIS
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH THIS 6
+@6
+D=A
+@THIS
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// PUSH 11
+@11
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
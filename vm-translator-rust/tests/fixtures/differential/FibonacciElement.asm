@@ -0,0 +1,421 @@
+// Bootstrap
+@256
+D=A
+@SP
+M=D
+// Call Sys.init 0
+@Bootstrap$ret.0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+D=M
+@5
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@Sys.init
+D;JMP
+(Bootstrap$ret.0)
+// Function Main.fibonacci 0
+(Main.fibonacci)
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 2
+@2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JLT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JLT_TRUE_Main_0
+D;JLT
+@SP
+A=M-1
+M=0
+@JLT_END_Main_0
+0;JMP
+(JLT_TRUE_Main_0)
+@SP
+A=M-1
+M=-1
+(JLT_END_Main_0)
+// IfGoto N_LT_2
+@SP
+AM=M-1
+D=M
+@Main.fibonacci$N_LT_2
+D;JNE
+// Goto N_GE_2
+@Main.fibonacci$N_GE_2
+D;JMP
+// Label N_LT_2
+(Main.fibonacci$N_LT_2)
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// Return
+@LCL
+D=M
+@R14
+M=D
+@5
+A=D-A
+D=M
+@R15
+M=D
+@SP
+AM=M-1
+D=M
+@ARG
+A=M
+M=D
+@ARG
+D=M+1
+@SP
+M=D
+@R14
+AM=M-1
+D=M
+@THAT
+M=D
+@R14
+AM=M-1
+D=M
+@THIS
+M=D
+@R14
+AM=M-1
+D=M
+@ARG
+M=D
+@R14
+AM=M-1
+D=M
+@LCL
+M=D
+@R15
+A=M
+0;JMP
+// Label N_GE_2
+(Main.fibonacci$N_GE_2)
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 2
+@2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// Call Main.fibonacci 1
+@Main.fibonacci$ret.1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+D=M
+@6
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@Main.fibonacci
+D;JMP
+(Main.fibonacci$ret.1)
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// Call Main.fibonacci 1
+@Main.fibonacci$ret.2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+D=M
+@6
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@Main.fibonacci
+D;JMP
+(Main.fibonacci$ret.2)
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// Return
+@LCL
+D=M
+@R14
+M=D
+@5
+A=D-A
+D=M
+@R15
+M=D
+@SP
+AM=M-1
+D=M
+@ARG
+A=M
+M=D
+@ARG
+D=M+1
+@SP
+M=D
+@R14
+AM=M-1
+D=M
+@THAT
+M=D
+@R14
+AM=M-1
+D=M
+@THIS
+M=D
+@R14
+AM=M-1
+D=M
+@ARG
+M=D
+@R14
+AM=M-1
+D=M
+@LCL
+M=D
+@R15
+A=M
+0;JMP
+// Function Sys.init 0
+(Sys.init)
+// PUSH 4
+@4
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// Call Main.fibonacci 1
+@Sys.init$ret.3
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+D=M
+@6
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@Main.fibonacci
+D;JMP
+(Sys.init$ret.3)
+// Label END
+(Sys.init$END)
+// Goto END
+@Sys.init$END
+D;JMP
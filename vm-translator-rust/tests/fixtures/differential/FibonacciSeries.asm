@@ -0,0 +1,233 @@
+// PUSH ARG 1
+@1
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT
+@SP
+AM=M-1
+D=M
+@THAT
+M=D
+// PUSH 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT 0
+@0
+D=A
+@THAT
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT 1
+@1
+D=A
+@THAT
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 2
+@2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// POP ARG 0
+@0
+D=A
+@ARG
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// Label LOOP
+(FibonacciSeries.vm$LOOP)
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// IfGoto COMPUTE_ELEMENT
+@SP
+AM=M-1
+D=M
+@FibonacciSeries.vm$COMPUTE_ELEMENT
+D;JNE
+// Goto END
+@FibonacciSeries.vm$END
+D;JMP
+// Label COMPUTE_ELEMENT
+(FibonacciSeries.vm$COMPUTE_ELEMENT)
+// PUSH THAT 0
+@0
+D=A
+@THAT
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH THAT 1
+@1
+D=A
+@THAT
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// POP THAT 2
+@2
+D=A
+@THAT
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH THAT
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// POP THAT
+@SP
+AM=M-1
+D=M
+@THAT
+M=D
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// POP ARG 0
+@0
+D=A
+@ARG
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// Goto LOOP
+@FibonacciSeries.vm$LOOP
+D;JMP
+// Label END
+(FibonacciSeries.vm$END)
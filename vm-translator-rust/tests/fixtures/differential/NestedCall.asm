@@ -0,0 +1,546 @@
+// Bootstrap
+@256
+D=A
+@SP
+M=D
+// Call Sys.init 0
+@Bootstrap$ret.0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+D=M
+@5
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@Sys.init
+D;JMP
+(Bootstrap$ret.0)
+// Function Sys.init 0
+(Sys.init)
+// PUSH 4000
+@4000
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THIS
+@SP
+AM=M-1
+D=M
+@THIS
+M=D
+// PUSH 5000
+@5000
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT
+@SP
+AM=M-1
+D=M
+@THAT
+M=D
+// Call Sys.main 0
+@Sys.init$ret.1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+D=M
+@5
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@Sys.main
+D;JMP
+(Sys.init$ret.1)
+// POP R6
+@SP
+AM=M-1
+D=M
+@R6
+M=D
+// Label LOOP
+(Sys.init$LOOP)
+// Goto LOOP
+@Sys.init$LOOP
+D;JMP
+// Function Sys.main 5
+(Sys.main)
+D=0
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 4001
+@4001
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THIS
+@SP
+AM=M-1
+D=M
+@THIS
+M=D
+// PUSH 5001
+@5001
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT
+@SP
+AM=M-1
+D=M
+@THAT
+M=D
+// PUSH 200
+@200
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP LCL 1
+@1
+D=A
+@LCL
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 40
+@40
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP LCL 2
+@2
+D=A
+@LCL
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 6
+@6
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP LCL 3
+@3
+D=A
+@LCL
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 123
+@123
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// Call Sys.add12 1
+@Sys.main$ret.2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@LCL
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@ARG
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+@SP
+D=M
+@6
+D=D-A
+@ARG
+M=D
+@SP
+D=M
+@LCL
+M=D
+@Sys.add12
+D;JMP
+(Sys.main$ret.2)
+// POP R5
+@SP
+AM=M-1
+D=M
+@R5
+M=D
+// PUSH LCL 0
+@0
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH LCL 1
+@1
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH LCL 2
+@2
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH LCL 3
+@3
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH LCL 4
+@4
+D=A
+@LCL
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// Return
+@LCL
+D=M
+@R14
+M=D
+@5
+A=D-A
+D=M
+@R15
+M=D
+@SP
+AM=M-1
+D=M
+@ARG
+A=M
+M=D
+@ARG
+D=M+1
+@SP
+M=D
+@R14
+AM=M-1
+D=M
+@THAT
+M=D
+@R14
+AM=M-1
+D=M
+@THIS
+M=D
+@R14
+AM=M-1
+D=M
+@ARG
+M=D
+@R14
+AM=M-1
+D=M
+@LCL
+M=D
+@R15
+A=M
+0;JMP
+// Function Sys.add12 0
+(Sys.add12)
+// PUSH 4002
+@4002
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THIS
+@SP
+AM=M-1
+D=M
+@THIS
+M=D
+// PUSH 5002
+@5002
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT
+@SP
+AM=M-1
+D=M
+@THAT
+M=D
+// PUSH ARG 0
+@0
+D=A
+@ARG
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 12
+@12
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// Return
+@LCL
+D=M
+@R14
+M=D
+@5
+A=D-A
+D=M
+@R15
+M=D
+@SP
+AM=M-1
+D=M
+@ARG
+A=M
+M=D
+@ARG
+D=M+1
+@SP
+M=D
+@R14
+AM=M-1
+D=M
+@THAT
+M=D
+@R14
+AM=M-1
+D=M
+@THIS
+M=D
+@R14
+AM=M-1
+D=M
+@ARG
+M=D
+@R14
+AM=M-1
+D=M
+@LCL
+M=D
+@R15
+A=M
+0;JMP
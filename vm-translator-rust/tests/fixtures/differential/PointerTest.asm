@@ -0,0 +1,126 @@
+// PUSH 3030
+@3030
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THIS
+@SP
+AM=M-1
+D=M
+@THIS
+M=D
+// PUSH 3040
+@3040
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT
+@SP
+AM=M-1
+D=M
+@THAT
+M=D
+// PUSH 32
+@32
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THIS 2
+@2
+D=A
+@THIS
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH 46
+@46
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP THAT 6
+@6
+D=A
+@THAT
+D=D+M
+@R13
+M=D
+@SP
+AM=M-1
+D=M
+@R13
+A=M
+M=D
+// PUSH THIS
+@THIS
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH THAT
+@THAT
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// PUSH THIS 2
+@2
+D=A
+@THIS
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// PUSH THAT 6
+@6
+D=A
+@THAT
+A=D+M
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
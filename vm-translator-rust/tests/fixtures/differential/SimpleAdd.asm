@@ -0,0 +1,22 @@
+// PUSH 7
+@7
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 8
+@8
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
@@ -0,0 +1,378 @@
+// PUSH 17
+@17
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 17
+@17
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JEQ
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JEQ_TRUE_StackTest_0
+D;JEQ
+@SP
+A=M-1
+M=0
+@JEQ_END_StackTest_0
+0;JMP
+(JEQ_TRUE_StackTest_0)
+@SP
+A=M-1
+M=-1
+(JEQ_END_StackTest_0)
+// PUSH 17
+@17
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 16
+@16
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JEQ
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JEQ_TRUE_StackTest_1
+D;JEQ
+@SP
+A=M-1
+M=0
+@JEQ_END_StackTest_1
+0;JMP
+(JEQ_TRUE_StackTest_1)
+@SP
+A=M-1
+M=-1
+(JEQ_END_StackTest_1)
+// PUSH 16
+@16
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 17
+@17
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JEQ
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JEQ_TRUE_StackTest_2
+D;JEQ
+@SP
+A=M-1
+M=0
+@JEQ_END_StackTest_2
+0;JMP
+(JEQ_TRUE_StackTest_2)
+@SP
+A=M-1
+M=-1
+(JEQ_END_StackTest_2)
+// PUSH 892
+@892
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 891
+@891
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JLT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JLT_TRUE_StackTest_3
+D;JLT
+@SP
+A=M-1
+M=0
+@JLT_END_StackTest_3
+0;JMP
+(JLT_TRUE_StackTest_3)
+@SP
+A=M-1
+M=-1
+(JLT_END_StackTest_3)
+// PUSH 891
+@891
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 892
+@892
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JLT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JLT_TRUE_StackTest_4
+D;JLT
+@SP
+A=M-1
+M=0
+@JLT_END_StackTest_4
+0;JMP
+(JLT_TRUE_StackTest_4)
+@SP
+A=M-1
+M=-1
+(JLT_END_StackTest_4)
+// PUSH 891
+@891
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 891
+@891
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JLT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JLT_TRUE_StackTest_5
+D;JLT
+@SP
+A=M-1
+M=0
+@JLT_END_StackTest_5
+0;JMP
+(JLT_TRUE_StackTest_5)
+@SP
+A=M-1
+M=-1
+(JLT_END_StackTest_5)
+// PUSH 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 32766
+@32766
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JGT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JGT_TRUE_StackTest_6
+D;JGT
+@SP
+A=M-1
+M=0
+@JGT_END_StackTest_6
+0;JMP
+(JGT_TRUE_StackTest_6)
+@SP
+A=M-1
+M=-1
+(JGT_END_StackTest_6)
+// PUSH 32766
+@32766
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JGT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JGT_TRUE_StackTest_7
+D;JGT
+@SP
+A=M-1
+M=0
+@JGT_END_StackTest_7
+0;JMP
+(JGT_TRUE_StackTest_7)
+@SP
+A=M-1
+M=-1
+(JGT_END_StackTest_7)
+// PUSH 32766
+@32766
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 32766
+@32766
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JGT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JGT_TRUE_StackTest_8
+D;JGT
+@SP
+A=M-1
+M=0
+@JGT_END_StackTest_8
+0;JMP
+(JGT_TRUE_StackTest_8)
+@SP
+A=M-1
+M=-1
+(JGT_END_StackTest_8)
+// PUSH 57
+@57
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 31
+@31
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 53
+@53
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// PUSH 112
+@112
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// neg
+@SP
+A=M-1
+M=-M
+// MATH or LOGICAL D&M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D&M
+// PUSH 82
+@82
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D|M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D|M
+// not
+@SP
+A=M-1
+M=!M
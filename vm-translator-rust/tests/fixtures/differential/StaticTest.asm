@@ -0,0 +1,78 @@
+// PUSH 111
+@111
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 333
+@333
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 888
+@888
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP StaticTest.8
+@SP
+AM=M-1
+D=M
+@StaticTest.8
+M=D
+// POP StaticTest.3
+@SP
+AM=M-1
+D=M
+@StaticTest.3
+M=D
+// POP StaticTest.1
+@SP
+AM=M-1
+D=M
+@StaticTest.1
+M=D
+// PUSH StaticTest.3
+@StaticTest.3
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH StaticTest.1
+@StaticTest.1
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// PUSH StaticTest.8
+@StaticTest.8
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
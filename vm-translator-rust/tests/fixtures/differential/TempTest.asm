@@ -0,0 +1,236 @@
+// PUSH 100
+@100
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R5
+@SP
+AM=M-1
+D=M
+@R5
+M=D
+// PUSH 101
+@101
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R6
+@SP
+AM=M-1
+D=M
+@R6
+M=D
+// PUSH 102
+@102
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R7
+@SP
+AM=M-1
+D=M
+@R7
+M=D
+// PUSH 103
+@103
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R8
+@SP
+AM=M-1
+D=M
+@R8
+M=D
+// PUSH 104
+@104
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R9
+@SP
+AM=M-1
+D=M
+@R9
+M=D
+// PUSH 105
+@105
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R10
+@SP
+AM=M-1
+D=M
+@R10
+M=D
+// PUSH 106
+@106
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R11
+@SP
+AM=M-1
+D=M
+@R11
+M=D
+// PUSH 107
+@107
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// POP R12
+@SP
+AM=M-1
+D=M
+@R12
+M=D
+// PUSH 12
+@12
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 5
+@5
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL M-D
+@SP
+AM=M-1
+D=M
+A=A-1
+M=M-D
+// POP R8
+@SP
+AM=M-1
+D=M
+@R8
+M=D
+// PUSH 8
+@8
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 10
+@10
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// POP TempTest.0
+@SP
+AM=M-1
+D=M
+@TempTest.0
+M=D
+// PUSH 11
+@11
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 6
+@6
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// OPERATION IS LOGICAL JGT
+@SP
+AM=M-1
+D=M
+A=A-1
+D=M-D
+@JGT_TRUE_TempTest_0
+D;JGT
+@SP
+A=M-1
+M=0
+@JGT_END_TempTest_0
+0;JMP
+(JGT_TRUE_TempTest_0)
+@SP
+A=M-1
+M=-1
+(JGT_END_TempTest_0)
+// POP TempTest.1
+@SP
+AM=M-1
+D=M
+@TempTest.1
+M=D
+// PUSH 7
+@7
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// PUSH 9
+@9
+D=M
+@SP
+A=M
+M=D
+@SP
+M=M+1
+// MATH or LOGICAL D+M
+@SP
+AM=M-1
+D=M
+A=A-1
+M=D+M
+// POP R5
+@SP
+AM=M-1
+D=M
+@R5
+M=D
@@ -0,0 +1,27 @@
+mod common;
+
+use common::{check_against_cmp, translate_program};
+use vm_translator_rust::ir::IrOptions;
+
+const PROGRAMS: [&str; 4] = [
+    "8/FunctionCalls/SimpleFunction",
+    "8/FunctionCalls/NestedCall",
+    "8/FunctionCalls/FibonacciElement",
+    "8/FunctionCalls/StaticsTest",
+];
+
+#[test]
+fn test_inlined_programs_match_cmp() {
+    for shared_runtime in [false, true] {
+        for threshold in [4, 8, 64] {
+            let options = IrOptions {
+                shared_runtime,
+                inline: Some(threshold),
+                ..IrOptions::default()
+            };
+            for program in PROGRAMS {
+                check_against_cmp(&translate_program(program, &options), program, 2);
+            }
+        }
+    }
+}
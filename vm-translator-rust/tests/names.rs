@@ -0,0 +1,63 @@
+mod common;
+
+use common::run;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use vm_translator_rust::code::translate_sources;
+use vm_translator_rust::ir::IrOptions;
+
+const SOURCE: &str = "push constant 7\npop static 0\npush static 0\npop temp 0\n";
+
+#[test]
+fn test_any_file_name_translates() {
+    for name in [
+        "main.vm",
+        "gen-output.vm",
+        "3d vector.vm",
+        "NoExtension",
+        "Foo.gen.vm",
+    ] {
+        let files = vec![(name.to_string(), SOURCE.to_string())];
+        let assembly = translate_sources(&files, &IrOptions::default()).unwrap();
+        assert!(!assembly.contains(".vm."), "{}", name);
+        let ram = run(&assembly, &[(0, 256)], 100);
+        assert_eq!(ram.read(5), 7, "{}", name);
+    }
+}
+
+#[test]
+fn test_files_with_sanitized_names_keep_their_statics_apart() {
+    let files = vec![
+        (
+            "a-b.vm".to_string(),
+            "push constant 1\npop static 0\n".to_string(),
+        ),
+        (
+            "a_c.vm".to_string(),
+            "push constant 2\npop static 0\n".to_string(),
+        ),
+    ];
+    let assembly = translate_sources(&files, &IrOptions::default()).unwrap();
+    assert!(assembly.contains("@a_b.0") && assembly.contains("@a_c.0"));
+}
+
+#[test]
+fn test_stdin_with_name() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vm-translator-rust"))
+        .args(["-", "--name=Gen"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(SOURCE.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let assembly = String::from_utf8(output.stdout).unwrap();
+    assert!(assembly.contains("@Gen.0"), "{}", assembly);
+    let ram = run(&assembly, &[(0, 256)], 100);
+    assert_eq!(ram.read(5), 7);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{check_against_cmp, program_sources};
+use vm_translator_rust::code::{translate_program, translate_sources};
+use vm_translator_rust::ir::IrOptions;
+use vm_translator_rust::parser::Segment;
+use vm_translator_rust::program::{Operation, Statement, VmFile, VmFunction, VmProgram};
+
+/// a program parsed into a `VmProgram` translates exactly like its source.
+#[test]
+fn test_program_tree_translates_like_source() {
+    for program in [
+        "7/MemoryAccess/BasicTest",
+        "8/FunctionCalls/FibonacciElement",
+        "8/FunctionCalls/StaticsTest",
+    ] {
+        let files = program_sources(program);
+        let tree = VmProgram::parse(&files, false).unwrap();
+        let options = IrOptions::default();
+        let assembly = translate_program(&tree, &options).unwrap();
+        assert_eq!(assembly, translate_sources(&files, &options).unwrap());
+        assert_eq!(
+            translate_sources(&tree.sources(), &options).unwrap(),
+            assembly
+        );
+        check_against_cmp(&assembly, program, 1);
+    }
+}
+
+#[test]
+fn test_program_built_in_memory() {
+    let mut init = VmFunction::new("Sys.init", 0);
+    init.push(Statement::Push(Segment::Constant, 20));
+    init.push(Statement::Call {
+        name: "Main.square".to_string(),
+        args: 1,
+    });
+    init.push(Statement::Pop(Segment::Temp, 0));
+    init.push(Statement::Label("HALT".to_string()));
+    init.push(Statement::Goto("HALT".to_string()));
+    let mut square = VmFunction::new("Main.square", 0);
+    square.push(Statement::Push(Segment::Argument, 0));
+    square.push(Statement::Operation(Operation::Dup));
+    square.push(Statement::Operation(Operation::Mul));
+    square.push(Statement::Return);
+
+    let mut file = VmFile::new("Main.vm");
+    file.functions = vec![init, square];
+    let program = VmProgram { files: vec![file] };
+    let options = IrOptions {
+        extensions: true,
+        ..IrOptions::default()
+    };
+    let assembly = translate_program(&program, &options).unwrap();
+    let ram = common::run(&assembly, &[], 2_000);
+    assert_eq!(ram.read(5), 400);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{assemble, check_against_cmp, run, translate_program};
+use vm_translator_rust::code::translate_sources;
+use vm_translator_rust::ir::{IrOptions, Pass};
+
+const PROGRAMS: [&str; 5] = [
+    "7/StackArithmetic/SimpleAdd",
+    "7/StackArithmetic/StackTest",
+    "7/MemoryAccess/BasicTest",
+    "7/MemoryAccess/PointerTest",
+    "7/MemoryAccess/StaticTest",
+];
+
+#[test]
+fn test_project7_matches_cmp() {
+    for program in PROGRAMS {
+        let assembly = translate_program(program, &IrOptions::default());
+        check_against_cmp(&assembly, program, 1);
+    }
+}
+
+#[test]
+fn test_stack_test_is_folded() {
+    // every command in StackTest operates on constants, so the whole program folds to pushes.
+    let program = "7/StackArithmetic/StackTest";
+    let assembly = translate_program(program, &IrOptions::default());
+    assert!(assemble(&assembly).len() < 100);
+    // without folding the comparisons run as lowered code and still give the expected stack
+    let unfolded = IrOptions {
+        skip_pass: Some(Pass::Peephole),
+        ..IrOptions::default()
+    };
+    let assembly = translate_program(program, &unfolded);
+    assert!(assemble(&assembly).len() >= 100);
+    check_against_cmp(&assembly, program, 1);
+}
+
+/// folded comparisons give what the lowered code computes, even where x - y overflows.
+#[test]
+fn test_folded_comparisons_run_the_same() {
+    let pairs = [
+        (32767, -1),
+        (-32768, 1),
+        (1, -32768),
+        (-32768, 32767),
+        (-2, 32767),
+        (5, 3),
+    ];
+    let mut source = String::new();
+    for (i, (lhs, rhs)) in pairs.iter().enumerate() {
+        for (j, comparison) in ["gt", "lt"].iter().enumerate() {
+            for value in [*lhs, *rhs] {
+                // negative constants are pushed negated, -32768 as -32767 - 1
+                source.push_str(&match value {
+                    -32768 => "push constant 32767\nneg\npush constant 1\nsub\n".to_string(),
+                    value if value < 0 => format!("push constant {}\nneg\n", -value),
+                    value => format!("push constant {}\n", value),
+                });
+            }
+            source.push_str(&format!("{}\npop static {}\n", comparison, 2 * i + j));
+        }
+    }
+    let files = vec![("Compare.vm".to_string(), source)];
+    let unfolded = IrOptions {
+        skip_pass: Some(Pass::Peephole),
+        ..IrOptions::default()
+    };
+    let folded = translate_sources(&files, &IrOptions::default()).unwrap();
+    let unfolded = translate_sources(&files, &unfolded).unwrap();
+    assert!(assemble(&folded).len() < assemble(&unfolded).len());
+    let folded = run(&folded, &[(0, 256)], 10_000);
+    let unfolded = run(&unfolded, &[(0, 256)], 10_000);
+    for address in 16..16 + 2 * pairs.len() {
+        assert_eq!(
+            folded.read(address),
+            unfolded.read(address),
+            "RAM[{}]",
+            address
+        );
+    }
+}
+
+/// an invalid command fails the translation rather than cutting the program short before it
+/// is optimized.
+#[test]
+fn test_parse_error_is_reported() {
+    for options in [
+        IrOptions::default(),
+        IrOptions {
+            inline: Some(4),
+            ..IrOptions::default()
+        },
+    ] {
+        let files = vec![(
+            "Main.vm".to_string(),
+            "push constant 1\npop static 0\npush nothing 2\npop static 1\n".to_string(),
+        )];
+        let error = translate_sources(&files, &options).unwrap_err();
+        assert!(error.contains("line 3"), "{}", error);
+    }
+}
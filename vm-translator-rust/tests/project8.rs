@@ -0,0 +1,61 @@
+mod common;
+
+use common::{check_against_cmp, translate_program};
+use vm_translator_rust::code::count_instructions;
+use vm_translator_rust::ir::IrOptions;
+
+const PROGRAMS: [&str; 6] = [
+    "8/ProgramFlow/BasicLoop",
+    "8/ProgramFlow/FibonacciSeries",
+    "8/FunctionCalls/SimpleFunction",
+    "8/FunctionCalls/NestedCall",
+    "8/FunctionCalls/FibonacciElement",
+    "8/FunctionCalls/StaticsTest",
+];
+
+fn shared() -> IrOptions {
+    IrOptions {
+        shared_runtime: true,
+        ..IrOptions::default()
+    }
+}
+
+#[test]
+fn test_project8_matches_cmp() {
+    for program in PROGRAMS {
+        let assembly = translate_program(program, &IrOptions::default());
+        check_against_cmp(&assembly, program, 1);
+    }
+}
+
+#[test]
+fn test_project8_shared_runtime_matches_cmp() {
+    for program in PROGRAMS {
+        let assembly = translate_program(program, &shared());
+        check_against_cmp(&assembly, program, 2);
+    }
+}
+
+#[test]
+fn test_project7_shared_runtime_matches_cmp() {
+    for program in [
+        "7/StackArithmetic/SimpleAdd",
+        "7/StackArithmetic/StackTest",
+        "7/MemoryAccess/BasicTest",
+    ] {
+        let assembly = translate_program(program, &shared());
+        check_against_cmp(&assembly, program, 2);
+    }
+}
+
+#[test]
+fn test_shared_runtime_is_smaller() {
+    for program in [
+        "8/FunctionCalls/FibonacciElement",
+        "8/FunctionCalls/StaticsTest",
+    ] {
+        let inline = count_instructions(&translate_program(program, &IrOptions::default()));
+        let shared = count_instructions(&translate_program(program, &shared()));
+        assert!(shared < inline, "{}: {} >= {}", program, shared, inline);
+    }
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use assembler_rust::code::CodeGenerator;
+use assembler_rust::listing::listing;
+use assembler_rust::source_map::SourceMap;
+use common::{assemble, program_sources};
+use vm_translator_rust::code::{translate_sources, translate_sources_with_map};
+use vm_translator_rust::ir::IrOptions;
+
+/// every mapped address holds an instruction, and the asm line of its entry is that instruction.
+#[test]
+fn test_source_map_points_at_instructions() {
+    for shared_runtime in [false, true] {
+        let options = IrOptions {
+            shared_runtime,
+            ..IrOptions::default()
+        };
+        let files = program_sources("8/FunctionCalls/FibonacciElement");
+        let (asm, map) = translate_sources_with_map(&files, &options).unwrap();
+        assert_eq!(asm, translate_sources(&files, &options).unwrap());
+
+        let rom = assemble(&asm);
+        let lines = asm.lines().collect::<Vec<&str>>();
+        assert!(!map.entries().is_empty());
+        for entry in map.entries() {
+            assert!((entry.address as usize) < rom.len());
+            let line = lines[entry.asm_line - 1].trim();
+            assert!(!line.is_empty() && !line.starts_with("//") && !line.starts_with('('));
+        }
+        // the bootstrap isn't part of any file
+        assert!(map.lookup(0).is_none());
+        assert_eq!(SourceMap::parse(&map.to_string()).unwrap(), map);
+    }
+}
+
+#[test]
+fn test_source_map_locations() {
+    let files = program_sources("8/FunctionCalls/FibonacciElement");
+    let (_, map) = translate_sources_with_map(&files, &IrOptions::default()).unwrap();
+    let call = map
+        .entries()
+        .iter()
+        .find(|e| e.location.file == "Main.vm" && e.location.line == 25)
+        .unwrap();
+    assert_eq!(call.location.command, "call Main.fibonacci 1");
+    assert_eq!(call.location.function.as_deref(), Some("Main.fibonacci"));
+    assert_eq!(
+        call.location.to_string(),
+        "Main.vm:25 call Main.fibonacci 1"
+    );
+
+    let init = map
+        .entries()
+        .iter()
+        .find(|e| e.location.file == "Sys.vm")
+        .unwrap();
+    assert_eq!(init.location.function.as_deref(), Some("Sys.init"));
+}
+
+#[test]
+fn test_listing_with_source_map() {
+    let files = program_sources("8/FunctionCalls/FibonacciElement");
+    let (asm, map) = translate_sources_with_map(&files, &IrOptions::default()).unwrap();
+    let mut code = CodeGenerator::new(&asm);
+    code.generate().unwrap();
+    let listing = listing(&asm, &code.take_code(), Some(&map)).unwrap();
+    assert!(listing.contains("// Main.vm:25 call Main.fibonacci 1"));
+    assert!(listing.contains("// Main.vm:13 push argument 0"));
+}
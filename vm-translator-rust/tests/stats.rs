@@ -0,0 +1,93 @@
+mod common;
+
+use common::{program_sources, translate_program};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use vm_translator_rust::code::count_instructions;
+use vm_translator_rust::ir::IrOptions;
+use vm_translator_rust::stats::Stats;
+
+const PROGRAMS: [&str; 3] = [
+    "7/MemoryAccess/BasicTest",
+    "8/FunctionCalls/FibonacciElement",
+    "8/FunctionCalls/StaticsTest",
+];
+
+#[test]
+fn test_every_instruction_is_counted_once() {
+    for shared_runtime in [false, true] {
+        let options = IrOptions {
+            shared_runtime,
+            ..IrOptions::default()
+        };
+        for program in PROGRAMS {
+            let stats = Stats::collect(&program_sources(program), &options).unwrap();
+            let size = count_instructions(&translate_program(program, &options));
+            let by_command = stats
+                .files
+                .iter()
+                .flat_map(|file| &file.functions)
+                .flat_map(|function| function.by_command.values())
+                .map(|count| count.instructions)
+                .sum::<usize>();
+            assert_eq!(by_command + stats.runtime, size, "{}", program);
+            assert_eq!(stats.total.instructions, size, "{}", program);
+        }
+    }
+}
+
+#[test]
+fn test_pass_savings() {
+    let mut files = program_sources("8/FunctionCalls/FibonacciElement");
+    files.push((
+        "Unused.vm".to_string(),
+        "function Unused.f 0\npush constant 1\nreturn\n".to_string(),
+    ));
+    let options = IrOptions {
+        inline: Some(8),
+        ..IrOptions::default()
+    };
+    let stats = Stats::collect(&files, &options).unwrap();
+    let passes = stats
+        .passes
+        .iter()
+        .map(|pass| pass.name)
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        passes,
+        ["peephole", "dead code", "push/pop fusion", "inline"]
+    );
+    let unused = &stats.files[2];
+    assert_eq!((unused.total.commands, unused.total.instructions), (3, 0));
+    assert!(stats.passes[1].saved > 0);
+    for pass in &stats.passes {
+        assert_eq!(
+            pass.saved,
+            pass.without as i64 - stats.total.instructions as i64
+        );
+    }
+}
+
+/// a program that doesn't translate reports the error once and fails, the statistics aren't
+/// collected.
+#[test]
+fn test_error_stops_the_run() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vm-translator-rust"))
+        .args(["-", "--stats=table", "--size-report"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"push constant 1\nfrobnicate\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let report = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(report.lines().count(), 1, "{}", report);
+    assert!(report.starts_with("[err] "), "{}", report);
+}
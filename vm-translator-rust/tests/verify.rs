@@ -0,0 +1,59 @@
+mod common;
+
+use common::program_sources;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use vm_translator_rust::verify::verify;
+
+const PROGRAMS: [&str; 11] = [
+    "7/StackArithmetic/SimpleAdd",
+    "7/StackArithmetic/StackTest",
+    "7/MemoryAccess/BasicTest",
+    "7/MemoryAccess/PointerTest",
+    "7/MemoryAccess/StaticTest",
+    "8/ProgramFlow/BasicLoop",
+    "8/ProgramFlow/FibonacciSeries",
+    "8/FunctionCalls/SimpleFunction",
+    "8/FunctionCalls/NestedCall",
+    "8/FunctionCalls/FibonacciElement",
+    "8/FunctionCalls/StaticsTest",
+];
+
+#[test]
+fn test_project_programs_verify() {
+    for program in PROGRAMS {
+        assert_eq!(verify(&program_sources(program)), vec![], "{}", program);
+    }
+}
+
+#[test]
+fn test_broken_compiler_output() {
+    // a `do` statement whose result isn't discarded, and a pop of the value it never pushed
+    let source = "function Main.main 1\ncall Output.println 0\npop local 0\npop temp 0\npush constant 0\nreturn\n";
+    let problems = verify(&[("Main.vm".to_string(), source.to_string())]);
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].line, 4);
+    assert_eq!(problems[0].function.as_deref(), Some("Main.main"));
+}
+
+#[test]
+fn test_failed_verification_exits_with_an_error() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vm-translator-rust"))
+        .args(["-", "--verify"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"pop constant 3\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let report = String::from_utf8(output.stderr).unwrap();
+    assert!(report.contains("[err] verification found"), "{}", report);
+}